@@ -4,9 +4,11 @@ pub mod content;
 pub mod fetch;
 pub mod ledger;
 pub mod monitor;
+pub mod operator;
 pub mod package;
 pub mod paths;
 pub mod proof;
+pub mod witness;
 
 use serde::{Deserialize, Serialize};
 
@@ -28,6 +28,11 @@ pub fn publish_package_record(log_id: &LogId) -> String {
     format!("v1/package/{log_id}/record")
 }
 
+/// The path of the "publish operator record" API.
+pub fn publish_operator_record() -> &'static str {
+    "v1/operator/record"
+}
+
 /// The path to request download of content digest.
 pub fn content_sources(digest: &AnyHash) -> String {
     format!("v1/content/{digest}")
@@ -38,6 +43,11 @@ pub fn package_record(log_id: &LogId, record_id: &RecordId) -> String {
     format!("v1/package/{log_id}/record/{record_id}")
 }
 
+/// The path for an operator record.
+pub fn operator_record(record_id: &RecordId) -> String {
+    format!("v1/operator/record/{record_id}")
+}
+
 /// The path for proving checkpoint consistency.
 pub fn prove_consistency() -> &'static str {
     "v1/proof/consistency"
@@ -48,7 +58,27 @@ pub fn prove_inclusion() -> &'static str {
     "v1/proof/inclusion"
 }
 
+/// The path for proving absence of package logs at a checkpoint.
+pub fn prove_absence() -> &'static str {
+    "v1/proof/absence"
+}
+
+/// The path for proving the transition of package logs between checkpoints.
+pub fn prove_transition() -> &'static str {
+    "v1/proof/transition"
+}
+
 /// The path for verifying a checkpoint.
 pub fn verify_checkpoint() -> &'static str {
     "v1/verify/checkpoint"
 }
+
+/// The path for fetching the latest checkpoint for witnesses to cosign.
+pub fn witness_checkpoint() -> &'static str {
+    "v1/witness/checkpoint"
+}
+
+/// The path for submitting a witness cosignature of a checkpoint.
+pub fn witness_cosignature() -> &'static str {
+    "v1/witness/cosignature"
+}
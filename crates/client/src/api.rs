@@ -18,20 +18,27 @@ use warg_api::v1::{
     },
     ledger::{LedgerError, LedgerSourcesResponse},
     monitor::{CheckpointVerificationResponse, MonitorError},
+    operator::{self, OperatorError, OperatorRecord},
     package::{ContentSource, PackageError, PackageRecord, PublishRecordRequest},
     paths,
     proof::{
-        ConsistencyRequest, ConsistencyResponse, InclusionRequest, InclusionResponse, ProofError,
+        AbsenceRequest, AbsenceResponse, ConsistencyRequest, ConsistencyResponse, InclusionRequest,
+        InclusionResponse, ProofError, TransitionRequest, TransitionResponse,
     },
+    witness::{CosignCheckpointRequest, WitnessError},
+};
+use warg_crypto::hash::{
+    AnyHash, Blake3, HashAlgorithm, HashError, Sha256, Sha512, SupportedDigest,
 };
-use warg_crypto::hash::{AnyHash, HashError, Sha256};
 use warg_protocol::{
-    registry::{Checkpoint, LogId, LogLeaf, MapLeaf, RecordId, TimestampedCheckpoint},
+    registry::{
+        Checkpoint, CosignedCheckpoint, LogId, LogLeaf, MapLeaf, RecordId, TimestampedCheckpoint,
+    },
     SerdeEnvelope,
 };
 use warg_transparency::{
     log::{ConsistencyProofError, InclusionProofError, LogProofBundle, ProofBundle},
-    map::MapProofBundle,
+    map::{AbsenceProofError, MapProofBundle, TransitionProofError},
 };
 
 use crate::registry_url::RegistryUrl;
@@ -42,6 +49,9 @@ pub enum ClientError {
     /// An error was returned from the fetch API.
     #[error(transparent)]
     Fetch(#[from] FetchError),
+    /// An error was returned from the operator API.
+    #[error(transparent)]
+    Operator(#[from] OperatorError),
     /// An error was returned from the package API.
     #[error(transparent)]
     Package(#[from] PackageError),
@@ -57,6 +67,9 @@ pub enum ClientError {
     /// An error was returned from the ledger API.
     #[error(transparent)]
     Ledger(#[from] LedgerError),
+    /// An error was returned from the witness API.
+    #[error(transparent)]
+    Witness(#[from] WitnessError),
     /// An error occurred while communicating with the registry.
     #[error("failed to send request to registry server: {0}")]
     Communication(#[from] reqwest::Error),
@@ -81,12 +94,21 @@ pub enum ClientError {
     /// A hash returned from the server was incorrect.
     #[error("the server returned an invalid hash: {0}")]
     Hash(#[from] HashError),
+    /// The registry uses a hash algorithm that is not supported.
+    #[error("the registry uses unsupported hash algorithm `{0}`")]
+    UnsupportedHashAlgorithm(HashAlgorithm),
     /// The client failed a consistency proof.
     #[error("the client failed a consistency proof: {0}")]
     ConsistencyProof(#[from] ConsistencyProofError),
     /// The client failed an inclusion proof.
     #[error("the client failed an inclusion proof: {0}")]
     InclusionProof(#[from] InclusionProofError),
+    /// The client failed an absence proof.
+    #[error("the client failed an absence proof: {0}")]
+    AbsenceProof(#[from] AbsenceProofError),
+    /// The client failed a map transition proof.
+    #[error("the client failed a map transition proof: {0}")]
+    TransitionProof(#[from] TransitionProofError),
     /// The record was not published.
     #[error("record `{0}` has not been published")]
     RecordNotPublished(RecordId),
@@ -184,6 +206,35 @@ impl Client {
         into_result::<_, FetchError>(reqwest::get(url).await?).await
     }
 
+    /// Gets the latest checkpoint from the registry along with the
+    /// cosignatures of the registry's witnesses.
+    pub async fn latest_cosigned_checkpoint(&self) -> Result<CosignedCheckpoint, ClientError> {
+        let url = self.url.join(paths::fetch_checkpoint());
+        tracing::debug!("getting latest cosigned checkpoint at `{url}`");
+        into_result::<_, FetchError>(reqwest::get(url).await?).await
+    }
+
+    /// Gets the latest checkpoint for a witness to cosign.
+    pub async fn witness_checkpoint(
+        &self,
+    ) -> Result<SerdeEnvelope<TimestampedCheckpoint>, ClientError> {
+        let url = self.url.join(paths::witness_checkpoint());
+        tracing::debug!("getting checkpoint to witness at `{url}`");
+        into_result::<_, WitnessError>(reqwest::get(url).await?).await
+    }
+
+    /// Submits a witness cosignature of a checkpoint to the registry.
+    pub async fn cosign_checkpoint(
+        &self,
+        request: &CosignCheckpointRequest,
+    ) -> Result<CosignedCheckpoint, ClientError> {
+        let url = self.url.join(paths::witness_cosignature());
+        tracing::debug!("submitting witness cosignature at `{url}`");
+
+        let response = self.client.post(url).json(request).send().await?;
+        into_result::<_, WitnessError>(response).await
+    }
+
     /// Verify checkpoint of the registry.
     pub async fn verify_checkpoint(
         &self,
@@ -229,6 +280,30 @@ impl Client {
         into_result::<_, LedgerError>(response).await
     }
 
+    /// Publish a new record to the operator log.
+    pub async fn publish_operator_record(
+        &self,
+        request: operator::PublishRecordRequest<'_>,
+    ) -> Result<OperatorRecord, ClientError> {
+        let url = self.url.join(paths::publish_operator_record());
+        tracing::debug!("appending record to operator log at `{url}`");
+
+        let response = self.client.post(url).json(&request).send().await?;
+        into_result::<_, OperatorError>(response).await
+    }
+
+    /// Gets an operator record from the registry.
+    pub async fn get_operator_record(
+        &self,
+        record_id: &RecordId,
+    ) -> Result<OperatorRecord, ClientError> {
+        let url = self.url.join(&paths::operator_record(record_id));
+        tracing::debug!("getting operator record `{record_id}` at `{url}`");
+
+        let response = reqwest::get(url).await?;
+        into_result::<_, OperatorError>(response).await
+    }
+
     /// Publish a new record to a package log.
     pub async fn publish_package_record(
         &self,
@@ -321,6 +396,69 @@ impl Client {
         Self::validate_inclusion_response(response, checkpoint, leafs)
     }
 
+    /// Proves the absence of the given package logs in the registry at the
+    /// given checkpoint.
+    pub async fn prove_absence(
+        &self,
+        request: AbsenceRequest,
+        checkpoint: &Checkpoint,
+    ) -> Result<(), ClientError> {
+        let url = self.url.join(paths::prove_absence());
+        tracing::debug!("proving package log absence at `{url}`");
+
+        let log_ids = request.log_ids.clone();
+        let response = into_result::<AbsenceResponse, ProofError>(
+            self.client.post(url).json(&request).send().await?,
+        )
+        .await?;
+
+        match checkpoint.map_root.algorithm() {
+            HashAlgorithm::Sha256 => {
+                Self::validate_absence_proofs::<Sha256>(response, checkpoint, &log_ids)
+            }
+            HashAlgorithm::Sha512 => {
+                Self::validate_absence_proofs::<Sha512>(response, checkpoint, &log_ids)
+            }
+            HashAlgorithm::Blake3 => {
+                Self::validate_absence_proofs::<Blake3>(response, checkpoint, &log_ids)
+            }
+            algorithm => Err(ClientError::UnsupportedHashAlgorithm(algorithm)),
+        }
+    }
+
+    /// Proves the transition of the given package logs from their map leafs
+    /// at one checkpoint to their map leafs at another.
+    ///
+    /// A map leaf of `None` means the package log is absent at that checkpoint.
+    pub async fn prove_transition(
+        &self,
+        request: TransitionRequest,
+        from: &Checkpoint,
+        to: &Checkpoint,
+        transitions: &[(LogId, Option<MapLeaf>, Option<MapLeaf>)],
+    ) -> Result<(), ClientError> {
+        let url = self.url.join(paths::prove_transition());
+        tracing::debug!("proving map transition at `{url}`");
+
+        let response = into_result::<TransitionResponse, ProofError>(
+            self.client.post(url).json(&request).send().await?,
+        )
+        .await?;
+
+        match to.map_root.algorithm() {
+            HashAlgorithm::Sha256 => {
+                Self::validate_transition_proof::<Sha256>(response, from, to, transitions)
+            }
+            HashAlgorithm::Sha512 => {
+                Self::validate_transition_proof::<Sha512>(response, from, to, transitions)
+            }
+            HashAlgorithm::Blake3 => {
+                Self::validate_transition_proof::<Blake3>(response, from, to, transitions)
+            }
+            algorithm => Err(ClientError::UnsupportedHashAlgorithm(algorithm)),
+        }
+    }
+
     /// Proves consistency between two log roots.
     pub async fn prove_log_consistency(
         &self,
@@ -334,7 +472,26 @@ impl Client {
         )
         .await?;
 
-        let proof = ProofBundle::<Sha256, LogLeaf>::decode(&response.proof).unwrap();
+        match to_log_root.algorithm() {
+            HashAlgorithm::Sha256 => {
+                Self::validate_consistency_proof::<Sha256>(&response, from_log_root, to_log_root)
+            }
+            HashAlgorithm::Sha512 => {
+                Self::validate_consistency_proof::<Sha512>(&response, from_log_root, to_log_root)
+            }
+            HashAlgorithm::Blake3 => {
+                Self::validate_consistency_proof::<Blake3>(&response, from_log_root, to_log_root)
+            }
+            algorithm => Err(ClientError::UnsupportedHashAlgorithm(algorithm)),
+        }
+    }
+
+    fn validate_consistency_proof<D: SupportedDigest>(
+        response: &ConsistencyResponse,
+        from_log_root: Cow<'_, AnyHash>,
+        to_log_root: Cow<'_, AnyHash>,
+    ) -> Result<(), ClientError> {
+        let proof = ProofBundle::<D, LogLeaf>::decode(&response.proof).unwrap();
         let (log_data, consistencies, inclusions) = proof.unbundle();
         if !inclusions.is_empty() {
             return Err(ClientError::Proof(ProofError::BundleFailure(
@@ -425,7 +582,26 @@ impl Client {
         checkpoint: &Checkpoint,
         leafs: &[LogLeaf],
     ) -> Result<(), ClientError> {
-        let log_proof_bundle: LogProofBundle<Sha256, LogLeaf> =
+        match checkpoint.log_root.algorithm() {
+            HashAlgorithm::Sha256 => {
+                Self::validate_inclusion_proofs::<Sha256>(response, checkpoint, leafs)
+            }
+            HashAlgorithm::Sha512 => {
+                Self::validate_inclusion_proofs::<Sha512>(response, checkpoint, leafs)
+            }
+            HashAlgorithm::Blake3 => {
+                Self::validate_inclusion_proofs::<Blake3>(response, checkpoint, leafs)
+            }
+            algorithm => Err(ClientError::UnsupportedHashAlgorithm(algorithm)),
+        }
+    }
+
+    fn validate_inclusion_proofs<D: SupportedDigest>(
+        response: InclusionResponse,
+        checkpoint: &Checkpoint,
+        leafs: &[LogLeaf],
+    ) -> Result<(), ClientError> {
+        let log_proof_bundle: LogProofBundle<D, LogLeaf> =
             LogProofBundle::decode(response.log.as_slice())?;
         let (log_data, _, log_inclusions) = log_proof_bundle.unbundle();
         for (leaf, proof) in leafs.iter().zip(log_inclusions.iter()) {
@@ -439,7 +615,7 @@ impl Client {
             }
         }
 
-        let map_proof_bundle: MapProofBundle<Sha256, LogId, MapLeaf> =
+        let map_proof_bundle: MapProofBundle<D, LogId, MapLeaf> =
             MapProofBundle::decode(response.map.as_slice())?;
         let map_inclusions = map_proof_bundle.unbundle();
         for (leaf, proof) in leafs.iter().zip(map_inclusions.iter()) {
@@ -460,4 +636,66 @@ impl Client {
 
         Ok(())
     }
+
+    fn validate_absence_proofs<D: SupportedDigest>(
+        response: AbsenceResponse,
+        checkpoint: &Checkpoint,
+        log_ids: &[LogId],
+    ) -> Result<(), ClientError> {
+        let map_proof_bundle: MapProofBundle<D, LogId, MapLeaf> =
+            MapProofBundle::decode(response.map.as_slice())?;
+        let map_absences = map_proof_bundle.unbundle_absences();
+
+        // Every requested log must be proven absent
+        if let Some(log_id) = log_ids.get(map_absences.len()) {
+            return Err(ClientError::Proof(ProofError::PackageLogIncluded(
+                log_id.clone(),
+            )));
+        }
+
+        for (log_id, proof) in log_ids.iter().zip(map_absences.iter()) {
+            let found = proof.evaluate(log_id)?;
+            let root = checkpoint.map_root.clone().try_into()?;
+            if found != root {
+                return Err(ClientError::Proof(ProofError::IncorrectProof {
+                    root: checkpoint.map_root.clone(),
+                    found: found.into(),
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn validate_transition_proof<D: SupportedDigest>(
+        response: TransitionResponse,
+        from: &Checkpoint,
+        to: &Checkpoint,
+        transitions: &[(LogId, Option<MapLeaf>, Option<MapLeaf>)],
+    ) -> Result<(), ClientError> {
+        let map_proof_bundle: MapProofBundle<D, LogId, MapLeaf> =
+            MapProofBundle::decode(response.map.as_slice())?;
+        let proof = match <[_; 1]>::try_from(map_proof_bundle.unbundle_transitions()) {
+            Ok([proof]) => proof,
+            Err(proofs) => {
+                return Err(ClientError::Other(anyhow!(
+                    "expected one map transition proof but the registry returned {len}",
+                    len = proofs.len()
+                )))
+            }
+        };
+
+        let (from_root, to_root) = proof.evaluate(transitions)?;
+        for (checkpoint, found) in [(from, from_root), (to, to_root)] {
+            let root = checkpoint.map_root.clone().try_into()?;
+            if found != root {
+                return Err(ClientError::Proof(ProofError::IncorrectProof {
+                    root: checkpoint.map_root.clone(),
+                    found: found.into(),
+                }));
+            }
+        }
+
+        Ok(())
+    }
 }
@@ -10,6 +10,7 @@ use std::{
     fs::{self, File},
     path::{Component, Path, PathBuf},
 };
+use warg_crypto::signing::PublicKey;
 
 static CACHE_DIR: Lazy<Option<PathBuf>> = Lazy::new(dirs::cache_dir);
 static CONFIG_DIR: Lazy<Option<PathBuf>> = Lazy::new(dirs::config_dir);
@@ -94,6 +95,33 @@ pub struct Config {
     /// `$CACHE_DIR` is the platform-specific cache directory.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub content_dir: Option<PathBuf>,
+
+    /// The public keys of the witnesses trusted to cosign registry checkpoints.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub witness_keys: Vec<PublicKey>,
+
+    /// The number of trusted witnesses that must cosign a checkpoint for the
+    /// client to accept it.
+    ///
+    /// If zero, checkpoints are accepted without witness cosignatures.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub witness_threshold: usize,
+
+    /// Whether to accept package log updates without proving their map
+    /// transition when the registry no longer has the map of the package's
+    /// last known checkpoint.
+    ///
+    /// If `false`, such updates are rejected.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub allow_unproven_map_transitions: bool,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
 }
 
 impl Config {
@@ -165,6 +193,9 @@ impl Config {
                 assert!(p.is_absolute());
                 pathdiff::diff_paths(&p, &parent).unwrap()
             }),
+            witness_keys: self.witness_keys.clone(),
+            witness_threshold: self.witness_threshold,
+            allow_unproven_map_transitions: self.allow_unproven_map_transitions,
         };
 
         serde_json::to_writer_pretty(
@@ -6,7 +6,12 @@ use crate::storage::PackageInfo;
 use anyhow::{anyhow, Context, Result};
 use reqwest::{Body, IntoUrl};
 use std::cmp::Ordering;
-use std::{borrow::Cow, collections::HashMap, path::PathBuf, time::Duration};
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
 use storage::{
     ContentStorage, FileSystemContentStorage, FileSystemRegistryStorage, PublishInfo,
     RegistryStorage,
@@ -14,20 +19,23 @@ use storage::{
 use thiserror::Error;
 use warg_api::v1::{
     fetch::{FetchError, FetchLogsRequest, FetchLogsResponse},
+    operator::{OperatorRecordState, PublishRecordRequest as PublishOperatorRecordRequest},
     package::{
         MissingContent, PackageError, PackageRecord, PackageRecordState, PublishRecordRequest,
         UploadEndpoint,
     },
-    proof::{ConsistencyRequest, InclusionRequest},
+    proof::{AbsenceRequest, ConsistencyRequest, InclusionRequest, ProofError, TransitionRequest},
 };
 use warg_crypto::{
-    hash::{AnyHash, Sha256},
+    hash::{AnyHash, HashAlgorithm},
     signing, Encode, Signable,
 };
 use warg_protocol::{
     operator, package,
-    registry::{LogId, LogLeaf, PackageName, RecordId, RegistryLen, TimestampedCheckpoint},
-    PublishedProtoEnvelope, SerdeEnvelope, Version, VersionReq,
+    registry::{
+        LogId, LogLeaf, MapLeaf, PackageName, RecordId, RegistryLen, TimestampedCheckpoint,
+    },
+    ProtoEnvelope, PublishedProtoEnvelope, SerdeEnvelope, Version, VersionReq,
 };
 
 pub mod api;
@@ -43,6 +51,9 @@ pub struct Client<R, C> {
     registry: R,
     content: C,
     api: api::Client,
+    witness_keys: Vec<signing::PublicKey>,
+    witness_threshold: usize,
+    allow_unproven_map_transitions: bool,
 }
 
 impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
@@ -53,9 +64,36 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             registry,
             content,
             api: api::Client::new(url)?,
+            witness_keys: Vec::new(),
+            witness_threshold: 0,
+            allow_unproven_map_transitions: false,
         })
     }
 
+    /// Requires checkpoints to be cosigned by at least `threshold` of the
+    /// given witness keys.
+    ///
+    /// Checkpoints without enough witness cosignatures are rejected.
+    pub fn with_witnesses(
+        mut self,
+        keys: impl IntoIterator<Item = signing::PublicKey>,
+        threshold: usize,
+    ) -> Self {
+        self.witness_keys = keys.into_iter().collect();
+        self.witness_threshold = threshold;
+        self
+    }
+
+    /// Sets whether package log updates are accepted without proving their
+    /// map transition when the registry no longer has the map of the
+    /// package's last known checkpoint.
+    ///
+    /// By default, such updates are rejected.
+    pub fn with_unproven_map_transitions(mut self, allow: bool) -> Self {
+        self.allow_unproven_map_transitions = allow;
+        self
+    }
+
     /// Gets the URL of the client.
     pub fn url(&self) -> &RegistryUrl {
         self.api.url()
@@ -96,14 +134,14 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
     /// Returns the identifier of the record that was published.
     ///
     /// Use `wait_for_publish` to wait for the record to transition to the `published` state.
-    pub async fn publish(&self, signing_key: &signing::PrivateKey) -> ClientResult<RecordId> {
+    pub async fn publish(&self, signer: &dyn signing::Signer) -> ClientResult<RecordId> {
         let info = self
             .registry
             .load_publish()
             .await?
             .ok_or(ClientError::NotPublishing)?;
 
-        let res = self.publish_with_info(signing_key, info).await;
+        let res = self.publish_with_info(signer, info).await;
         self.registry.store_publish(None).await?;
         res
     }
@@ -112,63 +150,37 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
     ///
     /// Any publish information in client storage is ignored.
     ///
+    /// If the publish information was signed with `sign_with_info`, the
+    /// signed record is submitted as-is and `signer` is not used.
+    ///
     /// Returns the identifier of the record that was published.
     ///
     /// Use `wait_for_publish` to wait for the record to transition to the `published` state.
     pub async fn publish_with_info(
         &self,
-        signing_key: &signing::PrivateKey,
+        signer: &dyn signing::Signer,
         mut info: PublishInfo,
     ) -> ClientResult<RecordId> {
-        if info.entries.is_empty() {
-            return Err(ClientError::NothingToPublish {
-                name: info.name.clone(),
-            });
-        }
-
-        let initializing = info.initializing();
-
-        tracing::info!(
-            "publishing {new}package `{name}`",
-            name = info.name,
-            new = if initializing { "new " } else { "" }
-        );
-        tracing::debug!("entries: {:?}", info.entries);
-
-        let mut package = self
-            .registry
-            .load_package(&info.name)
-            .await?
-            .unwrap_or_else(|| PackageInfo::new(info.name.clone()));
-
-        // If we're not initializing the package and a head was not explicitly specified,
-        // updated to the latest checkpoint to get the latest known head.
-        if !initializing && info.head.is_none() {
-            self.update_checkpoint(&self.api.latest_checkpoint().await?, [&mut package])
-                .await?;
-
-            info.head = package.state.head().as_ref().map(|h| h.digest.clone());
-        }
-
-        match (initializing, info.head.is_some()) {
-            (true, true) => {
-                return Err(ClientError::CannotInitializePackage { name: package.name })
-            }
-            (false, false) => {
-                return Err(ClientError::MustInitializePackage { name: package.name })
+        let record = match info.record.take() {
+            Some(record) => record,
+            None => {
+                self.prepare_publish(&mut info).await?;
+                info.clone()
+                    .finalize(signer, self.hash_algorithm().await?)
+                    .await?
+                    .into()
             }
-            _ => (),
-        }
+        };
 
-        let record = info.finalize(signing_key)?;
-        let log_id = LogId::package_log::<Sha256>(&package.name);
+        let name = info.name;
+        let log_id = LogId::package_log_for(self.hash_algorithm().await?, &name);
         let record = self
             .api
             .publish_package_record(
                 &log_id,
                 PublishRecordRequest {
-                    package_name: Cow::Borrowed(&package.name),
-                    record: Cow::Owned(record.into()),
+                    package_name: Cow::Borrowed(&name),
+                    record: Cow::Owned(record),
                     content_sources: Default::default(),
                 },
             )
@@ -176,7 +188,7 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             .map_err(|e| {
                 ClientError::translate_log_not_found(e, |id| {
                     if id == &log_id {
-                        Some(package.name.clone())
+                        Some(name.clone())
                     } else {
                         None
                     }
@@ -210,7 +222,7 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                 .map_err(|e| match e {
                     api::ClientError::Package(PackageError::Rejection(reason)) => {
                         ClientError::PublishRejected {
-                            name: package.name.clone(),
+                            name: name.clone(),
                             record_id: record.record_id.clone(),
                             reason,
                         }
@@ -222,6 +234,81 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
         Ok(record.record_id)
     }
 
+    /// Signs the provided publish information so that it may be cosigned
+    /// by other keys before it is submitted.
+    ///
+    /// If the publish information has not been signed yet, its record is
+    /// finalized and signed by the given signer; otherwise, the given signer
+    /// cosigns the previously signed record.
+    ///
+    /// The entries of signed publish information must not be changed.
+    pub async fn sign_with_info(
+        &self,
+        signer: &dyn signing::Signer,
+        info: &mut PublishInfo,
+    ) -> ClientResult<()> {
+        let record = match info.record.take() {
+            Some(record) => {
+                let mut envelope: ProtoEnvelope<package::PackageRecord> = record.try_into()?;
+                envelope
+                    .cosign_with(signer)
+                    .await
+                    .map_err(anyhow::Error::from)?;
+                envelope.into()
+            }
+            None => {
+                self.prepare_publish(info).await?;
+                info.clone()
+                    .finalize(signer, self.hash_algorithm().await?)
+                    .await?
+                    .into()
+            }
+        };
+
+        info.record = Some(record);
+        Ok(())
+    }
+
+    /// Prepares publish information to be finalized by resolving the
+    /// head of the package log.
+    async fn prepare_publish(&self, info: &mut PublishInfo) -> ClientResult<()> {
+        if info.entries.is_empty() {
+            return Err(ClientError::NothingToPublish {
+                name: info.name.clone(),
+            });
+        }
+
+        let initializing = info.initializing();
+
+        tracing::info!(
+            "publishing {new}package `{name}`",
+            name = info.name,
+            new = if initializing { "new " } else { "" }
+        );
+        tracing::debug!("entries: {:?}", info.entries);
+
+        let mut package = self
+            .registry
+            .load_package(&info.name)
+            .await?
+            .unwrap_or_else(|| PackageInfo::new(info.name.clone()));
+
+        // If we're not initializing the package and a head was not explicitly specified,
+        // updated to the latest checkpoint to get the latest known head.
+        if !initializing && info.head.is_none() {
+            self.update_checkpoint(&self.latest_checkpoint().await?, [&mut package])
+                .await?;
+
+            info.head = package.state.head().as_ref().map(|h| h.digest.clone());
+        }
+
+        match (initializing, info.head.is_some()) {
+            (true, true) => Err(ClientError::CannotInitializePackage { name: package.name }),
+            (false, false) => Err(ClientError::MustInitializePackage { name: package.name }),
+            _ => Ok(()),
+        }
+    }
+
     /// Waits for a package record to transition to the `published` state.
     ///
     /// The `interval` is the amount of time to wait between checks.
@@ -233,7 +320,7 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
         record_id: &RecordId,
         interval: Duration,
     ) -> ClientResult<()> {
-        let log_id = LogId::package_log::<Sha256>(package);
+        let log_id = LogId::package_log_for(self.hash_algorithm().await?, package);
         let mut current = self.get_package_record(package, &log_id, record_id).await?;
 
         loop {
@@ -259,12 +346,89 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
         }
     }
 
+    /// Submits a record with the given entries to the operator log.
+    ///
+    /// The operator log is first updated to the latest checkpoint so that
+    /// the new record is appended to the latest known head.
+    ///
+    /// Returns the identifier of the record that was published.
+    ///
+    /// Use `wait_for_operator_publish` to wait for the record to transition to the `published` state.
+    pub async fn publish_operator_record(
+        &self,
+        signer: &dyn signing::Signer,
+        entries: Vec<operator::OperatorEntry>,
+    ) -> ClientResult<RecordId> {
+        if entries.is_empty() {
+            return Err(ClientError::NothingToPublishOperator);
+        }
+
+        tracing::info!("publishing operator record");
+        tracing::debug!("entries: {entries:?}");
+
+        self.update_checkpoint(&self.latest_checkpoint().await?, [])
+            .await?;
+
+        let operator = self
+            .registry
+            .load_operator()
+            .await?
+            .ok_or(ClientError::NoOperatorRecords)?;
+
+        let record = operator::OperatorRecord {
+            prev: operator.state.head().as_ref().map(|h| h.digest.clone()),
+            version: operator::OPERATOR_RECORD_VERSION,
+            timestamp: SystemTime::now(),
+            entries,
+        };
+
+        let record = ProtoEnvelope::signed_contents_with(signer, record)
+            .await
+            .map_err(|e| anyhow!(e))?;
+        let record = self
+            .api
+            .publish_operator_record(PublishOperatorRecordRequest {
+                record: Cow::Owned(record.into()),
+            })
+            .await?;
+
+        Ok(record.record_id)
+    }
+
+    /// Waits for an operator record to transition to the `published` state.
+    ///
+    /// The `interval` is the amount of time to wait between checks.
+    ///
+    /// Returns an error if the operator record was rejected.
+    pub async fn wait_for_operator_publish(
+        &self,
+        record_id: &RecordId,
+        interval: Duration,
+    ) -> ClientResult<()> {
+        loop {
+            match self.api.get_operator_record(record_id).await?.state {
+                OperatorRecordState::Published { .. } => {
+                    return Ok(());
+                }
+                OperatorRecordState::Rejected { reason } => {
+                    return Err(ClientError::OperatorPublishRejected {
+                        record_id: record_id.clone(),
+                        reason,
+                    });
+                }
+                OperatorRecordState::Processing => {
+                    tokio::time::sleep(interval).await;
+                }
+            }
+        }
+    }
+
     /// Updates every package log in client storage to the latest registry checkpoint.
     pub async fn update(&self) -> ClientResult<()> {
         tracing::info!("updating all packages to latest checkpoint");
 
         let mut updating = self.registry.load_packages().await?;
-        self.update_checkpoint(&self.api.latest_checkpoint().await?, &mut updating)
+        self.update_checkpoint(&self.latest_checkpoint().await?, &mut updating)
             .await?;
 
         Ok(())
@@ -290,7 +454,7 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             );
         }
 
-        self.update_checkpoint(&self.api.latest_checkpoint().await?, &mut updating)
+        self.update_checkpoint(&self.latest_checkpoint().await?, &mut updating)
             .await?;
 
         Ok(())
@@ -302,13 +466,15 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
     /// If the requested package log is not present in client storage, it
     /// will be fetched from the registry first.
     ///
-    /// An error is returned if the package does not exist.
+    /// An error is returned if the package does not exist or if every
+    /// version satisfying the requirement has been yanked.
     ///
     /// If a version satisfying the requirement does not exist, `None` is
     /// returned.
     ///
     /// Returns the path within client storage of the package contents for
-    /// the resolved version.
+    /// the resolved version; for multi-file releases, this is a directory
+    /// containing the file tree of the release.
     pub async fn download(
         &self,
         name: &PackageName,
@@ -323,14 +489,28 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                     .content()
                     .context("invalid state: not yanked but missing content")?
                     .clone();
-                let path = self.download_content(&digest).await?;
+                let path = self.download_release(name, release, &digest).await?;
                 Ok(Some(PackageDownload {
                     version: release.version.clone(),
                     digest,
                     path,
+                    dependencies: release.dependencies.clone(),
+                    deprecation: info.state.deprecation().cloned(),
                 }))
             }
-            None => Ok(None),
+            None => match info
+                .state
+                .releases()
+                .filter(|r| r.yanked() && requirement.matches(&r.version))
+                .max_by(|a, b| a.version.cmp(&b.version))
+            {
+                Some(release) => Err(ClientError::PackageVersionYanked {
+                    version: release.version.clone(),
+                    name: name.clone(),
+                    reason: release.yank_reason().cloned(),
+                }),
+                None => Ok(None),
+            },
         }
     }
 
@@ -339,10 +519,12 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
     /// If the requested package log is not present in client storage, it
     /// will be fetched from the registry first.
     ///
-    /// An error is returned if the package does not exist.
+    /// An error is returned if the package or version does not exist or if
+    /// the version has been yanked.
     ///
     /// Returns the path within client storage of the package contents for
-    /// the specified version.
+    /// the specified version; for multi-file releases, this is a directory
+    /// containing the file tree of the release.
     pub async fn download_exact(
         &self,
         package: &PackageName,
@@ -361,18 +543,157 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
 
         let digest = release
             .content()
-            .ok_or_else(|| ClientError::PackageVersionDoesNotExist {
+            .ok_or_else(|| ClientError::PackageVersionYanked {
                 version: version.clone(),
                 name: package.clone(),
+                reason: release.yank_reason().cloned(),
             })?;
 
         Ok(PackageDownload {
             version: version.clone(),
             digest: digest.clone(),
-            path: self.download_content(digest).await?,
+            path: self.download_release(package, release, digest).await?,
+            dependencies: release.dependencies.clone(),
+            deprecation: info.state.deprecation().cloned(),
         })
     }
 
+    /// Gets the dependencies declared by the specified version of a package.
+    ///
+    /// If the requested package log is not present in client storage, it
+    /// will be fetched from the registry first.
+    ///
+    /// Dependencies are read from the package log alone, so resolvers may
+    /// walk the dependency graph without downloading any content.
+    ///
+    /// An error is returned if the package or version does not exist.
+    pub async fn dependencies(
+        &self,
+        package: &PackageName,
+        version: &Version,
+    ) -> Result<Vec<package::ReleaseDependency>, ClientError> {
+        let info = self.fetch_package(package).await?;
+        info.state
+            .release(version)
+            .map(|release| release.dependencies.clone())
+            .ok_or_else(|| ClientError::PackageVersionDoesNotExist {
+                version: version.clone(),
+                name: package.clone(),
+            })
+    }
+
+    /// Downloads the content of a release into client storage.
+    ///
+    /// For multi-file releases, the content of every entry of the content
+    /// index is downloaded and the file tree of the release materialized.
+    async fn download_release(
+        &self,
+        name: &PackageName,
+        release: &package::Release,
+        digest: &AnyHash,
+    ) -> Result<PathBuf, ClientError> {
+        self.verify_not_blocked(name, digest).await?;
+
+        let index = match &release.index {
+            Some(index) => index,
+            None => return self.download_content(digest).await,
+        };
+
+        for entry in index.digests() {
+            self.verify_not_blocked(name, entry).await?;
+            self.download_content(entry).await?;
+        }
+
+        Ok(self.content.materialize_index(index).await?)
+    }
+
+    /// Verifies that neither the package nor the given content digest has
+    /// been blocked by the registry operator.
+    async fn verify_not_blocked(
+        &self,
+        name: &PackageName,
+        digest: &AnyHash,
+    ) -> Result<(), ClientError> {
+        let operator = self.registry.load_operator().await?.unwrap_or_default();
+
+        if let Some(block) = operator.state.package_block(name) {
+            return Err(ClientError::PackageBlocked {
+                name: name.clone(),
+                reason: block.reason.clone(),
+            });
+        }
+
+        if let Some(block) = operator.state.content_block(digest) {
+            return Err(ClientError::ContentBlocked {
+                name: name.clone(),
+                digest: digest.clone(),
+                reason: block.reason.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Gets the hash algorithm used by the registry.
+    ///
+    /// The hash algorithm is set by the init entry of the registry's operator
+    /// log; if the operator log has not been fetched yet, its first record is
+    /// fetched and validated to determine the algorithm.
+    async fn hash_algorithm(&self) -> ClientResult<HashAlgorithm> {
+        if let Some(algorithm) = self
+            .registry
+            .load_operator()
+            .await?
+            .and_then(|operator| operator.state.hash_algorithm())
+        {
+            return Ok(algorithm);
+        }
+
+        let checkpoint = self.api.latest_checkpoint().await?;
+        let response = self
+            .api
+            .fetch_logs(FetchLogsRequest {
+                log_length: checkpoint.as_ref().checkpoint.log_length,
+                operator: None,
+                limit: Some(1),
+                packages: Cow::Owned(HashMap::new()),
+            })
+            .await?;
+
+        let record = response
+            .operator
+            .into_iter()
+            .next()
+            .ok_or(ClientError::NoOperatorRecords)?;
+        let envelope: PublishedProtoEnvelope<operator::OperatorRecord> =
+            record.envelope.try_into()?;
+        let state = operator::LogState::default()
+            .validate(&envelope.envelope)
+            .map_err(|inner| ClientError::OperatorValidationFailed { inner })?;
+
+        state.hash_algorithm().ok_or(ClientError::NoOperatorRecords)
+    }
+
+    /// Gets the latest checkpoint from the registry.
+    ///
+    /// If witnesses are required, the checkpoint must be cosigned by enough
+    /// trusted witnesses.
+    async fn latest_checkpoint(&self) -> ClientResult<SerdeEnvelope<TimestampedCheckpoint>> {
+        let checkpoint = self.api.latest_cosigned_checkpoint().await?;
+        if self.witness_threshold > 0 {
+            let found = checkpoint.count_witnesses(&self.witness_keys);
+            if found < self.witness_threshold {
+                return Err(ClientError::InsufficientWitnesses {
+                    log_length: checkpoint.checkpoint.as_ref().checkpoint.log_length,
+                    needed: self.witness_threshold,
+                    found,
+                });
+            }
+        }
+
+        Ok(checkpoint.checkpoint)
+    }
+
     async fn update_checkpoint<'a>(
         &self,
         ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
@@ -384,6 +705,7 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             checkpoint.log_length
         );
 
+        let hash_algorithm = self.hash_algorithm().await?;
         let mut operator = self.registry.load_operator().await?.unwrap_or_default();
 
         // Map package names to package logs that need to be updated
@@ -392,12 +714,18 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             .filter_map(|p| match &p.checkpoint {
                 // Don't bother updating if the package is already at the specified checkpoint
                 Some(c) if c == checkpoint => None,
-                _ => Some((LogId::package_log::<Sha256>(&p.name), p)),
+                _ => Some((LogId::package_log_for(hash_algorithm, &p.name), p)),
             })
             .inspect(|(_, p)| tracing::info!("package `{name}` will be updated", name = p.name))
             .collect::<HashMap<_, _>>();
+
+        // Don't bother updating if there are no packages to update and the
+        // operator log is already at the specified checkpoint
         if packages.is_empty() {
-            return Ok(());
+            match self.registry.load_checkpoint().await? {
+                Some(c) if &c.as_ref().checkpoint == checkpoint => return Ok(()),
+                _ => {}
+            }
         }
 
         let mut last_known = packages
@@ -405,8 +733,25 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             .map(|(id, p)| (id.clone(), p.head_fetch_token.clone()))
             .collect::<HashMap<_, _>>();
 
+        // Remember the map leaf of each package log at its last known
+        // checkpoint so that its transition to this checkpoint can be proven
+        let previous = packages
+            .iter()
+            .filter_map(|(id, p)| {
+                let from = p.checkpoint.clone()?;
+                let leaf = p.state.head().as_ref().map(|head| MapLeaf {
+                    record_id: head.digest.clone(),
+                });
+                (from.log_length < checkpoint.log_length).then(|| (id.clone(), from, leaf))
+            })
+            .collect::<Vec<_>>();
+
+        // Package logs the registry claims do not exist; the claim is only
+        // checked once the checkpoint has been authenticated
+        let mut absent = Vec::new();
+
         loop {
-            let response: FetchLogsResponse = self
+            let response: FetchLogsResponse = match self
                 .api
                 .fetch_logs(FetchLogsRequest {
                     log_length: checkpoint.log_length,
@@ -418,12 +763,28 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                     packages: Cow::Borrowed(&last_known),
                 })
                 .await
-                .map_err(|e| {
-                    ClientError::translate_log_not_found(e, |id| {
+            {
+                Ok(response) => response,
+                Err(api::ClientError::Fetch(FetchError::LogNotFound(log_id)))
+                    if packages.contains_key(&log_id) =>
+                {
+                    // Fetch the remaining logs without the missing one
+                    let package = packages.remove(&log_id).unwrap();
+                    last_known.remove(&log_id);
+                    absent.push((log_id, package));
+                    continue;
+                }
+                Err(e) => {
+                    return Err(ClientError::translate_log_not_found(e, |id| {
                         packages.get(id).map(|p| p.name.clone())
-                    })
-                })?;
+                    }))
+                }
+            };
 
+            // validate the new records of each log together so that their
+            // signatures are verified as a batch
+            let mut envelopes = Vec::new();
+            let mut head = None;
             for record in response.operator {
                 let proto_envelope: PublishedProtoEnvelope<operator::OperatorRecord> =
                     record.envelope.try_into()?;
@@ -432,20 +793,26 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                 if operator.head_registry_index.is_none()
                     || proto_envelope.registry_index > operator.head_registry_index.unwrap()
                 {
-                    operator.state = operator
-                        .state
-                        .validate(&proto_envelope.envelope)
-                        .map_err(|inner| ClientError::OperatorValidationFailed { inner })?;
-                    operator.head_registry_index = Some(proto_envelope.registry_index);
-                    operator.head_fetch_token = Some(record.fetch_token);
+                    head = Some((proto_envelope.registry_index, record.fetch_token));
+                    envelopes.push((proto_envelope.registry_index, proto_envelope.envelope));
                 }
             }
 
+            if let Some((registry_index, fetch_token)) = head {
+                operator.state = std::mem::take(&mut operator.state)
+                    .validate_all_at(envelopes.iter().map(|(index, envelope)| (*index, envelope)))
+                    .map_err(|inner| ClientError::OperatorValidationFailed { inner })?;
+                operator.head_registry_index = Some(registry_index);
+                operator.head_fetch_token = Some(fetch_token);
+            }
+
             for (log_id, records) in response.packages {
                 let package = packages.get_mut(&log_id).ok_or_else(|| {
                     anyhow!("received records for unknown package log `{log_id}`")
                 })?;
 
+                let mut envelopes = Vec::new();
+                let mut head = None;
                 for record in records {
                     let proto_envelope: PublishedProtoEnvelope<package::PackageRecord> =
                         record.envelope.try_into()?;
@@ -454,19 +821,23 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                     if package.head_registry_index.is_none()
                         || proto_envelope.registry_index > package.head_registry_index.unwrap()
                     {
-                        let state = std::mem::take(&mut package.state);
-                        package.state =
-                            state.validate(&proto_envelope.envelope).map_err(|inner| {
-                                ClientError::PackageValidationFailed {
-                                    name: package.name.clone(),
-                                    inner,
-                                }
-                            })?;
-                        package.head_registry_index = Some(proto_envelope.registry_index);
-                        package.head_fetch_token = Some(record.fetch_token);
+                        head = Some((proto_envelope.registry_index, record.fetch_token));
+                        envelopes.push(proto_envelope.envelope);
                     }
                 }
 
+                if let Some((registry_index, fetch_token)) = head {
+                    let state = std::mem::take(&mut package.state);
+                    package.state = state.validate_all(&envelopes).map_err(|inner| {
+                        ClientError::PackageValidationFailed {
+                            name: package.name.clone(),
+                            inner,
+                        }
+                    })?;
+                    package.head_registry_index = Some(registry_index);
+                    package.head_fetch_token = Some(fetch_token);
+                }
+
                 // At this point, the package log should not be empty
                 if package.state.head().is_none() {
                     return Err(ClientError::PackageLogEmpty {
@@ -497,6 +868,18 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
         )
         .or(Err(ClientError::InvalidCheckpointSignature))?;
 
+        // verify the checkpoint key had permission to sign checkpoints of its log length;
+        // checkpoints signed by a retired operator key remain valid if they do not
+        // include the record that retired it
+        if !operator.state.key_had_permission_to_sign_checkpoint(
+            ts_checkpoint.key_id(),
+            ts_checkpoint.as_ref().checkpoint.log_length,
+        ) {
+            return Err(ClientError::UnauthorizedCheckpointKeyId {
+                key_id: ts_checkpoint.key_id().clone(),
+            });
+        }
+
         // Prove inclusion for the current log heads
         let mut leaf_indices = Vec::with_capacity(packages.len() + 1 /* for operator */);
         let mut leafs = Vec::with_capacity(leaf_indices.len());
@@ -505,7 +888,7 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
         if let Some(index) = operator.head_registry_index {
             leaf_indices.push(index);
             leafs.push(LogLeaf {
-                log_id: LogId::operator_log::<Sha256>(),
+                log_id: LogId::operator_log_for(hash_algorithm),
                 record_id: operator.state.head().as_ref().unwrap().digest.clone(),
             });
         } else {
@@ -540,6 +923,108 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
                 .await?;
         }
 
+        if let Some((_, package)) = absent.first() {
+            // The absence can only be trusted for a checkpoint
+            // consistent with the last one the client verified
+            self.verify_checkpoint_consistency(ts_checkpoint).await?;
+
+            // A package log included in a previous checkpoint
+            // cannot be absent from a later one
+            if let Some((_, package)) = absent
+                .iter()
+                .find(|(_, p)| p.checkpoint.is_some() || p.head_registry_index.is_some())
+            {
+                return Err(ClientError::PackageLogRemoved {
+                    name: package.name.clone(),
+                });
+            }
+
+            // Don't take the registry's word that the package does
+            // not exist; have it prove the package log's absence
+            // from the authenticated checkpoint
+            self.api
+                .prove_absence(
+                    AbsenceRequest {
+                        log_length: checkpoint.log_length,
+                        log_ids: absent.iter().map(|(id, _)| id.clone()).collect(),
+                    },
+                    checkpoint,
+                )
+                .await?;
+            return Err(ClientError::PackageDoesNotExist {
+                name: package.name.clone(),
+            });
+        }
+
+        // Prove the map transition of the package logs from their last known
+        // checkpoints, grouping the package logs by checkpoint
+        let mut transitions = BTreeMap::new();
+        for (log_id, from, leaf) in previous {
+            let head = MapLeaf {
+                record_id: packages[&log_id]
+                    .state
+                    .head()
+                    .as_ref()
+                    .unwrap()
+                    .digest
+                    .clone(),
+            };
+            transitions
+                .entry(from.log_length)
+                .or_insert_with(|| (from, Vec::new()))
+                .1
+                .push((log_id, leaf, Some(head)));
+        }
+
+        for (from, transitions) in transitions.into_values() {
+            let request = TransitionRequest {
+                from: from.log_length,
+                to: checkpoint.log_length,
+                log_ids: transitions.iter().map(|(id, ..)| id.clone()).collect(),
+            };
+            match self
+                .api
+                .prove_transition(request, &from, checkpoint, &transitions)
+                .await
+            {
+                Ok(()) => {}
+                // The registry may only retain the maps of recent checkpoints
+                Err(api::ClientError::Proof(ProofError::CheckpointNotFound(log_length)))
+                    if log_length == from.log_length =>
+                {
+                    if !self.allow_unproven_map_transitions {
+                        return Err(ClientError::MapTransitionUnavailable { log_length });
+                    }
+
+                    tracing::warn!(
+                        "registry no longer has the map of checkpoint log length `{log_length}`; \
+                         skipping map transition proof"
+                    );
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        self.verify_checkpoint_consistency(ts_checkpoint).await?;
+
+        self.registry.store_operator(operator).await?;
+
+        for package in packages.values_mut() {
+            package.checkpoint = Some(checkpoint.clone());
+            self.registry.store_package(package).await?;
+        }
+
+        self.registry.store_checkpoint(ts_checkpoint).await?;
+
+        Ok(())
+    }
+
+    // Verifies that the given checkpoint is consistent with the last
+    // checkpoint stored by the client, if any
+    async fn verify_checkpoint_consistency(
+        &self,
+        ts_checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<(), ClientError> {
         if let Some(from) = self.registry.load_checkpoint().await? {
             let from_log_length = from.as_ref().checkpoint.log_length;
             let to_log_length = ts_checkpoint.as_ref().checkpoint.log_length;
@@ -577,15 +1062,6 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             }
         }
 
-        self.registry.store_operator(operator).await?;
-
-        for package in packages.values_mut() {
-            package.checkpoint = Some(checkpoint.clone());
-            self.registry.store_package(package).await?;
-        }
-
-        self.registry.store_checkpoint(ts_checkpoint).await?;
-
         Ok(())
     }
 
@@ -597,7 +1073,7 @@ impl<R: RegistryStorage, C: ContentStorage> Client<R, C> {
             }
             None => {
                 let mut info = PackageInfo::new(name.clone());
-                self.update_checkpoint(&self.api.latest_checkpoint().await?, [&mut info])
+                self.update_checkpoint(&self.latest_checkpoint().await?, [&mut info])
                     .await?;
 
                 Ok(info)
@@ -695,11 +1171,11 @@ impl FileSystemClient {
             (_, None) => return Ok(StorageLockResult::NotAcquired(content_dir)),
         };
 
-        Ok(StorageLockResult::Acquired(Self::new(
-            url.into_url(),
-            packages,
-            content,
-        )?))
+        Ok(StorageLockResult::Acquired(
+            Self::new(url.into_url(), packages, content)?
+                .with_witnesses(config.witness_keys.clone(), config.witness_threshold)
+                .with_unproven_map_transitions(config.allow_unproven_map_transitions),
+        ))
     }
 
     /// Creates a client for the given registry URL.
@@ -714,11 +1190,13 @@ impl FileSystemClient {
             registries_dir,
             content_dir,
         } = config.storage_paths_for_url(url)?;
-        Self::new(
+        Ok(Self::new(
             registry_url.into_url(),
             FileSystemRegistryStorage::lock(registries_dir)?,
             FileSystemContentStorage::lock(content_dir)?,
-        )
+        )?
+        .with_witnesses(config.witness_keys.clone(), config.witness_threshold)
+        .with_unproven_map_transitions(config.allow_unproven_map_transitions))
     }
 }
 
@@ -730,7 +1208,14 @@ pub struct PackageDownload {
     /// The digest of the package contents.
     pub digest: AnyHash,
     /// The path to the downloaded package contents.
+    ///
+    /// For multi-file releases, this is a directory containing the file
+    /// tree of the release.
     pub path: PathBuf,
+    /// The registry packages the downloaded version was built against.
+    pub dependencies: Vec<package::ReleaseDependency>,
+    /// The deprecation of the package, if it is deprecated.
+    pub deprecation: Option<package::Deprecation>,
 }
 
 /// Represents an error returned by Warg registry clients.
@@ -759,6 +1244,24 @@ pub enum ClientError {
         key_id: signing::KeyID,
     },
 
+    /// Checkpoint was signed by a key without permission to sign checkpoints
+    #[error("checkpoint key ID `{key_id}` does not have permission to sign checkpoints")]
+    UnauthorizedCheckpointKeyId {
+        /// The signature key ID.
+        key_id: signing::KeyID,
+    },
+
+    /// The checkpoint was not cosigned by enough trusted witnesses.
+    #[error("checkpoint log length `{log_length}` was cosigned by {found} trusted witness(es) but {needed} are required")]
+    InsufficientWitnesses {
+        /// The log length of the checkpoint.
+        log_length: RegistryLen,
+        /// The number of cosignatures required.
+        needed: usize,
+        /// The number of valid cosignatures by trusted witnesses.
+        found: usize,
+    },
+
     /// The server did not provide operator records.
     #[error("the server did not provide any operator records")]
     NoOperatorRecords,
@@ -784,6 +1287,19 @@ pub enum ClientError {
         name: PackageName,
     },
 
+    /// There are no entries to publish to the operator log.
+    #[error("the operator record has no entries to publish")]
+    NothingToPublishOperator,
+
+    /// An operator record publish was rejected.
+    #[error("the publishing of operator record `{record_id}` was rejected due to: {reason}")]
+    OperatorPublishRejected {
+        /// The record identifier for the record that was rejected.
+        record_id: RecordId,
+        /// The reason it was rejected.
+        reason: String,
+    },
+
     /// There is no publish operation in progress.
     #[error("there is no publish operation in progress")]
     NotPublishing,
@@ -811,6 +1327,40 @@ pub enum ClientError {
         name: PackageName,
     },
 
+    /// The package has been blocked by the registry operator.
+    #[error("package `{name}` has been blocked by the registry operator: {reason}")]
+    PackageBlocked {
+        /// The blocked package.
+        name: PackageName,
+        /// The reason the package was blocked.
+        reason: String,
+    },
+
+    /// The content of a package release has been blocked by the registry operator.
+    #[error("content with digest `{digest}` of package `{name}` has been blocked by the registry operator: {reason}")]
+    ContentBlocked {
+        /// The package with the blocked content.
+        name: PackageName,
+        /// The blocked content digest.
+        digest: AnyHash,
+        /// The reason the content was blocked.
+        reason: String,
+    },
+
+    /// The package version has been yanked.
+    #[error(
+        "version `{version}` of package `{name}` has been yanked{}",
+        reason.as_ref().map(|r| format!(" (reason: {r})")).unwrap_or_default()
+    )]
+    PackageVersionYanked {
+        /// The yanked version of the package.
+        version: Version,
+        /// The package with the yanked version.
+        name: PackageName,
+        /// The reason the version was yanked, if one was given.
+        reason: Option<package::YankReason>,
+    },
+
     /// The package failed validation.
     #[error("package `{name}` failed validation: {inner}")]
     PackageValidationFailed {
@@ -859,6 +1409,22 @@ pub enum ClientError {
         to: RegistryLen,
     },
 
+    /// The registry no longer has the map of a package's last known
+    /// checkpoint, so the transition of the package log cannot be proven.
+    #[error("registry cannot prove the map transition from checkpoint log length `{log_length}` as it no longer has its map")]
+    MapTransitionUnavailable {
+        /// The log length of the checkpoint.
+        log_length: RegistryLen,
+    },
+
+    /// The registry claimed that a package log included in a previous
+    /// checkpoint does not exist.
+    #[error("registry claimed package `{name}` does not exist although it was included in a previous checkpoint")]
+    PackageLogRemoved {
+        /// The package whose log was removed.
+        name: PackageName,
+    },
+
     /// The registry provided a checkpoint with a different `log_root` and
     /// `map_root` than a previously provided checkpoint.
     #[error("registry provided a new checkpoint with the same log length `{log_length}` as previously fetched but different log root or map root")]
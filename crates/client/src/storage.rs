@@ -11,10 +11,14 @@ use warg_crypto::{
     signing::{self, KeyID, PublicKey},
 };
 use warg_protocol::{
+    content::ContentIndex,
     operator,
-    package::{self, PackageRecord, Permission, PACKAGE_RECORD_VERSION},
+    package::{
+        self, PackageMetadata, PackageRecord, Permission, ReleaseDependency, YankReason,
+        PACKAGE_RECORD_VERSION,
+    },
     registry::{Checkpoint, PackageName, RecordId, RegistryIndex, TimestampedCheckpoint},
-    ProtoEnvelope, SerdeEnvelope, Version,
+    ProtoEnvelope, ProtoEnvelopeBody, SerdeEnvelope, Version, VersionReq,
 };
 
 mod fs;
@@ -106,6 +110,13 @@ pub trait ContentStorage: Send + Sync {
         stream: Pin<Box<dyn Stream<Item = Result<Bytes>> + Send + Sync>>,
         expected_digest: Option<&AnyHash>,
     ) -> Result<AnyHash>;
+
+    /// Materializes the file tree described by the given content index.
+    ///
+    /// The content of every entry in the index must already be stored.
+    ///
+    /// Returns the path of the directory containing the file tree.
+    async fn materialize_index(&self, index: &ContentIndex) -> Result<PathBuf>;
 }
 
 /// Represents information about a registry operator.
@@ -124,24 +135,25 @@ pub struct OperatorInfo {
 }
 
 /// Represents information about a registry package.
+///
+/// Package state that fails to deserialize, such as state stored by an
+/// older client, is discarded so that the package log is fetched and
+/// validated again.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "camelCase")]
+#[serde(rename_all = "camelCase", from = "StoredPackageInfo")]
 pub struct PackageInfo {
     /// The package name to publish.
-    /// TODO: drop alias after sufficient time according to release policy.
-    #[serde(alias = "id")]
     pub name: PackageName,
     /// The last known checkpoint of the package.
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub checkpoint: Option<Checkpoint>,
     /// The current package log state
-    #[serde(default)]
     pub state: package::LogState,
     /// The registry log index of the most recent record
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub head_registry_index: Option<RegistryIndex>,
     /// The fetch token for the most recent record
-    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub head_fetch_token: Option<String>,
 }
 
@@ -158,6 +170,39 @@ impl PackageInfo {
     }
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredPackageInfo {
+    // TODO: drop alias after sufficient time according to release policy.
+    #[serde(alias = "id")]
+    name: PackageName,
+    #[serde(default)]
+    checkpoint: Option<Checkpoint>,
+    #[serde(default)]
+    state: Option<serde_json::Value>,
+    #[serde(default)]
+    head_registry_index: Option<RegistryIndex>,
+    #[serde(default)]
+    head_fetch_token: Option<String>,
+}
+
+impl From<StoredPackageInfo> for PackageInfo {
+    fn from(info: StoredPackageInfo) -> Self {
+        let state = match info.state.map(serde_json::from_value).transpose() {
+            Ok(state) => state.unwrap_or_default(),
+            Err(_) => return Self::new(info.name),
+        };
+
+        Self {
+            name: info.name,
+            checkpoint: info.checkpoint,
+            state,
+            head_registry_index: info.head_registry_index,
+            head_fetch_token: info.head_fetch_token,
+        }
+    }
+}
+
 /// Represents a record entry being published.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "camelCase")]
@@ -170,11 +215,25 @@ pub enum PublishEntry {
         version: Version,
         /// The content digest of the release.
         content: AnyHash,
+        /// The content index of the release, if it is a multi-file release.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        index: Option<ContentIndex>,
+        /// The registry packages the release was built against.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        dependencies: Vec<ReleaseDependency>,
     },
     /// A release is being yanked.
     Yank {
         /// The version of the release being yanked.
         version: Version,
+        /// The reason the release is being yanked.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<YankReason>,
+    },
+    /// A yanked release is being unyanked.
+    Unyank {
+        /// The version of the release being unyanked.
+        version: Version,
     },
     /// A key is being granted permission(s).
     Grant {
@@ -182,6 +241,12 @@ pub enum PublishEntry {
         key: PublicKey,
         /// The permission(s) being granted.
         permissions: Vec<Permission>,
+        /// The time after which the granted permission(s) expire.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expires: Option<SystemTime>,
+        /// The versions the granted permission(s) are limited to.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        versions: Option<VersionReq>,
     },
     /// A key's permission(s) are being revoked.
     Revoke {
@@ -190,6 +255,39 @@ pub enum PublishEntry {
         /// The permission(s) being revoked.
         permissions: Vec<Permission>,
     },
+    /// A key's permissions are being moved to a new key.
+    RotateKey {
+        /// The key ID being rotated.
+        old_key_id: KeyID,
+        /// The public key receiving the old key's permissions.
+        new_key: PublicKey,
+    },
+    /// The package is being deprecated.
+    Deprecate {
+        /// The package that supersedes the deprecated package.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        successor: Option<PackageName>,
+        /// The deprecation message.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+    /// The deprecation of the package is being removed.
+    Undeprecate,
+    /// The release threshold of the package is being set.
+    ReleaseThreshold {
+        /// The number of keys with the release permission that must sign releases.
+        threshold: u32,
+    },
+    /// The metadata of the package or a release is being set.
+    Metadata {
+        /// The version of the release the metadata applies to.
+        ///
+        /// If `None`, the metadata applies to the package.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        version: Option<Version>,
+        /// The metadata being set.
+        metadata: PackageMetadata,
+    },
 }
 
 /// Represents information about a package publish.
@@ -207,36 +305,69 @@ pub struct PublishInfo {
     pub head: Option<RecordId>,
     /// The new record entries to publish.
     pub entries: Vec<PublishEntry>,
+    /// The signed record of the entries, if the publish has been signed
+    /// so that it may be cosigned by other keys.
+    ///
+    /// The entries of a signed publish cannot be changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record: Option<ProtoEnvelopeBody>,
 }
 
 impl PublishInfo {
+    /// Determines if the publish information has been signed.
+    pub fn signed(&self) -> bool {
+        self.record.is_some()
+    }
+
     /// Determines if the publish information is initializing the package.
     pub fn initializing(&self) -> bool {
         self.entries.iter().any(|e| matches!(e, PublishEntry::Init))
     }
 
-    pub(crate) fn finalize(
+    pub(crate) async fn finalize(
         self,
-        signing_key: &signing::PrivateKey,
+        signer: &dyn signing::Signer,
+        hash_algorithm: HashAlgorithm,
     ) -> Result<ProtoEnvelope<PackageRecord>> {
         let mut entries = Vec::with_capacity(self.entries.len());
         for entry in self.entries {
             match entry {
                 PublishEntry::Init => {
                     entries.push(package::PackageEntry::Init {
-                        hash_algorithm: HashAlgorithm::Sha256,
-                        key: signing_key.public_key(),
+                        hash_algorithm,
+                        key: signer.public_key(),
                     });
                 }
-                PublishEntry::Release { version, content } => {
-                    entries.push(package::PackageEntry::Release { version, content });
+                PublishEntry::Release {
+                    version,
+                    content,
+                    index,
+                    dependencies,
+                } => {
+                    entries.push(package::PackageEntry::Release {
+                        version,
+                        content,
+                        index,
+                        dependencies,
+                    });
                 }
-                PublishEntry::Yank { version } => {
-                    entries.push(package::PackageEntry::Yank { version })
+                PublishEntry::Yank { version, reason } => {
+                    entries.push(package::PackageEntry::Yank { version, reason })
                 }
-                PublishEntry::Grant { key, permissions } => {
-                    entries.push(package::PackageEntry::GrantFlat { key, permissions })
+                PublishEntry::Unyank { version } => {
+                    entries.push(package::PackageEntry::Unyank { version })
                 }
+                PublishEntry::Grant {
+                    key,
+                    permissions,
+                    expires,
+                    versions,
+                } => entries.push(package::PackageEntry::GrantFlat {
+                    key,
+                    permissions,
+                    expires,
+                    versions,
+                }),
                 PublishEntry::Revoke {
                     key_id,
                     permissions,
@@ -244,6 +375,23 @@ impl PublishInfo {
                     key_id,
                     permissions,
                 }),
+                PublishEntry::RotateKey {
+                    old_key_id,
+                    new_key,
+                } => entries.push(package::PackageEntry::RotateKey {
+                    old_key_id,
+                    new_key,
+                }),
+                PublishEntry::Deprecate { successor, message } => {
+                    entries.push(package::PackageEntry::Deprecate { successor, message })
+                }
+                PublishEntry::Undeprecate => entries.push(package::PackageEntry::Undeprecate),
+                PublishEntry::ReleaseThreshold { threshold } => {
+                    entries.push(package::PackageEntry::ReleaseThreshold { threshold })
+                }
+                PublishEntry::Metadata { version, metadata } => {
+                    entries.push(package::PackageEntry::Metadata { version, metadata })
+                }
             }
         }
 
@@ -257,6 +405,6 @@ impl PublishInfo {
             entries,
         };
 
-        Ok(ProtoEnvelope::signed_contents(signing_key, record)?)
+        Ok(ProtoEnvelope::signed_contents_with(signer, record).await?)
     }
 }
@@ -13,12 +13,13 @@ use std::{
     path::{Path, PathBuf},
     pin::Pin,
 };
-use tempfile::NamedTempFile;
+use tempfile::{NamedTempFile, TempDir};
 use tokio::io::{AsyncWriteExt, BufReader, BufWriter};
 use tokio_util::io::ReaderStream;
 use walkdir::WalkDir;
-use warg_crypto::hash::{AnyHash, Digest, Hash, Sha256};
+use warg_crypto::hash::{AnyHash, HashAlgorithm, Sha256};
 use warg_protocol::{
+    content::ContentIndex,
     registry::{LogId, PackageName, TimestampedCheckpoint},
     SerdeEnvelope,
 };
@@ -71,6 +72,9 @@ impl FileSystemRegistryStorage {
     }
 
     fn package_path(&self, name: &PackageName) -> PathBuf {
+        // The hash only names the package's state file and is never compared
+        // to the registry's log IDs, so it stays SHA-256 regardless of the
+        // registry's hash algorithm to keep existing storage readable
         self.base_dir.join(PACKAGE_LOGS_DIR).join(
             LogId::package_log::<Sha256>(name)
                 .to_string()
@@ -232,6 +236,22 @@ impl FileSystemContentStorage {
         })
     }
 
+    fn temp_dir(&self) -> Result<TempDir> {
+        fs::create_dir_all(&self.temp_dir).with_context(|| {
+            format!(
+                "failed to create directory `{path}`",
+                path = self.temp_dir.display()
+            )
+        })?;
+
+        TempDir::new_in(&self.temp_dir).with_context(|| {
+            format!(
+                "failed to create temporary directory in `{path}`",
+                path = self.temp_dir.display()
+            )
+        })
+    }
+
     fn content_path(&self, digest: &AnyHash) -> PathBuf {
         self.base_dir.join(digest.to_string().replace(':', "/"))
     }
@@ -278,7 +298,11 @@ impl ContentStorage for FileSystemContentStorage {
     ) -> Result<AnyHash> {
         let (file, path) = self.temp_file()?.into_parts();
         let mut writer = BufWriter::new(tokio::fs::File::from_std(file));
-        let mut hasher = Sha256::new();
+        // Content is hashed with the algorithm of the expected digest, if any
+        let mut hasher = expected_digest
+            .map(AnyHash::algorithm)
+            .unwrap_or(HashAlgorithm::Sha256)
+            .hasher();
 
         while let Some(bytes) = stream.next().await.transpose()? {
             hasher.update(&bytes);
@@ -288,7 +312,7 @@ impl ContentStorage for FileSystemContentStorage {
                 .with_context(|| format!("failed to write to `{path}`", path = path.display()))?;
         }
 
-        let hash = AnyHash::from(Hash::<Sha256>::from(hasher.finalize()));
+        let hash = hasher.finalize();
 
         if let Some(expected) = expected_digest {
             if hash != *expected {
@@ -326,6 +350,61 @@ impl ContentStorage for FileSystemContentStorage {
 
         Ok(hash)
     }
+
+    async fn materialize_index(&self, index: &ContentIndex) -> Result<PathBuf> {
+        let index_path = self.content_path(&index.digest());
+        if index_path.is_dir() {
+            return Ok(index_path);
+        }
+
+        let dir = self.temp_dir()?;
+        for (path, digest) in index.entries() {
+            let source = self.content_path(digest);
+            if !source.is_file() {
+                bail!("content for entry `{path}` with digest `{digest}` was not found");
+            }
+
+            let target = path
+                .segments()
+                .fold(dir.path().to_path_buf(), |target, segment| {
+                    target.join(segment)
+                });
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).with_context(|| {
+                    format!(
+                        "failed to create directory `{path}`",
+                        path = parent.display()
+                    )
+                })?;
+            }
+
+            tokio::fs::copy(&source, &target).await.with_context(|| {
+                format!(
+                    "failed to copy `{source}` to `{target}`",
+                    source = source.display(),
+                    target = target.display()
+                )
+            })?;
+        }
+
+        if let Some(parent) = index_path.parent() {
+            fs::create_dir_all(parent).with_context(|| {
+                format!(
+                    "failed to create directory `{path}`",
+                    path = parent.display()
+                )
+            })?;
+        }
+
+        fs::rename(dir.path(), &index_path).with_context(|| {
+            format!(
+                "failed to persist temporary directory to `{path}`",
+                path = index_path.display()
+            )
+        })?;
+
+        Ok(index_path)
+    }
 }
 
 async fn remove(path: &Path) -> Result<()> {
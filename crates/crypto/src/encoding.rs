@@ -48,4 +48,16 @@ pub trait Signable: Encode {
         let prefixed_content = [Self::PREFIX, b":", msg].concat();
         public_key.verify(&prefixed_content, signature)
     }
+
+    /// Adds the verification of a signature to the given batch, returning
+    /// its index in the batch.
+    fn verify_batched(
+        batch: &mut signing::BatchVerifier,
+        public_key: &signing::PublicKey,
+        msg: &[u8],
+        signature: &signing::Signature,
+    ) -> usize {
+        let prefixed_content = [Self::PREFIX, b":", msg].concat();
+        batch.push(public_key.clone(), prefixed_content, signature.clone())
+    }
 }
@@ -1,4 +1,5 @@
-use super::{Digest, HashAlgorithm, Sha256};
+use super::{Blake3, Digest, HashAlgorithm, Sha256, Sha512};
+use crate::{ByteVisitor, VisitBytes};
 use anyhow::Error;
 use serde::{Deserialize, Serialize};
 use std::{fmt, ops::Deref, str::FromStr};
@@ -6,56 +7,72 @@ use thiserror::Error;
 
 pub enum Hasher {
     Sha256(Sha256),
+    Sha512(Sha512),
+    Blake3(Box<Blake3>),
 }
 
 impl Hasher {
     pub fn update(&mut self, bytes: &[u8]) {
         match self {
             Self::Sha256(d) => d.update(bytes),
+            Self::Sha512(d) => d.update(bytes),
+            Self::Blake3(d) => Digest::update(d.as_mut(), bytes),
         }
     }
 
     pub fn finalize(self) -> AnyHash {
         let (algo, bytes) = match self {
             Self::Sha256(d) => (HashAlgorithm::Sha256, d.finalize().deref().into()),
+            Self::Sha512(d) => (HashAlgorithm::Sha512, d.finalize().deref().into()),
+            Self::Blake3(d) => (HashAlgorithm::Blake3, Digest::finalize(*d).deref().into()),
         };
 
         AnyHash { algo, bytes }
     }
 }
 
+impl ByteVisitor for Hasher {
+    fn visit_bytes(&mut self, bytes: impl AsRef<[u8]>) {
+        self.update(bytes.as_ref())
+    }
+}
+
 impl HashAlgorithm {
     pub fn hasher(&self) -> Hasher {
         match self {
             HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Sha512 => Hasher::Sha512(Sha512::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3(Box::new(Blake3::new())),
         }
     }
 
     pub fn digest(&self, content_bytes: &[u8]) -> AnyHash {
-        let hash_bytes: Vec<u8> = match self {
-            HashAlgorithm::Sha256 => {
-                let mut d = Sha256::new();
-                d.update(content_bytes);
-                d.finalize().deref().into()
-            }
-        };
-
-        AnyHash {
-            algo: *self,
-            bytes: hash_bytes,
-        }
+        let mut hasher = self.hasher();
+        hasher.update(content_bytes);
+        hasher.finalize()
     }
 }
 
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct AnyHash {
     pub(crate) algo: HashAlgorithm,
-    pub(crate) bytes: Vec<u8>,
+    // Stored as a boxed slice to keep hashes (and the errors that carry
+    // them) small.
+    pub(crate) bytes: Box<[u8]>,
 }
 
 impl AnyHash {
     pub fn new(algo: HashAlgorithm, bytes: Vec<u8>) -> AnyHash {
-        AnyHash { algo, bytes }
+        AnyHash {
+            algo,
+            bytes: bytes.into(),
+        }
+    }
+
+    pub fn of(algo: HashAlgorithm, content: impl VisitBytes) -> AnyHash {
+        let mut hasher = algo.hasher();
+        content.visit(&mut hasher);
+        hasher.finalize()
     }
 
     pub fn algorithm(&self) -> HashAlgorithm {
@@ -69,13 +86,13 @@ impl AnyHash {
 
 impl fmt::Display for AnyHash {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "{}:{}", self.algo, hex::encode(self.bytes.as_slice()))
+        write!(f, "{}:{}", self.algo, hex::encode(&self.bytes))
     }
 }
 
 impl fmt::Debug for AnyHash {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "{}:{}", self.algo, hex::encode(self.bytes.as_slice()))
+        write!(f, "{}:{}", self.algo, hex::encode(&self.bytes))
     }
 }
 
@@ -92,7 +109,7 @@ impl FromStr for AnyHash {
         }
 
         let algo = algo_part.parse::<HashAlgorithm>()?;
-        let bytes = hex::decode(bytes_part)?;
+        let bytes = hex::decode(bytes_part)?.into();
 
         Ok(AnyHash { algo, bytes })
     }
@@ -143,6 +160,45 @@ mod tests {
         assert_eq!(output, expected)
     }
 
+    #[test]
+    fn test_sha512_labeled_digest() {
+        let output = HashAlgorithm::Sha512.digest(b"abc");
+        let output = format!("{}", output);
+
+        let expected = "sha512:ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f";
+
+        assert_eq!(output, expected)
+    }
+
+    #[test]
+    fn test_blake3_labeled_digest() {
+        let output = HashAlgorithm::Blake3.digest(b"");
+        let output = format!("{}", output);
+
+        let expected = "blake3:af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262";
+
+        assert_eq!(output, expected)
+    }
+
+    #[test]
+    fn test_any_hash_of_matches_static_hash() {
+        use crate::hash::{Blake3, Hash, Sha256, Sha512};
+
+        let content = (b"prefix".as_slice(), "content");
+        assert_eq!(
+            AnyHash::of(HashAlgorithm::Sha256, content),
+            Hash::<Sha256>::of(content).into()
+        );
+        assert_eq!(
+            AnyHash::of(HashAlgorithm::Sha512, content),
+            Hash::<Sha512>::of(content).into()
+        );
+        assert_eq!(
+            AnyHash::of(HashAlgorithm::Blake3, content),
+            Hash::<Blake3>::of(content).into()
+        );
+    }
+
     #[test]
     fn test_labeled_digest_parse_rejects_uppercase() {
         let digest_str = "sha256:7d38b5cd25a2baf85ad3bb5b9311383e671a8a142eb302b324d4a5fba8748c69";
@@ -155,8 +211,13 @@ mod tests {
 
     #[test]
     fn test_labeled_digest_roundtrip() {
-        let input = "sha256:7d38b5cd25a2baf85ad3bb5b9311383e671a8a142eb302b324d4a5fba8748c69";
-        let output = format!("{}", input.parse::<AnyHash>().unwrap());
-        assert_eq!(input, &output);
+        for input in [
+            "sha256:7d38b5cd25a2baf85ad3bb5b9311383e671a8a142eb302b324d4a5fba8748c69",
+            "sha512:ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+            "blake3:af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+        ] {
+            let output = format!("{}", input.parse::<AnyHash>().unwrap());
+            assert_eq!(input, &output);
+        }
     }
 }
@@ -7,10 +7,11 @@ use thiserror::Error;
 mod dynamic;
 mod r#static;
 
+pub use blake3::Hasher as Blake3;
 pub use digest::{Digest, Output};
 pub use dynamic::{AnyHash, AnyHashError};
 pub use r#static::Hash;
-pub use sha2::Sha256;
+pub use sha2::{Sha256, Sha512};
 
 use crate::VisitBytes;
 
@@ -21,12 +22,16 @@ use self::r#static::IncorrectLengthError;
 #[non_exhaustive]
 pub enum HashAlgorithm {
     Sha256,
+    Sha512,
+    Blake3,
 }
 
 impl fmt::Display for HashAlgorithm {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             HashAlgorithm::Sha256 => write!(f, "sha256"),
+            HashAlgorithm::Sha512 => write!(f, "sha512"),
+            HashAlgorithm::Blake3 => write!(f, "blake3"),
         }
     }
 }
@@ -37,26 +42,30 @@ impl FromStr for HashAlgorithm {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "sha256" => Ok(HashAlgorithm::Sha256),
+            "sha512" => Ok(HashAlgorithm::Sha512),
+            "blake3" => Ok(HashAlgorithm::Blake3),
             _ => Err(Error::msg(format!("Illegal hash algorithm '{}'", s))),
         }
     }
 }
 
-static EMPTY_TREE_HASH: Lazy<Vec<Hash<Sha256>>> = Lazy::new(|| {
-    let mut v: Vec<Hash<Sha256>> = Vec::with_capacity(257);
-    fn empty_tree_hash<D: SupportedDigest>(v: &mut Vec<Hash<D>>, height: u32) -> Hash<D> {
-        let hash: Hash<D> = if height == 0 {
-            hash_empty()
-        } else {
-            let last_hash = empty_tree_hash(v, height - 1);
-            hash_branch(&last_hash, &last_hash)
-        };
-        v.push(hash.clone());
-        hash
+// Computes the empty tree hashes for every height of a sparse Merkle tree
+// keyed by digests of `D` (i.e. heights `0..=D::output_size() * 8`).
+fn empty_tree_hashes<D: SupportedDigest>() -> Vec<Hash<D>> {
+    let height = <D as Digest>::output_size() * 8;
+    let mut v: Vec<Hash<D>> = Vec::with_capacity(height + 1);
+    v.push(hash_empty());
+    for _ in 0..height {
+        let last_hash = v.last().unwrap();
+        let hash = hash_branch(last_hash, last_hash);
+        v.push(hash);
     }
-    empty_tree_hash(&mut v, 256);
     v
-});
+}
+
+static SHA256_EMPTY_TREE_HASH: Lazy<Vec<Hash<Sha256>>> = Lazy::new(empty_tree_hashes);
+static SHA512_EMPTY_TREE_HASH: Lazy<Vec<Hash<Sha512>>> = Lazy::new(empty_tree_hashes);
+static BLAKE3_EMPTY_TREE_HASH: Lazy<Vec<Hash<Blake3>>> = Lazy::new(empty_tree_hashes);
 
 // If updating this function, also update `hash_empty` in transparency map
 pub(crate) fn hash_empty<D: SupportedDigest>() -> Hash<D> {
@@ -88,15 +97,32 @@ pub trait SupportedDigest: Digest + private::Sealed + Default + Sized + 'static
 impl SupportedDigest for Sha256 {
     const ALGORITHM: HashAlgorithm = HashAlgorithm::Sha256;
     fn empty_tree_hash(height: usize) -> &'static Hash<Sha256> {
-        &EMPTY_TREE_HASH[height]
+        &SHA256_EMPTY_TREE_HASH[height]
+    }
+}
+
+impl SupportedDigest for Sha512 {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Sha512;
+    fn empty_tree_hash(height: usize) -> &'static Hash<Sha512> {
+        &SHA512_EMPTY_TREE_HASH[height]
+    }
+}
+
+impl SupportedDigest for Blake3 {
+    const ALGORITHM: HashAlgorithm = HashAlgorithm::Blake3;
+    fn empty_tree_hash(height: usize) -> &'static Hash<Blake3> {
+        &BLAKE3_EMPTY_TREE_HASH[height]
     }
 }
 
 mod private {
-    use sha2::Sha256;
+    use super::Blake3;
+    use sha2::{Sha256, Sha512};
 
     pub trait Sealed {}
     impl Sealed for Sha256 {}
+    impl Sealed for Sha512 {}
+    impl Sealed for Blake3 {}
 }
 
 impl<D: SupportedDigest> From<Hash<D>> for AnyHash {
@@ -109,7 +135,7 @@ impl<D: SupportedDigest> From<&Hash<D>> for AnyHash {
     fn from(value: &Hash<D>) -> Self {
         AnyHash {
             algo: D::ALGORITHM,
-            bytes: value.digest.to_vec(),
+            bytes: value.digest.as_slice().into(),
         }
     }
 }
@@ -136,7 +162,7 @@ impl<D: SupportedDigest> TryFrom<AnyHash> for Hash<D> {
     fn try_from(value: AnyHash) -> Result<Self, Self::Error> {
         if value.algorithm() == D::ALGORITHM {
             let len = value.bytes.len();
-            match Hash::try_from(value.bytes) {
+            match Hash::try_from(value.bytes.into_vec()) {
                 Ok(hash) => Ok(hash),
                 Err(IncorrectLengthError) => Err(HashError::IncorrectLength {
                     expected: <D as Digest>::output_size(),
@@ -0,0 +1,282 @@
+use super::{PublicKey, Signature, SignatureError};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// Represents an invalid signature found by a [`BatchVerifier`].
+#[derive(Error, Debug)]
+#[error("signature {index} of the batch failed to verify")]
+pub struct BatchVerificationError {
+    /// The index of the first invalid signature in the batch.
+    pub index: usize,
+    /// The error verifying the signature.
+    #[source]
+    pub source: SignatureError,
+}
+
+struct Entry {
+    key: PublicKey,
+    msg: Vec<u8>,
+    signature: Signature,
+}
+
+/// Verifies many signatures at once.
+///
+/// The result is the same as calling [`PublicKey::verify`] for each
+/// signature in turn, which uses strict verification for Ed25519.
+///
+/// Signatures are grouped by algorithm. Ed25519 signatures, from any number
+/// of keys, are checked together with a single batch equation; ECDSA P-256
+/// has no batch equation, so those signatures are checked one at a time.
+///
+/// The batch equation accepts the same Ed25519 signatures as strict
+/// verification only if the signature's `R` and the public key are points
+/// in the prime-order subgroup and `R` is canonically encoded and not of
+/// small order. Signatures that do not meet these conditions are verified
+/// individually instead. If the batch equation fails, the Ed25519 signatures
+/// are verified individually to find the first invalid one.
+#[derive(Default)]
+pub struct BatchVerifier {
+    entries: Vec<Entry>,
+    unbatched: bool,
+}
+
+impl BatchVerifier {
+    /// Creates a new, empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new, empty batch whose signatures are verified one at a
+    /// time with [`PublicKey::verify`].
+    ///
+    /// This is for verifying a few signatures, where the batch equation
+    /// would save little.
+    pub fn unbatched() -> Self {
+        Self {
+            entries: Vec::new(),
+            unbatched: true,
+        }
+    }
+
+    /// Adds a signature of the given message to the batch.
+    ///
+    /// Returns the index of the signature in the batch.
+    pub fn push(&mut self, key: PublicKey, msg: impl Into<Vec<u8>>, signature: Signature) -> usize {
+        self.entries.push(Entry {
+            key,
+            msg: msg.into(),
+            signature,
+        });
+        self.entries.len() - 1
+    }
+
+    /// Gets the number of signatures in the batch.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Determines if the batch is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Verifies every signature in the batch, leaving the batch empty.
+    ///
+    /// On failure, the error identifies the first invalid signature.
+    pub fn verify(&mut self) -> Result<(), BatchVerificationError> {
+        let entries = std::mem::take(&mut self.entries);
+
+        let mut ed25519 = Vec::new();
+        let mut first_error = None;
+        let mut subgroup_keys = HashMap::new();
+        for (index, entry) in entries.iter().enumerate() {
+            match (&entry.key, &entry.signature) {
+                (PublicKey::Ed25519(key), Signature::Ed25519(signature))
+                    if !self.unbatched && batchable(key, signature, &mut subgroup_keys) =>
+                {
+                    ed25519.push((index, key, signature))
+                }
+                _ => {
+                    if let Err(source) = entry.key.verify(&entry.msg, &entry.signature) {
+                        first_error = Some(BatchVerificationError { index, source });
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Only Ed25519 signatures preceding the first individual failure
+        // need to be checked
+        let bound = first_error.as_ref().map_or(entries.len(), |e| e.index);
+        ed25519.retain(|(index, _, _)| *index < bound);
+
+        if !ed25519.is_empty() {
+            let messages: Vec<&[u8]> = ed25519
+                .iter()
+                .map(|(index, _, _)| entries[*index].msg.as_slice())
+                .collect();
+            let signatures: Vec<_> = ed25519.iter().map(|(_, _, s)| **s).collect();
+            let keys: Vec<_> = ed25519.iter().map(|(_, k, _)| **k).collect();
+
+            if ed25519_dalek::verify_batch(&messages, &signatures, &keys).is_err() {
+                for (index, key, signature) in &ed25519 {
+                    if let Err(source) = key.verify_strict(&entries[*index].msg, signature) {
+                        return Err(BatchVerificationError {
+                            index: *index,
+                            source,
+                        });
+                    }
+                }
+            }
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Determines if an Ed25519 signature can be checked with the batch equation
+/// without changing the result of strict verification.
+///
+/// Strict verification rejects small-order keys and `R`, and compares the
+/// encoding of `R` with that of the point it computes. The batch equation
+/// only compares points, so a non-canonical encoding of `R` or a component
+/// of `R` or the key outside of the prime-order subgroup could make it
+/// accept a signature that strict verification rejects.
+fn batchable(
+    key: &ed25519_dalek::VerifyingKey,
+    signature: &ed25519_dalek::Signature,
+    subgroup_keys: &mut HashMap<[u8; 32], bool>,
+) -> bool {
+    let r = match CompressedEdwardsY(*signature.r_bytes()).decompress() {
+        Some(r) => r,
+        None => return false,
+    };
+
+    if r.compress().as_bytes() != signature.r_bytes() || r.is_small_order() || !r.is_torsion_free()
+    {
+        return false;
+    }
+
+    // A signer usually signs many records, so check each key only once
+    *subgroup_keys
+        .entry(key.to_bytes())
+        .or_insert_with(|| !key.is_weak() && key.to_edwards().is_torsion_free())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::{generate_ed25519_pair, generate_p256_pair};
+
+    #[test]
+    fn test_batch_verify() {
+        let keys = [
+            generate_ed25519_pair(),
+            generate_p256_pair(),
+            generate_ed25519_pair(),
+        ];
+
+        let mut batch = BatchVerifier::new();
+        for i in 0..30u8 {
+            let (public_key, private_key) = &keys[usize::from(i) % keys.len()];
+            let signature = private_key.sign(&[i]).unwrap();
+            assert_eq!(
+                batch.push(public_key.clone(), [i], signature),
+                usize::from(i)
+            );
+        }
+
+        assert_eq!(batch.len(), 30);
+        batch.verify().unwrap();
+        assert!(batch.is_empty());
+    }
+
+    #[test]
+    fn test_batch_verify_reports_first_invalid_signature() {
+        let (ed25519_public, ed25519_private) = generate_ed25519_pair();
+        let (p256_public, p256_private) = generate_p256_pair();
+
+        let mut batch = BatchVerifier::new();
+        for i in 0..10u8 {
+            batch.push(
+                ed25519_public.clone(),
+                [i],
+                ed25519_private.sign(&[i]).unwrap(),
+            );
+            batch.push(p256_public.clone(), [i], p256_private.sign(&[i]).unwrap());
+        }
+
+        // An Ed25519 signature of a different message
+        batch.push(
+            ed25519_public.clone(),
+            [0],
+            ed25519_private.sign(&[1]).unwrap(),
+        );
+        // A signature by a key of a different algorithm
+        batch.push(p256_public, [0], ed25519_private.sign(&[0]).unwrap());
+
+        let err = batch.verify().unwrap_err();
+        assert_eq!(err.index, 20);
+
+        // The first invalid signature is found regardless of algorithm
+        batch.push(
+            ed25519_public.clone(),
+            [0],
+            p256_private.sign(&[0]).unwrap(),
+        );
+        batch.push(ed25519_public, [0], ed25519_private.sign(&[1]).unwrap());
+        assert_eq!(batch.verify().unwrap_err().index, 0);
+    }
+
+    #[test]
+    fn test_batch_verify_is_strict() {
+        use curve25519_dalek::constants::EIGHT_TORSION;
+
+        let (public_key, private_key) = generate_ed25519_pair();
+        let (key, signature) = match (&public_key, private_key.sign(&[0]).unwrap()) {
+            (PublicKey::Ed25519(key), Signature::Ed25519(signature)) => (*key, signature),
+            _ => unreachable!(),
+        };
+        assert!(batchable(&key, &signature, &mut HashMap::new()));
+
+        // An `R` with a small-order component
+        let r = CompressedEdwardsY(*signature.r_bytes())
+            .decompress()
+            .unwrap()
+            + EIGHT_TORSION[1];
+        let mixed = ed25519_dalek::Signature::from_components(
+            r.compress().to_bytes(),
+            *signature.s_bytes(),
+        );
+
+        // A small-order `R`
+        let small = ed25519_dalek::Signature::from_components(
+            EIGHT_TORSION[1].compress().to_bytes(),
+            *signature.s_bytes(),
+        );
+
+        // A non-canonical encoding of `R`, with y = p + 1
+        let mut bytes = [0xff; 32];
+        bytes[0] = 0xee;
+        bytes[31] = 0x7f;
+        let non_canonical = ed25519_dalek::Signature::from_components(bytes, *signature.s_bytes());
+
+        for signature in [mixed, small, non_canonical] {
+            assert!(!batchable(&key, &signature, &mut HashMap::new()));
+
+            let mut batch = BatchVerifier::new();
+            for i in 1..10u8 {
+                batch.push(public_key.clone(), [i], private_key.sign(&[i]).unwrap());
+            }
+            batch.push(public_key.clone(), [0], Signature::Ed25519(signature));
+            assert_eq!(batch.verify().unwrap_err().index, 9);
+            assert!(public_key
+                .verify(&[0], &Signature::Ed25519(signature))
+                .is_err());
+        }
+    }
+}
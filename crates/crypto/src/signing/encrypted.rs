@@ -0,0 +1,422 @@
+//! Passphrase-encrypted private keys.
+//!
+//! An encrypted private key is stored as a single line of text:
+//!
+//! ```text
+//! warg-encrypted-key:v1:argon2id:m=<memory>,t=<time>,p=<parallelism>:<salt>:<nonce>:<ciphertext>
+//! ```
+//!
+//! The encryption key is derived from the passphrase with Argon2id using the
+//! given parameters and salt, and the `<alg>:<base64>` encoding of the private
+//! key is encrypted with XChaCha20-Poly1305. Everything preceding the
+//! ciphertext is authenticated as associated data. The salt, nonce, and
+//! ciphertext are base64-encoded.
+
+use super::{PrivateKey, PrivateKeyParseError};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chacha20poly1305::{
+    aead::{Aead, Payload},
+    KeyInit, XChaCha20Poly1305, XNonce,
+};
+use core::fmt;
+use rand_core::{OsRng, RngCore};
+use secrecy::{zeroize::Zeroizing, ExposeSecret, SecretString};
+use std::str::FromStr;
+use thiserror::Error;
+
+const PREFIX: &str = "warg-encrypted-key";
+const VERSION: &str = "v1";
+const KDF: &str = "argon2id";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+// The largest key derivation parameters accepted, so that a key file cannot
+// make decryption exhaust memory or run indefinitely
+const MAX_MEMORY_COST: u32 = 1 << 20; // 1 GiB
+const MAX_TIME_COST: u32 = 16;
+const MAX_PARALLELISM: u32 = 16;
+
+/// Represents an error with an encrypted private key.
+#[derive(Error, Debug)]
+pub enum EncryptedPrivateKeyError {
+    #[error("not an encrypted private key")]
+    NotEncrypted,
+
+    #[error("unsupported encrypted private key version `{0}`")]
+    UnsupportedVersion(String),
+
+    #[error("unsupported key derivation function `{0}`")]
+    UnsupportedKdf(String),
+
+    #[error("invalid key derivation parameters `{0}`")]
+    InvalidKdfParams(String),
+
+    #[error("invalid encrypted private key: {0}")]
+    Invalid(&'static str),
+
+    #[error("base64 decode failed")]
+    Base64DecodeError(#[from] base64::DecodeError),
+
+    #[error("incorrect passphrase or corrupted key")]
+    DecryptionFailed,
+
+    #[error("decrypted private key is invalid")]
+    InvalidKey(#[source] PrivateKeyParseError),
+}
+
+/// The Argon2id parameters used to derive the encryption key of an
+/// encrypted private key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// The memory cost in KiB.
+    pub memory_cost: u32,
+    /// The number of iterations.
+    pub time_cost: u32,
+    /// The degree of parallelism.
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            memory_cost: Params::DEFAULT_M_COST,
+            time_cost: Params::DEFAULT_T_COST,
+            parallelism: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl KdfParams {
+    fn within_limits(&self) -> bool {
+        self.memory_cost <= MAX_MEMORY_COST
+            && self.time_cost <= MAX_TIME_COST
+            && self.parallelism <= MAX_PARALLELISM
+    }
+
+    fn derive_key(
+        &self,
+        passphrase: &SecretString,
+        salt: &[u8],
+    ) -> Result<Zeroizing<[u8; KEY_LEN]>, EncryptedPrivateKeyError> {
+        if !self.within_limits() {
+            return Err(EncryptedPrivateKeyError::InvalidKdfParams(self.to_string()));
+        }
+
+        let params = Params::new(
+            self.memory_cost,
+            self.time_cost,
+            self.parallelism,
+            Some(KEY_LEN),
+        )
+        .map_err(|_| EncryptedPrivateKeyError::InvalidKdfParams(self.to_string()))?;
+
+        let mut key = Zeroizing::new([0; KEY_LEN]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.expose_secret().as_bytes(), salt, key.as_mut())
+            .map_err(|_| EncryptedPrivateKeyError::InvalidKdfParams(self.to_string()))?;
+        Ok(key)
+    }
+}
+
+impl fmt::Display for KdfParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "m={m},t={t},p={p}",
+            m = self.memory_cost,
+            t = self.time_cost,
+            p = self.parallelism
+        )
+    }
+}
+
+impl FromStr for KdfParams {
+    type Err = EncryptedPrivateKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || EncryptedPrivateKeyError::InvalidKdfParams(s.to_string());
+        let mut params = s.split(',').map(|param| {
+            param
+                .split_once('=')
+                .and_then(|(name, value)| Some((name, value.parse::<u32>().ok()?)))
+                .ok_or_else(invalid)
+        });
+
+        match (params.next(), params.next(), params.next(), params.next()) {
+            (
+                Some(Ok(("m", memory_cost))),
+                Some(Ok(("t", time_cost))),
+                Some(Ok(("p", parallelism))),
+                None,
+            ) => {
+                let params = Self {
+                    memory_cost,
+                    time_cost,
+                    parallelism,
+                };
+
+                if params.within_limits() {
+                    Ok(params)
+                } else {
+                    Err(invalid())
+                }
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Represents a private key encrypted with a passphrase.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedPrivateKey {
+    params: KdfParams,
+    salt: Vec<u8>,
+    nonce: Vec<u8>,
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedPrivateKey {
+    /// Determines if the given string is an encrypted private key.
+    pub fn is_encrypted(s: &str) -> bool {
+        s.strip_prefix(PREFIX)
+            .map(|rest| rest.starts_with(':'))
+            .unwrap_or(false)
+    }
+
+    /// Encrypts a private key with the given passphrase.
+    pub fn encrypt(
+        key: &PrivateKey,
+        passphrase: &SecretString,
+    ) -> Result<Self, EncryptedPrivateKeyError> {
+        Self::encrypt_with(key, passphrase, KdfParams::default())
+    }
+
+    /// Encrypts a private key with the given passphrase and key derivation
+    /// parameters.
+    pub fn encrypt_with(
+        key: &PrivateKey,
+        passphrase: &SecretString,
+        params: KdfParams,
+    ) -> Result<Self, EncryptedPrivateKeyError> {
+        let mut salt = vec![0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        let mut nonce = vec![0; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce);
+
+        let mut encrypted = Self {
+            params,
+            salt,
+            nonce,
+            ciphertext: Vec::new(),
+        };
+
+        let encoded = key.encode();
+        encrypted.ciphertext = encrypted
+            .cipher(passphrase)?
+            .encrypt(
+                XNonce::from_slice(&encrypted.nonce),
+                Payload {
+                    msg: encoded.as_bytes(),
+                    aad: encrypted.header().as_bytes(),
+                },
+            )
+            .map_err(|_| EncryptedPrivateKeyError::Invalid("encryption failed"))?;
+
+        Ok(encrypted)
+    }
+
+    /// Decrypts the private key with the given passphrase.
+    pub fn decrypt(
+        &self,
+        passphrase: &SecretString,
+    ) -> Result<PrivateKey, EncryptedPrivateKeyError> {
+        let decrypted = Zeroizing::new(
+            self.cipher(passphrase)?
+                .decrypt(
+                    XNonce::from_slice(&self.nonce),
+                    Payload {
+                        msg: &self.ciphertext,
+                        aad: self.header().as_bytes(),
+                    },
+                )
+                .map_err(|_| EncryptedPrivateKeyError::DecryptionFailed)?,
+        );
+
+        let encoded = std::str::from_utf8(&decrypted)
+            .map_err(|_| EncryptedPrivateKeyError::Invalid("decrypted key is not UTF-8"))?;
+        PrivateKey::decode(encoded.to_string()).map_err(EncryptedPrivateKeyError::InvalidKey)
+    }
+
+    fn cipher(
+        &self,
+        passphrase: &SecretString,
+    ) -> Result<XChaCha20Poly1305, EncryptedPrivateKeyError> {
+        let key = self.params.derive_key(passphrase, &self.salt)?;
+        Ok(XChaCha20Poly1305::new(key.as_ref().into()))
+    }
+
+    // Everything preceding the ciphertext, which is authenticated with it
+    fn header(&self) -> String {
+        format!(
+            "{PREFIX}:{VERSION}:{KDF}:{params}:{salt}:{nonce}",
+            params = self.params,
+            salt = STANDARD.encode(&self.salt),
+            nonce = STANDARD.encode(&self.nonce),
+        )
+    }
+}
+
+impl fmt::Display for EncryptedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{header}:{ciphertext}",
+            header = self.header(),
+            ciphertext = STANDARD.encode(&self.ciphertext)
+        )
+    }
+}
+
+impl FromStr for EncryptedPrivateKey {
+    type Err = EncryptedPrivateKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.trim().split(':').collect();
+        match parts.as_slice() {
+            [PREFIX, VERSION, KDF, params, salt, nonce, ciphertext] => {
+                let salt = STANDARD.decode(salt)?;
+                let nonce = STANDARD.decode(nonce)?;
+                if salt.len() != SALT_LEN {
+                    return Err(EncryptedPrivateKeyError::Invalid(
+                        "salt has an invalid length",
+                    ));
+                }
+                if nonce.len() != NONCE_LEN {
+                    return Err(EncryptedPrivateKeyError::Invalid(
+                        "nonce has an invalid length",
+                    ));
+                }
+
+                Ok(Self {
+                    params: params.parse()?,
+                    salt,
+                    nonce,
+                    ciphertext: STANDARD.decode(ciphertext)?,
+                })
+            }
+            [PREFIX, VERSION, kdf, ..] if *kdf != KDF => {
+                Err(EncryptedPrivateKeyError::UnsupportedKdf(kdf.to_string()))
+            }
+            [PREFIX, version, ..] if *version != VERSION => Err(
+                EncryptedPrivateKeyError::UnsupportedVersion(version.to_string()),
+            ),
+            [PREFIX, ..] => Err(EncryptedPrivateKeyError::Invalid("expected 7 parts")),
+            _ => Err(EncryptedPrivateKeyError::NotEncrypted),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // Small parameters to keep the tests fast
+    const TEST_PARAMS: KdfParams = KdfParams {
+        memory_cost: 64,
+        time_cost: 1,
+        parallelism: 1,
+    };
+
+    fn test_key() -> PrivateKey {
+        PrivateKey::decode("ed25519:nWGxne/9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A=".to_string())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let passphrase = SecretString::from("correct horse battery staple".to_string());
+        let encrypted = EncryptedPrivateKey::encrypt_with(&test_key(), &passphrase, TEST_PARAMS)
+            .unwrap()
+            .to_string();
+        assert!(EncryptedPrivateKey::is_encrypted(&encrypted));
+        assert!(encrypted.starts_with("warg-encrypted-key:v1:argon2id:m=64,t=1,p=1:"));
+
+        let decrypted = encrypted
+            .parse::<EncryptedPrivateKey>()
+            .unwrap()
+            .decrypt(&passphrase)
+            .unwrap();
+        assert_eq!(decrypted.encode(), test_key().encode());
+    }
+
+    #[test]
+    fn test_wrong_passphrase() {
+        let encrypted = EncryptedPrivateKey::encrypt_with(
+            &test_key(),
+            &SecretString::from("right".to_string()),
+            TEST_PARAMS,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            encrypted.decrypt(&SecretString::from("wrong".to_string())),
+            Err(EncryptedPrivateKeyError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn test_tampered_params() {
+        let passphrase = SecretString::from("passphrase".to_string());
+        let encrypted = EncryptedPrivateKey::encrypt_with(&test_key(), &passphrase, TEST_PARAMS)
+            .unwrap()
+            .to_string()
+            .replace("m=64,t=1,p=1", "m=64,t=2,p=1");
+
+        assert!(matches!(
+            encrypted
+                .parse::<EncryptedPrivateKey>()
+                .unwrap()
+                .decrypt(&passphrase),
+            Err(EncryptedPrivateKeyError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(!EncryptedPrivateKey::is_encrypted(
+            "ed25519:nWGxne/9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A="
+        ));
+        assert!(matches!(
+            "ed25519:nWGxne/9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A=".parse::<EncryptedPrivateKey>(),
+            Err(EncryptedPrivateKeyError::NotEncrypted)
+        ));
+        assert!(matches!(
+            "warg-encrypted-key:v2:argon2id:m=64,t=1,p=1:a:b:c".parse::<EncryptedPrivateKey>(),
+            Err(EncryptedPrivateKeyError::UnsupportedVersion(_))
+        ));
+        assert!(matches!(
+            "warg-encrypted-key:v1:scrypt:n=1:a:b:c".parse::<EncryptedPrivateKey>(),
+            Err(EncryptedPrivateKeyError::UnsupportedKdf(_))
+        ));
+        assert!(matches!(
+            "m=64,t=1".parse::<KdfParams>(),
+            Err(EncryptedPrivateKeyError::InvalidKdfParams(_))
+        ));
+        for params in [
+            "m=4294967295,t=1,p=1",
+            "m=64,t=4294967295,p=1",
+            "m=64,t=1,p=16777215",
+        ] {
+            assert!(
+                matches!(
+                    params.parse::<KdfParams>(),
+                    Err(EncryptedPrivateKeyError::InvalidKdfParams(_))
+                ),
+                "expected `{params}` to be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,472 @@
+//! Signing with an external signing daemon.
+//!
+//! An external signer is either a command that is run once per request or a
+//! Unix domain socket that is connected to once per request. Each request is
+//! a single line of JSON and is answered with a single line of JSON:
+//!
+//! ```text
+//! {"operation":"publicKey"}                  => {"publicKey":"<key>"}
+//! {"operation":"sign","message":"<base64>"}  => {"signature":"<signature>"}
+//! ```
+//!
+//! A signer may answer any request with `{"error":"<message>"}`.
+//!
+//! A signer that does not answer a request within the signer's timeout
+//! (by default, [`DEFAULT_TIMEOUT`]) fails the request.
+
+use super::{PublicKey, Signature, SignatureError, Signer};
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use std::{fmt, io, path::PathBuf, process::Stdio, str::FromStr, time::Duration};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    process::Command,
+};
+
+/// The default time allowed for an external signer to answer a request.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Represents an error communicating with an external signer.
+#[derive(Debug, Error)]
+pub enum ExternalSignerError {
+    /// The signer endpoint could not be parsed.
+    #[error("invalid signer `{0}`: expected `exec:<command>` or `unix:<path>`")]
+    InvalidEndpoint(String),
+    /// An I/O error occurred while communicating with the signer.
+    #[error("failed to communicate with signer `{endpoint}`: {source}")]
+    Io {
+        /// The signer endpoint.
+        endpoint: SignerEndpoint,
+        /// The underlying I/O error.
+        #[source]
+        source: io::Error,
+    },
+    /// The signer sent a response that could not be understood.
+    #[error("signer `{endpoint}` sent an invalid response: {message}")]
+    InvalidResponse {
+        /// The signer endpoint.
+        endpoint: SignerEndpoint,
+        /// A description of the problem with the response.
+        message: String,
+    },
+    /// The signer reported an error.
+    #[error("signer `{endpoint}` failed: {message}")]
+    Signer {
+        /// The signer endpoint.
+        endpoint: SignerEndpoint,
+        /// The error message reported by the signer.
+        message: String,
+    },
+}
+
+/// Represents where an external signer can be reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerEndpoint {
+    /// A command that is run for each request.
+    ///
+    /// The request is written to the command's stdin and the response is
+    /// read from its stdout.
+    Command {
+        /// The program to run.
+        program: PathBuf,
+        /// The arguments to pass to the program.
+        args: Vec<String>,
+    },
+    /// A Unix domain socket that is connected to for each request.
+    Socket(PathBuf),
+}
+
+impl SignerEndpoint {
+    async fn exchange(&self, request: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Self::Command { program, args } => {
+                let mut child = Command::new(program)
+                    .args(args)
+                    .stdin(Stdio::piped())
+                    .stdout(Stdio::piped())
+                    .kill_on_drop(true)
+                    .spawn()?;
+
+                let mut stdin = child.stdin.take().expect("stdin should be piped");
+                stdin.write_all(request).await?;
+                drop(stdin);
+
+                let output = child.wait_with_output().await?;
+                if !output.status.success() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::Other,
+                        format!("signer command {status}", status = output.status),
+                    ));
+                }
+
+                Ok(output.stdout)
+            }
+            #[cfg(unix)]
+            Self::Socket(path) => {
+                let mut stream = tokio::net::UnixStream::connect(path).await?;
+                stream.write_all(request).await?;
+                stream.shutdown().await?;
+
+                let mut response = Vec::new();
+                stream.read_to_end(&mut response).await?;
+                Ok(response)
+            }
+            #[cfg(not(unix))]
+            Self::Socket(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Unix domain sockets are not supported on this platform",
+            )),
+        }
+    }
+}
+
+impl fmt::Display for SignerEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Command { program, args } => {
+                write!(f, "exec:{program}", program = program.display())?;
+                for arg in args {
+                    write!(f, " {arg}")?;
+                }
+                Ok(())
+            }
+            Self::Socket(path) => write!(f, "unix:{path}", path = path.display()),
+        }
+    }
+}
+
+impl FromStr for SignerEndpoint {
+    type Err = ExternalSignerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ExternalSignerError::InvalidEndpoint(s.to_string());
+        match s.split_once(':') {
+            Some(("exec", command)) => {
+                let mut parts = command.split_whitespace();
+                let program = parts.next().ok_or_else(invalid)?;
+                Ok(Self::Command {
+                    program: program.into(),
+                    args: parts.map(Into::into).collect(),
+                })
+            }
+            Some(("unix", path)) if !path.is_empty() => Ok(Self::Socket(path.into())),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// A request sent to an external signer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "operation", rename_all = "camelCase")]
+pub enum SignerRequest {
+    /// Requests the public key of the signer.
+    PublicKey,
+    /// Requests a signature of the given message.
+    Sign {
+        /// The message to sign.
+        #[serde(with = "base64_bytes")]
+        message: Vec<u8>,
+    },
+}
+
+impl SignerRequest {
+    /// Responds to the request with the given signer.
+    ///
+    /// This is intended for implementing signing daemons.
+    pub async fn respond(&self, signer: &dyn Signer) -> SignerResponse {
+        match self {
+            Self::PublicKey => SignerResponse::PublicKey(signer.public_key()),
+            Self::Sign { message } => match signer.sign(message).await {
+                Ok(signature) => SignerResponse::Signature(signature),
+                Err(e) => SignerResponse::Error(e.to_string()),
+            },
+        }
+    }
+}
+
+/// A response from an external signer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SignerResponse {
+    /// The public key of the signer.
+    PublicKey(PublicKey),
+    /// The requested signature.
+    Signature(Signature),
+    /// The signer failed to process the request.
+    Error(String),
+}
+
+/// A signer that delegates to an external signing daemon.
+///
+/// The public key of the signer is requested once, when the signer is
+/// connected.
+#[derive(Debug, Clone)]
+pub struct ExternalSigner {
+    endpoint: SignerEndpoint,
+    public_key: PublicKey,
+    timeout: Duration,
+}
+
+impl ExternalSigner {
+    /// Connects to the external signer at the given endpoint.
+    ///
+    /// Requests to the signer time out after [`DEFAULT_TIMEOUT`].
+    pub async fn connect(endpoint: SignerEndpoint) -> Result<Self, ExternalSignerError> {
+        Self::connect_with_timeout(endpoint, DEFAULT_TIMEOUT).await
+    }
+
+    /// Connects to the external signer at the given endpoint.
+    ///
+    /// Requests to the signer, including the request for its public key,
+    /// fail if the signer does not answer within the given timeout.
+    pub async fn connect_with_timeout(
+        endpoint: SignerEndpoint,
+        timeout: Duration,
+    ) -> Result<Self, ExternalSignerError> {
+        let public_key = match request(&endpoint, &SignerRequest::PublicKey, timeout).await? {
+            SignerResponse::PublicKey(key) => key,
+            _ => {
+                return Err(ExternalSignerError::InvalidResponse {
+                    endpoint,
+                    message: "expected a public key".to_string(),
+                })
+            }
+        };
+
+        Ok(Self {
+            endpoint,
+            public_key,
+            timeout,
+        })
+    }
+
+    /// Gets the endpoint of the signer.
+    pub fn endpoint(&self) -> &SignerEndpoint {
+        &self.endpoint
+    }
+
+    async fn try_sign(&self, msg: &[u8]) -> Result<Signature, ExternalSignerError> {
+        let signature = match request(
+            &self.endpoint,
+            &SignerRequest::Sign {
+                message: msg.to_vec(),
+            },
+            self.timeout,
+        )
+        .await?
+        {
+            SignerResponse::Signature(signature) => signature,
+            _ => return Err(self.invalid_response("expected a signature")),
+        };
+
+        // Catch a misconfigured signer before its signature is published
+        self.public_key
+            .verify(msg, &signature)
+            .map_err(|_| self.invalid_response("the signature does not match the signer's key"))?;
+
+        Ok(signature)
+    }
+
+    fn invalid_response(&self, message: &str) -> ExternalSignerError {
+        ExternalSignerError::InvalidResponse {
+            endpoint: self.endpoint.clone(),
+            message: message.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for ExternalSigner {
+    fn public_key(&self) -> PublicKey {
+        self.public_key.clone()
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, SignatureError> {
+        self.try_sign(msg)
+            .await
+            .map_err(SignatureError::from_source)
+    }
+}
+
+/// Sends a request to the signer at the given endpoint.
+///
+/// A signer command still running when the timeout expires is killed.
+async fn request(
+    endpoint: &SignerEndpoint,
+    request: &SignerRequest,
+    timeout: Duration,
+) -> Result<SignerResponse, ExternalSignerError> {
+    let mut line = serde_json::to_vec(request).expect("request should serialize");
+    line.push(b'\n');
+
+    let response = tokio::time::timeout(timeout, endpoint.exchange(&line))
+        .await
+        .unwrap_or_else(|_| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("no response within {timeout:?}"),
+            ))
+        })
+        .map_err(|source| ExternalSignerError::Io {
+            endpoint: endpoint.clone(),
+            source,
+        })?;
+
+    let line = response.split(|b| *b == b'\n').next().unwrap_or_default();
+    match serde_json::from_slice(line) {
+        Ok(SignerResponse::Error(message)) => Err(ExternalSignerError::Signer {
+            endpoint: endpoint.clone(),
+            message,
+        }),
+        Ok(response) => Ok(response),
+        Err(e) => Err(ExternalSignerError::InvalidResponse {
+            endpoint: endpoint.clone(),
+            message: e.to_string(),
+        }),
+    }
+}
+
+mod base64_bytes {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        STANDARD
+            .decode(String::deserialize(deserializer)?)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::{generate_ed25519_pair, generate_p256_pair};
+
+    #[test]
+    fn test_endpoints() {
+        assert_eq!(
+            "exec:warg-signer --key operator"
+                .parse::<SignerEndpoint>()
+                .unwrap(),
+            SignerEndpoint::Command {
+                program: "warg-signer".into(),
+                args: vec!["--key".to_string(), "operator".to_string()],
+            }
+        );
+        assert_eq!(
+            "unix:/run/warg/signer.sock"
+                .parse::<SignerEndpoint>()
+                .unwrap(),
+            SignerEndpoint::Socket("/run/warg/signer.sock".into())
+        );
+
+        for endpoint in ["", "exec:", "unix:", "/run/warg/signer.sock", "tcp:1.2.3.4"] {
+            assert!(
+                matches!(
+                    endpoint.parse::<SignerEndpoint>(),
+                    Err(ExternalSignerError::InvalidEndpoint(_))
+                ),
+                "expected `{endpoint}` to be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_protocol() {
+        assert_eq!(
+            serde_json::to_string(&SignerRequest::PublicKey).unwrap(),
+            r#"{"operation":"publicKey"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&SignerRequest::Sign {
+                message: b"hello".to_vec()
+            })
+            .unwrap(),
+            r#"{"operation":"sign","message":"aGVsbG8="}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<SignerResponse>(r#"{"error":"key is locked"}"#).unwrap(),
+            SignerResponse::Error("key is locked".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_command_signer_error() {
+        let endpoint = SignerEndpoint::Command {
+            program: "sh".into(),
+            args: vec![
+                "-c".to_string(),
+                r#"cat > /dev/null; echo '{"error":"key is locked"}'"#.to_string(),
+            ],
+        };
+
+        match ExternalSigner::connect(endpoint).await {
+            Err(ExternalSignerError::Signer { message, .. }) => {
+                assert_eq!(message, "key is locked")
+            }
+            other => panic!("expected a signer error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signer_timeout() {
+        let endpoint = SignerEndpoint::Command {
+            program: "sh".into(),
+            args: vec!["-c".to_string(), "sleep 10".to_string()],
+        };
+
+        match ExternalSigner::connect_with_timeout(endpoint, Duration::from_millis(100)).await {
+            Err(ExternalSignerError::Io { source, .. }) => {
+                assert_eq!(source.kind(), io::ErrorKind::TimedOut)
+            }
+            other => panic!("expected a timeout, got {other:?}"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_socket_signer() {
+        use tokio::{
+            io::{AsyncBufReadExt, BufReader},
+            net::UnixListener,
+        };
+
+        for (public_key, private_key) in [generate_p256_pair(), generate_ed25519_pair()] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("signer.sock");
+            let listener = UnixListener::bind(&path).unwrap();
+
+            let daemon = tokio::spawn(async move {
+                loop {
+                    let (stream, _) = listener.accept().await.unwrap();
+                    let mut stream = BufReader::new(stream);
+                    let mut line = String::new();
+                    stream.read_line(&mut line).await.unwrap();
+
+                    let request: SignerRequest = serde_json::from_str(&line).unwrap();
+                    let mut response =
+                        serde_json::to_vec(&request.respond(&private_key).await).unwrap();
+                    response.push(b'\n');
+                    stream.get_mut().write_all(&response).await.unwrap();
+                }
+            });
+
+            let signer = ExternalSigner::connect(SignerEndpoint::Socket(path))
+                .await
+                .unwrap();
+            assert_eq!(Signer::public_key(&signer), public_key);
+
+            let msg = b"checkpoint";
+            let signature = Signer::sign(&signer, msg).await.unwrap();
+            public_key.verify(msg, &signature).unwrap();
+
+            daemon.abort();
+        }
+    }
+}
@@ -0,0 +1,232 @@
+//! Conversions between keys and JSON Web Keys (RFC 7517).
+//!
+//! ECDSA P-256 keys are represented as `EC` keys (RFC 7518) and Ed25519 keys
+//! as `OKP` keys (RFC 8037).
+
+use super::{PrivateKey, PublicKey};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use secrecy::{zeroize::Zeroizing, Zeroize};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Represents an error converting a JSON Web Key.
+#[derive(Error, Debug)]
+pub enum JwkError {
+    #[error("invalid JSON Web Key: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("unsupported JSON Web Key type `{kty}` with curve `{crv}`")]
+    UnsupportedKey { kty: String, crv: String },
+
+    #[error("JSON Web Key is missing the `{0}` parameter")]
+    MissingParameter(&'static str),
+
+    #[error("JSON Web Key has an invalid `{0}` parameter")]
+    InvalidParameter(&'static str),
+
+    #[error("JSON Web Key private and public parameters do not match")]
+    MismatchedPublicKey,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Jwk {
+    kty: String,
+    crv: String,
+    x: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    y: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    d: Option<String>,
+}
+
+impl Drop for Jwk {
+    fn drop(&mut self) {
+        self.d.zeroize();
+    }
+}
+
+impl Jwk {
+    fn parse(s: &str) -> Result<Self, JwkError> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    fn public_key(&self) -> Result<PublicKey, JwkError> {
+        match (self.kty.as_str(), self.crv.as_str()) {
+            ("EC", "P-256") => {
+                let x = decode_param("x", &self.x, 32)?;
+                let y = decode_param(
+                    "y",
+                    self.y.as_deref().ok_or(JwkError::MissingParameter("y"))?,
+                    32,
+                )?;
+                let point = p256::EncodedPoint::from_affine_coordinates(
+                    x.as_slice().into(),
+                    y.as_slice().into(),
+                    false,
+                );
+                p256::ecdsa::VerifyingKey::from_encoded_point(&point)
+                    .map(PublicKey::EcdsaP256)
+                    .map_err(|_| JwkError::InvalidParameter("x"))
+            }
+            ("OKP", "Ed25519") => {
+                let x = decode_param("x", &self.x, 32)?;
+                ed25519_dalek::VerifyingKey::try_from(x.as_slice())
+                    .map(PublicKey::Ed25519)
+                    .map_err(|_| JwkError::InvalidParameter("x"))
+            }
+            (kty, crv) => Err(JwkError::UnsupportedKey {
+                kty: kty.to_string(),
+                crv: crv.to_string(),
+            }),
+        }
+    }
+
+    fn private_key(&self) -> Result<PrivateKey, JwkError> {
+        let public_key = self.public_key()?;
+        let d = Zeroizing::new(decode_param(
+            "d",
+            self.d.as_deref().ok_or(JwkError::MissingParameter("d"))?,
+            32,
+        )?);
+
+        let private_key = match public_key {
+            PublicKey::EcdsaP256(_) => p256::ecdsa::SigningKey::from_slice(&d)
+                .map(PrivateKey::from)
+                .map_err(|_| JwkError::InvalidParameter("d"))?,
+            PublicKey::Ed25519(_) => ed25519_dalek::SigningKey::try_from(d.as_slice())
+                .map(PrivateKey::from)
+                .map_err(|_| JwkError::InvalidParameter("d"))?,
+        };
+
+        if private_key.public_key() != public_key {
+            return Err(JwkError::MismatchedPublicKey);
+        }
+
+        Ok(private_key)
+    }
+
+    fn from_public_key(key: &PublicKey) -> Self {
+        match key {
+            PublicKey::EcdsaP256(key) => {
+                let point = key.to_encoded_point(false);
+                Self {
+                    kty: "EC".to_string(),
+                    crv: "P-256".to_string(),
+                    x: URL_SAFE_NO_PAD.encode(point.x().expect("point should not be identity")),
+                    y: Some(
+                        URL_SAFE_NO_PAD.encode(point.y().expect("point should be uncompressed")),
+                    ),
+                    d: None,
+                }
+            }
+            PublicKey::Ed25519(key) => Self {
+                kty: "OKP".to_string(),
+                crv: "Ed25519".to_string(),
+                x: URL_SAFE_NO_PAD.encode(key.as_bytes()),
+                y: None,
+                d: None,
+            },
+        }
+    }
+
+    fn encode(&self) -> String {
+        serde_json::to_string(self).expect("JSON Web Key should serialize")
+    }
+}
+
+fn decode_param(name: &'static str, value: &str, len: usize) -> Result<Vec<u8>, JwkError> {
+    match URL_SAFE_NO_PAD.decode(value) {
+        Ok(bytes) if bytes.len() == len => Ok(bytes),
+        _ => Err(JwkError::InvalidParameter(name)),
+    }
+}
+
+impl PublicKey {
+    /// Decode a public key from a JSON Web Key.
+    pub fn from_jwk(jwk: &str) -> Result<Self, JwkError> {
+        Jwk::parse(jwk)?.public_key()
+    }
+
+    /// Encode the key as a JSON Web Key.
+    pub fn to_jwk(&self) -> String {
+        Jwk::from_public_key(self).encode()
+    }
+}
+
+impl PrivateKey {
+    /// Decode a private key from a JSON Web Key.
+    pub fn from_jwk(jwk: &str) -> Result<Self, JwkError> {
+        Jwk::parse(jwk)?.private_key()
+    }
+
+    /// Encode the key as a JSON Web Key, including its public parameters.
+    pub fn to_jwk(&self) -> Zeroizing<String> {
+        let mut jwk = Jwk::from_public_key(&self.public_key());
+        jwk.d = Some(URL_SAFE_NO_PAD.encode(Zeroizing::new(self.bytes())));
+        Zeroizing::new(jwk.encode())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // From RFC 8037, appendix A
+    const RFC8037_JWK: &str = r#"{"kty":"OKP","crv":"Ed25519","d":"nWGxne_9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#;
+
+    #[test]
+    fn test_ed25519_jwk() {
+        let key = PrivateKey::from_jwk(RFC8037_JWK).unwrap();
+        assert_eq!(
+            &*key.encode(),
+            "ed25519:nWGxne/9WmC6hEr0kuwsxERJxWl7MmkZcDusAxyuf2A="
+        );
+        assert_eq!(
+            key.public_key().to_jwk(),
+            r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#
+        );
+        assert_eq!(
+            PrivateKey::from_jwk(&key.to_jwk()).unwrap().encode(),
+            key.encode()
+        );
+    }
+
+    #[test]
+    fn test_p256_jwk() {
+        let key = PrivateKey::decode(
+            "ecdsa-p256:I+UlDo0HxyBBFeelhPPWmD+LnklOpqZDkrFP5VduASk=".to_string(),
+        )
+        .unwrap();
+
+        let public_key = PublicKey::from_jwk(&key.public_key().to_jwk()).unwrap();
+        assert_eq!(public_key, key.public_key());
+        assert_eq!(
+            PrivateKey::from_jwk(&key.to_jwk()).unwrap().encode(),
+            key.encode()
+        );
+    }
+
+    #[test]
+    fn test_invalid_jwk() {
+        assert!(matches!(
+            PrivateKey::from_jwk(r#"{"kty":"RSA","crv":"","x":""}"#),
+            Err(JwkError::UnsupportedKey { .. })
+        ));
+        assert!(matches!(
+            PrivateKey::from_jwk(
+                r#"{"kty":"OKP","crv":"Ed25519","x":"11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo"}"#
+            ),
+            Err(JwkError::MissingParameter("d"))
+        ));
+
+        // The private key does not match the public key
+        let (_, other) = super::super::generate_ed25519_pair();
+        let mut jwk: serde_json::Value = serde_json::from_str(RFC8037_JWK).unwrap();
+        jwk["d"] = URL_SAFE_NO_PAD.encode(other.bytes()).into();
+        assert!(matches!(
+            PrivateKey::from_jwk(&jwk.to_string()),
+            Err(JwkError::MismatchedPublicKey)
+        ));
+    }
+}
@@ -5,18 +5,31 @@ use thiserror::Error;
 
 use crate::hash::HashAlgorithm;
 
+mod batch;
+mod encrypted;
+pub mod external;
+mod jwk;
+mod pem;
 mod private_key;
 mod public_key;
 mod signature;
+mod signer;
 
+pub use self::batch::{BatchVerificationError, BatchVerifier};
+pub use self::encrypted::{EncryptedPrivateKey, EncryptedPrivateKeyError, KdfParams};
+pub use self::jwk::JwkError;
+pub use self::pem::PemError;
 pub use self::private_key::{PrivateKey, PrivateKeyParseError, SignatureError};
 pub use self::public_key::{KeyID, PublicKey, PublicKeyParseError};
 pub use self::signature::{Signature, SignatureParseError};
+pub use self::signer::Signer;
+pub use external::ExternalSigner;
 
 /// A signature algorithm supported by WARG
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub enum SignatureAlgorithm {
     EcdsaP256,
+    Ed25519,
 }
 
 impl SignatureAlgorithm {
@@ -24,7 +37,7 @@ impl SignatureAlgorithm {
     /// signing algorithm to generate digests.
     pub fn digest_algorithm(&self) -> HashAlgorithm {
         match self {
-            SignatureAlgorithm::EcdsaP256 => HashAlgorithm::Sha256,
+            SignatureAlgorithm::EcdsaP256 | SignatureAlgorithm::Ed25519 => HashAlgorithm::Sha256,
         }
     }
 }
@@ -33,6 +46,7 @@ impl fmt::Display for SignatureAlgorithm {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             SignatureAlgorithm::EcdsaP256 => write!(f, "ecdsa-p256"),
+            SignatureAlgorithm::Ed25519 => write!(f, "ed25519"),
         }
     }
 }
@@ -43,6 +57,7 @@ impl FromStr for SignatureAlgorithm {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "ecdsa-p256" => Ok(SignatureAlgorithm::EcdsaP256),
+            "ed25519" => Ok(SignatureAlgorithm::Ed25519),
             _ => Err(SignatureAlgorithmParseError {
                 value: s.to_owned(),
             }),
@@ -62,28 +77,54 @@ pub fn generate_p256_pair() -> (PublicKey, PrivateKey) {
     (PublicKey::from(public_key), PrivateKey::from(private_key))
 }
 
+pub fn generate_ed25519_pair() -> (PublicKey, PrivateKey) {
+    let private_key = ed25519_dalek::SigningKey::generate(&mut OsRng);
+    let public_key = private_key.verifying_key();
+    (PublicKey::from(public_key), PrivateKey::from(private_key))
+}
+
+/// Generate a key pair using the given signature algorithm.
+pub fn generate_pair(algorithm: &SignatureAlgorithm) -> (PublicKey, PrivateKey) {
+    match algorithm {
+        SignatureAlgorithm::EcdsaP256 => generate_p256_pair(),
+        SignatureAlgorithm::Ed25519 => generate_ed25519_pair(),
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
 
+    const ALGORITHMS: [SignatureAlgorithm; 2] =
+        [SignatureAlgorithm::EcdsaP256, SignatureAlgorithm::Ed25519];
+
     #[test]
     pub fn test_correct_key_passes_verify() {
-        let (public, private) = generate_p256_pair();
-        let msg = (0..255u8).collect::<Vec<u8>>();
-        let signature = private.sign(&msg).unwrap();
-        public.verify(&msg, &signature).unwrap();
+        for algorithm in &ALGORITHMS {
+            let (public, private) = generate_pair(algorithm);
+            let msg = (0..255u8).collect::<Vec<u8>>();
+            let signature = private.sign(&msg).unwrap();
+            assert_eq!(&signature.signature_algorithm(), algorithm);
+            public.verify(&msg, &signature).unwrap();
+        }
     }
 
     #[test]
     pub fn test_wrong_key_fails_verify() {
-        let (alice_public, alice_private) = generate_p256_pair();
-        let (bob_public, bob_private) = generate_p256_pair();
+        for (alice, bob) in [
+            (&ALGORITHMS[0], &ALGORITHMS[0]),
+            (&ALGORITHMS[1], &ALGORITHMS[1]),
+            (&ALGORITHMS[0], &ALGORITHMS[1]),
+        ] {
+            let (alice_public, alice_private) = generate_pair(alice);
+            let (bob_public, bob_private) = generate_pair(bob);
 
-        let msg = (0..255u8).collect::<Vec<u8>>();
-        let alice_signature = alice_private.sign(&msg).unwrap();
-        let bob_signature = bob_private.sign(&msg).unwrap();
+            let msg = (0..255u8).collect::<Vec<u8>>();
+            let alice_signature = alice_private.sign(&msg).unwrap();
+            let bob_signature = bob_private.sign(&msg).unwrap();
 
-        assert!(bob_public.verify(&msg, &alice_signature).is_err());
-        assert!(alice_public.verify(&msg, &bob_signature).is_err());
+            assert!(bob_public.verify(&msg, &alice_signature).is_err());
+            assert!(alice_public.verify(&msg, &bob_signature).is_err());
+        }
     }
 }
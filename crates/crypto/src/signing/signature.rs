@@ -10,6 +10,7 @@ use thiserror::Error;
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Signature {
     P256(p256::ecdsa::Signature),
+    Ed25519(ed25519_dalek::Signature),
 }
 
 impl Signature {
@@ -17,6 +18,7 @@ impl Signature {
     pub fn signature_algorithm(&self) -> SignatureAlgorithm {
         match self {
             Signature::P256(_) => SignatureAlgorithm::EcdsaP256,
+            Signature::Ed25519(_) => SignatureAlgorithm::Ed25519,
         }
     }
 
@@ -24,6 +26,7 @@ impl Signature {
     pub fn bytes(&self) -> Vec<u8> {
         match self {
             Signature::P256(key) => key.to_der().to_bytes().to_vec(),
+            Signature::Ed25519(key) => key.to_bytes().to_vec(),
         }
     }
 }
@@ -54,6 +57,9 @@ impl FromStr for Signature {
             SignatureAlgorithm::EcdsaP256 => {
                 Signature::P256(p256::ecdsa::Signature::from_der(&bytes)?)
             }
+            SignatureAlgorithm::Ed25519 => {
+                Signature::Ed25519(ed25519_dalek::Signature::from_slice(&bytes)?)
+            }
         };
 
         Ok(sig)
@@ -0,0 +1,39 @@
+use super::{PrivateKey, PublicKey, Signature, SignatureError};
+use async_trait::async_trait;
+use std::sync::Arc;
+
+/// Represents something that can sign messages.
+///
+/// A signer need not hold its key in process memory; for example, an
+/// [`ExternalSigner`](super::ExternalSigner) delegates signing to a separate
+/// signing daemon.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// Gets the public key of the signer.
+    fn public_key(&self) -> PublicKey;
+
+    /// Signs the given message.
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, SignatureError>;
+}
+
+#[async_trait]
+impl Signer for PrivateKey {
+    fn public_key(&self) -> PublicKey {
+        PrivateKey::public_key(self)
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, SignatureError> {
+        PrivateKey::sign(self, msg)
+    }
+}
+
+#[async_trait]
+impl<T: Signer + ?Sized> Signer for Arc<T> {
+    fn public_key(&self) -> PublicKey {
+        T::public_key(self)
+    }
+
+    async fn sign(&self, msg: &[u8]) -> Result<Signature, SignatureError> {
+        T::sign(self, msg).await
+    }
+}
@@ -0,0 +1,326 @@
+//! Types relating to the content of multi-file releases.
+//!
+//! A multi-file release is described by a "content index": a list of
+//! (path, digest) entries ordered byte-wise by path. The content digest of
+//! such a release is the SHA-256 digest of the encoded index:
+//!
+//! ```text
+//! <content-digest> := SHA-256(<content-index>)
+//! <content-index>  := <content-entry>+
+//! <content-entry>  := <entry-digest> <entry-path-vec>
+//! <entry-digest>   := SHA-256([entry content])
+//! <entry-path-vec> := LENGTH(<entry-path>) <entry-path>
+//! ```
+//!
+//! where `LENGTH` is the size of the path in bytes as a 16-bit, unsigned,
+//! little-endian integer.
+
+use serde::{Deserialize, Serialize};
+use std::{borrow::Borrow, collections::BTreeMap, fmt, str::FromStr};
+use thiserror::Error;
+use warg_crypto::hash::{AnyHash, HashAlgorithm};
+
+/// The name of the entry containing the component of a multi-file
+/// component release.
+pub const COMPONENT_ENTRY_PATH: &str = "component.wasm";
+
+/// The hash algorithm used for content index digests.
+const CONTENT_INDEX_ALGORITHM: HashAlgorithm = HashAlgorithm::Sha256;
+
+/// Represents an error with a content index.
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum ContentIndexError {
+    /// The path of a content entry is invalid.
+    #[error("invalid content path `{path}`: {reason}")]
+    InvalidPath {
+        /// The invalid path.
+        path: String,
+        /// The reason the path is invalid.
+        reason: &'static str,
+    },
+    /// A path appears more than once in a content index.
+    #[error("content path `{path}` appears more than once in the content index")]
+    DuplicatePath {
+        /// The duplicated path.
+        path: ContentPath,
+    },
+    /// The digest of a content entry uses an unsupported algorithm.
+    #[error(
+        "content path `{path}` has a {algorithm} digest but {CONTENT_INDEX_ALGORITHM} was expected"
+    )]
+    UnsupportedAlgorithm {
+        /// The path of the content entry.
+        path: ContentPath,
+        /// The algorithm of the entry's digest.
+        algorithm: HashAlgorithm,
+    },
+    /// The content index has no entries.
+    #[error("a content index must contain at least one entry")]
+    Empty,
+}
+
+/// Represents the path of an entry in a content index.
+///
+/// Paths follow the rules of the Fuchsia archive format: they are
+/// `/`-separated, may not begin or end with `/`, may not contain empty,
+/// `.`, or `..` segments, may not contain NUL bytes, and must be at most
+/// 65535 bytes long.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ContentPath(String);
+
+impl ContentPath {
+    /// Creates a new content path, validating it.
+    pub fn new(path: impl Into<String>) -> Result<Self, ContentIndexError> {
+        let path = path.into();
+        let invalid = |reason| ContentIndexError::InvalidPath {
+            path: path.clone(),
+            reason,
+        };
+
+        if path.is_empty() {
+            return Err(invalid("paths must not be empty"));
+        }
+
+        if path.len() > u16::MAX as usize {
+            return Err(invalid("paths must be at most 65535 bytes long"));
+        }
+
+        if path.contains('\0') {
+            return Err(invalid("paths must not contain NUL bytes"));
+        }
+
+        if path.starts_with('/') || path.ends_with('/') {
+            return Err(invalid("paths must not begin or end with `/`"));
+        }
+
+        for segment in path.split('/') {
+            match segment {
+                "" => return Err(invalid("paths must not contain empty segments")),
+                "." | ".." => return Err(invalid("paths must not contain `.` or `..` segments")),
+                _ => {}
+            }
+        }
+
+        Ok(Self(path))
+    }
+
+    /// Gets the path as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Gets the segments of the path.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.0.split('/')
+    }
+}
+
+impl fmt::Display for ContentPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for ContentPath {
+    type Err = ContentIndexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::new(s)
+    }
+}
+
+impl TryFrom<String> for ContentPath {
+    type Error = ContentIndexError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::new(s)
+    }
+}
+
+impl From<ContentPath> for String {
+    fn from(path: ContentPath) -> Self {
+        path.0
+    }
+}
+
+impl Borrow<str> for ContentPath {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for ContentPath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Represents the content index of a multi-file release.
+///
+/// Entries are kept ordered byte-wise by path.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    try_from = "BTreeMap<ContentPath, AnyHash>",
+    into = "BTreeMap<ContentPath, AnyHash>"
+)]
+pub struct ContentIndex {
+    entries: BTreeMap<ContentPath, AnyHash>,
+}
+
+impl ContentIndex {
+    /// Creates a new, empty content index.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts an entry into the content index.
+    ///
+    /// Returns an error if the path is already present in the index or if
+    /// the digest does not use the content index algorithm.
+    pub fn insert(&mut self, path: ContentPath, digest: AnyHash) -> Result<(), ContentIndexError> {
+        if digest.algorithm() != CONTENT_INDEX_ALGORITHM {
+            return Err(ContentIndexError::UnsupportedAlgorithm {
+                path,
+                algorithm: digest.algorithm(),
+            });
+        }
+
+        if self.entries.contains_key(&path) {
+            return Err(ContentIndexError::DuplicatePath { path });
+        }
+
+        self.entries.insert(path, digest);
+        Ok(())
+    }
+
+    /// Gets the digest of the entry with the given path.
+    pub fn get(&self, path: &str) -> Option<&AnyHash> {
+        self.entries.get(path)
+    }
+
+    /// Gets the entries of the content index, ordered by path.
+    pub fn entries(&self) -> impl Iterator<Item = (&ContentPath, &AnyHash)> {
+        self.entries.iter()
+    }
+
+    /// Gets the digests of the content index entries.
+    pub fn digests(&self) -> impl Iterator<Item = &AnyHash> {
+        self.entries.values()
+    }
+
+    /// Gets the number of entries in the content index.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Determines if the content index has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Encodes the content index.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for (path, digest) in &self.entries {
+            bytes.extend_from_slice(digest.bytes());
+            bytes.extend_from_slice(&(path.0.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(path.0.as_bytes());
+        }
+        bytes
+    }
+
+    /// Computes the content digest of the content index.
+    pub fn digest(&self) -> AnyHash {
+        CONTENT_INDEX_ALGORITHM.digest(&self.encode())
+    }
+}
+
+impl TryFrom<BTreeMap<ContentPath, AnyHash>> for ContentIndex {
+    type Error = ContentIndexError;
+
+    fn try_from(entries: BTreeMap<ContentPath, AnyHash>) -> Result<Self, Self::Error> {
+        let mut index = Self::new();
+        for (path, digest) in entries {
+            index.insert(path, digest)?;
+        }
+        Ok(index)
+    }
+}
+
+impl From<ContentIndex> for BTreeMap<ContentPath, AnyHash> {
+    fn from(index: ContentIndex) -> Self {
+        index.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use warg_crypto::hash::{Hash, Sha256};
+
+    #[test]
+    fn test_content_paths() {
+        for path in [
+            "component.wasm",
+            "assets/logo.png",
+            "a/b/c.d",
+            ".hidden",
+            "..dots",
+        ] {
+            assert_eq!(ContentPath::new(path).unwrap().as_str(), path);
+        }
+
+        for path in [
+            "",
+            "/absolute",
+            "trailing/",
+            "empty//segment",
+            "./dot",
+            "a/../b",
+            "nul\0byte",
+        ] {
+            assert!(
+                matches!(
+                    ContentPath::new(path),
+                    Err(ContentIndexError::InvalidPath { .. })
+                ),
+                "expected `{path}` to be invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_content_index_digest() {
+        let component = AnyHash::from(Hash::<Sha256>::of("component"));
+        let readme = AnyHash::from(Hash::<Sha256>::of("readme"));
+
+        let mut index = ContentIndex::new();
+        index
+            .insert("README.md".parse().unwrap(), readme.clone())
+            .unwrap();
+        index
+            .insert(COMPONENT_ENTRY_PATH.parse().unwrap(), component.clone())
+            .unwrap();
+
+        // Entries are ordered byte-wise by path
+        let mut expected = Vec::new();
+        expected.extend_from_slice(readme.bytes());
+        expected.extend_from_slice(&9u16.to_le_bytes());
+        expected.extend_from_slice(b"README.md");
+        expected.extend_from_slice(component.bytes());
+        expected.extend_from_slice(&14u16.to_le_bytes());
+        expected.extend_from_slice(b"component.wasm");
+
+        assert_eq!(index.encode(), expected);
+        assert_eq!(index.digest(), HashAlgorithm::Sha256.digest(&expected));
+        assert_eq!(index.get(COMPONENT_ENTRY_PATH), Some(&component));
+
+        assert_eq!(
+            index.insert("README.md".parse().unwrap(), readme),
+            Err(ContentIndexError::DuplicatePath {
+                path: "README.md".parse().unwrap()
+            })
+        );
+    }
+}
@@ -2,6 +2,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use std::collections::HashSet;
 use warg_crypto::{hash::AnyHash, Decode};
 
+pub mod content;
 pub mod operator;
 pub mod package;
 mod proto_envelope;
@@ -9,7 +10,8 @@ pub mod registry;
 mod serde_envelope;
 
 pub use proto_envelope::{
-    ProtoEnvelope, ProtoEnvelopeBody, PublishedProtoEnvelope, PublishedProtoEnvelopeBody,
+    CosignError, Cosignature, ProtoEnvelope, ProtoEnvelopeBody, PublishedProtoEnvelope,
+    PublishedProtoEnvelopeBody,
 };
 pub use semver::{Version, VersionReq};
 pub use serde_envelope::SerdeEnvelope;
@@ -34,6 +36,32 @@ pub trait Validator:
 
     /// Validates the given record.
     fn validate(self, record: &ProtoEnvelope<Self::Record>) -> Result<Self, Self::Error>;
+
+    /// Validates the given record at the given index of the registry log.
+    ///
+    /// Validators that do not depend on the position of records in the
+    /// registry log need not implement this.
+    fn validate_at(
+        self,
+        record: &ProtoEnvelope<Self::Record>,
+        _registry_index: registry::RegistryIndex,
+    ) -> Result<Self, Self::Error> {
+        self.validate(record)
+    }
+
+    /// Validates the given records in order.
+    fn validate_all<'a>(
+        mut self,
+        records: impl IntoIterator<Item = &'a ProtoEnvelope<Self::Record>>,
+    ) -> Result<Self, Self::Error>
+    where
+        Self::Record: 'a,
+    {
+        for record in records {
+            self = self.validate(record)?;
+        }
+        Ok(self)
+    }
 }
 
 /// Helpers for converting to and from protobuf
@@ -96,4 +124,33 @@ mod timestamp {
                 nsecs.parse::<u32>().map_err(D::Error::custom)?,
             ))
     }
+
+    /// Helper module for serializing and deserializing optional timestamps.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+        use std::time::SystemTime;
+
+        pub fn serialize<S>(
+            timestamp: &Option<SystemTime>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            match timestamp {
+                Some(timestamp) => super::serialize(timestamp, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<SystemTime>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(with = "super")] SystemTime);
+
+            Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(timestamp)| timestamp))
+        }
+    }
 }
@@ -9,11 +9,19 @@ use crate::{pbjson_to_prost_timestamp, prost_to_pbjson_timestamp, registry::Reco
 mod model;
 mod state;
 
-pub use model::{OperatorEntry, OperatorRecord};
-pub use state::{LogState, NamespaceState, ValidationError};
+pub use model::{OperatorEntry, OperatorRecord, Permission};
+pub use state::{Block, LogState, NamespaceState, RetiredKey, ValidationError};
 
 /// The currently supported operator protocol version.
-pub const OPERATOR_RECORD_VERSION: u32 = 0;
+///
+/// Version 1 introduces the `block` permission and the entries that block
+/// packages and content.
+pub const OPERATOR_RECORD_VERSION: u32 = 1;
+
+/// The legacy operator protocol version.
+///
+/// In version 0, packages and content cannot be blocked.
+pub const LEGACY_OPERATOR_RECORD_VERSION: u32 = 0;
 
 impl Decode for model::OperatorRecord {
     fn decode(bytes: &[u8]) -> Result<Self, Error> {
@@ -90,6 +98,14 @@ impl TryFrom<protobuf::OperatorEntry> for model::OperatorEntry {
                 namespace: import_namespace.namespace,
                 registry: import_namespace.registry,
             },
+            Contents::BlockPackage(block_package) => model::OperatorEntry::BlockPackage {
+                name: block_package.name.parse()?,
+                reason: block_package.reason,
+            },
+            Contents::BlockContent(block_content) => model::OperatorEntry::BlockContent {
+                content: block_content.content_hash.parse()?,
+                reason: block_content.reason,
+            },
         };
         Ok(output)
     }
@@ -112,6 +128,7 @@ impl TryFrom<i32> for model::Permission {
             protobuf::OperatorPermission::Commit => Ok(model::Permission::Commit),
             protobuf::OperatorPermission::DefineNamespace => Ok(model::Permission::DefineNamespace),
             protobuf::OperatorPermission::ImportNamespace => Ok(model::Permission::ImportNamespace),
+            protobuf::OperatorPermission::Block => Ok(model::Permission::Block),
         }
     }
 }
@@ -182,6 +199,18 @@ impl<'a> From<&'a model::OperatorEntry> for protobuf::OperatorEntry {
                 namespace: namespace.clone(),
                 registry: registry.clone(),
             }),
+            model::OperatorEntry::BlockPackage { name, reason } => {
+                Contents::BlockPackage(protobuf::OperatorBlockPackage {
+                    name: name.to_string(),
+                    reason: reason.clone(),
+                })
+            }
+            model::OperatorEntry::BlockContent { content, reason } => {
+                Contents::BlockContent(protobuf::OperatorBlockContent {
+                    content_hash: content.to_string(),
+                    reason: reason.clone(),
+                })
+            }
         };
         let contents = Some(contents);
         protobuf::OperatorEntry { contents }
@@ -194,6 +223,7 @@ impl<'a> From<&'a model::Permission> for i32 {
             model::Permission::Commit => protobuf::OperatorPermission::Commit,
             model::Permission::DefineNamespace => protobuf::OperatorPermission::DefineNamespace,
             model::Permission::ImportNamespace => protobuf::OperatorPermission::ImportNamespace,
+            model::Permission::Block => protobuf::OperatorPermission::Block,
         };
         proto_perm.into()
     }
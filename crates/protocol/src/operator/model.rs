@@ -1,4 +1,4 @@
-use crate::registry::RecordId;
+use crate::registry::{PackageName, RecordId};
 use core::fmt;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
@@ -36,10 +36,15 @@ pub enum Permission {
     DefineNamespace,
     /// Permission to import namespace from another registry and add to the operator log.
     ImportNamespace,
+    /// Permission to block packages and content in the registry.
+    ///
+    /// Only valid in records using protocol version 1 or later; it is not
+    /// granted by the `init` entry and must be granted explicitly.
+    Block,
 }
 
 impl Permission {
-    /// Gets an array of all permissions.
+    /// Gets an array of the permissions granted by the `init` entry.
     pub const fn all() -> [Permission; 3] {
         [
             Permission::Commit,
@@ -55,6 +60,7 @@ impl fmt::Display for Permission {
             Permission::Commit => write!(f, "commit"),
             Permission::DefineNamespace => write!(f, "defineNamespace"),
             Permission::ImportNamespace => write!(f, "importNamespace"),
+            Permission::Block => write!(f, "block"),
         }
     }
 }
@@ -67,6 +73,7 @@ impl FromStr for Permission {
             "commit" => Ok(Permission::Commit),
             "defineNamespace" => Ok(Permission::DefineNamespace),
             "importNamespace" => Ok(Permission::ImportNamespace),
+            "block" => Ok(Permission::Block),
             _ => Err(()),
         }
     }
@@ -84,13 +91,15 @@ pub enum OperatorEntry {
         key: signing::PublicKey,
     },
     /// Grant the specified key a permission.
-    /// The author of this entry must have the permission.
+    /// The author of this entry must have the permission; the `block`
+    /// permission may be granted by any key with the `commit` permission.
     GrantFlat {
         key: signing::PublicKey,
         permissions: Vec<Permission>,
     },
     /// Remove a permission from a key.
-    /// The author of this entry must have the permission.
+    /// The author of this entry must have the permission; the `block`
+    /// permission may be revoked by any key with the `commit` permission.
     RevokeFlat {
         key_id: signing::KeyID,
         permissions: Vec<Permission>,
@@ -99,6 +108,13 @@ pub enum OperatorEntry {
     DefineNamespace { namespace: String },
     /// The registry defines a namespace as imported from another registry.
     ImportNamespace { namespace: String, registry: String },
+    /// The registry blocks a package from being published or downloaded.
+    /// Requires protocol version 1 or later.
+    BlockPackage { name: PackageName, reason: String },
+    /// The registry blocks the content of a release from being served or
+    /// downloaded.
+    /// Requires protocol version 1 or later.
+    BlockContent { content: AnyHash, reason: String },
 }
 
 impl OperatorEntry {
@@ -109,6 +125,19 @@ impl OperatorEntry {
             Self::GrantFlat { .. } | Self::RevokeFlat { .. } => Some(Permission::Commit),
             Self::DefineNamespace { .. } => Some(Permission::DefineNamespace),
             Self::ImportNamespace { .. } => Some(Permission::ImportNamespace),
+            Self::BlockPackage { .. } | Self::BlockContent { .. } => Some(Permission::Block),
+        }
+    }
+
+    /// Check if this entry blocks packages or content, or delegates the
+    /// permission to do so
+    pub fn blocks(&self) -> bool {
+        match self {
+            Self::GrantFlat { permissions, .. } | Self::RevokeFlat { permissions, .. } => {
+                permissions.contains(&Permission::Block)
+            }
+            Self::BlockPackage { .. } | Self::BlockContent { .. } => true,
+            _ => false,
         }
     }
 }
@@ -1,12 +1,13 @@
-use super::{model, OPERATOR_RECORD_VERSION};
+use super::{model, LEGACY_OPERATOR_RECORD_VERSION, OPERATOR_RECORD_VERSION};
 use crate::registry::PackageName;
 use crate::registry::RecordId;
+use crate::registry::{RegistryIndex, RegistryLen};
 use crate::ProtoEnvelope;
-use indexmap::{IndexMap, IndexSet};
+use indexmap::{map::Entry, IndexMap, IndexSet};
 use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
 use thiserror::Error;
-use warg_crypto::hash::{HashAlgorithm, Sha256};
+use warg_crypto::hash::{AnyHash, HashAlgorithm};
 use warg_crypto::{signing, Signable};
 
 #[derive(Error, Debug)]
@@ -56,6 +57,14 @@ pub enum ValidationError {
     #[error("protocol version {version} not allowed")]
     ProtocolVersionNotAllowed { version: u32 },
 
+    #[error(
+        "protocol version {version} is lower than the operator log's protocol version {expected}"
+    )]
+    ProtocolVersionDowngrade { version: u32, expected: u32 },
+
+    #[error("protocol version {version} does not support blocking packages or content")]
+    BlockingNotSupported { version: u32 },
+
     #[error("record has lower timestamp than previous")]
     TimestampLowerThanPrevious,
 
@@ -67,6 +76,12 @@ pub enum ValidationError {
 
     #[error("the namespace `{namespace}` is already defined and cannot be redefined")]
     NamespaceAlreadyDefined { namespace: String },
+
+    #[error("the package `{name}` is already blocked")]
+    PackageAlreadyBlocked { name: PackageName },
+
+    #[error("the content with digest `{content}` is already blocked")]
+    ContentAlreadyBlocked { content: AnyHash },
 }
 
 /// The namespace definition.
@@ -93,6 +108,41 @@ pub enum NamespaceState {
     },
 }
 
+/// Represents information about a package or content blocked by the
+/// registry operator.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Block {
+    /// The key id that published the block.
+    pub by: signing::KeyID,
+    /// The timestamp of the block.
+    #[serde(with = "crate::timestamp")]
+    pub timestamp: SystemTime,
+    /// The reason for the block.
+    pub reason: String,
+}
+
+/// Represents information about a key whose permission to sign
+/// checkpoints was revoked.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RetiredKey {
+    /// The key id that revoked the permission.
+    pub by: signing::KeyID,
+    /// The timestamp of the revocation.
+    #[serde(with = "crate::timestamp")]
+    pub timestamp: SystemTime,
+    /// The registry index of the record that revoked the permission.
+    ///
+    /// Checkpoints signed by the key remain valid only for log lengths up
+    /// to this index, i.e. for checkpoints that do not include the
+    /// revocation. This is `None` if the record was validated without its
+    /// position in the registry log, in which case no checkpoints signed by
+    /// the key are accepted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub registry_index: Option<RegistryIndex>,
+}
+
 /// Information about the current head of the operator log.
 ///
 /// A head is the last validated record digest and timestamp.
@@ -117,6 +167,12 @@ pub struct LogState {
     /// The current head of the state.
     #[serde(skip_serializing_if = "Option::is_none")]
     head: Option<Head>,
+    /// The protocol version of the last validated record.
+    ///
+    /// States persisted before versioned records were introduced default to
+    /// the legacy version.
+    #[serde(skip_serializing_if = "is_legacy_version")]
+    version: u32,
     /// The permissions of each key.
     #[serde(skip_serializing_if = "IndexMap::is_empty")]
     permissions: IndexMap<signing::KeyID, IndexSet<model::Permission>>,
@@ -126,6 +182,15 @@ pub struct LogState {
     /// The namespaces known to the state. The key is the lowercased namespace.
     #[serde(skip_serializing_if = "IndexMap::is_empty")]
     namespaces: IndexMap<String, NamespaceDefinition>,
+    /// The packages blocked by the operator.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    blocked_packages: IndexMap<PackageName, Block>,
+    /// The content blocked by the operator.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    blocked_content: IndexMap<AnyHash, Block>,
+    /// The keys that previously had permission to sign checkpoints.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    retired_keys: IndexMap<signing::KeyID, RetiredKey>,
 }
 
 impl LogState {
@@ -141,6 +206,14 @@ impl LogState {
         &self.head
     }
 
+    /// Gets the hash algorithm used by the registry, as set by the init
+    /// entry of the log.
+    ///
+    /// Returns `None` if no records have been validated yet.
+    pub fn hash_algorithm(&self) -> Option<HashAlgorithm> {
+        self.algorithm
+    }
+
     /// Validates an individual operator record.
     ///
     /// It is expected that `validate` is called in order of the
@@ -149,10 +222,93 @@ impl LogState {
     /// Note that on failure, the log state is consumed to prevent
     /// invalid state from being used in future validations.
     pub fn validate(
-        mut self,
+        self,
+        record: &ProtoEnvelope<model::OperatorRecord>,
+    ) -> Result<Self, ValidationError> {
+        self.validate_records(
+            std::iter::once((None, record)),
+            signing::BatchVerifier::unbatched(),
+        )
+    }
+
+    /// Validates an individual operator record at the given index of the
+    /// registry log.
+    ///
+    /// Unlike [`LogState::validate`], this records where in the registry log
+    /// keys were retired so that checkpoints they signed beforehand remain
+    /// valid; see [`LogState::key_had_permission_to_sign_checkpoint`].
+    ///
+    /// Note that on failure, the log state is consumed to prevent
+    /// invalid state from being used in future validations.
+    pub fn validate_at(
+        self,
         record: &ProtoEnvelope<model::OperatorRecord>,
+        registry_index: RegistryIndex,
     ) -> Result<Self, ValidationError> {
-        self.validate_record(record)?;
+        self.validate_records(
+            std::iter::once((Some(registry_index), record)),
+            signing::BatchVerifier::unbatched(),
+        )
+    }
+
+    /// Validates a sequence of operator records.
+    ///
+    /// This is equivalent to calling [`LogState::validate`] for each record
+    /// in order, but the record signatures are verified as a batch, which is
+    /// considerably faster for long sequences of records.
+    ///
+    /// Note that on failure, the log state is consumed to prevent
+    /// invalid state from being used in future validations.
+    pub fn validate_all<'a>(
+        self,
+        records: impl IntoIterator<Item = &'a ProtoEnvelope<model::OperatorRecord>>,
+    ) -> Result<Self, ValidationError> {
+        self.validate_records(
+            records.into_iter().map(|record| (None, record)),
+            signing::BatchVerifier::new(),
+        )
+    }
+
+    /// Validates a sequence of operator records, each at the given index of
+    /// the registry log.
+    ///
+    /// This is equivalent to calling [`LogState::validate_at`] for each
+    /// record in order, but the record signatures are verified as a batch.
+    ///
+    /// Note that on failure, the log state is consumed to prevent
+    /// invalid state from being used in future validations.
+    pub fn validate_all_at<'a>(
+        self,
+        records: impl IntoIterator<Item = (RegistryIndex, &'a ProtoEnvelope<model::OperatorRecord>)>,
+    ) -> Result<Self, ValidationError> {
+        self.validate_records(
+            records
+                .into_iter()
+                .map(|(registry_index, record)| (Some(registry_index), record)),
+            signing::BatchVerifier::new(),
+        )
+    }
+
+    fn validate_records<'a>(
+        mut self,
+        records: impl IntoIterator<
+            Item = (
+                Option<RegistryIndex>,
+                &'a ProtoEnvelope<model::OperatorRecord>,
+            ),
+        >,
+        mut signatures: signing::BatchVerifier,
+    ) -> Result<Self, ValidationError> {
+        for (registry_index, record) in records {
+            if let Err(e) = self.validate_record(record, registry_index, &mut signatures) {
+                // Report an invalid signature of an earlier record first, as
+                // validating the records one at a time would
+                verify_signatures(&mut signatures)?;
+                return Err(e);
+            }
+        }
+
+        verify_signatures(&mut signatures)?;
         Ok(self)
     }
 
@@ -180,12 +336,63 @@ impl LogState {
         }
     }
 
+    /// Gets the block of the given package.
+    ///
+    /// Returns `None` if the package is not blocked.
+    pub fn package_block(&self, name: &PackageName) -> Option<&Block> {
+        self.blocked_packages.get(name)
+    }
+
+    /// Gets the packages blocked by the operator.
+    pub fn blocked_packages(&self) -> impl Iterator<Item = (&PackageName, &Block)> {
+        self.blocked_packages.iter()
+    }
+
+    /// Gets the block of the given content digest.
+    ///
+    /// Returns `None` if the content is not blocked.
+    pub fn content_block(&self, content: &AnyHash) -> Option<&Block> {
+        self.blocked_content.get(content)
+    }
+
     /// Checks the key has permission to sign checkpoints.
     pub fn key_has_permission_to_sign_checkpoints(&self, key_id: &signing::KeyID) -> bool {
         self.check_key_permissions(key_id, &[model::Permission::Commit])
             .is_ok()
     }
 
+    /// Checks the key had permission to sign a checkpoint of the given
+    /// log length.
+    ///
+    /// Unlike [`LogState::key_has_permission_to_sign_checkpoints`], this
+    /// accepts keys that have since been retired, provided the checkpoint
+    /// does not include the record that revoked the key's permission.
+    ///
+    /// Retirement is bound to the position of the revocation in the registry
+    /// log rather than to a time, as a checkpoint's timestamp is chosen by
+    /// its signer.
+    pub fn key_had_permission_to_sign_checkpoint(
+        &self,
+        key_id: &signing::KeyID,
+        log_length: RegistryLen,
+    ) -> bool {
+        self.key_has_permission_to_sign_checkpoints(key_id)
+            || self
+                .retired_keys
+                .get(key_id)
+                .and_then(|retired| retired.registry_index)
+                .map(|index| log_length <= index)
+                .unwrap_or(false)
+    }
+
+    /// Gets the retirement information of a key that previously had
+    /// permission to sign checkpoints.
+    ///
+    /// Returns `None` if the key was never retired.
+    pub fn retired_key(&self, key_id: &signing::KeyID) -> Option<&RetiredKey> {
+        self.retired_keys.get(key_id)
+    }
+
     fn initialized(&self) -> bool {
         // The package log is initialized if the hash algorithm is set
         self.algorithm.is_some()
@@ -194,6 +401,8 @@ impl LogState {
     fn validate_record(
         &mut self,
         envelope: &ProtoEnvelope<model::OperatorRecord>,
+        registry_index: Option<RegistryIndex>,
+        signatures: &mut signing::BatchVerifier,
     ) -> Result<(), ValidationError> {
         let record = envelope.as_ref();
 
@@ -207,10 +416,15 @@ impl LogState {
         self.validate_record_timestamp(record)?;
 
         // Validate entries
-        self.validate_record_entries(envelope.key_id(), &record.entries)?;
+        self.validate_record_entries(
+            envelope.key_id(),
+            record.timestamp,
+            registry_index,
+            &record.entries,
+        )?;
 
         // At this point the digest algorithm must be set via an init entry
-        let _algorithm = self
+        let algorithm = self
             .algorithm
             .ok_or(ValidationError::InitialRecordDoesNotInit)?;
 
@@ -221,12 +435,17 @@ impl LogState {
             }
         })?;
 
-        // Validate the envelope signature
-        model::OperatorRecord::verify(key, envelope.content_bytes(), envelope.signature())?;
+        // Queue the envelope signature for verification
+        model::OperatorRecord::verify_batched(
+            signatures,
+            key,
+            envelope.content_bytes(),
+            envelope.signature(),
+        );
 
         // Update the state head
         self.head = Some(Head {
-            digest: RecordId::operator_record::<Sha256>(envelope),
+            digest: RecordId::operator_record_for(algorithm, envelope),
             timestamp: record.timestamp,
         });
 
@@ -256,16 +475,25 @@ impl LogState {
     }
 
     fn validate_record_version(
-        &self,
+        &mut self,
         record: &model::OperatorRecord,
     ) -> Result<(), ValidationError> {
-        if record.version == OPERATOR_RECORD_VERSION {
-            Ok(())
-        } else {
-            Err(ValidationError::ProtocolVersionNotAllowed {
+        if record.version > OPERATOR_RECORD_VERSION {
+            return Err(ValidationError::ProtocolVersionNotAllowed {
                 version: record.version,
-            })
+            });
         }
+
+        // A record cannot select an older interpretation of the log
+        if record.version < self.version {
+            return Err(ValidationError::ProtocolVersionDowngrade {
+                version: record.version,
+                expected: self.version,
+            });
+        }
+
+        self.version = record.version;
+        Ok(())
     }
 
     fn validate_record_timestamp(
@@ -284,9 +512,17 @@ impl LogState {
     fn validate_record_entries(
         &mut self,
         signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        registry_index: Option<RegistryIndex>,
         entries: &[model::OperatorEntry],
     ) -> Result<(), ValidationError> {
         for entry in entries {
+            if self.version == LEGACY_OPERATOR_RECORD_VERSION && entry.blocks() {
+                return Err(ValidationError::BlockingNotSupported {
+                    version: self.version,
+                });
+            }
+
             if let Some(permission) = entry.required_permission() {
                 self.check_key_permissions(signer_key_id, &[permission])?;
             }
@@ -314,7 +550,13 @@ impl LogState {
                 model::OperatorEntry::RevokeFlat {
                     key_id,
                     permissions,
-                } => self.validate_revoke_entry(signer_key_id, key_id, permissions)?,
+                } => self.validate_revoke_entry(
+                    signer_key_id,
+                    timestamp,
+                    registry_index,
+                    key_id,
+                    permissions,
+                )?,
                 model::OperatorEntry::DefineNamespace { namespace } => {
                     self.validate_namespace(namespace, NamespaceState::Defined)?
                 }
@@ -327,6 +569,12 @@ impl LogState {
                         registry: registry.to_string(),
                     },
                 )?,
+                model::OperatorEntry::BlockPackage { name, reason } => {
+                    self.validate_block_package_entry(signer_key_id, timestamp, name, reason)?
+                }
+                model::OperatorEntry::BlockContent { content, reason } => {
+                    self.validate_block_content_entry(signer_key_id, timestamp, content, reason)?
+                }
             }
         }
 
@@ -363,9 +611,13 @@ impl LogState {
         permissions: &[model::Permission],
     ) -> Result<(), ValidationError> {
         // Check that the current key has the permission they're trying to grant
-        self.check_key_permissions(signer_key_id, permissions)?;
+        self.check_delegated_permissions(signer_key_id, permissions)?;
 
         let grant_key_id = key.fingerprint();
+        if permissions.contains(&model::Permission::Commit) {
+            self.retired_keys.swap_remove(&grant_key_id);
+        }
+
         self.keys.insert(grant_key_id.clone(), key.clone());
         self.permissions
             .entry(grant_key_id)
@@ -378,11 +630,13 @@ impl LogState {
     fn validate_revoke_entry(
         &mut self,
         signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        registry_index: Option<RegistryIndex>,
         key_id: &signing::KeyID,
         permissions: &[model::Permission],
     ) -> Result<(), ValidationError> {
         // Check that the current key has the permission they're trying to revoke
-        self.check_key_permissions(signer_key_id, permissions)?;
+        self.check_delegated_permissions(signer_key_id, permissions)?;
 
         for permission in permissions {
             if !self
@@ -396,10 +650,65 @@ impl LogState {
                     key_id: key_id.clone(),
                 });
             }
+
+            if *permission == model::Permission::Commit {
+                self.retired_keys.insert(
+                    key_id.clone(),
+                    RetiredKey {
+                        by: signer_key_id.clone(),
+                        timestamp,
+                        registry_index,
+                    },
+                );
+            }
         }
         Ok(())
     }
 
+    fn validate_block_package_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        name: &PackageName,
+        reason: &str,
+    ) -> Result<(), ValidationError> {
+        match self.blocked_packages.entry(name.clone()) {
+            Entry::Occupied(_) => {
+                Err(ValidationError::PackageAlreadyBlocked { name: name.clone() })
+            }
+            Entry::Vacant(e) => {
+                e.insert(Block {
+                    by: signer_key_id.clone(),
+                    timestamp,
+                    reason: reason.to_string(),
+                });
+                Ok(())
+            }
+        }
+    }
+
+    fn validate_block_content_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        content: &AnyHash,
+        reason: &str,
+    ) -> Result<(), ValidationError> {
+        match self.blocked_content.entry(content.clone()) {
+            Entry::Occupied(_) => Err(ValidationError::ContentAlreadyBlocked {
+                content: content.clone(),
+            }),
+            Entry::Vacant(e) => {
+                e.insert(Block {
+                    by: signer_key_id.clone(),
+                    timestamp,
+                    reason: reason.to_string(),
+                });
+                Ok(())
+            }
+        }
+    }
+
     fn validate_namespace(
         &mut self,
         namespace: &str,
@@ -440,6 +749,27 @@ impl LogState {
         }
     }
 
+    // Checks that a key may grant or revoke the given permissions.
+    //
+    // The `block` permission is never held by the init key, so it is
+    // delegated by keys with the `commit` permission required of every
+    // grant and revoke entry.
+    fn check_delegated_permissions(
+        &self,
+        key_id: &signing::KeyID,
+        permissions: &[model::Permission],
+    ) -> Result<(), ValidationError> {
+        for permission in permissions {
+            let needed = match permission {
+                model::Permission::Block => model::Permission::Commit,
+                permission => *permission,
+            };
+
+            self.check_key_permissions(key_id, &[needed])?;
+        }
+        Ok(())
+    }
+
     fn check_key_permissions(
         &self,
         key_id: &signing::KeyID,
@@ -462,6 +792,16 @@ impl LogState {
     }
 }
 
+fn is_legacy_version(version: &u32) -> bool {
+    *version == LEGACY_OPERATOR_RECORD_VERSION
+}
+
+fn verify_signatures(signatures: &mut signing::BatchVerifier) -> Result<(), ValidationError> {
+    signatures
+        .verify()
+        .map_err(|e| ValidationError::SignatureError(e.source))
+}
+
 impl crate::Validator for LogState {
     type Record = model::OperatorRecord;
     type Error = ValidationError;
@@ -469,6 +809,21 @@ impl crate::Validator for LogState {
     fn validate(self, record: &ProtoEnvelope<Self::Record>) -> Result<Self, Self::Error> {
         self.validate(record)
     }
+
+    fn validate_at(
+        self,
+        record: &ProtoEnvelope<Self::Record>,
+        registry_index: RegistryIndex,
+    ) -> Result<Self, Self::Error> {
+        self.validate_at(record, registry_index)
+    }
+
+    fn validate_all<'a>(
+        self,
+        records: impl IntoIterator<Item = &'a ProtoEnvelope<Self::Record>>,
+    ) -> Result<Self, Self::Error> {
+        self.validate_all(records)
+    }
 }
 
 #[cfg(test)]
@@ -478,8 +833,8 @@ mod tests {
     use super::*;
     use warg_crypto::signing::generate_p256_pair;
 
-    use std::time::SystemTime;
-    use warg_crypto::hash::HashAlgorithm;
+    use std::time::{Duration, SystemTime};
+    use warg_crypto::hash::{HashAlgorithm, Sha256};
 
     #[test]
     fn test_validate_base_log() {
@@ -510,16 +865,20 @@ mod tests {
                     timestamp,
                 }),
                 algorithm: Some(HashAlgorithm::Sha256),
+                version: LEGACY_OPERATOR_RECORD_VERSION,
                 permissions: IndexMap::from([(
                     alice_id.clone(),
                     IndexSet::from([
                         model::Permission::Commit,
                         model::Permission::DefineNamespace,
-                        model::Permission::ImportNamespace
+                        model::Permission::ImportNamespace,
                     ]),
                 )]),
                 keys: IndexMap::from([(alice_id, alice_pub)]),
                 namespaces: IndexMap::new(),
+                blocked_packages: IndexMap::new(),
+                blocked_content: IndexMap::new(),
+                retired_keys: IndexMap::new(),
             }
         );
     }
@@ -552,6 +911,7 @@ mod tests {
                 timestamp,
             }),
             algorithm: Some(HashAlgorithm::Sha256),
+            version: LEGACY_OPERATOR_RECORD_VERSION,
             permissions: IndexMap::from([(
                 alice_id.clone(),
                 IndexSet::from([
@@ -562,6 +922,9 @@ mod tests {
             )]),
             keys: IndexMap::from([(alice_id, alice_pub)]),
             namespaces: IndexMap::new(),
+            blocked_packages: IndexMap::new(),
+            blocked_content: IndexMap::new(),
+            retired_keys: IndexMap::new(),
         };
 
         assert_eq!(state, expected);
@@ -634,6 +997,7 @@ mod tests {
                 timestamp,
             }),
             algorithm: Some(HashAlgorithm::Sha256),
+            version: LEGACY_OPERATOR_RECORD_VERSION,
             permissions: IndexMap::from([(
                 alice_id.clone(),
                 IndexSet::from([
@@ -661,6 +1025,9 @@ mod tests {
                     },
                 ),
             ]),
+            blocked_packages: IndexMap::new(),
+            blocked_content: IndexMap::new(),
+            retired_keys: IndexMap::new(),
         };
 
         assert_eq!(state, expected);
@@ -721,4 +1088,91 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_key_rotation() {
+        let (alice_pub, alice_priv) = generate_p256_pair();
+        let alice_id = alice_pub.fingerprint();
+        let (bob_pub, bob_priv) = generate_p256_pair();
+        let bob_id = bob_pub.fingerprint();
+
+        let init_timestamp = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let record = model::OperatorRecord {
+            prev: None,
+            version: 0,
+            timestamp: init_timestamp,
+            entries: vec![model::OperatorEntry::Init {
+                hash_algorithm: HashAlgorithm::Sha256,
+                key: alice_pub,
+            }],
+        };
+
+        let init_envelope =
+            ProtoEnvelope::signed_contents(&alice_priv, record).expect("failed to sign envelope");
+        let state = LogState::default().validate_at(&init_envelope, 0).unwrap();
+
+        // Rotate the checkpoint key from alice to bob at registry index 5
+        let rotate_timestamp = init_timestamp + Duration::from_secs(1000);
+        let record = model::OperatorRecord {
+            prev: Some(RecordId::operator_record::<Sha256>(&init_envelope)),
+            version: 0,
+            timestamp: rotate_timestamp,
+            entries: vec![
+                model::OperatorEntry::GrantFlat {
+                    key: bob_pub.clone(),
+                    permissions: vec![model::Permission::Commit],
+                },
+                model::OperatorEntry::RevokeFlat {
+                    key_id: alice_id.clone(),
+                    permissions: vec![model::Permission::Commit],
+                },
+            ],
+        };
+
+        let envelope =
+            ProtoEnvelope::signed_contents(&alice_priv, record).expect("failed to sign envelope");
+        let state = state.validate_at(&envelope, 5).unwrap();
+
+        assert!(!state.key_has_permission_to_sign_checkpoints(&alice_id));
+        assert!(state.key_has_permission_to_sign_checkpoints(&bob_id));
+        assert_eq!(
+            state.retired_key(&alice_id),
+            Some(&RetiredKey {
+                by: alice_id.clone(),
+                timestamp: rotate_timestamp,
+                registry_index: Some(5),
+            })
+        );
+
+        // Checkpoints signed by alice that do not include the rotation remain
+        // valid, regardless of their timestamps
+        assert!(state.key_had_permission_to_sign_checkpoint(&alice_id, 1));
+        assert!(state.key_had_permission_to_sign_checkpoint(&alice_id, 5));
+        assert!(!state.key_had_permission_to_sign_checkpoint(&alice_id, 6));
+        assert!(state.key_had_permission_to_sign_checkpoint(&bob_id, 6));
+
+        // Without the position of the rotation, no checkpoints signed by
+        // alice are accepted
+        let unpositioned = LogState::default()
+            .validate_all([&init_envelope, &envelope])
+            .unwrap();
+        assert!(!unpositioned.key_had_permission_to_sign_checkpoint(&alice_id, 1));
+
+        // Granting the permission back un-retires the key
+        let record = model::OperatorRecord {
+            prev: Some(RecordId::operator_record::<Sha256>(&envelope)),
+            version: 0,
+            timestamp: rotate_timestamp + Duration::from_secs(1000),
+            entries: vec![model::OperatorEntry::GrantFlat {
+                key: state.public_key(&alice_id).unwrap().clone(),
+                permissions: vec![model::Permission::Commit],
+            }],
+        };
+
+        let envelope =
+            ProtoEnvelope::signed_contents(&bob_priv, record).expect("failed to sign envelope");
+        let state = state.validate(&envelope).unwrap();
+        assert!(state.key_has_permission_to_sign_checkpoints(&alice_id));
+        assert!(state.retired_key(&alice_id).is_none());
+    }
 }
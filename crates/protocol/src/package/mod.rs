@@ -4,16 +4,28 @@ use thiserror::Error;
 use warg_crypto::{hash::AnyHash, Decode, Encode, Signable};
 use warg_protobuf::protocol as protobuf;
 
-use crate::{pbjson_to_prost_timestamp, prost_to_pbjson_timestamp, registry::RecordId};
+use crate::{
+    content::ContentIndex, pbjson_to_prost_timestamp, prost_to_pbjson_timestamp, registry::RecordId,
+};
 
 mod model;
 mod state;
 
-pub use model::{PackageEntry, PackageRecord, Permission};
-pub use state::{LogState, Release, ReleaseState, ValidationError};
+pub use model::{
+    PackageEntry, PackageMetadata, PackageRecord, Permission, ReleaseDependency, YankReason,
+};
+pub use state::{Deprecation, GrantScope, LogState, Release, ReleaseState, ValidationError};
 
 /// The currently supported package protocol version.
-pub const PACKAGE_RECORD_VERSION: u32 = 0;
+///
+/// Version 1 requires the `admin` permission to grant and revoke permissions.
+pub const PACKAGE_RECORD_VERSION: u32 = 1;
+
+/// The legacy package protocol version.
+///
+/// In version 0, holding a permission allows granting it to and revoking it
+/// from other keys.
+pub const LEGACY_PACKAGE_RECORD_VERSION: u32 = 0;
 
 impl Decode for model::PackageRecord {
     fn decode(bytes: &[u8]) -> Result<Self, Error> {
@@ -74,6 +86,11 @@ impl TryFrom<protobuf::PackageEntry> for model::PackageEntry {
                     .into_iter()
                     .map(TryInto::try_into)
                     .collect::<Result<_, _>>()?,
+                expires: grant_flat
+                    .expires
+                    .map(|timestamp| pbjson_to_prost_timestamp(timestamp).try_into())
+                    .transpose()?,
+                versions: grant_flat.versions.map(|v| v.parse()).transpose()?,
             },
             Contents::RevokeFlat(revoke_flat) => model::PackageEntry::RevokeFlat {
                 key_id: revoke_flat.key_id.into(),
@@ -83,15 +100,61 @@ impl TryFrom<protobuf::PackageEntry> for model::PackageEntry {
                     .map(TryInto::try_into)
                     .collect::<Result<_, _>>()?,
             },
+            Contents::RotateKey(rotate_key) => model::PackageEntry::RotateKey {
+                old_key_id: rotate_key.old_key_id.into(),
+                new_key: rotate_key.new_key.parse()?,
+            },
             Contents::Release(release) => model::PackageEntry::Release {
                 version: release
                     .version
                     .parse()
                     .map_err(|error| Error::new(error) as Error)?,
                 content: release.content_hash.parse()?,
+                index: if release.index.is_empty() {
+                    None
+                } else {
+                    let mut index = ContentIndex::new();
+                    for entry in release.index {
+                        index.insert(entry.path.parse()?, entry.content_hash.parse()?)?;
+                    }
+                    Some(index)
+                },
+                dependencies: release
+                    .dependencies
+                    .into_iter()
+                    .map(|dependency| -> Result<_, Error> {
+                        Ok(model::ReleaseDependency {
+                            name: dependency.name.parse()?,
+                            requirement: dependency.version_req.parse()?,
+                        })
+                    })
+                    .collect::<Result<_, _>>()?,
             },
             Contents::Yank(yank) => model::PackageEntry::Yank {
                 version: yank.version.parse()?,
+                reason: yank.reason.map(Into::into),
+            },
+            Contents::Unyank(unyank) => model::PackageEntry::Unyank {
+                version: unyank.version.parse()?,
+            },
+            Contents::Deprecate(deprecate) => model::PackageEntry::Deprecate {
+                successor: deprecate.successor.map(|s| s.parse()).transpose()?,
+                message: deprecate.message,
+            },
+            Contents::Undeprecate(_) => model::PackageEntry::Undeprecate,
+            Contents::ReleaseThreshold(release_threshold) => {
+                model::PackageEntry::ReleaseThreshold {
+                    threshold: release_threshold.threshold,
+                }
+            }
+            Contents::Metadata(metadata) => model::PackageEntry::Metadata {
+                version: metadata.version.map(|v| v.parse()).transpose()?,
+                metadata: model::PackageMetadata {
+                    description: metadata.description,
+                    license: metadata.license,
+                    repository: metadata.repository,
+                    homepage: metadata.homepage,
+                },
             },
         };
         Ok(output)
@@ -114,6 +177,7 @@ impl TryFrom<i32> for model::Permission {
             }
             protobuf::PackagePermission::Release => Ok(model::Permission::Release),
             protobuf::PackagePermission::Yank => Ok(model::Permission::Yank),
+            protobuf::PackagePermission::Admin => Ok(model::Permission::Admin),
         }
     }
 }
@@ -159,12 +223,17 @@ impl<'a> From<&'a model::PackageEntry> for protobuf::PackageEntry {
                 key: key.to_string(),
                 hash_algorithm: hash_algorithm.to_string(),
             }),
-            model::PackageEntry::GrantFlat { key, permissions } => {
-                Contents::GrantFlat(protobuf::PackageGrantFlat {
-                    key: key.to_string(),
-                    permissions: permissions.iter().map(Into::into).collect(),
-                })
-            }
+            model::PackageEntry::GrantFlat {
+                key,
+                permissions,
+                expires,
+                versions,
+            } => Contents::GrantFlat(protobuf::PackageGrantFlat {
+                key: key.to_string(),
+                permissions: permissions.iter().map(Into::into).collect(),
+                expires: expires.map(|timestamp| prost_to_pbjson_timestamp(timestamp.into())),
+                versions: versions.as_ref().map(ToString::to_string),
+            }),
             model::PackageEntry::RevokeFlat {
                 key_id,
                 permissions,
@@ -172,15 +241,69 @@ impl<'a> From<&'a model::PackageEntry> for protobuf::PackageEntry {
                 key_id: key_id.to_string(),
                 permissions: permissions.iter().map(Into::into).collect(),
             }),
-            model::PackageEntry::Release { version, content } => {
-                Contents::Release(protobuf::PackageRelease {
+            model::PackageEntry::RotateKey {
+                old_key_id,
+                new_key,
+            } => Contents::RotateKey(protobuf::PackageRotateKey {
+                old_key_id: old_key_id.to_string(),
+                new_key: new_key.to_string(),
+            }),
+            model::PackageEntry::Release {
+                version,
+                content,
+                index,
+                dependencies,
+            } => Contents::Release(protobuf::PackageRelease {
+                version: version.to_string(),
+                content_hash: content.to_string(),
+                index: index
+                    .iter()
+                    .flat_map(ContentIndex::entries)
+                    .map(|(path, digest)| protobuf::PackageContentEntry {
+                        path: path.to_string(),
+                        content_hash: digest.to_string(),
+                    })
+                    .collect(),
+                dependencies: dependencies
+                    .iter()
+                    .map(|dependency| protobuf::PackageDependency {
+                        name: dependency.name.to_string(),
+                        version_req: dependency.requirement.to_string(),
+                    })
+                    .collect(),
+            }),
+            model::PackageEntry::Yank { version, reason } => {
+                Contents::Yank(protobuf::PackageYank {
                     version: version.to_string(),
-                    content_hash: content.to_string(),
+                    reason: reason.clone().map(Into::into),
                 })
             }
-            model::PackageEntry::Yank { version } => Contents::Yank(protobuf::PackageYank {
+            model::PackageEntry::Unyank { version } => Contents::Unyank(protobuf::PackageUnyank {
                 version: version.to_string(),
             }),
+            model::PackageEntry::Deprecate { successor, message } => {
+                Contents::Deprecate(protobuf::PackageDeprecate {
+                    successor: successor.as_ref().map(ToString::to_string),
+                    message: message.clone(),
+                })
+            }
+            model::PackageEntry::Undeprecate => {
+                Contents::Undeprecate(protobuf::PackageUndeprecate {})
+            }
+            model::PackageEntry::ReleaseThreshold { threshold } => {
+                Contents::ReleaseThreshold(protobuf::PackageReleaseThreshold {
+                    threshold: *threshold,
+                })
+            }
+            model::PackageEntry::Metadata { version, metadata } => {
+                Contents::Metadata(protobuf::PackageMetadata {
+                    version: version.as_ref().map(ToString::to_string),
+                    description: metadata.description.clone(),
+                    license: metadata.license.clone(),
+                    repository: metadata.repository.clone(),
+                    homepage: metadata.homepage.clone(),
+                })
+            }
         };
         let contents = Some(contents);
         protobuf::PackageEntry { contents }
@@ -192,6 +315,7 @@ impl<'a> From<&'a model::Permission> for i32 {
         let proto_perm = match permission {
             model::Permission::Release => protobuf::PackagePermission::Release,
             model::Permission::Yank => protobuf::PackagePermission::Yank,
+            model::Permission::Admin => protobuf::PackagePermission::Admin,
         };
         proto_perm.into()
     }
@@ -213,7 +337,22 @@ mod tests {
     #[test]
     fn test_envelope_roundtrip() {
         let (alice_pub, alice_priv) = generate_p256_pair();
-        let (bob_pub, _bob_priv) = generate_p256_pair();
+        let (bob_pub, bob_priv) = generate_p256_pair();
+        let (carol_pub, _carol_priv) = generate_p256_pair();
+
+        let mut index = ContentIndex::new();
+        index
+            .insert(
+                crate::content::COMPONENT_ENTRY_PATH.parse().unwrap(),
+                HashAlgorithm::Sha256.digest(&[0, 1, 2, 3]),
+            )
+            .unwrap();
+        index
+            .insert(
+                "assets/README.md".parse().unwrap(),
+                HashAlgorithm::Sha256.digest(&[4, 5, 6]),
+            )
+            .unwrap();
 
         let record = model::PackageRecord {
             prev: None,
@@ -227,23 +366,87 @@ mod tests {
                 model::PackageEntry::GrantFlat {
                     key: bob_pub.clone(),
                     permissions: vec![model::Permission::Release, model::Permission::Yank],
+                    expires: None,
+                    versions: None,
+                },
+                model::PackageEntry::GrantFlat {
+                    key: carol_pub.clone(),
+                    permissions: vec![model::Permission::Release],
+                    expires: Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(60)),
+                    versions: Some("^1.0".parse().unwrap()),
                 },
                 model::PackageEntry::RevokeFlat {
                     key_id: bob_pub.fingerprint(),
                     permissions: vec![model::Permission::Release],
                 },
+                model::PackageEntry::RotateKey {
+                    old_key_id: bob_pub.fingerprint(),
+                    new_key: carol_pub,
+                },
                 model::PackageEntry::Release {
                     version: Version::new(1, 0, 0),
                     content: HashAlgorithm::Sha256.digest(&[0, 1, 2, 3]),
+                    index: None,
+                    dependencies: Vec::new(),
+                },
+                model::PackageEntry::Release {
+                    version: Version::new(1, 1, 0),
+                    content: index.digest(),
+                    index: Some(index),
+                    dependencies: vec![model::ReleaseDependency {
+                        name: "example:dep".parse().unwrap(),
+                        requirement: "^1.2".parse().unwrap(),
+                    }],
+                },
+                model::PackageEntry::Metadata {
+                    version: None,
+                    metadata: model::PackageMetadata {
+                        description: Some("An example package".to_string()),
+                        license: Some("Apache-2.0".to_string()),
+                        repository: None,
+                        homepage: Some("https://example.com".to_string()),
+                    },
+                },
+                model::PackageEntry::Metadata {
+                    version: Some(Version::new(1, 0, 0)),
+                    metadata: model::PackageMetadata {
+                        repository: Some("https://example.com/repo".to_string()),
+                        ..Default::default()
+                    },
+                },
+                model::PackageEntry::Yank {
+                    version: Version::new(1, 0, 0),
+                    reason: Some(model::YankReason::Security),
+                },
+                model::PackageEntry::Deprecate {
+                    successor: Some("example:successor".parse().unwrap()),
+                    message: Some("use example:successor instead".to_string()),
+                },
+                model::PackageEntry::Undeprecate,
+                model::PackageEntry::ReleaseThreshold { threshold: 2 },
+                model::PackageEntry::Deprecate {
+                    successor: None,
+                    message: None,
+                },
+                model::PackageEntry::Unyank {
+                    version: Version::new(1, 0, 0),
+                },
+                model::PackageEntry::Yank {
+                    version: Version::new(1, 0, 0),
+                    reason: Some(model::YankReason::Other("typo".to_string())),
                 },
             ],
         };
 
-        let first_envelope = match ProtoEnvelope::signed_contents(&alice_priv, record) {
+        let mut first_envelope = match ProtoEnvelope::signed_contents(&alice_priv, record) {
             Ok(value) => value,
             Err(error) => panic!("Failed to sign envelope 1: {:?}", error),
         };
 
+        if let Err(error) = first_envelope.cosign(&bob_priv) {
+            panic!("Failed to cosign envelope 1: {:?}", error);
+        }
+
         let bytes = first_envelope.to_protobuf();
 
         let second_envelope: ProtoEnvelope<model::PackageRecord> =
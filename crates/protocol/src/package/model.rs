@@ -1,6 +1,7 @@
-use crate::registry::RecordId;
+use crate::content::ContentIndex;
+use crate::registry::{PackageName, RecordId};
 use core::fmt;
-use semver::Version;
+use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::{str::FromStr, time::SystemTime};
@@ -24,7 +25,15 @@ impl crate::Record for PackageRecord {
     fn contents(&self) -> HashSet<&AnyHash> {
         self.entries
             .iter()
-            .filter_map(PackageEntry::content)
+            .flat_map(|entry| -> Box<dyn Iterator<Item = &AnyHash>> {
+                match entry {
+                    // The content of a multi-file release is its entries
+                    PackageEntry::Release {
+                        index: Some(index), ..
+                    } => Box::new(index.digests()),
+                    entry => Box::new(entry.content().into_iter()),
+                }
+            })
             .collect()
     }
 }
@@ -36,11 +45,21 @@ impl crate::Record for PackageRecord {
 pub enum Permission {
     Release,
     Yank,
+    /// Allows granting and revoking permissions.
+    ///
+    /// Only enforced for packages using protocol version 1 or later.
+    Admin,
 }
 
 impl Permission {
     /// Gets an array of all permissions.
-    pub const fn all() -> [Permission; 2] {
+    pub const fn all() -> [Permission; 3] {
+        [Permission::Release, Permission::Yank, Permission::Admin]
+    }
+
+    /// Gets an array of the permissions that existed before the
+    /// `admin` permission was introduced.
+    pub const fn legacy() -> [Permission; 2] {
         [Permission::Release, Permission::Yank]
     }
 }
@@ -50,6 +69,7 @@ impl fmt::Display for Permission {
         match self {
             Permission::Release => write!(f, "release"),
             Permission::Yank => write!(f, "yank"),
+            Permission::Admin => write!(f, "admin"),
         }
     }
 }
@@ -61,11 +81,124 @@ impl FromStr for Permission {
         match s {
             "release" => Ok(Permission::Release),
             "yank" => Ok(Permission::Yank),
+            "admin" => Ok(Permission::Admin),
             _ => Err(format!("invalid permission {s:?}")),
         }
     }
 }
 
+/// Descriptive metadata for a package or one of its releases.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageMetadata {
+    /// A short description of the package.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The license of the package (e.g. an SPDX license expression).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// The URL of the package's source repository.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// The URL of the package's homepage.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub homepage: Option<String>,
+}
+
+impl PackageMetadata {
+    /// Determines if no metadata fields are set.
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none()
+            && self.license.is_none()
+            && self.repository.is_none()
+            && self.homepage.is_none()
+    }
+}
+
+/// A registry package a release depends on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseDependency {
+    /// The name of the package depended on.
+    pub name: PackageName,
+    /// The version requirement of the dependency.
+    pub requirement: VersionReq,
+}
+
+impl fmt::Display for ReleaseDependency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{name}@{req}", name = self.name, req = self.requirement)
+    }
+}
+
+impl FromStr for ReleaseDependency {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, requirement) = s.split_once('@').ok_or_else(|| {
+            anyhow::anyhow!("dependency `{s}` must be of the form `<package>@<requirement>`")
+        })?;
+
+        Ok(Self {
+            name: name.parse()?,
+            requirement: requirement.parse()?,
+        })
+    }
+}
+
+/// The reason a release was yanked.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum YankReason {
+    /// The release has a security vulnerability.
+    Security,
+    /// The release is broken and should not be used.
+    Broken,
+    /// The release has been superseded by another release.
+    Superseded,
+    /// A free-text reason for the yank.
+    Other(String),
+}
+
+impl fmt::Display for YankReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            YankReason::Security => write!(f, "security"),
+            YankReason::Broken => write!(f, "broken"),
+            YankReason::Superseded => write!(f, "superseded"),
+            YankReason::Other(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+impl FromStr for YankReason {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(s.to_string().into())
+    }
+}
+
+impl From<String> for YankReason {
+    fn from(s: String) -> Self {
+        match s.as_str() {
+            "security" => YankReason::Security,
+            "broken" => YankReason::Broken,
+            "superseded" => YankReason::Superseded,
+            _ => YankReason::Other(s),
+        }
+    }
+}
+
+impl From<YankReason> for String {
+    fn from(reason: YankReason) -> Self {
+        match reason {
+            YankReason::Other(reason) => reason,
+            reason => reason.to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[non_exhaustive]
 pub enum PackageEntry {
@@ -78,32 +211,108 @@ pub enum PackageEntry {
         key: signing::PublicKey,
     },
     /// Grant the specified key a permission.
-    /// The author of this entry must have the permission.
+    /// The author of this entry must have the `admin` permission; for
+    /// packages using legacy protocol version 0, the author must instead
+    /// have the permission being granted.
+    ///
+    /// A grant may be limited to records published before an expiry
+    /// and to versions matching a version requirement; a grant cannot
+    /// exceed the limits of the author's own grant.
     GrantFlat {
         key: signing::PublicKey,
         permissions: Vec<Permission>,
+        /// The time after which the granted permissions may no longer be used.
+        expires: Option<SystemTime>,
+        /// The versions the granted permissions are limited to.
+        versions: Option<VersionReq>,
     },
     /// Remove a permission from a key.
-    /// The author of this entry must have the permission.
+    /// The author of this entry must have the `admin` permission; for
+    /// packages using legacy protocol version 0, the author must instead
+    /// have the permission being revoked.
     RevokeFlat {
         key_id: signing::KeyID,
         permissions: Vec<Permission>,
     },
+    /// Atomically move every permission of a key to a new key.
+    /// The author of this entry must be the old key or have the `admin`
+    /// permission; for packages using legacy protocol version 0, the author
+    /// must instead have every permission held by the old key.
+    RotateKey {
+        old_key_id: signing::KeyID,
+        new_key: signing::PublicKey,
+    },
     /// Release a version of a package.
     /// The version must not have been released yet.
-    Release { version: Version, content: AnyHash },
+    /// A multi-file release has a content index; its content is the
+    /// digest of the index.
+    /// A release may declare the packages it was built against; each
+    /// package may only be declared once.
+    Release {
+        version: Version,
+        content: AnyHash,
+        index: Option<ContentIndex>,
+        dependencies: Vec<ReleaseDependency>,
+    },
     /// Yank a version of a package.
     /// The version must have been released and not yanked.
-    Yank { version: Version },
+    Yank {
+        version: Version,
+        reason: Option<YankReason>,
+    },
+    /// Unyank a version of a package, restoring its original content.
+    /// The version must have been yanked.
+    Unyank { version: Version },
+    /// Deprecate the package, optionally pointing to a successor package.
+    /// A deprecated package may be deprecated again to replace the
+    /// successor or message.
+    Deprecate {
+        successor: Option<PackageName>,
+        message: Option<String>,
+    },
+    /// Remove the deprecation of the package.
+    /// The package must be deprecated.
+    Undeprecate,
+    /// Set the number of keys with the release permission that must sign
+    /// records releasing versions of the package.
+    /// The record containing this entry must satisfy the previous threshold.
+    ReleaseThreshold { threshold: u32 },
+    /// Set the metadata of the package or, if a version is specified,
+    /// of a release of the package.
+    /// The metadata replaces any previously set metadata.
+    Metadata {
+        version: Option<Version>,
+        metadata: PackageMetadata,
+    },
 }
 
 impl PackageEntry {
     /// Check permission is required to submit this entry
     pub fn required_permission(&self) -> Option<Permission> {
         match self {
-            Self::Init { .. } | Self::GrantFlat { .. } | Self::RevokeFlat { .. } => None,
-            Self::Release { .. } => Some(Permission::Release),
-            Self::Yank { .. } => Some(Permission::Yank),
+            Self::Init { .. }
+            | Self::GrantFlat { .. }
+            | Self::RevokeFlat { .. }
+            | Self::RotateKey { .. } => None,
+            Self::Release { .. }
+            | Self::Metadata { .. }
+            | Self::Deprecate { .. }
+            | Self::Undeprecate => Some(Permission::Release),
+            Self::Yank { .. } | Self::Unyank { .. } => Some(Permission::Yank),
+            Self::ReleaseThreshold { .. } => Some(Permission::Admin),
+        }
+    }
+
+    /// Determines if a record containing this entry must satisfy the
+    /// release threshold of the package.
+    ///
+    /// A key rotation requires it if another key rotates a key with the
+    /// release permission, which depends on the state of the package log.
+    pub fn requires_release_threshold(&self) -> bool {
+        match self {
+            Self::Release { .. } | Self::ReleaseThreshold { .. } => true,
+            Self::GrantFlat { permissions, .. } => permissions.contains(&Permission::Release),
+            _ => false,
         }
     }
 
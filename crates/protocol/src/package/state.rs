@@ -1,12 +1,13 @@
-use super::{model, PACKAGE_RECORD_VERSION};
-use crate::registry::RecordId;
+use super::{model, LEGACY_PACKAGE_RECORD_VERSION, PACKAGE_RECORD_VERSION};
+use crate::content::{ContentIndex, ContentIndexError};
+use crate::registry::{PackageName, RecordId};
 use crate::ProtoEnvelope;
 use indexmap::{map::Entry, IndexMap, IndexSet};
 use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
 use std::time::SystemTime;
 use thiserror::Error;
-use warg_crypto::hash::{AnyHash, HashAlgorithm, Sha256};
+use warg_crypto::hash::{AnyHash, HashAlgorithm};
 use warg_crypto::{signing, Signable};
 
 #[derive(Error, Debug)]
@@ -35,6 +36,57 @@ pub enum ValidationError {
         key_id: signing::KeyID,
     },
 
+    #[error("attempted to rotate key {key_id} which has no permissions")]
+    KeyNotFoundToRotate { key_id: signing::KeyID },
+
+    #[error("the key with ID {key_id} is not authorized to rotate key {old_key_id}")]
+    UnauthorizedKeyRotation {
+        key_id: signing::KeyID,
+        old_key_id: signing::KeyID,
+    },
+
+    #[error("the grant of permission {permission} to key {key_id} has expired")]
+    GrantExpired {
+        key_id: signing::KeyID,
+        permission: model::Permission,
+    },
+
+    #[error(
+        "the key with ID {key_id} is not granted permission {permission} for version {version}"
+    )]
+    VersionOutOfGrantScope {
+        key_id: signing::KeyID,
+        permission: model::Permission,
+        version: Version,
+    },
+
+    #[error(
+        "the key with ID {key_id} is only granted permission {permission} for versions {versions}"
+    )]
+    PackageEntryOutOfGrantScope {
+        key_id: signing::KeyID,
+        permission: model::Permission,
+        versions: VersionReq,
+    },
+
+    #[error("the key with ID {key_id} attempted to grant permission {permission} beyond the scope of its own grant")]
+    GrantExceedsScope {
+        key_id: signing::KeyID,
+        permission: model::Permission,
+    },
+
+    #[error("a release threshold of {threshold} cannot be met by the {keys} key(s) with a non-expiring release permission")]
+    InvalidReleaseThreshold { threshold: u32, keys: u32 },
+
+    #[error("the record requires signatures from {needed} keys with the release permission but has {found}")]
+    InsufficientSignatures { needed: u32, found: u32 },
+
+    #[error("the record was signed more than once by key {key_id}")]
+    DuplicateSignature { key_id: signing::KeyID },
+
+    #[error("an entry attempted to undeprecate a package which is not deprecated")]
+    UndeprecateOfNotDeprecated,
+
     #[error("an entry attempted to release version {version} which is already released")]
     ReleaseOfReleased { version: Version },
 
@@ -44,6 +96,35 @@ pub enum ValidationError {
     #[error("an entry attempted to yank version {version} which is already yanked")]
     YankOfYanked { version: Version },
 
+    #[error("an entry attempted to unyank version {version} which had not yet been released")]
+    UnyankOfUnreleased { version: Version },
+
+    #[error("an entry attempted to unyank version {version} which is not yanked")]
+    UnyankOfUnyanked { version: Version },
+
+    #[error(
+        "an entry attempted to set metadata of version {version} which had not yet been released"
+    )]
+    MetadataOfUnreleased { version: Version },
+
+    #[error(
+        "the content of version {version} is {content} but its content index has digest {expected}"
+    )]
+    ContentIndexDigestMismatch {
+        version: Version,
+        content: AnyHash,
+        expected: AnyHash,
+    },
+
+    #[error("the content index of version {version} is invalid: {source}")]
+    InvalidContentIndex {
+        version: Version,
+        source: ContentIndexError,
+    },
+
+    #[error("version {version} declares a dependency on package `{name}` more than once")]
+    DuplicateDependency { version: Version, name: PackageName },
+
     #[error("unable to verify signature")]
     SignatureError(#[from] signing::SignatureError),
 
@@ -65,6 +146,11 @@ pub enum ValidationError {
     #[error("protocol version {version} not allowed")]
     ProtocolVersionNotAllowed { version: u32 },
 
+    #[error(
+        "protocol version {version} is lower than the package log's protocol version {expected}"
+    )]
+    ProtocolVersionDowngrade { version: u32, expected: u32 },
+
     #[error("record has lower timestamp than previous")]
     TimestampLowerThanPrevious,
 }
@@ -85,6 +171,13 @@ pub enum ReleaseState {
         /// The timestamp of the yank.
         #[serde(with = "crate::timestamp")]
         timestamp: SystemTime,
+        /// The reason for the yank, if one was given.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        reason: Option<model::YankReason>,
+        /// The content digest the release had before it was yanked.
+        ///
+        /// This is used to restore the release if it is unyanked.
+        content: AnyHash,
     },
 }
 
@@ -103,6 +196,15 @@ pub struct Release {
     pub timestamp: SystemTime,
     /// The current state of the release.
     pub state: ReleaseState,
+    /// The metadata of the release.
+    #[serde(default, skip_serializing_if = "model::PackageMetadata::is_empty")]
+    pub metadata: model::PackageMetadata,
+    /// The content index of the release, if it is a multi-file release.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<ContentIndex>,
+    /// The registry packages the release was built against.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dependencies: Vec<model::ReleaseDependency>,
 }
 
 impl Release {
@@ -111,6 +213,17 @@ impl Release {
         matches!(self.state, ReleaseState::Yanked { .. })
     }
 
+    /// Gets the reason the release was yanked.
+    ///
+    /// Returns `None` if the release has not been yanked or if no
+    /// reason was given for the yank.
+    pub fn yank_reason(&self) -> Option<&model::YankReason> {
+        match &self.state {
+            ReleaseState::Yanked { reason, .. } => reason.as_ref(),
+            ReleaseState::Released { .. } => None,
+        }
+    }
+
     /// Gets the content associated with the release.
     ///
     /// Returns `None` if the release has been yanked.
@@ -120,6 +233,84 @@ impl Release {
             ReleaseState::Yanked { .. } => None,
         }
     }
+
+    /// Gets every content digest referenced by the release.
+    ///
+    /// Unlike [`Release::content`], this includes the content of a yanked
+    /// release and the files of a multi-file release.
+    pub fn contents(&self) -> impl Iterator<Item = &AnyHash> {
+        let content = match &self.state {
+            ReleaseState::Released { content } | ReleaseState::Yanked { content, .. } => content,
+        };
+
+        std::iter::once(content).chain(self.index.iter().flat_map(ContentIndex::digests))
+    }
+}
+
+/// Represents information about the deprecation of a package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Deprecation {
+    /// The key id that deprecated the package.
+    pub by: signing::KeyID,
+    /// The timestamp of the deprecation.
+    #[serde(with = "crate::timestamp")]
+    pub timestamp: SystemTime,
+    /// The package that supersedes the deprecated package, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub successor: Option<PackageName>,
+    /// The deprecation message, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// Represents the limits placed on a permission granted to a key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GrantScope {
+    /// The time after which the permission may no longer be used.
+    #[serde(
+        default,
+        with = "crate::timestamp::option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub expires: Option<SystemTime>,
+    /// The versions the permission is limited to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub versions: Option<VersionReq>,
+}
+
+impl GrantScope {
+    /// Determines if the scope places no limits on the permission.
+    pub fn is_unlimited(&self) -> bool {
+        self.expires.is_none() && self.versions.is_none()
+    }
+
+    /// Determines if the scope has expired as of the given time.
+    pub fn expired(&self, timestamp: SystemTime) -> bool {
+        self.expires
+            .map(|expires| timestamp >= expires)
+            .unwrap_or(false)
+    }
+
+    /// Determines if this scope is no broader than the given scope.
+    ///
+    /// Version requirements are compared exactly.
+    fn within(&self, other: &GrantScope) -> bool {
+        let expires = match (self.expires, other.expires) {
+            (_, None) => true,
+            (Some(expires), Some(limit)) => expires <= limit,
+            (None, Some(_)) => false,
+        };
+
+        let versions = match (&self.versions, &other.versions) {
+            (_, None) => true,
+            (Some(versions), Some(limit)) => versions == limit,
+            (None, Some(_)) => false,
+        };
+
+        expires && versions
+    }
 }
 
 /// Information about the current head of the package log.
@@ -146,15 +337,36 @@ pub struct LogState {
     /// The current head of the state.
     #[serde(skip_serializing_if = "Option::is_none")]
     head: Option<Head>,
+    /// The protocol version of the last validated record.
+    ///
+    /// This selects how permissions are delegated; states persisted before
+    /// versioned records were introduced default to the legacy version.
+    #[serde(skip_serializing_if = "is_legacy_version")]
+    version: u32,
     /// The permissions of each key.
     #[serde(skip_serializing_if = "IndexMap::is_empty")]
     permissions: IndexMap<signing::KeyID, IndexSet<model::Permission>>,
+    /// The scopes of permissions granted with limits.
+    ///
+    /// Permissions without an entry in this map are unlimited.
+    #[serde(skip_serializing_if = "IndexMap::is_empty")]
+    scopes: IndexMap<signing::KeyID, IndexMap<model::Permission, GrantScope>>,
     /// The releases in the package log.
     #[serde(skip_serializing_if = "IndexMap::is_empty")]
     releases: IndexMap<Version, Release>,
     /// The keys known to the state.
     #[serde(skip_serializing_if = "IndexMap::is_empty")]
     keys: IndexMap<signing::KeyID, signing::PublicKey>,
+    /// The latest metadata of the package.
+    #[serde(skip_serializing_if = "model::PackageMetadata::is_empty")]
+    metadata: model::PackageMetadata,
+    /// The deprecation of the package, if it is deprecated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deprecation: Option<Deprecation>,
+    /// The number of keys with the release permission that must sign
+    /// records releasing versions; `None` requires a single signature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    release_threshold: Option<u32>,
 }
 
 impl LogState {
@@ -178,10 +390,42 @@ impl LogState {
     /// Note that on failure, the log state is consumed to prevent
     /// invalid state from being used in future validations.
     pub fn validate(
-        mut self,
+        self,
         record: &ProtoEnvelope<model::PackageRecord>,
     ) -> Result<Self, ValidationError> {
-        self.validate_record(record)?;
+        self.validate_with(std::iter::once(record), signing::BatchVerifier::unbatched())
+    }
+
+    /// Validates a sequence of package records.
+    ///
+    /// This is equivalent to calling [`LogState::validate`] for each record
+    /// in order, but the record signatures are verified as a batch, which is
+    /// considerably faster for long sequences of records.
+    ///
+    /// Note that on failure, the log state is consumed to prevent
+    /// invalid state from being used in future validations.
+    pub fn validate_all<'a>(
+        self,
+        records: impl IntoIterator<Item = &'a ProtoEnvelope<model::PackageRecord>>,
+    ) -> Result<Self, ValidationError> {
+        self.validate_with(records, signing::BatchVerifier::new())
+    }
+
+    fn validate_with<'a>(
+        mut self,
+        records: impl IntoIterator<Item = &'a ProtoEnvelope<model::PackageRecord>>,
+        mut signatures: signing::BatchVerifier,
+    ) -> Result<Self, ValidationError> {
+        for record in records {
+            if let Err(e) = self.validate_record(record, &mut signatures) {
+                // Report an invalid signature of an earlier record first, as
+                // validating the records one at a time would
+                verify_signatures(&mut signatures)?;
+                return Err(e);
+            }
+        }
+
+        verify_signatures(&mut signatures)?;
         Ok(self)
     }
 
@@ -211,6 +455,124 @@ impl LogState {
             .max_by(|a, b| a.version.cmp(&b.version))
     }
 
+    /// Gets the latest metadata of the package.
+    ///
+    /// Release-specific metadata is available from each [`Release`].
+    pub fn metadata(&self) -> &model::PackageMetadata {
+        &self.metadata
+    }
+
+    /// Gets the deprecation of the package.
+    ///
+    /// Returns `None` if the package is not deprecated.
+    pub fn deprecation(&self) -> Option<&Deprecation> {
+        self.deprecation.as_ref()
+    }
+
+    /// Gets the scope of a permission granted to the given key id.
+    ///
+    /// Returns `None` if the permission was granted without limits.
+    pub fn grant_scope(
+        &self,
+        key_id: &signing::KeyID,
+        permission: model::Permission,
+    ) -> Option<&GrantScope> {
+        self.scopes.get(key_id).and_then(|s| s.get(&permission))
+    }
+
+    /// Gets the number of keys with the release permission that must sign
+    /// records releasing versions of the package.
+    pub fn release_threshold(&self) -> u32 {
+        self.release_threshold.unwrap_or(1)
+    }
+
+    /// Verifies the cosignatures of a record and that the record is signed
+    /// by enough keys to satisfy the release threshold of the package.
+    ///
+    /// The signature of the key that signed the envelope is not verified.
+    pub fn verify_cosignatures(
+        &self,
+        envelope: &ProtoEnvelope<model::PackageRecord>,
+    ) -> Result<(), ValidationError> {
+        let mut signatures = signing::BatchVerifier::unbatched();
+        self.check_cosignatures(envelope, &mut signatures)?;
+        verify_signatures(&mut signatures)
+    }
+
+    // Checks the cosigners of a record, queueing the cosignatures for
+    // verification
+    fn check_cosignatures(
+        &self,
+        envelope: &ProtoEnvelope<model::PackageRecord>,
+        signatures: &mut signing::BatchVerifier,
+    ) -> Result<(), ValidationError> {
+        let record = envelope.as_ref();
+        let mut signers = IndexSet::from([envelope.key_id()]);
+
+        for cosignature in envelope.cosignatures() {
+            if !signers.insert(&cosignature.key_id) {
+                return Err(ValidationError::DuplicateSignature {
+                    key_id: cosignature.key_id.clone(),
+                });
+            }
+
+            let key = self.keys.get(&cosignature.key_id).ok_or_else(|| {
+                ValidationError::KeyIDNotRecognized {
+                    key_id: cosignature.key_id.clone(),
+                }
+            })?;
+
+            model::PackageRecord::verify_batched(
+                signatures,
+                key,
+                envelope.content_bytes(),
+                &cosignature.signature,
+            );
+        }
+
+        let needed = self.release_threshold();
+        if needed > 1
+            && record
+                .entries
+                .iter()
+                .any(|entry| self.requires_release_threshold(envelope.key_id(), entry))
+        {
+            let found = signers
+                .into_iter()
+                .filter(|key_id| self.can_release(key_id, record.timestamp))
+                .count() as u32;
+
+            if found < needed {
+                return Err(ValidationError::InsufficientSignatures { needed, found });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Determines if a record signed by the given key containing the entry
+    // must satisfy the release threshold.
+    //
+    // Rotating another key with the release permission to a new key
+    // controlled by the signer would let the signer meet the threshold alone.
+    fn requires_release_threshold(
+        &self,
+        signer_key_id: &signing::KeyID,
+        entry: &model::PackageEntry,
+    ) -> bool {
+        match entry {
+            model::PackageEntry::RotateKey { old_key_id, .. } => {
+                signer_key_id != old_key_id
+                    && self
+                        .permissions
+                        .get(old_key_id)
+                        .map(|p| p.contains(&model::Permission::Release))
+                        .unwrap_or(false)
+            }
+            entry => entry.requires_release_threshold(),
+        }
+    }
+
     /// Gets the public key of the given key id.
     ///
     /// Returns `None` if the key id is not recognized.
@@ -223,12 +585,28 @@ impl LogState {
         self.algorithm.is_some()
     }
 
+    // Gets the hash algorithm used to identify the given record; this is the
+    // algorithm of the log, or the one set by the record's init entry when the
+    // record initializes the log.
+    //
+    // A record that fails to initialize an uninitialized log is rejected
+    // before its identifier is used.
+    fn record_algorithm(&self, record: &model::PackageRecord) -> HashAlgorithm {
+        self.algorithm
+            .or_else(|| match record.entries.first() {
+                Some(model::PackageEntry::Init { hash_algorithm, .. }) => Some(*hash_algorithm),
+                _ => None,
+            })
+            .unwrap_or(HashAlgorithm::Sha256)
+    }
+
     fn validate_record(
         &mut self,
         envelope: &ProtoEnvelope<model::PackageRecord>,
+        signatures: &mut signing::BatchVerifier,
     ) -> Result<(), ValidationError> {
         let record = envelope.as_ref();
-        let record_id = RecordId::package_record::<Sha256>(envelope);
+        let record_id = RecordId::package_record_for(self.record_algorithm(record), envelope);
 
         // Validate previous hash
         self.validate_record_hash(record)?;
@@ -239,6 +617,9 @@ impl LogState {
         // Validate timestamp
         self.validate_record_timestamp(record)?;
 
+        // Validate cosignatures against the state prior to the record
+        self.check_cosignatures(envelope, signatures)?;
+
         // Validate entries
         self.validate_record_entries(
             &record_id,
@@ -259,8 +640,13 @@ impl LogState {
             }
         })?;
 
-        // Validate the envelope signature
-        model::PackageRecord::verify(key, envelope.content_bytes(), envelope.signature())?;
+        // Queue the envelope signature for verification
+        model::PackageRecord::verify_batched(
+            signatures,
+            key,
+            envelope.content_bytes(),
+            envelope.signature(),
+        );
 
         // Update the state head
         self.head = Some(Head {
@@ -294,15 +680,51 @@ impl LogState {
     }
 
     fn validate_record_version(
-        &self,
+        &mut self,
         record: &model::PackageRecord,
     ) -> Result<(), ValidationError> {
-        if record.version == PACKAGE_RECORD_VERSION {
-            Ok(())
-        } else {
-            Err(ValidationError::ProtocolVersionNotAllowed {
+        if record.version > PACKAGE_RECORD_VERSION {
+            return Err(ValidationError::ProtocolVersionNotAllowed {
                 version: record.version,
-            })
+            });
+        }
+
+        // A record cannot select an older interpretation of the log
+        if record.version < self.version {
+            return Err(ValidationError::ProtocolVersionDowngrade {
+                version: record.version,
+                expected: self.version,
+            });
+        }
+
+        if self.version == LEGACY_PACKAGE_RECORD_VERSION
+            && record.version > LEGACY_PACKAGE_RECORD_VERSION
+        {
+            self.migrate_legacy_permissions();
+        }
+
+        self.version = record.version;
+        Ok(())
+    }
+
+    /// Migrates the permissions of a legacy package log.
+    ///
+    /// In a legacy log, a key holding every legacy permission without limits
+    /// could grant all of them to any other key, so such keys become admins.
+    fn migrate_legacy_permissions(&mut self) {
+        for (key_id, permissions) in &mut self.permissions {
+            let unlimited = model::Permission::legacy().iter().all(|permission| {
+                permissions.contains(permission)
+                    && self
+                        .scopes
+                        .get(key_id)
+                        .map(|s| !s.contains_key(permission))
+                        .unwrap_or(true)
+            });
+
+            if unlimited {
+                permissions.insert(model::Permission::Admin);
+            }
         }
     }
 
@@ -348,23 +770,74 @@ impl LogState {
 
             match entry {
                 model::PackageEntry::Init { .. } => unreachable!(), // handled above
-                model::PackageEntry::GrantFlat { key, permissions } => {
-                    self.validate_grant_entry(signer_key_id, key, permissions)?
-                }
-                model::PackageEntry::RevokeFlat {
-                    key_id,
+                model::PackageEntry::GrantFlat {
+                    key,
                     permissions,
-                } => self.validate_revoke_entry(signer_key_id, key_id, permissions)?,
-                model::PackageEntry::Release { version, content } => self.validate_release_entry(
-                    record_id,
+                    expires,
+                    versions,
+                } => self.validate_grant_entry(
                     signer_key_id,
                     timestamp,
+                    key,
+                    permissions,
+                    GrantScope {
+                        expires: *expires,
+                        versions: versions.clone(),
+                    },
+                )?,
+                model::PackageEntry::RevokeFlat {
+                    key_id,
+                    permissions,
+                } => self.validate_revoke_entry(signer_key_id, timestamp, key_id, permissions)?,
+                model::PackageEntry::RotateKey {
+                    old_key_id,
+                    new_key,
+                } => {
+                    self.validate_rotate_key_entry(signer_key_id, timestamp, old_key_id, new_key)?
+                }
+                model::PackageEntry::Release {
                     version,
                     content,
-                )?,
-                model::PackageEntry::Yank { version } => {
-                    self.validate_yank_entry(signer_key_id, timestamp, version)?
+                    index,
+                    dependencies,
+                } => self.validate_release_entry(Release {
+                    record_id: record_id.clone(),
+                    version: version.clone(),
+                    by: signer_key_id.clone(),
+                    timestamp,
+                    state: ReleaseState::Released {
+                        content: content.clone(),
+                    },
+                    metadata: Default::default(),
+                    index: index.clone(),
+                    dependencies: dependencies.clone(),
+                })?,
+                model::PackageEntry::Yank { version, reason } => {
+                    self.validate_yank_entry(signer_key_id, timestamp, version, reason.as_ref())?
+                }
+                model::PackageEntry::Unyank { version } => {
+                    self.validate_unyank_entry(signer_key_id, timestamp, version)?
                 }
+                model::PackageEntry::Deprecate { successor, message } => self
+                    .validate_deprecate_entry(
+                        signer_key_id,
+                        timestamp,
+                        successor.as_ref(),
+                        message.as_deref(),
+                    )?,
+                model::PackageEntry::Undeprecate => {
+                    self.validate_undeprecate_entry(signer_key_id, timestamp)?
+                }
+                model::PackageEntry::ReleaseThreshold { threshold } => {
+                    self.validate_release_threshold_entry(signer_key_id, timestamp, *threshold)?
+                }
+                model::PackageEntry::Metadata { version, metadata } => self
+                    .validate_metadata_entry(
+                        signer_key_id,
+                        timestamp,
+                        version.as_ref(),
+                        metadata,
+                    )?,
             }
         }
 
@@ -391,11 +864,14 @@ impl LogState {
         assert!(self.releases.is_empty());
         assert!(self.keys.is_empty());
 
+        let permissions = if self.version == LEGACY_PACKAGE_RECORD_VERSION {
+            IndexSet::from_iter(model::Permission::legacy())
+        } else {
+            IndexSet::from_iter(model::Permission::all())
+        };
+
         self.algorithm = Some(algorithm);
-        self.permissions.insert(
-            signer_key_id.clone(),
-            IndexSet::from(model::Permission::all()),
-        );
+        self.permissions.insert(signer_key_id.clone(), permissions);
         self.keys.insert(init_key.fingerprint(), init_key.clone());
 
         Ok(())
@@ -404,30 +880,75 @@ impl LogState {
     fn validate_grant_entry(
         &mut self,
         signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
         key: &signing::PublicKey,
         permissions: &[model::Permission],
+        scope: GrantScope,
     ) -> Result<(), ValidationError> {
-        // Check that the current key has the permission they're trying to grant
-        self.check_key_permissions(signer_key_id, permissions)?;
+        if self.version == LEGACY_PACKAGE_RECORD_VERSION {
+            // Check that the current key has the permission they're trying to grant
+            self.check_key_permissions(signer_key_id, permissions)?;
+
+            // Check that the grant does not exceed the scope of the current key's grant
+            for permission in permissions {
+                self.check_grant_delegation(signer_key_id, timestamp, *permission, &scope)?;
+            }
+        } else {
+            // Check that the current key is an admin whose grant covers the new grant
+            self.check_key_permissions(signer_key_id, &[model::Permission::Admin])?;
+            self.check_grant_delegation(
+                signer_key_id,
+                timestamp,
+                model::Permission::Admin,
+                &scope,
+            )?;
+        }
 
         let grant_key_id = key.fingerprint();
         self.keys.insert(grant_key_id.clone(), key.clone());
         self.permissions
-            .entry(grant_key_id)
+            .entry(grant_key_id.clone())
             .or_default()
             .extend(permissions);
 
-        Ok(())
+        // A new grant replaces the scope of any previous grant of the permission
+        let scopes = self.scopes.entry(grant_key_id.clone()).or_default();
+        for permission in permissions {
+            if scope.is_unlimited() {
+                scopes.shift_remove(permission);
+            } else {
+                scopes.insert(*permission, scope.clone());
+            }
+        }
+
+        if scopes.is_empty() {
+            self.scopes.shift_remove(&grant_key_id);
+        }
+
+        // Replacing a release grant with an expiring one leaves fewer
+        // release keys
+        self.check_release_threshold_reachable()
     }
 
     fn validate_revoke_entry(
         &mut self,
         signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
         key_id: &signing::KeyID,
         permissions: &[model::Permission],
     ) -> Result<(), ValidationError> {
-        // Check that the current key has the permission they're trying to revoke
-        self.check_key_permissions(signer_key_id, permissions)?;
+        if self.version == LEGACY_PACKAGE_RECORD_VERSION {
+            // Check that the current key has the permission they're trying to revoke
+            self.check_key_permissions(signer_key_id, permissions)?;
+
+            for permission in permissions {
+                self.check_grant_expiry(signer_key_id, timestamp, *permission)?;
+            }
+        } else {
+            // Check that the current key is an admin
+            self.check_key_permissions(signer_key_id, &[model::Permission::Admin])?;
+            self.check_grant_expiry(signer_key_id, timestamp, model::Permission::Admin)?;
+        }
 
         for permission in permissions {
             if !self
@@ -441,35 +962,151 @@ impl LogState {
                     key_id: key_id.clone(),
                 });
             }
+
+            if let Some(scopes) = self.scopes.get_mut(key_id) {
+                scopes.shift_remove(permission);
+                if scopes.is_empty() {
+                    self.scopes.shift_remove(key_id);
+                }
+            }
         }
-        Ok(())
+
+        // A release threshold must remain reachable after the revocation
+        self.check_release_threshold_reachable()
     }
 
-    fn validate_release_entry(
+    fn validate_rotate_key_entry(
         &mut self,
-        record_id: &RecordId,
         signer_key_id: &signing::KeyID,
         timestamp: SystemTime,
-        version: &Version,
-        content: &AnyHash,
+        old_key_id: &signing::KeyID,
+        new_key: &signing::PublicKey,
     ) -> Result<(), ValidationError> {
-        match self.releases.entry(version.clone()) {
+        let permissions = match self.permissions.get(old_key_id) {
+            Some(permissions) if !permissions.is_empty() => permissions.clone(),
+            _ => {
+                return Err(ValidationError::KeyNotFoundToRotate {
+                    key_id: old_key_id.clone(),
+                })
+            }
+        };
+
+        // The old key may rotate itself; otherwise the signer must be an
+        // admin whose grant covers the old key's grants, as rotating is a
+        // revoke and grant of every permission of the old key
+        if signer_key_id != old_key_id && self.version != LEGACY_PACKAGE_RECORD_VERSION {
+            self.check_key_permissions(signer_key_id, &[model::Permission::Admin])?;
+
+            for permission in &permissions {
+                let scope = self
+                    .grant_scope(old_key_id, *permission)
+                    .cloned()
+                    .unwrap_or_default();
+                self.check_grant_delegation(
+                    signer_key_id,
+                    timestamp,
+                    model::Permission::Admin,
+                    &scope,
+                )?;
+            }
+        } else if signer_key_id != old_key_id {
+            // In a legacy log, the signer must hold every permission the
+            // old key holds
+            let authorized = self
+                .permissions
+                .get(signer_key_id)
+                .map(|p| permissions.is_subset(p))
+                .unwrap_or(false);
+
+            if !authorized {
+                return Err(ValidationError::UnauthorizedKeyRotation {
+                    key_id: signer_key_id.clone(),
+                    old_key_id: old_key_id.clone(),
+                });
+            }
+
+            // The signer's grants must also be at least as broad as the old key's
+            for permission in &permissions {
+                let scope = self
+                    .grant_scope(old_key_id, *permission)
+                    .cloned()
+                    .unwrap_or_default();
+                self.check_grant_delegation(signer_key_id, timestamp, *permission, &scope)?;
+            }
+        }
+
+        let new_key_id = new_key.fingerprint();
+        self.permissions
+            .insert(old_key_id.clone(), IndexSet::default());
+        self.keys.insert(new_key_id.clone(), new_key.clone());
+        self.permissions
+            .entry(new_key_id.clone())
+            .or_default()
+            .extend(permissions.iter().copied());
+
+        // The scopes of the old key's permissions move to the new key
+        let old_scopes = self.scopes.shift_remove(old_key_id).unwrap_or_default();
+        let scopes = self.scopes.entry(new_key_id.clone()).or_default();
+        for permission in &permissions {
+            match old_scopes.get(permission) {
+                Some(scope) => scopes.insert(*permission, scope.clone()),
+                None => scopes.shift_remove(permission),
+            };
+        }
+
+        if scopes.is_empty() {
+            self.scopes.shift_remove(&new_key_id);
+        }
+
+        // Rotating to a key that already holds the release permission
+        // leaves fewer release keys
+        self.check_release_threshold_reachable()
+    }
+
+    fn validate_release_entry(&mut self, release: Release) -> Result<(), ValidationError> {
+        self.check_grant_scope(
+            &release.by,
+            release.timestamp,
+            model::Permission::Release,
+            Some(&release.version),
+        )?;
+
+        if let (Some(index), Some(content)) = (&release.index, release.content()) {
+            if index.is_empty() {
+                return Err(ValidationError::InvalidContentIndex {
+                    version: release.version.clone(),
+                    source: ContentIndexError::Empty,
+                });
+            }
+
+            let expected = index.digest();
+            if &expected != content {
+                return Err(ValidationError::ContentIndexDigestMismatch {
+                    version: release.version.clone(),
+                    content: content.clone(),
+                    expected,
+                });
+            }
+        }
+
+        let mut names = IndexSet::new();
+        for dependency in &release.dependencies {
+            if !names.insert(&dependency.name) {
+                return Err(ValidationError::DuplicateDependency {
+                    version: release.version.clone(),
+                    name: dependency.name.clone(),
+                });
+            }
+        }
+
+        match self.releases.entry(release.version.clone()) {
             Entry::Occupied(e) => {
                 return Err(ValidationError::ReleaseOfReleased {
                     version: e.key().clone(),
                 })
             }
             Entry::Vacant(e) => {
-                let version = e.key().clone();
-                e.insert(Release {
-                    record_id: record_id.clone(),
-                    version,
-                    by: signer_key_id.clone(),
-                    timestamp,
-                    state: ReleaseState::Released {
-                        content: content.clone(),
-                    },
-                });
+                e.insert(release);
             }
         }
 
@@ -481,16 +1118,26 @@ impl LogState {
         signer_key_id: &signing::KeyID,
         timestamp: SystemTime,
         version: &Version,
+        reason: Option<&model::YankReason>,
     ) -> Result<(), ValidationError> {
+        self.check_grant_scope(
+            signer_key_id,
+            timestamp,
+            model::Permission::Yank,
+            Some(version),
+        )?;
+
         match self.releases.get_mut(version) {
-            Some(e) => match e.state {
+            Some(e) => match &e.state {
                 ReleaseState::Yanked { .. } => Err(ValidationError::YankOfYanked {
                     version: version.clone(),
                 }),
-                ReleaseState::Released { .. } => {
+                ReleaseState::Released { content } => {
                     e.state = ReleaseState::Yanked {
                         by: signer_key_id.clone(),
                         timestamp,
+                        reason: reason.cloned(),
+                        content: content.clone(),
                     };
                     Ok(())
                 }
@@ -501,6 +1148,122 @@ impl LogState {
         }
     }
 
+    fn validate_unyank_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        version: &Version,
+    ) -> Result<(), ValidationError> {
+        self.check_grant_scope(
+            signer_key_id,
+            timestamp,
+            model::Permission::Yank,
+            Some(version),
+        )?;
+
+        match self.releases.get_mut(version) {
+            Some(e) => match &e.state {
+                ReleaseState::Released { .. } => Err(ValidationError::UnyankOfUnyanked {
+                    version: version.clone(),
+                }),
+                ReleaseState::Yanked { content, .. } => {
+                    e.state = ReleaseState::Released {
+                        content: content.clone(),
+                    };
+                    Ok(())
+                }
+            },
+            None => Err(ValidationError::UnyankOfUnreleased {
+                version: version.clone(),
+            }),
+        }
+    }
+
+    fn validate_deprecate_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        successor: Option<&PackageName>,
+        message: Option<&str>,
+    ) -> Result<(), ValidationError> {
+        self.check_grant_scope(signer_key_id, timestamp, model::Permission::Release, None)?;
+
+        self.deprecation = Some(Deprecation {
+            by: signer_key_id.clone(),
+            timestamp,
+            successor: successor.cloned(),
+            message: message.map(ToOwned::to_owned),
+        });
+
+        Ok(())
+    }
+
+    fn validate_undeprecate_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
+    ) -> Result<(), ValidationError> {
+        self.check_grant_scope(signer_key_id, timestamp, model::Permission::Release, None)?;
+
+        match self.deprecation.take() {
+            Some(_) => Ok(()),
+            None => Err(ValidationError::UndeprecateOfNotDeprecated),
+        }
+    }
+
+    fn validate_release_threshold_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        threshold: u32,
+    ) -> Result<(), ValidationError> {
+        self.check_grant_expiry(signer_key_id, timestamp, model::Permission::Admin)?;
+
+        let keys = self.release_key_count();
+        if threshold == 0 || threshold > keys {
+            return Err(ValidationError::InvalidReleaseThreshold { threshold, keys });
+        }
+
+        self.release_threshold = if threshold == 1 {
+            None
+        } else {
+            Some(threshold)
+        };
+
+        Ok(())
+    }
+
+    fn validate_metadata_entry(
+        &mut self,
+        signer_key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        version: Option<&Version>,
+        metadata: &model::PackageMetadata,
+    ) -> Result<(), ValidationError> {
+        self.check_grant_scope(
+            signer_key_id,
+            timestamp,
+            model::Permission::Release,
+            version,
+        )?;
+
+        match version {
+            Some(version) => match self.releases.get_mut(version) {
+                Some(release) => {
+                    release.metadata = metadata.clone();
+                    Ok(())
+                }
+                None => Err(ValidationError::MetadataOfUnreleased {
+                    version: version.clone(),
+                }),
+            },
+            None => {
+                self.metadata = metadata.clone();
+                Ok(())
+            }
+        }
+    }
+
     fn check_key_permissions(
         &self,
         key_id: &signing::KeyID,
@@ -521,6 +1284,130 @@ impl LogState {
         }
         Ok(())
     }
+
+    /// Determines if the key holds an unexpired release permission.
+    fn can_release(&self, key_id: &signing::KeyID, timestamp: SystemTime) -> bool {
+        self.permissions
+            .get(key_id)
+            .map(|p| p.contains(&model::Permission::Release))
+            .unwrap_or(false)
+            && self
+                .check_grant_expiry(key_id, timestamp, model::Permission::Release)
+                .is_ok()
+    }
+
+    /// Gets the number of keys holding a release permission that never
+    /// expires.
+    ///
+    /// Keys with an expiring grant are not counted so that a release
+    /// threshold cannot become unreachable once their grants expire.
+    fn release_key_count(&self) -> u32 {
+        self.permissions
+            .iter()
+            .filter(|(key_id, permissions)| {
+                permissions.contains(&model::Permission::Release)
+                    && self
+                        .grant_scope(key_id, model::Permission::Release)
+                        .map(|scope| scope.expires.is_none())
+                        .unwrap_or(true)
+            })
+            .count() as u32
+    }
+
+    /// Checks that enough keys hold a release permission that never
+    /// expires to meet the release threshold.
+    fn check_release_threshold_reachable(&self) -> Result<(), ValidationError> {
+        if let Some(threshold) = self.release_threshold {
+            let keys = self.release_key_count();
+            if threshold > keys {
+                return Err(ValidationError::InvalidReleaseThreshold { threshold, keys });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the key's grant of the permission has not expired as
+    /// of the given time.
+    fn check_grant_expiry(
+        &self,
+        key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        permission: model::Permission,
+    ) -> Result<(), ValidationError> {
+        match self.grant_scope(key_id, permission) {
+            Some(scope) if scope.expired(timestamp) => Err(ValidationError::GrantExpired {
+                key_id: key_id.clone(),
+                permission,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that the key's grant of the permission applies to an entry
+    /// published at the given time for the given version.
+    ///
+    /// Entries without a version are out of scope of a version-limited grant.
+    fn check_grant_scope(
+        &self,
+        key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        permission: model::Permission,
+        version: Option<&Version>,
+    ) -> Result<(), ValidationError> {
+        self.check_grant_expiry(key_id, timestamp, permission)?;
+
+        let versions = match self
+            .grant_scope(key_id, permission)
+            .and_then(|s| s.versions.as_ref())
+        {
+            Some(versions) => versions,
+            None => return Ok(()),
+        };
+
+        match version {
+            Some(version) if versions.matches(version) => Ok(()),
+            Some(version) => Err(ValidationError::VersionOutOfGrantScope {
+                key_id: key_id.clone(),
+                permission,
+                version: version.clone(),
+            }),
+            None => Err(ValidationError::PackageEntryOutOfGrantScope {
+                key_id: key_id.clone(),
+                permission,
+                versions: versions.clone(),
+            }),
+        }
+    }
+
+    /// Checks that the key may delegate the permission with the given scope.
+    fn check_grant_delegation(
+        &self,
+        key_id: &signing::KeyID,
+        timestamp: SystemTime,
+        permission: model::Permission,
+        scope: &GrantScope,
+    ) -> Result<(), ValidationError> {
+        self.check_grant_expiry(key_id, timestamp, permission)?;
+
+        match self.grant_scope(key_id, permission) {
+            Some(limit) if !scope.within(limit) => Err(ValidationError::GrantExceedsScope {
+                key_id: key_id.clone(),
+                permission,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_legacy_version(version: &u32) -> bool {
+    *version == LEGACY_PACKAGE_RECORD_VERSION
+}
+
+fn verify_signatures(signatures: &mut signing::BatchVerifier) -> Result<(), ValidationError> {
+    signatures
+        .verify()
+        .map_err(|e| ValidationError::SignatureError(e.source))
 }
 
 impl crate::Validator for LogState {
@@ -530,6 +1417,13 @@ impl crate::Validator for LogState {
     fn validate(self, record: &ProtoEnvelope<Self::Record>) -> Result<Self, Self::Error> {
         self.validate(record)
     }
+
+    fn validate_all<'a>(
+        self,
+        records: impl IntoIterator<Item = &'a ProtoEnvelope<Self::Record>>,
+    ) -> Result<Self, Self::Error> {
+        self.validate_all(records)
+    }
 }
 
 #[cfg(test)]
@@ -537,7 +1431,7 @@ mod tests {
     use super::*;
     use pretty_assertions::assert_eq;
     use std::time::{Duration, SystemTime};
-    use warg_crypto::hash::HashAlgorithm;
+    use warg_crypto::hash::{HashAlgorithm, Sha256};
     use warg_crypto::signing::generate_p256_pair;
 
     #[test]
@@ -568,16 +1462,65 @@ mod tests {
                     timestamp,
                 }),
                 algorithm: Some(HashAlgorithm::Sha256),
+                version: PACKAGE_RECORD_VERSION,
                 permissions: IndexMap::from([(
                     alice_id.clone(),
-                    IndexSet::from([model::Permission::Release, model::Permission::Yank]),
+                    IndexSet::from(model::Permission::all()),
                 )]),
                 releases: IndexMap::default(),
                 keys: IndexMap::from([(alice_id, alice_pub)]),
+                metadata: Default::default(),
+                deprecation: None,
+                scopes: IndexMap::new(),
+                release_threshold: None,
             }
         );
     }
 
+    #[test]
+    fn test_validate_log_hash_algorithm() {
+        let (alice_pub, alice_priv) = generate_p256_pair();
+
+        let timestamp = SystemTime::now();
+        let record = model::PackageRecord {
+            prev: None,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp,
+            entries: vec![model::PackageEntry::Init {
+                hash_algorithm: HashAlgorithm::Blake3,
+                key: alice_pub,
+            }],
+        };
+
+        let envelope = ProtoEnvelope::signed_contents(&alice_priv, record).unwrap();
+        let state = LogState::default().validate(&envelope).unwrap();
+
+        // Record identifiers use the algorithm of the log's init entry
+        let head = RecordId::package_record_for(HashAlgorithm::Blake3, &envelope);
+        assert_eq!(state.head().as_ref().unwrap().digest, head);
+
+        let record = model::PackageRecord {
+            prev: Some(head),
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: timestamp + Duration::from_secs(1),
+            entries: vec![model::PackageEntry::Release {
+                version: "1.0.0".parse().unwrap(),
+                content: "sha256:7d38b5cd25a2baf85ad3bb5b9311383e671a8a142eb302b324d4a5fba8748c69"
+                    .parse()
+                    .unwrap(),
+                index: None,
+                dependencies: Vec::new(),
+            }],
+        };
+
+        let envelope = ProtoEnvelope::signed_contents(&alice_priv, record).unwrap();
+        let state = state.validate(&envelope).unwrap();
+        assert_eq!(
+            state.head().as_ref().unwrap().digest.algorithm(),
+            HashAlgorithm::Blake3
+        );
+    }
+
     #[test]
     fn test_validate_larger_log() {
         let (alice_pub, alice_priv) = generate_p256_pair();
@@ -602,6 +1545,8 @@ mod tests {
                 model::PackageEntry::GrantFlat {
                     key: bob_pub.clone(),
                     permissions: model::Permission::all().into(),
+                    expires: None,
+                    versions: None,
                 },
             ],
         };
@@ -618,6 +1563,8 @@ mod tests {
             entries: vec![model::PackageEntry::Release {
                 version: Version::new(1, 1, 0),
                 content: content.clone(),
+                index: None,
+                dependencies: Vec::new(),
             }],
         };
 
@@ -635,7 +1582,10 @@ mod tests {
                 timestamp: timestamp1,
                 state: ReleaseState::Released {
                     content: content.clone()
-                }
+                },
+                metadata: Default::default(),
+                index: None,
+                dependencies: Vec::new(),
             })
         );
         assert!(state
@@ -648,7 +1598,12 @@ mod tests {
                 version: Version::new(1, 1, 0),
                 by: bob_id.clone(),
                 timestamp: timestamp1,
-                state: ReleaseState::Released { content }
+                state: ReleaseState::Released {
+                    content: content.clone()
+                },
+                metadata: Default::default(),
+                index: None,
+                dependencies: Vec::new(),
             }]
         );
 
@@ -665,6 +1620,7 @@ mod tests {
                 },
                 model::PackageEntry::Yank {
                     version: Version::new(1, 1, 0),
+                    reason: None,
                 },
             ],
         };
@@ -682,8 +1638,13 @@ mod tests {
                 timestamp: timestamp1,
                 state: ReleaseState::Yanked {
                     by: alice_id.clone(),
-                    timestamp: timestamp2
-                }
+                    timestamp: timestamp2,
+                    reason: None,
+                    content: content.clone(),
+                },
+                metadata: Default::default(),
+                index: None,
+                dependencies: Vec::new(),
             }]
         );
 
@@ -695,11 +1656,9 @@ mod tests {
                     digest: RecordId::package_record::<Sha256>(&envelope2),
                     timestamp: timestamp2,
                 }),
+                version: PACKAGE_RECORD_VERSION,
                 permissions: IndexMap::from([
-                    (
-                        alice_id.clone(),
-                        IndexSet::from([model::Permission::Release, model::Permission::Yank]),
-                    ),
+                    (alice_id.clone(), IndexSet::from(model::Permission::all())),
                     (bob_id.clone(), IndexSet::default()),
                 ]),
                 releases: IndexMap::from([(
@@ -711,11 +1670,20 @@ mod tests {
                         timestamp: timestamp1,
                         state: ReleaseState::Yanked {
                             by: alice_id.clone(),
-                            timestamp: timestamp2
-                        }
+                            timestamp: timestamp2,
+                            reason: None,
+                            content,
+                        },
+                        metadata: Default::default(),
+                        index: None,
+                        dependencies: Vec::new(),
                     }
                 )]),
                 keys: IndexMap::from([(alice_id, alice_pub), (bob_id, bob_pub),]),
+                metadata: Default::default(),
+                deprecation: None,
+                scopes: IndexMap::new(),
+                release_threshold: None,
             }
         );
     }
@@ -729,7 +1697,7 @@ mod tests {
         let timestamp = SystemTime::now();
         let record = model::PackageRecord {
             prev: None,
-            version: 0,
+            version: LEGACY_PACKAGE_RECORD_VERSION,
             timestamp,
             entries: vec![model::PackageEntry::Init {
                 hash_algorithm: HashAlgorithm::Sha256,
@@ -748,25 +1716,32 @@ mod tests {
                 timestamp,
             }),
             algorithm: Some(HashAlgorithm::Sha256),
+            version: LEGACY_PACKAGE_RECORD_VERSION,
             releases: IndexMap::new(),
             permissions: IndexMap::from([(
                 alice_id.clone(),
                 IndexSet::from([model::Permission::Release, model::Permission::Yank]),
             )]),
             keys: IndexMap::from([(alice_id, alice_pub)]),
+            metadata: Default::default(),
+            deprecation: None,
+            scopes: IndexMap::new(),
+            release_threshold: None,
         };
 
         assert_eq!(state, expected);
 
         let record = model::PackageRecord {
             prev: Some(RecordId::package_record::<Sha256>(&envelope)),
-            version: 0,
+            version: LEGACY_PACKAGE_RECORD_VERSION,
             timestamp: SystemTime::now(),
             entries: vec![
                 // This entry is valid
                 model::PackageEntry::GrantFlat {
                     key: bob_pub,
                     permissions: vec![model::Permission::Release],
+                    expires: None,
+                    versions: None,
                 },
                 // This entry is not valid
                 model::PackageEntry::RevokeFlat {
@@ -785,4 +1760,113 @@ mod tests {
             _ => panic!("expected a different error"),
         }
     }
+
+    #[test]
+    fn test_empty_content_index() {
+        let (alice_pub, alice_priv) = generate_p256_pair();
+
+        let record = model::PackageRecord {
+            prev: None,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: SystemTime::now(),
+            entries: vec![
+                model::PackageEntry::Init {
+                    hash_algorithm: HashAlgorithm::Sha256,
+                    key: alice_pub,
+                },
+                model::PackageEntry::Release {
+                    version: "1.0.0".parse().unwrap(),
+                    content: ContentIndex::new().digest(),
+                    index: Some(ContentIndex::new()),
+                    dependencies: Vec::new(),
+                },
+            ],
+        };
+
+        let envelope =
+            ProtoEnvelope::signed_contents(&alice_priv, record).expect("failed to sign envelope");
+
+        // A multi-file release must have at least one entry
+        match LogState::default().validate(&envelope).unwrap_err() {
+            ValidationError::InvalidContentIndex {
+                source: ContentIndexError::Empty,
+                ..
+            } => {}
+            _ => panic!("expected a different error"),
+        }
+    }
+
+    #[test]
+    fn test_validate_all() {
+        use prost::Message;
+        use warg_crypto::signing::generate_ed25519_pair;
+        use warg_protobuf::protocol as protobuf;
+
+        let (alice_pub, alice_priv) = generate_ed25519_pair();
+        let (bob_pub, bob_priv) = generate_p256_pair();
+
+        let timestamp = SystemTime::now();
+        let mut envelopes = vec![ProtoEnvelope::signed_contents(
+            &alice_priv,
+            model::PackageRecord {
+                prev: None,
+                version: PACKAGE_RECORD_VERSION,
+                timestamp,
+                entries: vec![
+                    model::PackageEntry::Init {
+                        hash_algorithm: HashAlgorithm::Sha256,
+                        key: alice_pub,
+                    },
+                    model::PackageEntry::GrantFlat {
+                        key: bob_pub,
+                        permissions: vec![model::Permission::Release],
+                        expires: None,
+                        versions: None,
+                    },
+                ],
+            },
+        )
+        .unwrap()];
+
+        for i in 1..10u64 {
+            let key = if i % 2 == 0 { &alice_priv } else { &bob_priv };
+            let prev = envelopes.last().unwrap();
+            let record = model::PackageRecord {
+                prev: Some(RecordId::package_record::<Sha256>(prev)),
+                version: PACKAGE_RECORD_VERSION,
+                timestamp: timestamp + Duration::from_secs(i),
+                entries: vec![model::PackageEntry::Release {
+                    version: Version::new(1, i, 0),
+                    content: HashAlgorithm::Sha256.digest(&i.to_le_bytes()),
+                    index: None,
+                    dependencies: Vec::new(),
+                }],
+            };
+            envelopes.push(ProtoEnvelope::signed_contents(key, record).unwrap());
+        }
+
+        // Validating the records together is the same as one at a time
+        let expected = envelopes
+            .iter()
+            .try_fold(LogState::default(), LogState::validate)
+            .unwrap();
+        let state = LogState::default().validate_all(&envelopes).unwrap();
+        assert_eq!(state, expected);
+
+        // Replace the signature of a record with that of another record
+        let mut forged = protobuf::Envelope::decode(envelopes[4].to_protobuf().as_slice()).unwrap();
+        forged.signature = envelopes[2].signature().to_string();
+        envelopes[4] = ProtoEnvelope::from_protobuf(&forged.encode_to_vec()).unwrap();
+        assert!(matches!(
+            LogState::default().validate_all(&envelopes),
+            Err(ValidationError::SignatureError(_))
+        ));
+
+        // The invalid signature is reported before an error in a later record
+        envelopes.swap(6, 7);
+        assert!(matches!(
+            LogState::default().validate_all(&envelopes),
+            Err(ValidationError::SignatureError(_))
+        ));
+    }
 }
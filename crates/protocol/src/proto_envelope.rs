@@ -18,6 +18,16 @@ pub struct PublishedProtoEnvelope<Contents> {
     pub registry_index: RegistryIndex,
 }
 
+/// An additional signature on the contents of an envelope.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cosignature {
+    /// The hash of the key that produced the signature
+    pub key_id: signing::KeyID,
+    /// The signature for the content bytes
+    pub signature: signing::Signature,
+}
+
 /// The envelope struct is used to keep around the original
 /// bytes that the content was serialized into in case
 /// the serialization is not canonical.
@@ -31,6 +41,8 @@ pub struct ProtoEnvelope<Contents> {
     key_id: signing::KeyID,
     /// The signature for the content_bytes
     signature: signing::Signature,
+    /// Additional signatures for the content_bytes from other keys
+    cosignatures: Vec<Cosignature>,
 }
 
 impl<Contents> ProtoEnvelope<Contents> {
@@ -45,13 +57,105 @@ impl<Contents> ProtoEnvelope<Contents> {
         let content_bytes: Vec<u8> = contents.encode();
 
         let key_id = private_key.public_key().fingerprint();
-        let signature = contents.sign(private_key)?;
-        Ok(ProtoEnvelope {
+        let signature = private_key.sign(&Self::signed_bytes(&content_bytes))?;
+        Ok(Self::from_signature(
             contents,
             content_bytes,
             key_id,
             signature,
-        })
+        ))
+    }
+
+    /// Create an envelope for some contents using a signature from the given signer.
+    pub async fn signed_contents_with(
+        signer: &dyn signing::Signer,
+        contents: Contents,
+    ) -> Result<Self, signing::SignatureError>
+    where
+        Contents: Signable,
+    {
+        let content_bytes: Vec<u8> = contents.encode();
+
+        let key_id = signer.public_key().fingerprint();
+        let signature = signer.sign(&Self::signed_bytes(&content_bytes)).await?;
+        Ok(Self::from_signature(
+            contents,
+            content_bytes,
+            key_id,
+            signature,
+        ))
+    }
+
+    /// Adds a signature of the envelope contents from another key.
+    ///
+    /// A cosignature from a key that already cosigned the envelope is replaced.
+    pub fn cosign(&mut self, private_key: &signing::PrivateKey) -> Result<(), CosignError>
+    where
+        Contents: Signable,
+    {
+        let key_id = private_key.public_key().fingerprint();
+        let signature = private_key.sign(&self.cosigned_bytes(&key_id)?)?;
+        self.add_cosignature(key_id, signature);
+        Ok(())
+    }
+
+    /// Adds a signature of the envelope contents from another signer.
+    ///
+    /// A cosignature from a key that already cosigned the envelope is replaced.
+    pub async fn cosign_with(&mut self, signer: &dyn signing::Signer) -> Result<(), CosignError>
+    where
+        Contents: Signable,
+    {
+        let key_id = signer.public_key().fingerprint();
+        let signature = signer.sign(&self.cosigned_bytes(&key_id)?).await?;
+        self.add_cosignature(key_id, signature);
+        Ok(())
+    }
+
+    /// Gets the bytes that are signed for the given content bytes.
+    fn signed_bytes(content_bytes: &[u8]) -> Vec<u8>
+    where
+        Contents: Signable,
+    {
+        [Contents::PREFIX, b":", content_bytes].concat()
+    }
+
+    /// Creates an envelope from contents and the signature of their bytes.
+    fn from_signature(
+        contents: Contents,
+        content_bytes: Vec<u8>,
+        key_id: signing::KeyID,
+        signature: signing::Signature,
+    ) -> Self {
+        ProtoEnvelope {
+            contents,
+            content_bytes,
+            key_id,
+            signature,
+            cosignatures: Vec::new(),
+        }
+    }
+
+    /// Gets the bytes the given key signs to cosign the envelope.
+    ///
+    /// The key that signed the envelope cannot also cosign it.
+    fn cosigned_bytes(&self, key_id: &signing::KeyID) -> Result<Vec<u8>, CosignError>
+    where
+        Contents: Signable,
+    {
+        if *key_id == self.key_id {
+            return Err(CosignError::AlreadySigned {
+                key_id: key_id.clone(),
+            });
+        }
+
+        Ok(Self::signed_bytes(&self.content_bytes))
+    }
+
+    /// Adds a cosignature, replacing any existing one from the same key.
+    fn add_cosignature(&mut self, key_id: signing::KeyID, signature: signing::Signature) {
+        self.cosignatures.retain(|c| c.key_id != key_id);
+        self.cosignatures.push(Cosignature { key_id, signature });
     }
 
     /// Get the byte representation of the envelope contents.
@@ -67,6 +171,11 @@ impl<Contents> ProtoEnvelope<Contents> {
         &self.signature
     }
 
+    /// Gets the additional signatures of the envelope contents.
+    pub fn cosignatures(&self) -> &[Cosignature] {
+        &self.cosignatures
+    }
+
     /// Get the representation of the entire envelope as a byte vector.
     /// This is the logical inverse of `Envelope::from_bytes`.
     pub fn to_protobuf(&self) -> Vec<u8> {
@@ -74,6 +183,14 @@ impl<Contents> ProtoEnvelope<Contents> {
             contents: self.content_bytes.clone(),
             key_id: self.key_id.to_string(),
             signature: self.signature.to_string(),
+            cosignatures: self
+                .cosignatures
+                .iter()
+                .map(|c| protobuf::EnvelopeSignature {
+                    key_id: c.key_id.to_string(),
+                    signature: c.signature.to_string(),
+                })
+                .collect(),
         };
         proto_envelope.encode_to_vec()
     }
@@ -91,12 +208,23 @@ impl<Contents> ProtoEnvelope<Contents> {
         // Read key ID and signature
         let key_id = envelope.key_id.into();
         let signature = envelope.signature.parse()?;
+        let cosignatures = envelope
+            .cosignatures
+            .into_iter()
+            .map(|c| {
+                Ok(Cosignature {
+                    key_id: c.key_id.into(),
+                    signature: c.signature.parse()?,
+                })
+            })
+            .collect::<Result<_, ParseEnvelopeError>>()?;
 
         Ok(ProtoEnvelope {
             contents,
             content_bytes: envelope.contents,
             key_id,
             signature,
+            cosignatures,
         })
     }
 }
@@ -123,6 +251,16 @@ pub enum ParseEnvelopeError {
     Signature(#[from] signing::SignatureParseError),
 }
 
+/// Errors that occur when cosigning an envelope
+#[derive(Error, Debug)]
+pub enum CosignError {
+    #[error("the envelope is already signed by key `{key_id}`")]
+    AlreadySigned { key_id: signing::KeyID },
+
+    #[error("failed to sign envelope contents")]
+    Signature(#[from] signing::SignatureError),
+}
+
 #[serde_as]
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -134,6 +272,9 @@ pub struct ProtoEnvelopeBody {
     key_id: signing::KeyID,
     /// The signature for the content_bytes
     signature: signing::Signature,
+    /// Additional signatures for the content_bytes from other keys
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cosignatures: Vec<Cosignature>,
 }
 
 impl<Content> TryFrom<ProtoEnvelopeBody> for ProtoEnvelope<Content>
@@ -149,6 +290,7 @@ where
             content_bytes: value.content_bytes,
             key_id: value.key_id,
             signature: value.signature,
+            cosignatures: value.cosignatures,
         };
         Ok(envelope)
     }
@@ -160,6 +302,7 @@ impl<Content> From<ProtoEnvelope<Content>> for ProtoEnvelopeBody {
             content_bytes: value.content_bytes,
             key_id: value.key_id,
             signature: value.signature,
+            cosignatures: value.cosignatures,
         }
     }
 }
@@ -170,6 +313,7 @@ impl fmt::Debug for ProtoEnvelopeBody {
             .field("content_bytes", &STANDARD.encode(&self.content_bytes))
             .field("key_id", &self.key_id)
             .field("signature", &self.signature)
+            .field("cosignatures", &self.cosignatures)
             .finish()
     }
 }
@@ -217,6 +361,7 @@ impl fmt::Debug for PublishedProtoEnvelopeBody {
             )
             .field("key_id", &self.envelope.key_id)
             .field("signature", &self.envelope.signature)
+            .field("cosignatures", &self.envelope.cosignatures)
             .field("registry_index", &self.registry_index)
             .finish()
     }
@@ -1,12 +1,13 @@
-use crate::{operator::OperatorRecord, package::PackageRecord, ProtoEnvelope};
+use crate::{operator::OperatorRecord, package::PackageRecord, ProtoEnvelope, SerdeEnvelope};
 use anyhow::bail;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::fmt;
 use std::str::FromStr;
 use std::time::SystemTime;
-use warg_crypto::hash::{AnyHash, Hash, HashAlgorithm, SupportedDigest};
+use warg_crypto::hash::{AnyHash, HashAlgorithm, SupportedDigest};
 use warg_crypto::prefix::VisitPrefixEncode;
-use warg_crypto::{prefix, ByteVisitor, Signable, VisitBytes};
+use warg_crypto::{prefix, signing, ByteVisitor, Encode, Signable, VisitBytes};
 use wasmparser::names::KebabStr;
 
 /// Type alias for registry log index
@@ -58,6 +59,10 @@ impl TimestampedCheckpoint {
     pub fn now(checkpoint: Checkpoint) -> anyhow::Result<Self> {
         Self::new(checkpoint, SystemTime::now())
     }
+
+    pub fn time(&self) -> SystemTime {
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs(self.timestamp)
+    }
 }
 
 impl Signable for TimestampedCheckpoint {
@@ -81,6 +86,100 @@ impl VisitBytes for TimestampedCheckpoint {
     }
 }
 
+/// The checkpoint contents signed by a witness.
+///
+/// Witness cosignatures use a different signing prefix than operator
+/// signatures so that one can never be mistaken for the other.
+struct WitnessedCheckpoint<'a>(&'a TimestampedCheckpoint);
+
+impl Signable for WitnessedCheckpoint<'_> {
+    const PREFIX: &'static [u8] = b"WARG-WITNESS-COSIGNATURE-V0";
+}
+
+impl VisitBytes for WitnessedCheckpoint<'_> {
+    fn visit<BV: ?Sized + ByteVisitor>(&self, visitor: &mut BV) {
+        self.0.visit(visitor);
+    }
+}
+
+/// Represents a witness's cosignature of a checkpoint.
+///
+/// A witness cosigns a checkpoint after verifying that it is consistent
+/// with the last checkpoint the witness has seen.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WitnessCosignature {
+    /// The ID of the witness key that produced the cosignature.
+    pub key_id: signing::KeyID,
+    /// The cosignature of the checkpoint.
+    pub signature: signing::Signature,
+}
+
+impl WitnessCosignature {
+    /// Cosigns the given checkpoint with the witness's private key.
+    pub fn sign(
+        private_key: &signing::PrivateKey,
+        checkpoint: &TimestampedCheckpoint,
+    ) -> Result<Self, signing::SignatureError> {
+        Ok(Self {
+            key_id: private_key.public_key().fingerprint(),
+            signature: WitnessedCheckpoint(checkpoint).sign(private_key)?,
+        })
+    }
+
+    /// Verifies the cosignature of the given checkpoint with the witness's
+    /// public key.
+    pub fn verify(
+        &self,
+        public_key: &signing::PublicKey,
+        checkpoint: &TimestampedCheckpoint,
+    ) -> Result<(), signing::SignatureError> {
+        if public_key.fingerprint() != self.key_id {
+            return Err(signing::SignatureError::new());
+        }
+
+        WitnessedCheckpoint::verify(
+            public_key,
+            &WitnessedCheckpoint(checkpoint).encode(),
+            &self.signature,
+        )
+    }
+}
+
+/// Represents a signed checkpoint along with the cosignatures of witnesses.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CosignedCheckpoint {
+    /// The checkpoint signed by the registry operator.
+    #[serde(flatten)]
+    pub checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    /// The cosignatures of witnesses of the checkpoint.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub witnesses: Vec<WitnessCosignature>,
+}
+
+impl CosignedCheckpoint {
+    /// Counts the given witness keys with a valid cosignature of the
+    /// checkpoint.
+    ///
+    /// Each key is counted at most once.
+    pub fn count_witnesses<'a>(
+        &self,
+        keys: impl IntoIterator<Item = &'a signing::PublicKey>,
+    ) -> usize {
+        let checkpoint = self.checkpoint.as_ref();
+        let mut seen = HashSet::new();
+        keys.into_iter()
+            .filter(|key| seen.insert(key.fingerprint()))
+            .filter(|key| {
+                self.witnesses
+                    .iter()
+                    .any(|w| w.verify(key, checkpoint).is_ok())
+            })
+            .count()
+    }
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct MapLeaf {
     pub record_id: RecordId,
@@ -224,15 +323,25 @@ pub struct LogId(AnyHash);
 
 impl LogId {
     pub fn operator_log<D: SupportedDigest>() -> Self {
-        let prefix: &[u8] = b"WARG-OPERATOR-LOG-ID-V0".as_slice();
-        let hash: Hash<D> = Hash::of(prefix);
-        Self(hash.into())
+        Self::operator_log_for(D::ALGORITHM)
     }
 
     pub fn package_log<D: SupportedDigest>(name: &PackageName) -> Self {
+        Self::package_log_for(D::ALGORITHM, name)
+    }
+
+    /// Gets the operator log identifier for a registry using the given hash
+    /// algorithm.
+    pub fn operator_log_for(algorithm: HashAlgorithm) -> Self {
+        let prefix: &[u8] = b"WARG-OPERATOR-LOG-ID-V0".as_slice();
+        Self(AnyHash::of(algorithm, prefix))
+    }
+
+    /// Gets the log identifier of the given package for a registry using the
+    /// given hash algorithm.
+    pub fn package_log_for(algorithm: HashAlgorithm, name: &PackageName) -> Self {
         let prefix: &[u8] = b"WARG-PACKAGE-LOG-ID-V0:".as_slice();
-        let hash: Hash<D> = Hash::of((prefix, name));
-        Self(hash.into())
+        Self(AnyHash::of(algorithm, (prefix, name)))
     }
 }
 
@@ -276,15 +385,31 @@ impl RecordId {
     }
 
     pub fn operator_record<D: SupportedDigest>(record: &ProtoEnvelope<OperatorRecord>) -> Self {
-        let prefix: &[u8] = b"WARG-OPERATOR-LOG-RECORD-V0:".as_slice();
-        let hash: Hash<D> = Hash::of((prefix, record.content_bytes()));
-        Self(hash.into())
+        Self::operator_record_for(D::ALGORITHM, record)
     }
 
     pub fn package_record<D: SupportedDigest>(record: &ProtoEnvelope<PackageRecord>) -> Self {
+        Self::package_record_for(D::ALGORITHM, record)
+    }
+
+    /// Gets the identifier of the given operator record using the given hash
+    /// algorithm.
+    pub fn operator_record_for(
+        algorithm: HashAlgorithm,
+        record: &ProtoEnvelope<OperatorRecord>,
+    ) -> Self {
+        let prefix: &[u8] = b"WARG-OPERATOR-LOG-RECORD-V0:".as_slice();
+        Self(AnyHash::of(algorithm, (prefix, record.content_bytes())))
+    }
+
+    /// Gets the identifier of the given package record using the given hash
+    /// algorithm.
+    pub fn package_record_for(
+        algorithm: HashAlgorithm,
+        record: &ProtoEnvelope<PackageRecord>,
+    ) -> Self {
         let prefix: &[u8] = b"WARG-PACKAGE-LOG-RECORD-V0:".as_slice();
-        let hash: Hash<D> = Hash::of((prefix, record.content_bytes()));
-        Self(hash.into())
+        Self(AnyHash::of(algorithm, (prefix, record.content_bytes())))
     }
 }
 
@@ -328,4 +453,62 @@ mod tests {
             proof.evaluate(&LogId::operator_log::<Sha256>(), &"foobar")
         );
     }
+
+    #[test]
+    fn witness_cosignature() {
+        let (operator_public, operator_private) = signing::generate_ed25519_pair();
+        let witnesses: Vec<_> = (0..3).map(|_| signing::generate_ed25519_pair()).collect();
+
+        let checkpoint = TimestampedCheckpoint {
+            checkpoint: Checkpoint {
+                log_root: HashAlgorithm::Sha256.digest(b"log"),
+                log_length: 10,
+                map_root: HashAlgorithm::Sha256.digest(b"map"),
+            },
+            timestamp: 1,
+        };
+        let mut cosigned = CosignedCheckpoint {
+            checkpoint: SerdeEnvelope::signed_contents(&operator_private, checkpoint.clone())
+                .unwrap(),
+            witnesses: witnesses[..2]
+                .iter()
+                .map(|(_, key)| WitnessCosignature::sign(key, &checkpoint).unwrap())
+                .collect(),
+        };
+
+        // A witness cosignature is not an operator signature
+        assert!(TimestampedCheckpoint::verify(
+            &witnesses[0].0,
+            &checkpoint.encode(),
+            &cosigned.witnesses[0].signature
+        )
+        .is_err());
+        assert!(cosigned.witnesses[0]
+            .verify(&witnesses[1].0, &checkpoint)
+            .is_err());
+
+        let keys: Vec<_> = witnesses.iter().map(|(key, _)| key.clone()).collect();
+        assert_eq!(cosigned.count_witnesses(&keys), 2);
+        assert_eq!(cosigned.count_witnesses([&keys[0], &keys[0]]), 1);
+        assert_eq!(cosigned.count_witnesses([&operator_public]), 0);
+
+        // Cosignatures of a different checkpoint are not counted
+        let mut other = checkpoint;
+        other.timestamp += 1;
+        cosigned
+            .witnesses
+            .push(WitnessCosignature::sign(&witnesses[2].1, &other).unwrap());
+        assert_eq!(cosigned.count_witnesses(&keys), 2);
+
+        let json = serde_json::to_string(&cosigned).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CosignedCheckpoint>(&json).unwrap(),
+            cosigned
+        );
+        // Clients unaware of witnesses can still read the checkpoint
+        assert_eq!(
+            serde_json::from_str::<SerdeEnvelope<TimestampedCheckpoint>>(&json).unwrap(),
+            cosigned.checkpoint
+        );
+    }
 }
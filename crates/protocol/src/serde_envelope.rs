@@ -38,11 +38,21 @@ impl<Contents> SerdeEnvelope<Contents> {
     {
         let key_id = private_key.public_key().fingerprint();
         let signature = contents.sign(private_key)?;
-        Ok(SerdeEnvelope {
-            contents,
-            key_id,
-            signature,
-        })
+        Ok(Self::from_parts_unchecked(contents, key_id, signature))
+    }
+
+    /// Create an envelope for some contents using a signature from the given signer.
+    pub async fn signed_contents_with(
+        signer: &dyn signing::Signer,
+        contents: Contents,
+    ) -> Result<Self, signing::SignatureError>
+    where
+        Contents: Signable,
+    {
+        let key_id = signer.public_key().fingerprint();
+        let prefixed_content = [Contents::PREFIX, b":", contents.encode().as_slice()].concat();
+        let signature = signer.sign(&prefixed_content).await?;
+        Ok(Self::from_parts_unchecked(contents, key_id, signature))
     }
 
     pub fn into_contents(self) -> Contents {
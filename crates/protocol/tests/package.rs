@@ -40,7 +40,11 @@ fn validate_input(input: Vec<EnvelopeData>) -> Result<LogState> {
 
             record.prev = last.clone();
 
-            let envelope = ProtoEnvelope::signed_contents(&key, record).unwrap();
+            let mut envelope = ProtoEnvelope::signed_contents(&key, record).unwrap();
+            for cosigner in &e_data.cosigners {
+                let key = signing::PrivateKey::decode(cosigner.clone()).unwrap();
+                envelope.cosign(&key).unwrap();
+            }
 
             *last = Some(RecordId::package_record::<Sha256>(&envelope));
 
@@ -116,6 +120,8 @@ fn execute_test(input_path: &Path) {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct EnvelopeData {
     key: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cosigners: Vec<String>,
     contents: protobuf::PackageRecord,
 }
 
@@ -10,14 +10,14 @@ use axum::{
     Router,
 };
 use serde::Serialize;
-use warg_crypto::{
-    hash::{AnyHash, Sha256},
-    signing::KeyID,
-};
+use warg_crypto::{hash::AnyHash, signing::KeyID};
 use warg_protocol::{
-    package::{LogState, Permission, Release},
+    content::ContentIndex,
+    package::{
+        Deprecation, LogState, PackageMetadata, Permission, Release, ReleaseDependency, YankReason,
+    },
     registry::{LogId, PackageName, RecordId},
-    Version,
+    Version, VersionReq,
 };
 
 use crate::{api::v1::Json, services::CoreService};
@@ -59,6 +59,10 @@ struct PackageInfo {
     log_id: LogId,
     records: Vec<RecordInfo>,
     releases: Vec<Release>,
+    #[serde(skip_serializing_if = "PackageMetadata::is_empty")]
+    metadata: PackageMetadata,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deprecation: Option<Deprecation>,
 }
 
 #[derive(Serialize)]
@@ -80,9 +84,27 @@ struct EntryInfo {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     permissions: Vec<Permission>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    expires: Option<SystemTime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    versions: Option<VersionReq>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     version: Option<Version>,
     #[serde(skip_serializing_if = "Option::is_none")]
     content: Option<AnyHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    index: Option<ContentIndex>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    dependencies: Vec<ReleaseDependency>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    metadata: Option<PackageMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<YankReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    successor: Option<PackageName>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threshold: Option<u32>,
 }
 
 #[debug_handler]
@@ -98,20 +120,21 @@ async fn get_package_info(
         .context("get_latest_checkpoint")?;
     let checkpoint_log_length = checkpoint.as_ref().checkpoint.log_length;
 
-    let log_id = LogId::package_log::<Sha256>(&package_name);
+    let hash_algorithm = config.core_service.hash_algorithm();
+    let log_id = config.core_service.package_log_id(&package_name);
     let records = store
         .get_package_records(&log_id, checkpoint_log_length, None, u16::MAX)
         .await
         .context("get_package_records")?;
 
-    let mut package_state = LogState::new();
+    let package_state = LogState::new()
+        .validate_all(records.iter().map(|record| &record.envelope))
+        .context("validate")?;
 
     let records = records
         .into_iter()
         .map(|record| {
-            let state = std::mem::take(&mut package_state);
-            package_state = state.validate(&record.envelope).context("validate")?;
-            let record_id = RecordId::package_record::<Sha256>(&record.envelope);
+            let record_id = RecordId::package_record_for(hash_algorithm, &record.envelope);
             let timestamp = record
                 .envelope
                 .as_ref()
@@ -132,10 +155,17 @@ async fn get_package_info(
                             key: Some(key.to_string()),
                             ..Default::default()
                         },
-                        GrantFlat { key, permissions } => EntryInfo {
+                        GrantFlat {
+                            key,
+                            permissions,
+                            expires,
+                            versions,
+                        } => EntryInfo {
                             kind: "grant",
                             key: Some(key.to_string()),
                             permissions: permissions.clone(),
+                            expires: *expires,
+                            versions: versions.clone(),
                             ..Default::default()
                         },
                         RevokeFlat {
@@ -147,15 +177,58 @@ async fn get_package_info(
                             permissions: permissions.clone(),
                             ..Default::default()
                         },
-                        Release { version, content } => EntryInfo {
+                        RotateKey {
+                            old_key_id,
+                            new_key,
+                        } => EntryInfo {
+                            kind: "rotate-key",
+                            key_id: Some(old_key_id.clone()),
+                            key: Some(new_key.to_string()),
+                            ..Default::default()
+                        },
+                        Release {
+                            version,
+                            content,
+                            index,
+                            dependencies,
+                        } => EntryInfo {
                             kind: "release",
                             version: Some(version.clone()),
                             content: Some(content.clone()),
+                            index: index.clone(),
+                            dependencies: dependencies.clone(),
                             ..Default::default()
                         },
-                        Yank { version } => EntryInfo {
+                        Yank { version, reason } => EntryInfo {
                             kind: "yank",
                             version: Some(version.clone()),
+                            reason: reason.clone(),
+                            ..Default::default()
+                        },
+                        Unyank { version } => EntryInfo {
+                            kind: "unyank",
+                            version: Some(version.clone()),
+                            ..Default::default()
+                        },
+                        Deprecate { successor, message } => EntryInfo {
+                            kind: "deprecate",
+                            successor: successor.clone(),
+                            message: message.clone(),
+                            ..Default::default()
+                        },
+                        Undeprecate => EntryInfo {
+                            kind: "undeprecate",
+                            ..Default::default()
+                        },
+                        ReleaseThreshold { threshold } => EntryInfo {
+                            kind: "release-threshold",
+                            threshold: Some(*threshold),
+                            ..Default::default()
+                        },
+                        Metadata { version, metadata } => EntryInfo {
+                            kind: "metadata",
+                            version: version.clone(),
+                            metadata: Some(metadata.clone()),
                             ..Default::default()
                         },
                         _ => EntryInfo {
@@ -174,12 +247,16 @@ async fn get_package_info(
         .collect::<Result<_, DebugError>>()?;
 
     let releases = package_state.releases().cloned().collect();
+    let metadata = package_state.metadata().clone();
+    let deprecation = package_state.deprecation().cloned();
 
     Ok(Json(PackageInfo {
         package_name,
         log_id,
         records,
         releases,
+        metadata,
+        deprecation,
     }))
 }
 
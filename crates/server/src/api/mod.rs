@@ -1,8 +1,16 @@
 use crate::{
+    datastore::DataStoreError,
     policy::{content::ContentPolicy, record::RecordPolicy},
     services::CoreService,
 };
-use axum::{body::Body, http::Request, Router};
+use axum::{
+    body::Body,
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
 use std::{path::PathBuf, sync::Arc};
 use tower::ServiceBuilder;
 use tower_http::{
@@ -13,6 +21,7 @@ use tower_http::{
 };
 use tracing::{Level, Span};
 use url::Url;
+use warg_crypto::hash::AnyHash;
 
 pub mod v1;
 
@@ -27,23 +36,34 @@ pub fn create_router(
     files_dir: PathBuf,
     content_policy: Option<Arc<dyn ContentPolicy>>,
     record_policy: Option<Arc<dyn RecordPolicy>>,
+    witnesses: Arc<v1::witness::Witnesses>,
 ) -> Router {
     let router = Router::new();
     #[cfg(feature = "debug")]
     let router = router.nest("/debug", debug::Config::new(core.clone()).into_router());
+    let router = match core.tiles_dir() {
+        Some(tiles_dir) => router.nest_service("/tiles", ServeDir::new(tiles_dir)),
+        None => router,
+    };
     router
         .nest(
             "/v1",
             v1::create_router(
                 content_base_url,
-                core,
+                core.clone(),
                 temp_dir,
                 files_dir.clone(),
                 content_policy,
                 record_policy,
+                witnesses,
             ),
         )
-        .nest_service("/content", ServeDir::new(files_dir))
+        .nest_service(
+            "/content",
+            ServiceBuilder::new()
+                .layer(middleware::from_fn_with_state(core, reject_blocked_content))
+                .service(ServeDir::new(files_dir)),
+        )
         .layer(
             ServiceBuilder::new()
                 .layer(
@@ -69,3 +89,37 @@ pub fn create_router(
                 ),
         )
 }
+
+/// Middleware that prevents serving content files blocked by the operator.
+async fn reject_blocked_content(
+    State(core): State<CoreService>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    // Content files are named after their digest with the `:` replaced by `-`
+    let digest = request
+        .uri()
+        .path()
+        .trim_start_matches('/')
+        .replacen('-', ":", 1)
+        .parse::<AnyHash>();
+
+    if let Ok(digest) = digest {
+        match core
+            .store()
+            .verify_content_not_blocked(&core.operator_log_id(), &digest)
+            .await
+        {
+            Ok(()) => {}
+            Err(e @ DataStoreError::ContentBlocked { .. }) => {
+                return (StatusCode::GONE, e.to_string()).into_response();
+            }
+            Err(e) => {
+                tracing::error!("unexpected data store error: {e}");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    }
+
+    next.run(request).await
+}
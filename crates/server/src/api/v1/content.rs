@@ -1,4 +1,5 @@
 use super::{Json, Path, RegistryHeader};
+use crate::{datastore::DataStoreError, services::CoreService};
 use axum::{
     debug_handler, extract::State, http::StatusCode, response::IntoResponse, routing::get, Router,
 };
@@ -9,13 +10,15 @@ use warg_crypto::hash::AnyHash;
 
 #[derive(Clone)]
 pub struct Config {
+    core_service: CoreService,
     content_base_url: Url,
     files_dir: PathBuf,
 }
 
 impl Config {
-    pub fn new(content_base_url: Url, files_dir: PathBuf) -> Self {
+    pub fn new(core_service: CoreService, content_base_url: Url, files_dir: PathBuf) -> Self {
         Self {
+            core_service,
             content_base_url,
             files_dir,
         }
@@ -51,6 +54,25 @@ impl Config {
 
 struct ContentApiError(ContentError);
 
+impl From<DataStoreError> for ContentApiError {
+    fn from(e: DataStoreError) -> Self {
+        Self(match e {
+            DataStoreError::ContentBlocked { .. } => ContentError::Message {
+                status: StatusCode::GONE.as_u16(),
+                message: e.to_string(),
+            },
+            // Other errors are internal server errors
+            e => {
+                tracing::error!("unexpected data store error: {e}");
+                ContentError::Message {
+                    status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    message: "an error occurred while processing the request".into(),
+                }
+            }
+        })
+    }
+}
+
 impl IntoResponse for ContentApiError {
     fn into_response(self) -> axum::response::Response {
         (StatusCode::from_u16(self.0.status()).unwrap(), Json(self.0)).into_response()
@@ -67,6 +89,12 @@ async fn get_content(
         return Err(ContentApiError(ContentError::ContentDigestNotFound(digest)));
     }
 
+    config
+        .core_service
+        .store()
+        .verify_content_not_blocked(&config.core_service.operator_log_id(), &digest)
+        .await?;
+
     let mut content_sources = HashMap::with_capacity(1);
     let url = config.content_url(&digest);
     content_sources.insert(
@@ -1,4 +1,4 @@
-use super::{Json, RegistryHeader};
+use super::{witness::Witnesses, Json, RegistryHeader};
 use crate::datastore::DataStoreError;
 use crate::services::CoreService;
 use axum::http::StatusCode;
@@ -9,14 +9,13 @@ use axum::{
     routing::{get, post},
     Router,
 };
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 use warg_api::v1::fetch::{
     FetchError, FetchLogsRequest, FetchLogsResponse, FetchPackageNamesRequest,
     FetchPackageNamesResponse, PublishedRecord,
 };
-use warg_crypto::hash::{AnyHash, Sha256};
-use warg_protocol::registry::{LogId, RecordId, TimestampedCheckpoint};
-use warg_protocol::SerdeEnvelope;
+use warg_crypto::hash::AnyHash;
+use warg_protocol::registry::{CosignedCheckpoint, RecordId};
 
 const DEFAULT_RECORDS_LIMIT: u16 = 100;
 const MAX_RECORDS_LIMIT: u16 = 1000;
@@ -26,11 +25,15 @@ const MAX_PACKAGE_NAMES_LIMIT: usize = 1000;
 #[derive(Clone)]
 pub struct Config {
     core_service: CoreService,
+    witnesses: Arc<Witnesses>,
 }
 
 impl Config {
-    pub fn new(core_service: CoreService) -> Self {
-        Self { core_service }
+    pub fn new(core_service: CoreService, witnesses: Arc<Witnesses>) -> Self {
+        Self {
+            core_service,
+            witnesses,
+        }
     }
 
     pub fn into_router(self) -> Router {
@@ -94,6 +97,7 @@ async fn fetch_logs(
         )));
     }
 
+    let hash_algorithm = config.core_service.hash_algorithm();
     let operator_fetch_token: Option<RecordId> = match body.operator {
         Some(s) => Some(
             s.parse::<AnyHash>()
@@ -106,7 +110,7 @@ async fn fetch_logs(
         .core_service
         .store()
         .get_operator_records(
-            &LogId::operator_log::<Sha256>(),
+            &config.core_service.operator_log_id(),
             body.log_length,
             operator_fetch_token.as_ref(),
             limit,
@@ -115,7 +119,8 @@ async fn fetch_logs(
         .into_iter()
         .map(|envelope| {
             // use the record ID as the fetch token
-            let fetch_token = RecordId::operator_record::<Sha256>(&envelope.envelope).to_string();
+            let fetch_token =
+                RecordId::operator_record_for(hash_algorithm, &envelope.envelope).to_string();
             PublishedRecord {
                 envelope: envelope.into(),
                 fetch_token,
@@ -145,7 +150,7 @@ async fn fetch_logs(
             .map(|envelope| {
                 // use the record ID as the fetch token
                 let fetch_token =
-                    RecordId::package_record::<Sha256>(&envelope.envelope).to_string();
+                    RecordId::package_record_for(hash_algorithm, &envelope.envelope).to_string();
                 PublishedRecord {
                     envelope: envelope.into(),
                     fetch_token,
@@ -163,13 +168,21 @@ async fn fetch_logs(
     }))
 }
 
+/// Fetches the checkpoint clients should update to.
+///
+/// If a witness quorum is configured, this is the latest checkpoint cosigned
+/// by the quorum; until a checkpoint has been cosigned by the quorum, it is
+/// the latest checkpoint.
 #[debug_handler]
 async fn fetch_checkpoint(
     State(config): State<Config>,
     RegistryHeader(_registry_header): RegistryHeader,
-) -> Result<Json<SerdeEnvelope<TimestampedCheckpoint>>, FetchApiError> {
+) -> Result<Json<CosignedCheckpoint>, FetchApiError> {
     Ok(Json(
-        config.core_service.store().get_latest_checkpoint().await?,
+        config
+            .witnesses
+            .cosigned_checkpoint(config.core_service.store())
+            .await?,
     ))
 }
 
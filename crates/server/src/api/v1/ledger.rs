@@ -8,7 +8,6 @@ use axum::{
 use warg_api::v1::ledger::{
     LedgerError, LedgerSource, LedgerSourceContentType, LedgerSourcesResponse,
 };
-use warg_crypto::hash::HashAlgorithm;
 use warg_protocol::registry::RegistryIndex;
 
 const MAX_LEDGER_RECORDS_LIMIT: usize = 1000;
@@ -84,7 +83,7 @@ async fn get_ledger_sources(
         .collect::<Vec<LedgerSource>>();
 
     Ok(Json(LedgerSourcesResponse {
-        hash_algorithm: HashAlgorithm::Sha256,
+        hash_algorithm: config.core_service.hash_algorithm(),
         sources,
     }))
 }
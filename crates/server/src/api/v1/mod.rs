@@ -22,8 +22,10 @@ pub mod content;
 pub mod fetch;
 pub mod ledger;
 pub mod monitor;
+pub mod operator;
 pub mod package;
 pub mod proof;
+pub mod witness;
 
 /// An extractor that wraps the JSON extractor of Axum.
 ///
@@ -131,8 +133,10 @@ pub fn create_router(
     files_dir: PathBuf,
     content_policy: Option<Arc<dyn ContentPolicy>>,
     record_policy: Option<Arc<dyn RecordPolicy>>,
+    witnesses: Arc<witness::Witnesses>,
 ) -> Router {
     let proof_config = proof::Config::new(core.clone());
+    let operator_config = operator::Config::new(core.clone());
     let package_config = package::Config::new(
         core.clone(),
         files_dir.clone(),
@@ -140,8 +144,9 @@ pub fn create_router(
         content_policy,
         record_policy,
     );
-    let fetch_config = fetch::Config::new(core.clone());
-    let content_config = content::Config::new(content_base_url, files_dir);
+    let fetch_config = fetch::Config::new(core.clone(), witnesses.clone());
+    let witness_config = witness::Config::new(core.clone(), witnesses);
+    let content_config = content::Config::new(core.clone(), content_base_url, files_dir);
     let monitor_config = monitor::Config::new(core.clone());
     let ledger_config = ledger::Config::new(core);
 
@@ -149,8 +154,10 @@ pub fn create_router(
         .nest("/content", content_config.into_router())
         .nest("/fetch", fetch_config.into_router())
         .nest("/ledger", ledger_config.into_router())
+        .nest("/operator", operator_config.into_router())
         .nest("/package", package_config.into_router())
         .nest("/proof", proof_config.into_router())
         .nest("/verify", monitor_config.into_router())
+        .nest("/witness", witness_config.into_router())
         .fallback(not_found)
 }
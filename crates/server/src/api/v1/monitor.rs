@@ -4,8 +4,7 @@ use crate::services::CoreService;
 use axum::http::StatusCode;
 use axum::{debug_handler, extract::State, response::IntoResponse, routing::post, Router};
 use warg_api::v1::monitor::{CheckpointVerificationResponse, MonitorError, VerificationState};
-use warg_crypto::hash::Sha256;
-use warg_protocol::registry::{LogId, TimestampedCheckpoint};
+use warg_protocol::registry::TimestampedCheckpoint;
 use warg_protocol::SerdeEnvelope;
 
 #[derive(Clone)]
@@ -58,7 +57,8 @@ impl IntoResponse for MonitorApiError {
 ///
 /// Signature verification is `Verified` when either
 /// A) It matches the signature on the stored checkpoint, or
-/// B) It is a valid and authorized signature by a key in the operator log.
+/// B) It is a valid and authorized signature by a key in the operator log,
+///    including keys retired by a record not included in the checkpoint.
 ///
 /// Signature verification is `Invalid` otherwise.
 #[debug_handler]
@@ -76,7 +76,7 @@ async fn verify_checkpoint(
         match config
             .core_service
             .store()
-            .verify_timestamped_checkpoint_signature(&LogId::operator_log::<Sha256>(), &body)
+            .verify_timestamped_checkpoint_signature(&config.core_service.operator_log_id(), &body)
             .await
         {
             Ok(_) => VerificationState::Verified,
@@ -25,7 +25,7 @@ use warg_api::v1::package::{
     MissingContent, PackageError, PackageRecord, PackageRecordState, PublishRecordRequest,
     UploadEndpoint,
 };
-use warg_crypto::hash::{AnyHash, Sha256};
+use warg_crypto::hash::AnyHash;
 use warg_protocol::{
     package,
     registry::{LogId, RecordId},
@@ -151,6 +151,9 @@ impl From<DataStoreError> for PackageApiError {
             DataStoreError::PackageNameConflict { existing, .. } => {
                 PackageError::PackageNameConflict(existing)
             }
+            DataStoreError::PackageBlocked { .. } | DataStoreError::ContentBlocked { .. } => {
+                PackageError::Rejection(e.to_string())
+            }
             // Other errors are internal server errors
             e => {
                 tracing::error!("unexpected data store error: {e}");
@@ -193,7 +196,7 @@ async fn publish_record(
     RegistryHeader(_registry_header): RegistryHeader,
     Json(body): Json<PublishRecordRequest<'static>>,
 ) -> Result<impl IntoResponse, PackageApiError> {
-    let expected_log_id = LogId::package_log::<Sha256>(&body.package_name);
+    let expected_log_id = config.core_service.package_log_id(&body.package_name);
     if expected_log_id != log_id {
         return Err(PackageApiError::bad_request(format!(
             "package log identifier `{expected_log_id}` derived from `{name}` does not match provided log identifier `{log_id}`",
@@ -207,6 +210,19 @@ async fn publish_record(
         .try_into()
         .map_err(PackageApiError::bad_request)?;
 
+    // Package logs must use the hash algorithm of the registry
+    let hash_algorithm = config.core_service.hash_algorithm();
+    if let Some(found) = record.as_ref().entries.iter().find_map(|e| match e {
+        package::PackageEntry::Init { hash_algorithm, .. } => Some(*hash_algorithm),
+        _ => None,
+    }) {
+        if found != hash_algorithm {
+            return Err(PackageApiError::bad_request(format!(
+                "package log uses hash algorithm `{found}` but the registry uses `{hash_algorithm}`"
+            )));
+        }
+    }
+
     // Specifying content sources is not allowed in this implementation
     if !body.content_sources.is_empty() {
         return Err(PackageApiError::unsupported(
@@ -220,9 +236,28 @@ async fn publish_record(
     config
         .core_service
         .store()
-        .verify_can_publish_package(&LogId::operator_log::<Sha256>(), &body.package_name)
+        .verify_can_publish_package(&config.core_service.operator_log_id(), &body.package_name)
         .await?;
 
+    // Verify none of the record's content has been blocked by the operator.
+    // For multi-file releases, this includes both the release content digest
+    // and the digest of every entry in the content index.
+    let mut digests = record.as_ref().contents();
+    digests.extend(
+        record
+            .as_ref()
+            .entries
+            .iter()
+            .filter_map(package::PackageEntry::content),
+    );
+    for digest in digests {
+        config
+            .core_service
+            .store()
+            .verify_content_not_blocked(&config.core_service.operator_log_id(), digest)
+            .await?;
+    }
+
     // Preemptively perform the policy check on the record before storing it
     // This is performed here so that we never store an unauthorized record
     if let Some(policy) = &config.record_policy {
@@ -236,7 +271,7 @@ async fn publish_record(
         .verify_package_record_signature(&log_id, &record)
         .await?;
 
-    let record_id = RecordId::package_record::<Sha256>(&record);
+    let record_id = RecordId::package_record_for(hash_algorithm, &record);
     let mut missing = record.as_ref().contents();
     missing.retain(|d| !config.content_present(d));
 
@@ -251,7 +286,8 @@ async fn publish_record(
         config
             .core_service
             .submit_package_record(log_id, record_id.clone())
-            .await;
+            .await
+            .map_err(PackageApiError::internal_error)?;
 
         return Ok((
             StatusCode::ACCEPTED,
@@ -384,7 +420,8 @@ async fn upload_content(
         config
             .core_service
             .submit_package_record(log_id, record_id)
-            .await;
+            .await
+            .map_err(PackageApiError::internal_error)?;
     }
 
     Ok(StatusCode::CREATED)
@@ -4,7 +4,8 @@ use axum::{
     debug_handler, extract::State, http::StatusCode, response::IntoResponse, routing::post, Router,
 };
 use warg_api::v1::proof::{
-    ConsistencyRequest, ConsistencyResponse, InclusionRequest, InclusionResponse, ProofError,
+    AbsenceRequest, AbsenceResponse, ConsistencyRequest, ConsistencyResponse, InclusionRequest,
+    InclusionResponse, ProofError, TransitionRequest, TransitionResponse,
 };
 use warg_protocol::registry::{RegistryIndex, RegistryLen};
 
@@ -22,6 +23,8 @@ impl Config {
         Router::new()
             .route("/consistency", post(prove_consistency))
             .route("/inclusion", post(prove_inclusion))
+            .route("/absence", post(prove_absence))
+            .route("/transition", post(prove_transition))
             .with_state(self)
     }
 }
@@ -37,6 +40,7 @@ impl From<CoreServiceError> for ProofApiError {
             CoreServiceError::LeafNotFound(leaf) => ProofError::LeafNotFound(leaf),
             CoreServiceError::BundleFailure(e) => ProofError::BundleFailure(e.to_string()),
             CoreServiceError::PackageNotIncluded(id) => ProofError::PackageLogNotIncluded(id),
+            CoreServiceError::PackageIncluded(id) => ProofError::PackageLogIncluded(id),
             CoreServiceError::IncorrectProof { root, found } => {
                 ProofError::IncorrectProof { root, found }
             }
@@ -63,14 +67,12 @@ async fn prove_consistency(
     RegistryHeader(_registry_header): RegistryHeader,
     Json(body): Json<ConsistencyRequest>,
 ) -> Result<Json<ConsistencyResponse>, ProofApiError> {
-    let bundle = config
+    let proof = config
         .core
         .log_consistency_proof(body.from as RegistryLen, body.to as RegistryLen)
         .await?;
 
-    Ok(Json(ConsistencyResponse {
-        proof: bundle.encode(),
-    }))
+    Ok(Json(ConsistencyResponse { proof }))
 }
 
 #[debug_handler]
@@ -86,11 +88,40 @@ async fn prove_inclusion(
         .map(|index| index as RegistryIndex)
         .collect::<Vec<RegistryIndex>>();
 
-    let log_bundle = config.core.log_inclusion_proofs(log_length, &leafs).await?;
-    let map_bundle = config.core.map_inclusion_proofs(log_length, &leafs).await?;
+    let log = config.core.log_inclusion_proofs(log_length, &leafs).await?;
+    let map = config.core.map_inclusion_proofs(log_length, &leafs).await?;
 
-    Ok(Json(InclusionResponse {
-        log: log_bundle.encode(),
-        map: map_bundle.encode(),
-    }))
+    Ok(Json(InclusionResponse { log, map }))
+}
+
+#[debug_handler]
+async fn prove_absence(
+    State(config): State<Config>,
+    RegistryHeader(_registry_header): RegistryHeader,
+    Json(body): Json<AbsenceRequest>,
+) -> Result<Json<AbsenceResponse>, ProofApiError> {
+    let map = config
+        .core
+        .map_absence_proofs(body.log_length as RegistryLen, &body.log_ids)
+        .await?;
+
+    Ok(Json(AbsenceResponse { map }))
+}
+
+#[debug_handler]
+async fn prove_transition(
+    State(config): State<Config>,
+    RegistryHeader(_registry_header): RegistryHeader,
+    Json(body): Json<TransitionRequest>,
+) -> Result<Json<TransitionResponse>, ProofApiError> {
+    let map = config
+        .core
+        .map_transition_proof(
+            body.from as RegistryLen,
+            body.to as RegistryLen,
+            &body.log_ids,
+        )
+        .await?;
+
+    Ok(Json(TransitionResponse { map }))
 }
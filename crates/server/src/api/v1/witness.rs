@@ -0,0 +1,181 @@
+use super::{Json, RegistryHeader};
+use crate::datastore::{DataStore, DataStoreError};
+use crate::services::CoreService;
+use axum::http::StatusCode;
+use axum::{
+    debug_handler,
+    extract::State,
+    response::IntoResponse,
+    routing::{get, post},
+    Router,
+};
+use indexmap::IndexMap;
+use std::sync::Arc;
+use warg_api::v1::witness::{CosignCheckpointRequest, WitnessError};
+use warg_crypto::signing::{KeyID, PublicKey};
+use warg_protocol::registry::{CosignedCheckpoint, TimestampedCheckpoint};
+use warg_protocol::SerdeEnvelope;
+
+/// The witnesses trusted to cosign the registry's checkpoints.
+#[derive(Debug, Default)]
+pub struct Witnesses {
+    keys: IndexMap<KeyID, PublicKey>,
+    quorum: usize,
+}
+
+impl Witnesses {
+    /// Creates a new set of trusted witnesses.
+    ///
+    /// Once a checkpoint has been cosigned by `quorum` of the witnesses, the
+    /// fetch checkpoint API serves the latest such checkpoint rather than the
+    /// latest checkpoint.
+    pub fn new(keys: impl IntoIterator<Item = PublicKey>, quorum: usize) -> Self {
+        Self {
+            keys: keys.into_iter().map(|k| (k.fingerprint(), k)).collect(),
+            quorum,
+        }
+    }
+
+    /// Gets the checkpoint to serve to clients along with the cosignatures
+    /// of trusted witnesses.
+    pub(crate) async fn cosigned_checkpoint(
+        &self,
+        store: &dyn DataStore,
+    ) -> Result<CosignedCheckpoint, DataStoreError> {
+        let key_ids = self.keys.keys().cloned().collect::<Vec<_>>();
+        let checkpoint = match self.quorum {
+            0 => None,
+            quorum => {
+                store
+                    .get_latest_cosigned_checkpoint(&key_ids, quorum)
+                    .await?
+            }
+        };
+
+        let checkpoint = match checkpoint {
+            Some(checkpoint) => checkpoint,
+            None => store.get_latest_checkpoint().await?,
+        };
+
+        self.with_cosignatures(store, checkpoint).await
+    }
+
+    async fn with_cosignatures(
+        &self,
+        store: &dyn DataStore,
+        checkpoint: SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<CosignedCheckpoint, DataStoreError> {
+        let witnesses = store
+            .get_witness_cosignatures(checkpoint.as_ref().checkpoint.log_length)
+            .await?
+            .into_iter()
+            .filter(|c| self.keys.contains_key(&c.key_id))
+            .collect();
+
+        Ok(CosignedCheckpoint {
+            checkpoint,
+            witnesses,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct Config {
+    core_service: CoreService,
+    witnesses: Arc<Witnesses>,
+}
+
+impl Config {
+    pub fn new(core_service: CoreService, witnesses: Arc<Witnesses>) -> Self {
+        Self {
+            core_service,
+            witnesses,
+        }
+    }
+
+    pub fn into_router(self) -> Router {
+        Router::new()
+            .route("/checkpoint", get(get_checkpoint))
+            .route("/cosignature", post(cosign_checkpoint))
+            .with_state(self)
+    }
+}
+
+struct WitnessApiError(WitnessError);
+
+impl From<DataStoreError> for WitnessApiError {
+    fn from(e: DataStoreError) -> Self {
+        Self(match e {
+            DataStoreError::CheckpointNotFound(log_length) => {
+                WitnessError::CheckpointNotFound(log_length)
+            }
+            // Other errors are internal server errors
+            e => {
+                tracing::error!("unexpected data store error: {e}");
+                WitnessError::Message {
+                    status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
+                    message: "an error occurred while processing the request".into(),
+                }
+            }
+        })
+    }
+}
+
+impl IntoResponse for WitnessApiError {
+    fn into_response(self) -> axum::response::Response {
+        (StatusCode::from_u16(self.0.status()).unwrap(), Json(self.0)).into_response()
+    }
+}
+
+/// Gets the latest checkpoint for witnesses to cosign.
+///
+/// Unlike the fetch checkpoint API, this is always the latest checkpoint
+/// regardless of how many witnesses have cosigned it.
+#[debug_handler]
+async fn get_checkpoint(
+    State(config): State<Config>,
+    RegistryHeader(_registry_header): RegistryHeader,
+) -> Result<Json<SerdeEnvelope<TimestampedCheckpoint>>, WitnessApiError> {
+    Ok(Json(
+        config.core_service.store().get_latest_checkpoint().await?,
+    ))
+}
+
+/// Stores a witness cosignature of a checkpoint.
+///
+/// Only cosignatures by trusted witness keys are accepted; the response is
+/// the checkpoint with every trusted cosignature received so far.
+#[debug_handler]
+async fn cosign_checkpoint(
+    State(config): State<Config>,
+    RegistryHeader(_registry_header): RegistryHeader,
+    Json(body): Json<CosignCheckpointRequest>,
+) -> Result<Json<CosignedCheckpoint>, WitnessApiError> {
+    let CosignCheckpointRequest {
+        log_length,
+        cosignature,
+    } = body;
+
+    let key = config
+        .witnesses
+        .keys
+        .get(&cosignature.key_id)
+        .ok_or_else(|| WitnessApiError(WitnessError::UnknownWitness(cosignature.key_id.clone())))?;
+
+    let store = config.core_service.store();
+    let checkpoint = store.get_checkpoint(log_length).await?;
+    cosignature.verify(key, checkpoint.as_ref()).map_err(|_| {
+        WitnessApiError(WitnessError::InvalidCosignature(cosignature.key_id.clone()))
+    })?;
+
+    store
+        .store_witness_cosignature(log_length, cosignature)
+        .await?;
+
+    Ok(Json(
+        config
+            .witnesses
+            .with_cosignatures(store, checkpoint)
+            .await?,
+    ))
+}
@@ -1,11 +1,21 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use clap::{Parser, ValueEnum};
-use secrecy::SecretString;
-use std::{net::SocketAddr, path::PathBuf};
+#[cfg(unix)]
+use futures::{stream, Stream};
+use secrecy::{ExposeSecret, SecretString};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 use tokio::signal;
 use tracing_subscriber::filter::LevelFilter;
 use url::Url;
-use warg_crypto::signing::PrivateKey;
+#[cfg(unix)]
+use warg_crypto::signing::KeyID;
+use warg_crypto::{
+    hash::HashAlgorithm,
+    signing::{
+        external::{self, SignerEndpoint},
+        EncryptedPrivateKey, ExternalSigner, PrivateKey, PublicKey, Signer,
+    },
+};
 use warg_protocol::operator;
 use warg_server::{args::get_opt_secret, policy::record::AuthorizedKeyPolicy, Config, Server};
 
@@ -35,6 +45,34 @@ struct Args {
     #[arg(long, env = "WARG_CONTENT_BASE_URL")]
     content_base_url: Option<Url>,
 
+    /// The file to store the registry log in; if not set, the registry log
+    /// is kept in memory and rebuilt from the data store on restart.
+    ///
+    /// Together with `--map-file`, restarts resume from the latest
+    /// checkpoint instead of replaying every record.
+    #[arg(long, env = "WARG_LOG_FILE")]
+    log_file: Option<PathBuf>,
+
+    /// The file to store the checkpointed versions of the verifiable map in;
+    /// if not set, they are kept in memory.
+    #[arg(long, env = "WARG_MAP_FILE")]
+    map_file: Option<PathBuf>,
+
+    /// The number of latest checkpoints to serve map inclusion proofs for;
+    /// if not set, all checkpoints are retained.
+    #[arg(long, env = "WARG_MAP_RETENTION")]
+    map_retention: Option<usize>,
+
+    /// The directory to write the log tiles to; defaults to the `tiles`
+    /// subdirectory of the content directory.
+    #[arg(long, env = "WARG_TILES_DIR")]
+    tiles_dir: Option<PathBuf>,
+
+    /// The origin line of the log's checkpoint notes; defaults to the base
+    /// content URL without its scheme.
+    #[arg(long, env = "WARG_TILES_ORIGIN")]
+    tiles_origin: Option<String>,
+
     /// The data store to use for the server.
     #[arg(long, env = "WARG_DATA_STORE", default_value = "memory")]
     data_store: DataStoreKind,
@@ -64,9 +102,48 @@ struct Args {
     operator_key: Option<SecretString>,
 
     /// The path to the operator key.
+    ///
+    /// On Unix, sending the server `SIGHUP` reloads the operator key from
+    /// this file and rotates to it if it changed.
     #[arg(long, env = "WARG_OPERATOR_KEY_FILE", conflicts_with = "operator_key")]
     operator_key_file: Option<PathBuf>,
 
+    /// The passphrase of an encrypted operator key.
+    ///
+    /// Prefer using `operator-key-passphrase-file`, or environment variable variation.
+    #[arg(long, env = "WARG_OPERATOR_KEY_PASSPHRASE")]
+    operator_key_passphrase: Option<SecretString>,
+
+    /// The path to the passphrase of an encrypted operator key.
+    #[arg(
+        long,
+        env = "WARG_OPERATOR_KEY_PASSPHRASE_FILE",
+        conflicts_with = "operator_key_passphrase"
+    )]
+    operator_key_passphrase_file: Option<PathBuf>,
+
+    /// The external signer holding the operator key, instead of an operator key.
+    ///
+    /// Either `exec:<command>` to run a signing command for each request or
+    /// `unix:<path>` to connect to a signing daemon's Unix domain socket.
+    #[arg(
+        long,
+        env = "WARG_OPERATOR_SIGNER",
+        conflicts_with_all = ["operator_key", "operator_key_file"]
+    )]
+    operator_signer: Option<SignerEndpoint>,
+
+    /// The number of seconds the operator signer has to answer a request.
+    ///
+    /// Defaults to 30 seconds.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        env = "WARG_OPERATOR_SIGNER_TIMEOUT",
+        requires = "operator_signer"
+    )]
+    operator_signer_timeout: Option<u64>,
+
     /// The path to the authorized keys record policy file.
     #[arg(long, env = "WARG_AUTHORIZED_KEYS_FILE")]
     authorized_keys_file: Option<PathBuf>,
@@ -74,6 +151,33 @@ struct Args {
     /// The initial namespace defined for this registry.
     #[arg(long, env = "WARG_NAMESPACE")]
     namespace: Option<String>,
+
+    /// The hash algorithm of a new registry (`sha256`, `sha512`, or `blake3`).
+    ///
+    /// An existing registry keeps the hash algorithm of its operator log.
+    #[arg(long, env = "WARG_HASH_ALGORITHM", default_value = "sha256")]
+    hash_algorithm: HashAlgorithm,
+
+    /// The public key of a witness trusted to cosign checkpoints.
+    ///
+    /// May be specified multiple times.
+    #[arg(
+        long = "witness-key",
+        value_name = "KEY",
+        env = "WARG_WITNESS_KEYS",
+        value_delimiter = ','
+    )]
+    witness_keys: Vec<PublicKey>,
+
+    /// The number of witness cosignatures a checkpoint needs before it is
+    /// served to clients.
+    #[arg(
+        long,
+        env = "WARG_WITNESS_QUORUM",
+        default_value = "0",
+        requires = "witness_keys"
+    )]
+    witness_quorum: usize,
 }
 
 impl Args {
@@ -95,23 +199,108 @@ async fn main() -> Result<()> {
     args.init_tracing();
     tracing::debug!("args: {args:?}");
 
-    let operator_key_str =
-        get_opt_secret("operator-key", args.operator_key_file, args.operator_key)?;
-    let operator_key =
-        PrivateKey::decode(operator_key_str).context("failed to parse operator key")?;
+    #[cfg(unix)]
+    let mut key_reloads = None;
+    let operator_signer: Arc<dyn Signer> = match args.operator_signer {
+        Some(endpoint) => Arc::new(
+            ExternalSigner::connect_with_timeout(
+                endpoint,
+                args.operator_signer_timeout
+                    .map_or(external::DEFAULT_TIMEOUT, Duration::from_secs),
+            )
+            .await
+            .context("failed to connect to the operator signer")?,
+        ),
+        None => {
+            let operator_key_str = get_opt_secret(
+                "operator-key",
+                args.operator_key_file.clone(),
+                args.operator_key,
+            )?;
+            let passphrase = match (
+                args.operator_key_passphrase_file,
+                args.operator_key_passphrase,
+            ) {
+                (None, None) => None,
+                (path, val) => {
+                    // Passphrase files commonly end with a newline that isn't
+                    // part of the passphrase
+                    let passphrase = get_opt_secret("operator-key-passphrase", path, val)?;
+                    Some(SecretString::new(
+                        passphrase
+                            .expose_secret()
+                            .trim_end_matches(['\r', '\n'])
+                            .to_string(),
+                    ))
+                }
+            };
+            let operator_key = decode_operator_key(operator_key_str, passphrase.as_ref())?;
+            #[cfg(unix)]
+            {
+                key_reloads = args
+                    .operator_key_file
+                    .map(|path| {
+                        operator_key_reloads(
+                            path,
+                            passphrase,
+                            operator_key.public_key().fingerprint(),
+                        )
+                    })
+                    .transpose()?;
+            }
+            Arc::new(operator_key)
+        }
+    };
     let namespaces = args
         .namespace
         .as_ref()
         .map(|namespace| vec![(namespace.to_lowercase(), operator::NamespaceState::Defined)]);
 
-    let mut config = Config::new(operator_key, namespaces, args.content_dir)
+    let mut config = Config::new(operator_signer, namespaces, args.content_dir)
         .with_addr(args.listen)
+        .with_hash_algorithm(args.hash_algorithm)
         .with_shutdown(shutdown_signal());
 
+    if args.witness_quorum > args.witness_keys.len() {
+        bail!(
+            "witness quorum of {quorum} exceeds the number of witness keys",
+            quorum = args.witness_quorum
+        );
+    }
+
+    if !args.witness_keys.is_empty() {
+        config = config.with_witnesses(args.witness_keys, args.witness_quorum);
+    }
+
+    #[cfg(unix)]
+    if let Some(key_reloads) = key_reloads {
+        config = config.with_operator_key_rotation(key_reloads);
+    }
+
     if let Some(url) = args.content_base_url {
         config = config.with_content_base_url(url);
     }
 
+    if let Some(path) = args.log_file {
+        config = config.with_log_file(path);
+    }
+
+    if let Some(path) = args.map_file {
+        config = config.with_map_file(path);
+    }
+
+    if let Some(checkpoints) = args.map_retention {
+        config = config.with_map_retention(checkpoints);
+    }
+
+    if let Some(dir) = args.tiles_dir {
+        config = config.with_tiles_dir(dir);
+    }
+
+    if let Some(origin) = args.tiles_origin {
+        config = config.with_tiles_origin(origin);
+    }
+
     if let Some(path) = args.authorized_keys_file {
         let authorized_keys_data = std::fs::read_to_string(&path)
             .with_context(|| format!("failed to read authorized keys from {path:?}"))?;
@@ -143,6 +332,61 @@ async fn main() -> Result<()> {
     Server::new(config).run().await
 }
 
+/// Decodes an operator key, decrypting it with the given passphrase if it is
+/// encrypted.
+fn decode_operator_key(key: SecretString, passphrase: Option<&SecretString>) -> Result<PrivateKey> {
+    let key_str = key.expose_secret().trim();
+    if !EncryptedPrivateKey::is_encrypted(key_str) {
+        return PrivateKey::decode(key).context("failed to parse operator key");
+    }
+
+    let Some(passphrase) = passphrase else {
+        bail!("operator key is encrypted; option `operator-key-passphrase-file` or `operator-key-passphrase` needs to be specified");
+    };
+
+    key_str
+        .parse::<EncryptedPrivateKey>()
+        .context("failed to parse encrypted operator key")?
+        .decrypt(passphrase)
+        .context("failed to decrypt operator key")
+}
+
+/// Returns a stream of operator keys reloaded from the given file on `SIGHUP`.
+///
+/// Only keys that differ from the current operator key are yielded. Encrypted
+/// keys are decrypted with the given passphrase.
+#[cfg(unix)]
+fn operator_key_reloads(
+    path: PathBuf,
+    passphrase: Option<SecretString>,
+    current: KeyID,
+) -> Result<impl Stream<Item = PrivateKey>> {
+    let hangup = signal::unix::signal(signal::unix::SignalKind::hangup())
+        .context("failed to install signal handler")?;
+
+    Ok(stream::unfold(
+        (hangup, path, passphrase, current),
+        |(mut hangup, path, passphrase, mut current)| async move {
+            loop {
+                hangup.recv().await?;
+                tracing::info!("reloading operator key (SIGHUP)");
+
+                let key = get_opt_secret("operator-key", Some(path.clone()), None)
+                    .and_then(|key| decode_operator_key(key, passphrase.as_ref()));
+
+                match key {
+                    Ok(key) if key.public_key().fingerprint() != current => {
+                        current = key.public_key().fingerprint();
+                        return Some((key, (hangup, path, passphrase, current)));
+                    }
+                    Ok(_) => tracing::info!("operator key is unchanged"),
+                    Err(e) => tracing::error!("{e:#}"),
+                }
+            }
+        },
+    ))
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         signal::ctrl_c()
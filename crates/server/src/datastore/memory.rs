@@ -7,12 +7,13 @@ use std::{
     sync::Arc,
 };
 use tokio::sync::RwLock;
-use warg_crypto::{hash::AnyHash, Encode, Signable};
+use warg_crypto::{hash::AnyHash, signing::KeyID, Encode, Signable};
 use warg_protocol::{
     operator,
     package::{self, PackageEntry},
     registry::{
         LogId, LogLeaf, PackageName, RecordId, RegistryIndex, RegistryLen, TimestampedCheckpoint,
+        WitnessCosignature,
     },
     ProtoEnvelope, PublishedProtoEnvelope, SerdeEnvelope,
 };
@@ -80,6 +81,7 @@ struct State {
     package_names: HashMap<LogId, Option<PackageName>>,
     package_names_lowercase: HashMap<String, PackageName>,
     checkpoints: IndexMap<RegistryLen, SerdeEnvelope<TimestampedCheckpoint>>,
+    witness_cosignatures: HashMap<RegistryLen, IndexMap<KeyID, WitnessCosignature>>,
     records: HashMap<LogId, HashMap<RecordId, RecordStatus>>,
     log_leafs: HashMap<RegistryIndex, LogLeaf>,
 }
@@ -255,7 +257,7 @@ impl DataStore for MemoryDataStore {
                 match log
                     .state
                     .clone()
-                    .validate(&record)
+                    .validate_at(&record, registry_index)
                     .map_err(DataStoreError::from)
                 {
                     Ok(s) => {
@@ -514,6 +516,65 @@ impl DataStore for MemoryDataStore {
         Ok(checkpoint.clone())
     }
 
+    async fn store_witness_cosignature(
+        &self,
+        log_length: RegistryLen,
+        cosignature: WitnessCosignature,
+    ) -> Result<(), DataStoreError> {
+        let mut state = self.0.write().await;
+
+        if !state.checkpoints.contains_key(&log_length) {
+            return Err(DataStoreError::CheckpointNotFound(log_length));
+        }
+
+        state
+            .witness_cosignatures
+            .entry(log_length)
+            .or_default()
+            .insert(cosignature.key_id.clone(), cosignature);
+
+        Ok(())
+    }
+
+    async fn get_witness_cosignatures(
+        &self,
+        log_length: RegistryLen,
+    ) -> Result<Vec<WitnessCosignature>, DataStoreError> {
+        let state = self.0.read().await;
+
+        if !state.checkpoints.contains_key(&log_length) {
+            return Err(DataStoreError::CheckpointNotFound(log_length));
+        }
+
+        Ok(state
+            .witness_cosignatures
+            .get(&log_length)
+            .map(|c| c.values().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn get_latest_cosigned_checkpoint(
+        &self,
+        witnesses: &[KeyID],
+        quorum: usize,
+    ) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>, DataStoreError> {
+        let state = self.0.read().await;
+
+        Ok(state
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|(log_length, _)| {
+                let count = state
+                    .witness_cosignatures
+                    .get(log_length)
+                    .map(|c| witnesses.iter().filter(|k| c.contains_key(*k)).count())
+                    .unwrap_or_default();
+                count >= quorum
+            })
+            .map(|(_, checkpoint)| checkpoint.clone()))
+    }
+
     async fn get_operator_records(
         &self,
         log_id: &LogId,
@@ -700,6 +761,24 @@ impl DataStore for MemoryDataStore {
         })
     }
 
+    async fn verify_operator_record_signature(
+        &self,
+        log_id: &LogId,
+        record: &ProtoEnvelope<operator::OperatorRecord>,
+    ) -> Result<(), DataStoreError> {
+        let state = self.0.read().await;
+        let key = state
+            .operators
+            .get(log_id)
+            .ok_or_else(|| DataStoreError::LogNotFound(log_id.clone()))?
+            .state
+            .public_key(record.key_id())
+            .ok_or_else(|| DataStoreError::UnknownKey(record.key_id().clone()))?;
+
+        operator::OperatorRecord::verify(key, record.content_bytes(), record.signature())
+            .map_err(|_| DataStoreError::SignatureVerificationFailed(record.signature().clone()))
+    }
+
     async fn verify_package_record_signature(
         &self,
         log_id: &LogId,
@@ -720,7 +799,22 @@ impl DataStore for MemoryDataStore {
         .ok_or_else(|| DataStoreError::UnknownKey(record.key_id().clone()))?;
 
         package::PackageRecord::verify(key, record.content_bytes(), record.signature())
-            .map_err(|_| DataStoreError::SignatureVerificationFailed(record.signature().clone()))
+            .map_err(|_| DataStoreError::SignatureVerificationFailed(record.signature().clone()))?;
+
+        // Verify any cosignatures and the release threshold of the package
+        let default = package::LogState::default();
+        state
+            .packages
+            .get(log_id)
+            .map(|log| &log.state)
+            .unwrap_or(&default)
+            .verify_cosignatures(record)
+            .map_err(|e| match e {
+                package::ValidationError::KeyIDNotRecognized { key_id } => {
+                    DataStoreError::UnknownKey(key_id)
+                }
+                e => DataStoreError::PackageValidationFailed(e),
+            })
     }
 
     async fn verify_can_publish_package(
@@ -729,15 +823,22 @@ impl DataStore for MemoryDataStore {
         package_name: &PackageName,
     ) -> Result<(), DataStoreError> {
         let state = self.0.read().await;
-
-        // verify namespace is defined and not imported
-        match state
+        let operator = &state
             .operators
             .get(operator_log_id)
             .ok_or_else(|| DataStoreError::LogNotFound(operator_log_id.clone()))?
-            .state
-            .namespace_state(package_name.namespace())
-        {
+            .state;
+
+        // verify the package has not been blocked
+        if let Some(block) = operator.package_block(package_name) {
+            return Err(DataStoreError::PackageBlocked {
+                name: package_name.clone(),
+                reason: block.reason.clone(),
+            });
+        }
+
+        // verify namespace is defined and not imported
+        match operator.namespace_state(package_name.namespace()) {
             Ok(Some(state)) => match state {
                 operator::NamespaceState::Defined => {}
                 operator::NamespaceState::Imported { .. } => {
@@ -774,6 +875,49 @@ impl DataStore for MemoryDataStore {
         }
     }
 
+    async fn verify_content_not_blocked(
+        &self,
+        operator_log_id: &LogId,
+        digest: &AnyHash,
+    ) -> Result<(), DataStoreError> {
+        let state = self.0.read().await;
+        let operator = &state
+            .operators
+            .get(operator_log_id)
+            .ok_or_else(|| DataStoreError::LogNotFound(operator_log_id.clone()))?
+            .state;
+
+        if let Some(block) = operator.content_block(digest) {
+            return Err(DataStoreError::ContentBlocked {
+                digest: digest.clone(),
+                reason: block.reason.clone(),
+            });
+        }
+
+        // verify the content is not released by a blocked package
+        for (log_id, name) in &state.package_names {
+            let Some(block) = name.as_ref().and_then(|name| operator.package_block(name)) else {
+                continue;
+            };
+
+            if let Some(log) = state.packages.get(log_id) {
+                if log
+                    .state
+                    .releases()
+                    .flat_map(package::Release::contents)
+                    .any(|content| content == digest)
+                {
+                    return Err(DataStoreError::ContentBlocked {
+                        digest: digest.clone(),
+                        reason: block.reason.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     async fn verify_timestamped_checkpoint_signature(
         &self,
         operator_log_id: &LogId,
@@ -798,7 +942,12 @@ impl DataStore for MemoryDataStore {
             ts_checkpoint.signature().clone(),
         )))?;
 
-        if !state.key_has_permission_to_sign_checkpoints(ts_checkpoint.key_id()) {
+        // Checkpoints signed by a retired key remain valid if they do not
+        // include the record that revoked the key's permission
+        if !state.key_had_permission_to_sign_checkpoint(
+            ts_checkpoint.key_id(),
+            ts_checkpoint.as_ref().checkpoint.log_length,
+        ) {
             return Err(DataStoreError::KeyUnauthorized(
                 ts_checkpoint.key_id().clone(),
             ));
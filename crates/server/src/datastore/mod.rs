@@ -12,6 +12,7 @@ use warg_protocol::{
     operator, package,
     registry::{
         LogId, LogLeaf, PackageName, RecordId, RegistryIndex, RegistryLen, TimestampedCheckpoint,
+        WitnessCosignature,
     },
     ProtoEnvelope, PublishedProtoEnvelope, SerdeEnvelope,
 };
@@ -73,6 +74,14 @@ pub enum DataStoreError {
     )]
     PackageNamespaceImported(String),
 
+    #[error("the package `{name}` has been blocked by the registry operator: {reason}")]
+    PackageBlocked { name: PackageName, reason: String },
+
+    #[error(
+        "the content with digest `{digest}` has been blocked by the registry operator: {reason}"
+    )]
+    ContentBlocked { digest: AnyHash, reason: String },
+
     #[error("key id `{0}` does not have permission")]
     KeyUnauthorized(KeyID),
 
@@ -258,6 +267,32 @@ pub trait DataStore: Send + Sync {
         log_length: RegistryLen,
     ) -> Result<SerdeEnvelope<TimestampedCheckpoint>, DataStoreError>;
 
+    /// Stores a witness cosignature of the checkpoint with the given log length.
+    ///
+    /// Any existing cosignature of the checkpoint by the same witness key is
+    /// replaced.
+    async fn store_witness_cosignature(
+        &self,
+        log_length: RegistryLen,
+        cosignature: WitnessCosignature,
+    ) -> Result<(), DataStoreError>;
+
+    /// Gets the witness cosignatures of the checkpoint with the given log length.
+    async fn get_witness_cosignatures(
+        &self,
+        log_length: RegistryLen,
+    ) -> Result<Vec<WitnessCosignature>, DataStoreError>;
+
+    /// Gets the latest checkpoint cosigned by at least `quorum` of the given
+    /// witness keys.
+    ///
+    /// Returns `None` if no checkpoint has enough cosignatures.
+    async fn get_latest_cosigned_checkpoint(
+        &self,
+        witnesses: &[KeyID],
+        quorum: usize,
+    ) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>, DataStoreError>;
+
     /// Gets package names from log IDs. If package name is unavailable, a corresponding `None` is returned.
     async fn get_package_names(
         &self,
@@ -303,10 +338,24 @@ pub trait DataStore: Send + Sync {
         record_id: &RecordId,
     ) -> Result<Record<package::PackageRecord>, DataStoreError>;
 
+    /// Verifies the signature of an operator record.
+    ///
+    /// This is different from `commit_operator_record` in that
+    /// only the signature on the envelope is verified.
+    ///
+    /// It does not attempt to validate the record itself.
+    async fn verify_operator_record_signature(
+        &self,
+        log_id: &LogId,
+        record: &ProtoEnvelope<operator::OperatorRecord>,
+    ) -> Result<(), DataStoreError>;
+
     /// Verifies the signature of a package record.
     ///
     /// This is different from `validate_package_record` in that
-    /// only the signature on the envelope is verified.
+    /// only the signatures on the envelope are verified, including
+    /// that enough keys signed the record to meet the package's
+    /// release threshold.
     ///
     /// It does not attempt to validate the record itself.
     async fn verify_package_record_signature(
@@ -315,16 +364,29 @@ pub trait DataStore: Send + Sync {
         record: &ProtoEnvelope<package::PackageRecord>,
     ) -> Result<(), DataStoreError>;
 
-    /// Verifies the package name is unique in a case insensitive way and that the
+    /// Verifies the package name is unique in a case insensitive way, that the
     /// package namespace is defined for this registry and is not imported
-    /// from another registry.
+    /// from another registry, and that the package has not been blocked by
+    /// the operator.
     async fn verify_can_publish_package(
         &self,
         operator_log_id: &LogId,
         package_name: &PackageName,
     ) -> Result<(), DataStoreError>;
 
+    /// Verifies the given content digest has not been blocked by the operator,
+    /// either directly or by blocking a package with a release referencing
+    /// the content.
+    async fn verify_content_not_blocked(
+        &self,
+        operator_log_id: &LogId,
+        digest: &AnyHash,
+    ) -> Result<(), DataStoreError>;
+
     /// Verifies the TimestampedCheckpoint signature.
+    ///
+    /// The signing key must have permission to sign checkpoints or have
+    /// been retired by a record not included in the checkpoint.
     async fn verify_timestamped_checkpoint_signature(
         &self,
         operator_log_id: &LogId,
@@ -0,0 +1 @@
+DROP TABLE witness_cosignatures;
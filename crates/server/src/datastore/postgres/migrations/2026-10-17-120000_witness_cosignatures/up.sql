@@ -0,0 +1,11 @@
+CREATE TABLE witness_cosignatures (
+  id SERIAL PRIMARY KEY,
+  checkpoint_id INTEGER NOT NULL REFERENCES checkpoints(id) ON DELETE CASCADE,
+  key_id TEXT NOT NULL,
+  signature TEXT NOT NULL,
+  created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+  UNIQUE (checkpoint_id, key_id)
+);
+
+SELECT diesel_manage_updated_at('witness_cosignatures');
@@ -1,6 +1,6 @@
 use self::models::{
-    CheckpointData, NewCheckpoint, NewContent, NewLog, NewRecord, ParsedText, RecordContent,
-    RecordStatus, TextRef,
+    CheckpointData, NewCheckpoint, NewContent, NewLog, NewRecord, NewWitnessCosignature,
+    ParsedText, RecordContent, RecordStatus, Text as TextValue, TextRef,
 };
 use super::{DataStore, DataStoreError, Record};
 use anyhow::{anyhow, Result};
@@ -21,13 +21,17 @@ use std::{
     collections::{HashMap, HashSet},
     pin::Pin,
 };
-use warg_crypto::{hash::AnyHash, Decode, Encode, Signable};
+use warg_crypto::{
+    hash::AnyHash,
+    signing::{KeyID, Signature},
+    Decode, Encode, Signable,
+};
 use warg_protocol::{
     operator,
     package::{self, PackageEntry},
     registry::{
         Checkpoint, LogId, LogLeaf, PackageName, RecordId, RegistryIndex, RegistryLen,
-        TimestampedCheckpoint,
+        TimestampedCheckpoint, WitnessCosignature,
     },
     ProtoEnvelope, PublishedProtoEnvelope, Record as _, SerdeEnvelope, Validator,
 };
@@ -37,6 +41,19 @@ mod schema;
 
 sql_function!(fn lower(x: Nullable<Text>) -> Nullable<Text>);
 
+async fn get_checkpoint_id(
+    conn: &mut AsyncPgConnection,
+    log_length: RegistryLen,
+) -> Result<i32, DataStoreError> {
+    schema::checkpoints::table
+        .select(schema::checkpoints::id)
+        .filter(schema::checkpoints::log_length.eq(log_length as i64))
+        .first::<i32>(conn)
+        .await
+        .optional()?
+        .ok_or_else(|| DataStoreError::CheckpointNotFound(log_length))
+}
+
 async fn get_records<R: Decode>(
     conn: &mut AsyncPgConnection,
     log_id: i32,
@@ -220,7 +237,7 @@ where
     <V as Validator>::Error: ToString + Send + Sync,
     DataStoreError: From<<V as Validator>::Error>,
 {
-    let registry_index: i64 = registry_index.try_into().unwrap();
+    let registry_log_index: i64 = registry_index.try_into().unwrap();
     conn.transaction::<_, DataStoreError, _>(|conn| {
         async move {
             // Get the record content and validator
@@ -238,7 +255,7 @@ where
                         .and(schema::records::status.eq(RecordStatus::Pending)),
                 )
                 .for_update()
-                .first::<(i32, Vec<u8>, Json<V>)>(conn)
+                .first::<(i32, Vec<u8>, Json<serde_json::Value>)>(conn)
                 .await
                 .optional()?
                 .ok_or_else(|| DataStoreError::RecordNotPending(record_id.clone()))?;
@@ -250,8 +267,12 @@ where
                 }
             })?;
 
+            let validator = load_validator::<V>(conn, log_id, validator.0).await?;
+
             // Validate the record
-            let validator = validator.0.validate(&record).map_err(Into::into)?;
+            let validator = validator
+                .validate_at(&record, registry_index)
+                .map_err(Into::into)?;
 
             // Store the updated validation state
             diesel::update(schema::logs::table)
@@ -265,7 +286,7 @@ where
                 .filter(schema::records::id.eq(id))
                 .set((
                     schema::records::status.eq(RecordStatus::Validated),
-                    schema::records::registry_log_index.eq(Some(registry_index)),
+                    schema::records::registry_log_index.eq(Some(registry_log_index)),
                 ))
                 .execute(conn)
                 .await?;
@@ -277,6 +298,63 @@ where
     .await
 }
 
+/// Loads the stored validation state of a log.
+///
+/// Validation state stored by an earlier version of the registry may no
+/// longer deserialize; it is then rebuilt from the log's validated records.
+async fn load_validator<V>(
+    conn: &mut AsyncPgConnection,
+    log_id: i32,
+    validator: serde_json::Value,
+) -> Result<V, DataStoreError>
+where
+    V: Validator + 'static,
+    <V as Validator>::Error: ToString + Send + Sync,
+    DataStoreError: From<<V as Validator>::Error>,
+{
+    match serde_json::from_value(validator) {
+        Ok(validator) => Ok(validator),
+        Err(_) => revalidate_log(conn, log_id).await,
+    }
+}
+
+async fn revalidate_log<V>(conn: &mut AsyncPgConnection, log_id: i32) -> Result<V, DataStoreError>
+where
+    V: Validator + 'static,
+    <V as Validator>::Error: ToString + Send + Sync,
+    DataStoreError: From<<V as Validator>::Error>,
+{
+    let records = schema::records::table
+        .select((
+            schema::records::record_id,
+            schema::records::content,
+            schema::records::registry_log_index,
+        ))
+        .filter(
+            schema::records::log_id
+                .eq(log_id)
+                .and(schema::records::status.eq(RecordStatus::Validated)),
+        )
+        .order(schema::records::registry_log_index.asc())
+        .load::<(ParsedText<AnyHash>, Vec<u8>, Option<i64>)>(conn)
+        .await?;
+
+    records
+        .into_iter()
+        .try_fold(V::default(), |validator, (record_id, content, index)| {
+            let record = ProtoEnvelope::<V::Record>::from_protobuf(&content).map_err(|e| {
+                DataStoreError::InvalidRecordContents {
+                    record_id: record_id.0.into(),
+                    message: e.to_string(),
+                }
+            })?;
+
+            validator
+                .validate_at(&record, index.unwrap().try_into().unwrap())
+                .map_err(Into::into)
+        })
+}
+
 async fn get_record<V>(
     conn: &mut AsyncPgConnection,
     log_id: &LogId,
@@ -875,6 +953,103 @@ impl DataStore for PostgresDataStore {
         ))
     }
 
+    async fn store_witness_cosignature(
+        &self,
+        log_length: RegistryLen,
+        cosignature: WitnessCosignature,
+    ) -> Result<(), DataStoreError> {
+        let mut conn = self.pool.get().await?;
+        let checkpoint_id = get_checkpoint_id(&mut conn, log_length).await?;
+
+        // Replace any existing cosignature by the same witness key
+        diesel::insert_into(schema::witness_cosignatures::table)
+            .values(NewWitnessCosignature {
+                checkpoint_id,
+                key_id: TextRef(&cosignature.key_id),
+                signature: TextRef(&cosignature.signature),
+            })
+            .on_conflict((
+                schema::witness_cosignatures::checkpoint_id,
+                schema::witness_cosignatures::key_id,
+            ))
+            .do_update()
+            .set(schema::witness_cosignatures::signature.eq(TextRef(&cosignature.signature)))
+            .execute(&mut conn)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_witness_cosignatures(
+        &self,
+        log_length: RegistryLen,
+    ) -> Result<Vec<WitnessCosignature>, DataStoreError> {
+        let mut conn = self.pool.get().await?;
+        let checkpoint_id = get_checkpoint_id(&mut conn, log_length).await?;
+
+        Ok(schema::witness_cosignatures::table
+            .select((
+                schema::witness_cosignatures::key_id,
+                schema::witness_cosignatures::signature,
+            ))
+            .filter(schema::witness_cosignatures::checkpoint_id.eq(checkpoint_id))
+            .order_by(schema::witness_cosignatures::id)
+            .load::<(TextValue<KeyID>, ParsedText<Signature>)>(&mut conn)
+            .await?
+            .into_iter()
+            .map(|(key_id, signature)| WitnessCosignature {
+                key_id: key_id.0,
+                signature: signature.0,
+            })
+            .collect())
+    }
+
+    async fn get_latest_cosigned_checkpoint(
+        &self,
+        witnesses: &[KeyID],
+        quorum: usize,
+    ) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>, DataStoreError> {
+        if quorum == 0 {
+            return self.get_latest_checkpoint().await.map(Some);
+        }
+
+        let mut conn = self.pool.get().await?;
+        let witnesses = witnesses
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        let checkpoint = schema::checkpoints::table
+            .filter(
+                schema::checkpoints::id.eq_any(
+                    schema::witness_cosignatures::table
+                        .select(schema::witness_cosignatures::checkpoint_id)
+                        .filter(schema::witness_cosignatures::key_id.eq_any(witnesses))
+                        .group_by(schema::witness_cosignatures::checkpoint_id)
+                        .having(diesel::dsl::count_star().ge(quorum as i64)),
+                ),
+            )
+            .order_by(schema::checkpoints::id.desc())
+            .first::<CheckpointData>(&mut conn)
+            .await
+            .optional()?;
+
+        Ok(checkpoint.map(|checkpoint| {
+            SerdeEnvelope::from_parts_unchecked(
+                TimestampedCheckpoint {
+                    checkpoint: Checkpoint {
+                        log_root: checkpoint.log_root.0,
+                        log_length: checkpoint.log_length.try_into().unwrap(),
+                        map_root: checkpoint.map_root.0,
+                    },
+                    timestamp: checkpoint.timestamp.try_into().unwrap(),
+                },
+                checkpoint.key_id.0,
+                checkpoint.signature.0,
+            )
+        }))
+    }
+
     async fn get_operator_records(
         &self,
         log_id: &LogId,
@@ -931,19 +1106,48 @@ impl DataStore for PostgresDataStore {
         get_record::<package::LogState>(conn.as_mut(), log_id, record_id).await
     }
 
-    async fn verify_package_record_signature(
+    async fn verify_operator_record_signature(
         &self,
         log_id: &LogId,
-        record: &ProtoEnvelope<package::PackageRecord>,
+        record: &ProtoEnvelope<operator::OperatorRecord>,
     ) -> Result<(), DataStoreError> {
         let mut conn = self.pool.get().await?;
 
         let validator = schema::logs::table
             .select(schema::logs::validator)
             .filter(schema::logs::log_id.eq(TextRef(log_id)))
-            .first::<Json<package::LogState>>(&mut conn)
+            .first::<Json<operator::LogState>>(&mut conn)
             .await
-            .optional()?;
+            .optional()?
+            .ok_or_else(|| DataStoreError::LogNotFound(log_id.clone()))?;
+
+        let key = validator
+            .public_key(record.key_id())
+            .ok_or_else(|| DataStoreError::UnknownKey(record.key_id().clone()))?;
+
+        operator::OperatorRecord::verify(key, record.content_bytes(), record.signature())
+            .map_err(|_| DataStoreError::SignatureVerificationFailed(record.signature().clone()))
+    }
+
+    async fn verify_package_record_signature(
+        &self,
+        log_id: &LogId,
+        record: &ProtoEnvelope<package::PackageRecord>,
+    ) -> Result<(), DataStoreError> {
+        let mut conn = self.pool.get().await?;
+
+        let validator = match schema::logs::table
+            .select((schema::logs::id, schema::logs::validator))
+            .filter(schema::logs::log_id.eq(TextRef(log_id)))
+            .first::<(i32, Json<serde_json::Value>)>(&mut conn)
+            .await
+            .optional()?
+        {
+            Some((id, validator)) => {
+                Some(load_validator::<package::LogState>(&mut conn, id, validator.0).await?)
+            }
+            None => None,
+        };
 
         #[allow(clippy::get_first)] // Vec::first() conflicts with diesel's RunQueryDsl
         let key = match validator
@@ -958,7 +1162,18 @@ impl DataStore for PostgresDataStore {
         };
 
         package::PackageRecord::verify(key, record.content_bytes(), record.signature())
-            .map_err(|_| DataStoreError::SignatureVerificationFailed(record.signature().clone()))
+            .map_err(|_| DataStoreError::SignatureVerificationFailed(record.signature().clone()))?;
+
+        // Verify any cosignatures and the release threshold of the package
+        validator
+            .unwrap_or_default()
+            .verify_cosignatures(record)
+            .map_err(|e| match e {
+                package::ValidationError::KeyIDNotRecognized { key_id } => {
+                    DataStoreError::UnknownKey(key_id)
+                }
+                e => DataStoreError::PackageValidationFailed(e),
+            })
     }
 
     async fn verify_can_publish_package(
@@ -976,6 +1191,14 @@ impl DataStore for PostgresDataStore {
             .optional()?
             .ok_or_else(|| DataStoreError::LogNotFound(operator_log_id.clone()))?;
 
+        // verify the package has not been blocked
+        if let Some(block) = validator.package_block(package_name) {
+            return Err(DataStoreError::PackageBlocked {
+                name: package_name.clone(),
+                reason: block.reason.clone(),
+            });
+        }
+
         // verify namespace is defined and not imported
         match validator.namespace_state(package_name.namespace()) {
             Ok(Some(state)) => match state {
@@ -1019,6 +1242,66 @@ impl DataStore for PostgresDataStore {
         }
     }
 
+    async fn verify_content_not_blocked(
+        &self,
+        operator_log_id: &LogId,
+        digest: &AnyHash,
+    ) -> Result<(), DataStoreError> {
+        let mut conn = self.pool.get().await?;
+
+        let validator = schema::logs::table
+            .select(schema::logs::validator)
+            .filter(schema::logs::log_id.eq(TextRef(operator_log_id)))
+            .first::<Json<operator::LogState>>(&mut conn)
+            .await
+            .optional()?
+            .ok_or_else(|| DataStoreError::LogNotFound(operator_log_id.clone()))?;
+
+        if let Some(block) = validator.content_block(digest) {
+            return Err(DataStoreError::ContentBlocked {
+                digest: digest.clone(),
+                reason: block.reason.clone(),
+            });
+        }
+
+        // verify the content is not released by a blocked package
+        let names = validator
+            .blocked_packages()
+            .map(|(name, _)| name.to_string())
+            .collect::<Vec<_>>();
+        if names.is_empty() {
+            return Ok(());
+        }
+
+        let packages = schema::logs::table
+            .select((schema::logs::name, schema::logs::validator))
+            .filter(schema::logs::name.eq_any(names))
+            .load::<(Option<String>, Json<package::LogState>)>(&mut conn)
+            .await?;
+
+        for (name, state) in packages {
+            let Some(block) = name
+                .and_then(|name| name.parse().ok())
+                .and_then(|name| validator.package_block(&name))
+            else {
+                continue;
+            };
+
+            if state
+                .releases()
+                .flat_map(package::Release::contents)
+                .any(|content| content == digest)
+            {
+                return Err(DataStoreError::ContentBlocked {
+                    digest: digest.clone(),
+                    reason: block.reason.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     async fn verify_timestamped_checkpoint_signature(
         &self,
         operator_log_id: &LogId,
@@ -1045,7 +1328,12 @@ impl DataStore for PostgresDataStore {
             ts_checkpoint.signature().clone(),
         )))?;
 
-        if !validator.key_has_permission_to_sign_checkpoints(ts_checkpoint.key_id()) {
+        // Checkpoints signed by a retired key remain valid if they do not
+        // include the record that revoked the key's permission
+        if !validator.key_had_permission_to_sign_checkpoint(
+            ts_checkpoint.key_id(),
+            ts_checkpoint.as_ref().checkpoint.log_length,
+        ) {
             return Err(DataStoreError::KeyUnauthorized(
                 ts_checkpoint.key_id().clone(),
             ));
@@ -1,4 +1,4 @@
-use super::schema::{checkpoints, contents, logs, records};
+use super::schema::{checkpoints, contents, logs, records, witness_cosignatures};
 use chrono::{DateTime, Utc};
 use diesel::{
     deserialize::{self, FromSql},
@@ -104,6 +104,14 @@ pub struct CheckpointData {
     pub timestamp: i64,
 }
 
+#[derive(Insertable)]
+#[diesel(table_name = witness_cosignatures)]
+pub struct NewWitnessCosignature<'a> {
+    pub checkpoint_id: i32,
+    pub key_id: TextRef<'a, KeyID>,
+    pub signature: TextRef<'a, Signature>,
+}
+
 /// Selects only the record content and status
 #[derive(Queryable, Selectable)]
 #[diesel(table_name = records)]
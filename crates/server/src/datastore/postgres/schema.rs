@@ -60,7 +60,25 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    witness_cosignatures (id) {
+        id -> Int4,
+        checkpoint_id -> Int4,
+        key_id -> Text,
+        signature -> Text,
+        created_at -> Timestamptz,
+        updated_at -> Timestamptz,
+    }
+}
+
 diesel::joinable!(contents -> records (record_id));
 diesel::joinable!(records -> logs (log_id));
+diesel::joinable!(witness_cosignatures -> checkpoints (checkpoint_id));
 
-diesel::allow_tables_to_appear_in_same_query!(checkpoints, contents, logs, records,);
+diesel::allow_tables_to_appear_in_same_query!(
+    checkpoints,
+    contents,
+    logs,
+    records,
+    witness_cosignatures,
+);
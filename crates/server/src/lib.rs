@@ -1,14 +1,20 @@
-use crate::{api::create_router, datastore::MemoryDataStore};
-use anyhow::{Context, Result};
+use crate::{
+    api::{create_router, v1::witness::Witnesses},
+    datastore::MemoryDataStore,
+};
+use anyhow::{bail, Context, Result};
 use axum::Router;
 use datastore::DataStore;
-use futures::Future;
+use futures::{Future, Stream, StreamExt};
 use policy::{content::ContentPolicy, record::RecordPolicy};
-use services::CoreService;
+use services::{CoreService, CoreServiceError, StateStorage, TileWriter};
 use std::{fs, net::SocketAddr, path::PathBuf, pin::Pin, sync::Arc, time::Duration};
 use tokio::{net::TcpListener, task::JoinHandle};
 use url::Url;
-use warg_crypto::signing::PrivateKey;
+use warg_crypto::{
+    hash::HashAlgorithm,
+    signing::{PublicKey, Signer},
+};
 use warg_protocol::operator;
 
 pub mod api;
@@ -21,25 +27,34 @@ const DEFAULT_BIND_ADDRESS: &str = "127.0.0.1:8090";
 const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
 
 type ShutdownFut = Pin<Box<dyn Future<Output = ()> + Send + Sync>>;
+type OperatorKeyStream = Pin<Box<dyn Stream<Item = Arc<dyn Signer>> + Send>>;
 
 /// The server configuration.
 pub struct Config {
-    operator_key: PrivateKey,
+    operator_signer: Arc<dyn Signer>,
     namespaces: Option<Vec<(String, operator::NamespaceState)>>,
     addr: Option<SocketAddr>,
     data_store: Option<Box<dyn DataStore>>,
     content_dir: PathBuf,
     content_base_url: Option<Url>,
     shutdown: Option<ShutdownFut>,
+    operator_keys: Option<OperatorKeyStream>,
     checkpoint_interval: Option<Duration>,
+    hash_algorithm: Option<HashAlgorithm>,
     content_policy: Option<Arc<dyn ContentPolicy>>,
     record_policy: Option<Arc<dyn RecordPolicy>>,
+    witnesses: Witnesses,
+    log_file: Option<PathBuf>,
+    map_file: Option<PathBuf>,
+    map_retention: Option<usize>,
+    tiles_dir: Option<PathBuf>,
+    tiles_origin: Option<String>,
 }
 
 impl std::fmt::Debug for Config {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Config")
-            .field("operator_key", &"<redacted>")
+            .field("operator_signer", &"dyn Signer")
             .field("namespaces", &self.namespaces)
             .field("addr", &self.addr)
             .field(
@@ -48,7 +63,12 @@ impl std::fmt::Debug for Config {
             )
             .field("content_dir", &self.content_dir)
             .field("shutdown", &self.shutdown.as_ref().map(|_| "dyn Future"))
+            .field(
+                "operator_keys",
+                &self.operator_keys.as_ref().map(|_| "dyn Stream"),
+            )
             .field("checkpoint_interval", &self.checkpoint_interval)
+            .field("hash_algorithm", &self.hash_algorithm)
             .field(
                 "content_policy",
                 &self.content_policy.as_ref().map(|_| "dyn ContentPolicy"),
@@ -57,28 +77,46 @@ impl std::fmt::Debug for Config {
                 "record_policy",
                 &self.record_policy.as_ref().map(|_| "dyn RecordPolicy"),
             )
+            .field("witnesses", &self.witnesses)
+            .field("log_file", &self.log_file)
+            .field("map_file", &self.map_file)
+            .field("map_retention", &self.map_retention)
+            .field("tiles_dir", &self.tiles_dir)
+            .field("tiles_origin", &self.tiles_origin)
             .finish()
     }
 }
 
 impl Config {
     /// Creates a new server configuration.
+    ///
+    /// The operator signer signs operator records and checkpoints; it may be
+    /// a private key or a signer that keeps the key elsewhere, such as an
+    /// [`ExternalSigner`](warg_crypto::signing::ExternalSigner).
     pub fn new(
-        operator_key: PrivateKey,
+        operator_signer: impl Signer + 'static,
         namespaces: Option<Vec<(String, operator::NamespaceState)>>,
         content_dir: PathBuf,
     ) -> Self {
         Self {
-            operator_key,
+            operator_signer: Arc::new(operator_signer),
             namespaces,
             addr: None,
             data_store: None,
             content_dir,
             content_base_url: None,
             shutdown: None,
+            operator_keys: None,
             checkpoint_interval: None,
+            hash_algorithm: None,
             content_policy: None,
             record_policy: None,
+            witnesses: Witnesses::default(),
+            log_file: None,
+            map_file: None,
+            map_retention: None,
+            tiles_dir: None,
+            tiles_origin: None,
         }
     }
 
@@ -123,12 +161,39 @@ impl Config {
         self
     }
 
+    /// Specifies a stream of new operator keys to rotate to.
+    ///
+    /// Each signer yielded by the stream replaces the current operator signer
+    /// without restarting the server; the rotation is recorded in the
+    /// operator log so that checkpoints signed by retired keys remain
+    /// verifiable.
+    pub fn with_operator_key_rotation<S: Signer + 'static>(
+        mut self,
+        operator_keys: impl Stream<Item = S> + Send + 'static,
+    ) -> Self {
+        self.operator_keys = Some(Box::pin(
+            operator_keys.map(|signer| Arc::new(signer) as Arc<dyn Signer>),
+        ));
+        self
+    }
+
     /// Sets the checkpoint interval to use for the server.
     pub fn with_checkpoint_interval(mut self, interval: Duration) -> Self {
         self.checkpoint_interval = Some(interval);
         self
     }
 
+    /// Sets the hash algorithm to use when initializing a new registry.
+    ///
+    /// An existing registry always uses the hash algorithm set by the init
+    /// entry of its operator log.
+    ///
+    /// Defaults to SHA-256.
+    pub fn with_hash_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = Some(algorithm);
+        self
+    }
+
     /// Sets the content policy to use for the server.
     pub fn with_content_policy(mut self, policy: impl ContentPolicy + 'static) -> Self {
         self.content_policy = Some(Arc::new(policy));
@@ -140,6 +205,72 @@ impl Config {
         self.record_policy = Some(Arc::new(policy));
         self
     }
+
+    /// Sets the witnesses trusted to cosign checkpoints.
+    ///
+    /// Cosignatures are only accepted from the given witness keys. If
+    /// `quorum` is non-zero, clients are served the latest checkpoint
+    /// cosigned by at least `quorum` of the witnesses.
+    pub fn with_witnesses(
+        mut self,
+        keys: impl IntoIterator<Item = PublicKey>,
+        quorum: usize,
+    ) -> Self {
+        self.witnesses = Witnesses::new(keys, quorum);
+        self
+    }
+
+    /// Sets the file to store the registry log in.
+    ///
+    /// The log file is reopened on restart. If a map file is also set, the
+    /// service resumes from the latest checkpoint covered by both files and
+    /// replays only the later records from the data store; otherwise every
+    /// record is replayed to rebuild the verifiable map.
+    ///
+    /// If not set, the registry log is kept in memory.
+    pub fn with_log_file(mut self, path: PathBuf) -> Self {
+        self.log_file = Some(path);
+        self
+    }
+
+    /// Sets the file to store the checkpointed versions of the verifiable map
+    /// in.
+    ///
+    /// If not set, the map versions are kept in memory.
+    pub fn with_map_file(mut self, path: PathBuf) -> Self {
+        self.map_file = Some(path);
+        self
+    }
+
+    /// Sets the number of latest checkpoints to serve map inclusion proofs
+    /// for.
+    ///
+    /// Requests for map inclusion proofs of older checkpoints fail with a
+    /// checkpoint not found error. If not set, the map versions of all
+    /// checkpoints are retained.
+    pub fn with_map_retention(mut self, checkpoints: usize) -> Self {
+        self.map_retention = Some(checkpoints);
+        self
+    }
+
+    /// Sets the directory to write the log tiles to.
+    ///
+    /// The log is written as hash tiles and a signed checkpoint note in the
+    /// C2SP tlog-tiles layout and served under `/tiles`.
+    ///
+    /// Defaults to the `tiles` subdirectory of the content directory.
+    pub fn with_tiles_dir(mut self, dir: PathBuf) -> Self {
+        self.tiles_dir = Some(dir);
+        self
+    }
+
+    /// Sets the origin line of the log's checkpoint notes.
+    ///
+    /// If not set, the origin is derived from the content base URL.
+    pub fn with_tiles_origin(mut self, origin: impl Into<String>) -> Self {
+        self.tiles_origin = Some(origin.into());
+        self
+    }
 }
 
 /// Represents the warg registry server.
@@ -183,17 +314,46 @@ impl Server {
             config = self.config
         );
 
+        let content_base_url = self
+            .config
+            .content_base_url
+            .unwrap_or_else(|| Url::parse(&format!("http://{addr}")).unwrap());
+
+        let tiles_dir = self
+            .config
+            .tiles_dir
+            .unwrap_or_else(|| self.config.content_dir.join("tiles"));
+        fs::create_dir_all(&tiles_dir).with_context(|| {
+            format!(
+                "failed to create tiles directory `{path}`",
+                path = tiles_dir.display()
+            )
+        })?;
+
+        let tiles_origin = self.config.tiles_origin.unwrap_or_else(|| {
+            let url = content_base_url.as_str();
+            let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+            url.trim_end_matches('/').to_string()
+        });
+
         let store = self
             .config
             .data_store
             .unwrap_or_else(|| Box::<MemoryDataStore>::default());
         let (core, core_handle) = CoreService::start(
-            self.config.operator_key,
+            self.config.operator_signer,
             self.config.namespaces,
             store,
             self.config
                 .checkpoint_interval
                 .unwrap_or(DEFAULT_CHECKPOINT_INTERVAL),
+            self.config.hash_algorithm.unwrap_or(HashAlgorithm::Sha256),
+            StateStorage {
+                log_file: self.config.log_file,
+                map_file: self.config.map_file,
+                map_retention: self.config.map_retention,
+            },
+            Some(TileWriter::new(tiles_dir, tiles_origin)),
         )
         .await?;
 
@@ -213,10 +373,16 @@ impl Server {
             )
         })?;
 
-        let content_base_url = self
-            .config
-            .content_base_url
-            .unwrap_or_else(|| Url::parse(&format!("http://{addr}")).unwrap());
+        let rotation_handle = self.config.operator_keys.map(|mut operator_keys| {
+            let core = core.clone();
+            tokio::spawn(async move {
+                while let Some(operator_key) = operator_keys.next().await {
+                    if let Err(e) = core.rotate_operator_key(operator_key).await {
+                        tracing::error!("failed to rotate operator key: {e}");
+                    }
+                }
+            })
+        });
 
         let router = create_router(
             content_base_url,
@@ -225,12 +391,14 @@ impl Server {
             files_dir,
             self.config.content_policy,
             self.config.record_policy,
+            Arc::new(self.config.witnesses),
         );
 
         Ok(InitializedServer {
             listener,
             router,
             core_handle,
+            rotation_handle,
             shutdown: self.config.shutdown,
         })
     }
@@ -240,7 +408,8 @@ impl Server {
 pub struct InitializedServer {
     listener: TcpListener,
     router: Router,
-    core_handle: JoinHandle<()>,
+    core_handle: JoinHandle<Result<(), CoreServiceError>>,
+    rotation_handle: Option<JoinHandle<()>>,
     shutdown: Option<ShutdownFut>,
 }
 
@@ -260,16 +429,44 @@ impl InitializedServer {
 
         tracing::info!("listening on {addr}");
 
-        if let Some(shutdown) = self.shutdown {
-            tracing::debug!("server is running with a shutdown signal");
-            server.with_graceful_shutdown(shutdown).await?;
-        } else {
-            tracing::debug!("server is running without a shutdown signal");
-            server.await?;
+        let server = async move {
+            if let Some(shutdown) = self.shutdown {
+                tracing::debug!("server is running with a shutdown signal");
+                server.with_graceful_shutdown(shutdown).await
+            } else {
+                tracing::debug!("server is running without a shutdown signal");
+                server.await
+            }
+        };
+
+        // The core service only stops on its own if it failed, in which case
+        // the server stops serving too
+        let mut core_handle = self.core_handle;
+        let core_stopped = tokio::select! {
+            res = server => {
+                res?;
+                None
+            }
+            res = &mut core_handle => Some(res),
+        };
+
+        // The rotation task holds a handle to the core service, so stop it
+        // before waiting on the core service
+        if let Some(rotation_handle) = self.rotation_handle {
+            rotation_handle.abort();
+            rotation_handle.await.ok();
         }
 
-        tracing::info!("waiting for core service to stop");
-        self.core_handle.await?;
+        match core_stopped {
+            Some(res) => {
+                res?.context("core service failed")?;
+                bail!("core service stopped unexpectedly");
+            }
+            None => {
+                tracing::info!("waiting for core service to stop");
+                core_handle.await?.context("core service failed")?;
+            }
+        }
 
         tracing::info!("server shutdown complete");
         Ok(())
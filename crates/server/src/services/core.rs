@@ -1,10 +1,11 @@
 use std::{
     collections::HashMap,
+    path::{Path, PathBuf},
     sync::Arc,
     time::{Duration, SystemTime},
 };
 
-use futures::{pin_mut, StreamExt};
+use futures::StreamExt;
 use thiserror::Error;
 use tokio::{
     sync::{mpsc, RwLock},
@@ -12,50 +13,179 @@ use tokio::{
     time::MissedTickBehavior,
 };
 use warg_crypto::{
-    hash::{AnyHash, Hash, Sha256, SupportedDigest},
-    signing::PrivateKey,
+    hash::{AnyHash, Blake3, Hash, HashAlgorithm, Sha256, Sha512, SupportedDigest},
+    signing::{KeyID, SignatureError, Signer},
 };
 use warg_protocol::{
     operator,
     registry::{
-        Checkpoint, LogId, LogLeaf, MapLeaf, RecordId, RegistryIndex, RegistryLen,
+        Checkpoint, LogId, LogLeaf, MapLeaf, PackageName, RecordId, RegistryIndex, RegistryLen,
         TimestampedCheckpoint,
     },
     ProtoEnvelope, SerdeEnvelope,
 };
 use warg_transparency::{
-    log::{LogBuilder, LogData, LogProofBundle, Node, VecLog},
-    map::{Map, MapProofBundle},
+    log::{
+        self, tiles_between, FileLog, FileLogError, LogBuilder, LogData, LogProofBundle, Node,
+        VecLog, TILE_WIDTH,
+    },
+    map::{Map, MapProofBundle, MapStore, MapStoreError},
 };
 
+use super::tiles::{encode_entry_bundle, TileWriter};
 use crate::datastore::{DataStore, DataStoreError};
 
+// The number of records read from the data store at a time on startup
+const REPLAY_BATCH_SIZE: usize = 1000;
+
+/// The storage of the transparency state of a [`CoreService`].
+#[derive(Debug, Default, Clone)]
+pub struct StateStorage {
+    /// The file to store the registry log in.
+    ///
+    /// If set, the registry log is reopened on restart; otherwise the log is
+    /// kept in memory and rebuilt from the data store. If both files are set,
+    /// startup resumes from the latest checkpoint they cover.
+    pub log_file: Option<PathBuf>,
+    /// The file to store the checkpointed versions of the verifiable map in.
+    ///
+    /// If not set, the versions are kept in memory.
+    pub map_file: Option<PathBuf>,
+    /// The number of latest checkpoints to serve map inclusion proofs for.
+    ///
+    /// If not set, the map versions of all checkpoints are retained.
+    pub map_retention: Option<usize>,
+}
+
 #[derive(Clone)]
-pub struct CoreService<Digest: SupportedDigest = Sha256> {
-    inner: Arc<Inner<Digest>>,
+pub struct CoreService {
+    inner: Arc<dyn Transparency>,
+
+    // The hash algorithm of the registry, set by the operator log's init entry.
+    hash_algorithm: HashAlgorithm,
 
-    // Channel sender used by `submit_package_record` to serialize submissions.
+    // Channel sender used by `submit_operator_record` and `submit_package_record`
+    // to serialize submissions.
     submit_entry_tx: mpsc::Sender<LogLeaf>,
 }
 
-impl<Digest: SupportedDigest> CoreService<Digest> {
+impl CoreService {
     /// Starts the `CoreService`, returning a `clone`able handle to the
     /// service and a [`JoinHandle`] which should be awaited after dropping all
     /// copies of the service handle to allow for graceful shutdown.
+    ///
+    /// The hash algorithm of an existing registry is the one set by the init
+    /// entry of its operator log; the given hash algorithm is only used to
+    /// initialize a new registry.
+    ///
+    /// The registry log and the checkpointed versions of the verifiable map
+    /// are stored as described by the given state storage.
+    ///
+    /// If a tile writer is given, the log is written as tiles as checkpoints
+    /// are produced; tiles are only written for SHA-256 registries.
     pub async fn start(
-        operator_key: PrivateKey,
+        operator_signer: Arc<dyn Signer>,
         namespaces: Option<Vec<(String, operator::NamespaceState)>>,
         store: Box<dyn DataStore>,
         checkpoint_interval: Duration,
-    ) -> Result<(Self, JoinHandle<()>), CoreServiceError> {
+        hash_algorithm: HashAlgorithm,
+        storage: StateStorage,
+        tiles: Option<TileWriter>,
+    ) -> Result<(Self, JoinHandle<Result<(), CoreServiceError>>), CoreServiceError> {
+        let hash_algorithm = match stored_hash_algorithm(store.as_ref()).await? {
+            Some(stored) => {
+                if stored != hash_algorithm {
+                    tracing::warn!(
+                        "using hash algorithm `{stored}` of the existing registry instead of `{hash_algorithm}`"
+                    );
+                }
+                stored
+            }
+            None => hash_algorithm,
+        };
+
+        // The tlog-tiles layout is defined for SHA-256 logs only
+        let tiles = tiles.filter(|_| {
+            if hash_algorithm != HashAlgorithm::Sha256 {
+                tracing::warn!("not writing log tiles for a registry using `{hash_algorithm}`");
+                return false;
+            }
+            true
+        });
+
+        match hash_algorithm {
+            HashAlgorithm::Sha256 => {
+                Self::start_with::<Sha256>(
+                    operator_signer,
+                    namespaces,
+                    store,
+                    checkpoint_interval,
+                    storage,
+                    tiles,
+                )
+                .await
+            }
+            HashAlgorithm::Sha512 => {
+                Self::start_with::<Sha512>(
+                    operator_signer,
+                    namespaces,
+                    store,
+                    checkpoint_interval,
+                    storage,
+                    tiles,
+                )
+                .await
+            }
+            HashAlgorithm::Blake3 => {
+                Self::start_with::<Blake3>(
+                    operator_signer,
+                    namespaces,
+                    store,
+                    checkpoint_interval,
+                    storage,
+                    tiles,
+                )
+                .await
+            }
+            algorithm => Err(CoreServiceError::UnsupportedHashAlgorithm(algorithm)),
+        }
+    }
+
+    async fn start_with<Digest: SupportedDigest>(
+        operator_signer: Arc<dyn Signer>,
+        namespaces: Option<Vec<(String, operator::NamespaceState)>>,
+        store: Box<dyn DataStore>,
+        checkpoint_interval: Duration,
+        storage: StateStorage,
+        tiles: Option<TileWriter>,
+    ) -> Result<(Self, JoinHandle<Result<(), CoreServiceError>>), CoreServiceError> {
+        let mut state = State::default();
+        if let Some(path) = storage.log_file {
+            state.log = RegistryLog::File(FileLog::open(path)?);
+        }
+
+        let map_store = match storage.map_file {
+            Some(path) => MapStore::open(path)?,
+            None => MapStore::default(),
+        };
+        state.map_store = match storage.map_retention {
+            Some(retention) => map_store.with_retention(retention),
+            None => map_store,
+        };
+
         // Build service
-        let mut inner = Inner {
-            operator_key,
+        let mut inner = Inner::<Digest> {
+            operator_signer: RwLock::new(operator_signer),
             store,
-            state: Default::default(),
+            state: RwLock::new(state),
+            tiles,
         };
         inner.initialize(namespaces).await?;
 
+        if let Err(err) = inner.sync_tiles().await {
+            tracing::error!("Error writing log tiles: {err:?}");
+        }
+
         // Spawn state update task
         let inner = Arc::new(inner);
         let (submit_entry_tx, submit_entry_rx) = tokio::sync::mpsc::channel(4);
@@ -67,37 +197,244 @@ impl<Digest: SupportedDigest> CoreService<Digest> {
 
         let svc = Self {
             inner,
+            hash_algorithm: Digest::ALGORITHM,
             submit_entry_tx,
         };
         Ok((svc, handle))
     }
 
+    /// Gets the hash algorithm used by the registry.
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    /// Gets the identifier of the registry's operator log.
+    pub fn operator_log_id(&self) -> LogId {
+        LogId::operator_log_for(self.hash_algorithm)
+    }
+
+    /// Gets the identifier of the given package's log.
+    pub fn package_log_id(&self, name: &PackageName) -> LogId {
+        LogId::package_log_for(self.hash_algorithm, name)
+    }
+
     /// Constructs a log consistency proof between the given log tree roots.
+    ///
+    /// Returns the encoded proof bundle.
     pub async fn log_consistency_proof(
         &self,
         from_log_length: RegistryLen,
         to_log_length: RegistryLen,
-    ) -> Result<LogProofBundle<Digest, LogLeaf>, CoreServiceError> {
-        let state = self.inner.state.read().await;
-
-        let proof = state.log.prove_consistency(from_log_length, to_log_length);
-        LogProofBundle::bundle(vec![proof], vec![], &state.log)
-            .map_err(CoreServiceError::BundleFailure)
+    ) -> Result<Vec<u8>, CoreServiceError> {
+        self.inner
+            .log_consistency_proof(from_log_length, to_log_length)
+            .await
     }
 
     /// Constructs log inclusion proofs for the given entries at the given log tree root.
+    ///
+    /// Returns the encoded proof bundle.
     pub async fn log_inclusion_proofs(
         &self,
         log_length: RegistryLen,
         entries: &[RegistryIndex],
-    ) -> Result<LogProofBundle<Digest, LogLeaf>, CoreServiceError> {
-        let state = self.inner.state.read().await;
+    ) -> Result<Vec<u8>, CoreServiceError> {
+        self.inner.log_inclusion_proofs(log_length, entries).await
+    }
+
+    /// Constructs map inclusion proofs for the given entries at the given map tree root.
+    ///
+    /// Returns the encoded proof bundle.
+    pub async fn map_inclusion_proofs(
+        &self,
+        log_length: RegistryLen,
+        entries: &[RegistryIndex],
+    ) -> Result<Vec<u8>, CoreServiceError> {
+        self.inner.map_inclusion_proofs(log_length, entries).await
+    }
+
+    /// Constructs map absence proofs for the given package logs at the map
+    /// tree root of the given log length.
+    ///
+    /// Returns the encoded proof bundle.
+    pub async fn map_absence_proofs(
+        &self,
+        log_length: RegistryLen,
+        log_ids: &[LogId],
+    ) -> Result<Vec<u8>, CoreServiceError> {
+        self.inner.map_absence_proofs(log_length, log_ids).await
+    }
+
+    /// Constructs a map transition proof for the given package logs between
+    /// the map tree roots of the given log lengths.
+    ///
+    /// Returns the encoded proof bundle.
+    pub async fn map_transition_proof(
+        &self,
+        from_log_length: RegistryLen,
+        to_log_length: RegistryLen,
+        log_ids: &[LogId],
+    ) -> Result<Vec<u8>, CoreServiceError> {
+        self.inner
+            .map_transition_proof(from_log_length, to_log_length, log_ids)
+            .await
+    }
+
+    /// Rotates the operator signer used to sign checkpoints.
+    ///
+    /// An operator record signed by the current operator signer is committed
+    /// that grants the new signer's key permission to commit checkpoints and
+    /// revokes that permission from the current key; subsequent checkpoints
+    /// are signed by the new signer.
+    ///
+    /// Returns the identifier of the committed operator record.
+    pub async fn rotate_operator_key(
+        &self,
+        operator_signer: Arc<dyn Signer>,
+    ) -> Result<RecordId, CoreServiceError> {
+        self.inner.rotate_operator_key(operator_signer).await
+    }
+
+    /// Gets the data store associated with the transparency service.
+    pub fn store(&self) -> &dyn DataStore {
+        self.inner.store()
+    }
+
+    /// Gets the directory the log tiles are written to, if tiles are written.
+    pub fn tiles_dir(&self) -> Option<&Path> {
+        self.inner.tiles().map(TileWriter::dir)
+    }
+
+    /// Submits an operator record to be processed.
+    ///
+    /// Returns an error if the service has stopped.
+    pub async fn submit_operator_record(
+        &self,
+        record_id: RecordId,
+    ) -> Result<(), CoreServiceError> {
+        self.submit_entry_tx
+            .send(LogLeaf {
+                log_id: self.operator_log_id(),
+                record_id,
+            })
+            .await
+            .map_err(|_| CoreServiceError::Stopped)
+    }
+
+    /// Submits a package record to be processed.
+    ///
+    /// Returns an error if the service has stopped.
+    pub async fn submit_package_record(
+        &self,
+        log_id: LogId,
+        record_id: RecordId,
+    ) -> Result<(), CoreServiceError> {
+        self.submit_entry_tx
+            .send(LogLeaf { log_id, record_id })
+            .await
+            .map_err(|_| CoreServiceError::Stopped)
+    }
+}
+
+// Gets the hash algorithm of an existing registry from the init entry of its
+// operator log, which is the first record of the registry log.
+async fn stored_hash_algorithm(
+    store: &dyn DataStore,
+) -> Result<Option<HashAlgorithm>, CoreServiceError> {
+    let LogLeaf { log_id, record_id } = match store.get_log_leafs_with_registry_index(&[0]).await {
+        Ok(mut leafs) => leafs.remove(0),
+        Err(DataStoreError::LogLeafNotFound(_)) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    let record = store.get_operator_record(&log_id, &record_id).await?;
+    match record.envelope.as_ref().entries.first() {
+        Some(operator::OperatorEntry::Init { hash_algorithm, .. }) => Ok(Some(*hash_algorithm)),
+        _ => Err(CoreServiceError::InitializationFailure(
+            "the first record of the registry log does not initialize the operator log".into(),
+        )),
+    }
+}
+
+// The transparency state of the service for the registry's hash algorithm.
+#[axum::async_trait]
+trait Transparency: Send + Sync {
+    fn store(&self) -> &dyn DataStore;
+
+    fn tiles(&self) -> Option<&TileWriter>;
+
+    async fn log_consistency_proof(
+        &self,
+        from_log_length: RegistryLen,
+        to_log_length: RegistryLen,
+    ) -> Result<Vec<u8>, CoreServiceError>;
+
+    async fn log_inclusion_proofs(
+        &self,
+        log_length: RegistryLen,
+        entries: &[RegistryIndex],
+    ) -> Result<Vec<u8>, CoreServiceError>;
+
+    async fn map_inclusion_proofs(
+        &self,
+        log_length: RegistryLen,
+        entries: &[RegistryIndex],
+    ) -> Result<Vec<u8>, CoreServiceError>;
+
+    async fn map_absence_proofs(
+        &self,
+        log_length: RegistryLen,
+        log_ids: &[LogId],
+    ) -> Result<Vec<u8>, CoreServiceError>;
+
+    async fn map_transition_proof(
+        &self,
+        from_log_length: RegistryLen,
+        to_log_length: RegistryLen,
+        log_ids: &[LogId],
+    ) -> Result<Vec<u8>, CoreServiceError>;
+
+    async fn rotate_operator_key(
+        &self,
+        operator_signer: Arc<dyn Signer>,
+    ) -> Result<RecordId, CoreServiceError>;
+}
+
+#[axum::async_trait]
+impl<Digest: SupportedDigest> Transparency for Inner<Digest> {
+    fn store(&self) -> &dyn DataStore {
+        self.store.as_ref()
+    }
+
+    fn tiles(&self) -> Option<&TileWriter> {
+        self.tiles.as_ref()
+    }
+
+    async fn log_consistency_proof(
+        &self,
+        from_log_length: RegistryLen,
+        to_log_length: RegistryLen,
+    ) -> Result<Vec<u8>, CoreServiceError> {
+        let state = self.state.read().await;
+
+        let proof = state.log.prove_consistency(from_log_length, to_log_length);
+        let bundle = LogProofBundle::bundle(vec![proof], vec![], &state.log)
+            .map_err(CoreServiceError::BundleFailure)?;
+        Ok(bundle.encode())
+    }
+
+    async fn log_inclusion_proofs(
+        &self,
+        log_length: RegistryLen,
+        entries: &[RegistryIndex],
+    ) -> Result<Vec<u8>, CoreServiceError> {
+        let state = self.state.read().await;
 
         let proofs = entries
             .iter()
             .map(|&index| {
-                let node = if index < state.leaf_index.len() as RegistryIndex {
-                    state.leaf_index[index]
+                let node = if index < state.log.length() {
+                    Node(index * 2)
                 } else {
                     return Err(CoreServiceError::LeafNotFound(index));
                 };
@@ -105,24 +442,25 @@ impl<Digest: SupportedDigest> CoreService<Digest> {
             })
             .collect::<Result<Vec<_>, CoreServiceError>>()?;
 
-        LogProofBundle::bundle(vec![], proofs, &state.log).map_err(CoreServiceError::BundleFailure)
+        let bundle = LogProofBundle::bundle(vec![], proofs, &state.log)
+            .map_err(CoreServiceError::BundleFailure)?;
+        Ok(bundle.encode())
     }
 
-    /// Constructs map inclusion proofs for the given entries at the given map tree root.
-    pub async fn map_inclusion_proofs(
+    async fn map_inclusion_proofs(
         &self,
         log_length: RegistryLen,
         entries: &[RegistryIndex],
-    ) -> Result<MapProofBundle<Digest, LogId, MapLeaf>, CoreServiceError> {
-        let state = self.inner.state.read().await;
+    ) -> Result<Vec<u8>, CoreServiceError> {
+        let state = self.state.read().await;
 
-        let (map_root, map) = state
-            .map_index
-            .get(&log_length)
-            .ok_or_else(|| CoreServiceError::CheckpointNotFound(log_length))?;
+        let version = log_length as u64;
+        let map_root = state
+            .map_store
+            .root(version)
+            .ok_or(CoreServiceError::CheckpointNotFound(log_length))?;
 
         let indexes = self
-            .inner
             .store
             .get_log_leafs_with_registry_index(entries)
             .await
@@ -133,8 +471,9 @@ impl<Digest: SupportedDigest> CoreService<Digest> {
             .map(|log_leaf| {
                 let LogLeaf { log_id, record_id } = log_leaf;
 
-                let proof = map
-                    .prove(log_id.clone())
+                let proof = state
+                    .map_store
+                    .prove(version, log_id.clone())?
                     .ok_or_else(|| CoreServiceError::PackageNotIncluded(log_id.clone()))?;
 
                 let map_leaf = MapLeaf {
@@ -152,85 +491,256 @@ impl<Digest: SupportedDigest> CoreService<Digest> {
             })
             .collect::<Result<Vec<_>, CoreServiceError>>()?;
 
-        Ok(MapProofBundle::bundle(proofs))
+        Ok(MapProofBundle::bundle(proofs).encode())
     }
 
-    /// Gets the data store associated with the transparency service.
-    pub fn store(&self) -> &dyn DataStore {
-        self.inner.store.as_ref()
+    async fn map_absence_proofs(
+        &self,
+        log_length: RegistryLen,
+        log_ids: &[LogId],
+    ) -> Result<Vec<u8>, CoreServiceError> {
+        let state = self.state.read().await;
+
+        let version = log_length as u64;
+        let map_root = state
+            .map_store
+            .root(version)
+            .ok_or(CoreServiceError::CheckpointNotFound(log_length))?;
+
+        let proofs = log_ids
+            .iter()
+            .map(|log_id| {
+                let proof = state
+                    .map_store
+                    .prove_absence(version, log_id.clone())?
+                    .ok_or_else(|| CoreServiceError::PackageIncluded(log_id.clone()))?;
+
+                let found_root = proof
+                    .evaluate(log_id)
+                    .map_err(|e| CoreServiceError::BundleFailure(e.into()))?;
+                if &found_root != map_root {
+                    return Err(CoreServiceError::IncorrectProof {
+                        root: map_root.into(),
+                        found: found_root.into(),
+                    });
+                }
+
+                Ok(proof)
+            })
+            .collect::<Result<Vec<_>, CoreServiceError>>()?;
+
+        Ok(MapProofBundle::<Digest, LogId, MapLeaf>::bundle_absences(proofs).encode())
     }
 
-    /// Submits a package record to be processed.
-    pub async fn submit_package_record(&self, log_id: LogId, record_id: RecordId) {
-        self.submit_entry_tx
-            .send(LogLeaf { log_id, record_id })
-            .await
-            .unwrap()
+    async fn map_transition_proof(
+        &self,
+        from_log_length: RegistryLen,
+        to_log_length: RegistryLen,
+        log_ids: &[LogId],
+    ) -> Result<Vec<u8>, CoreServiceError> {
+        let state = self.state.read().await;
+
+        for log_length in [from_log_length, to_log_length] {
+            if state.map_store.root(log_length as u64).is_none() {
+                return Err(CoreServiceError::CheckpointNotFound(log_length));
+            }
+        }
+
+        let proof = state.map_store.prove_transition::<_, MapLeaf>(
+            from_log_length as u64,
+            to_log_length as u64,
+            log_ids.iter().cloned(),
+        )?;
+
+        Ok(MapProofBundle::bundle_transitions(vec![proof]).encode())
+    }
+
+    async fn rotate_operator_key(
+        &self,
+        operator_signer: Arc<dyn Signer>,
+    ) -> Result<RecordId, CoreServiceError> {
+        Inner::rotate_operator_key(self, operator_signer).await
     }
 }
 
 struct Inner<Digest: SupportedDigest> {
-    // Operator signing key
-    operator_key: PrivateKey,
+    // Operator signer; only replaced while holding the state lock
+    operator_signer: RwLock<Arc<dyn Signer>>,
 
     // DataStore persists transparency state.
     store: Box<dyn DataStore>,
 
     // In-memory transparency state.
     state: RwLock<State<Digest>>,
+
+    // Writes the log as tiles as checkpoints are produced.
+    tiles: Option<TileWriter>,
 }
 
 impl<Digest: SupportedDigest> Inner<Digest> {
-    // Load state from DataStore or initialize empty state, returning any
-    // entries that are not yet part of a checkpoint.
+    // Load state from DataStore or initialize empty state.
+    //
+    // The state is resumed from the latest checkpoint covered by both the log
+    // file and the map store; only the records after it are replayed.
     async fn initialize(
         &mut self,
         namespaces: Option<Vec<(String, operator::NamespaceState)>>,
     ) -> Result<(), CoreServiceError> {
         tracing::debug!("Initializing CoreService");
 
-        let published = self.store.get_all_validated_records().await?.peekable();
-        pin_mut!(published);
-
         // If there are no published records, initialize a new state
-        if published.as_mut().peek().await.is_none() {
+        if self
+            .store
+            .get_log_leafs_starting_with_registry_index(0, 1)
+            .await?
+            .is_empty()
+        {
             tracing::debug!("No existing records; initializing new state");
             return self.initialize_new(namespaces).await;
         }
 
-        // Reconstruct internal state from previously-stored data
-        let mut checkpoints = self.store.get_all_checkpoints().await?;
+        let mut log_length = self.resume().await?;
+
+        // Get the stored checkpoints after the resumed one to validate as the
+        // remaining records are replayed
+        let latest = self
+            .store
+            .get_latest_checkpoint()
+            .await?
+            .into_contents()
+            .checkpoint;
         let mut checkpoints_by_len: HashMap<RegistryLen, Checkpoint> = Default::default();
-        while let Some(checkpoint) = checkpoints.next().await {
-            let checkpoint = checkpoint?.checkpoint;
-            checkpoints_by_len.insert(checkpoint.log_length, checkpoint);
+        if latest.log_length > log_length {
+            let mut checkpoints = self.store.get_all_checkpoints().await?;
+            while let Some(checkpoint) = checkpoints.next().await {
+                let checkpoint = checkpoint?.checkpoint;
+                if checkpoint.log_length > log_length {
+                    checkpoints_by_len.insert(checkpoint.log_length, checkpoint);
+                }
+            }
         }
 
         let state = self.state.get_mut();
-        while let Some(entry) = published.next().await {
-            state.push_entry(entry?);
-            if let Some(stored_checkpoint) =
-                checkpoints_by_len.get(&(state.log.length() as RegistryLen))
-            {
-                // Validate stored checkpoint (and update internal state as a side-effect)
-                let computed_checkpoint = state.checkpoint();
-                assert!(stored_checkpoint == &computed_checkpoint);
+        if log_length == 0 {
+            if let RegistryLog::File(log) = &mut state.log {
+                // Without a map version to resume from, keep the entries of
+                // the log file up to the last checkpoint they are consistent
+                // with; all records are replayed, but only later entries are
+                // appended to the file
+                let mut lengths = checkpoints_by_len
+                    .keys()
+                    .copied()
+                    .filter(|length| *length <= log.length())
+                    .collect::<Vec<_>>();
+                lengths.sort_unstable();
+
+                let mut length = 0;
+                for candidate in lengths.into_iter().rev() {
+                    let root: AnyHash = log.checkpoint_at(candidate)?.root().into();
+                    if root == checkpoints_by_len[&candidate].log_root {
+                        length = candidate;
+                        break;
+                    }
+                }
+
+                if length < log.length() {
+                    tracing::debug!(
+                        "Truncating log file of length {found} to checkpointed length {length}",
+                        found = log.length()
+                    );
+                    log.truncate(length)?;
+                }
+            }
+        }
+
+        loop {
+            let entries = self
+                .store
+                .get_log_leafs_starting_with_registry_index(
+                    log_length as RegistryIndex,
+                    REPLAY_BATCH_SIZE,
+                )
+                .await?;
+            if entries.is_empty() {
+                break;
+            }
+
+            for (_, entry) in entries {
+                if log_length < state.log.length() {
+                    state.apply_entry(entry);
+                } else {
+                    state.push_entry(entry)?;
+                }
+
+                log_length += 1;
+                if let Some(stored_checkpoint) = checkpoints_by_len.get(&log_length) {
+                    // Validate stored checkpoint (and update internal state as a side-effect)
+                    let computed_checkpoint = state.checkpoint_at(log_length)?;
+                    assert!(stored_checkpoint == &computed_checkpoint);
+                }
             }
         }
 
         Ok(())
     }
 
+    // Restores the state at the latest checkpoint whose entries are in the
+    // log and whose map version is in the map store, returning its log
+    // length; returns zero if there is no such checkpoint
+    async fn resume(&mut self) -> Result<RegistryLen, CoreServiceError> {
+        let state = self.state.get_mut();
+        let versions = state.map_store.versions().collect::<Vec<_>>();
+        for version in versions.into_iter().rev() {
+            let log_length = version as RegistryLen;
+            if log_length > state.log.length() {
+                continue;
+            }
+
+            let checkpoint = match self.store.get_checkpoint(log_length).await {
+                Ok(checkpoint) => checkpoint.into_contents().checkpoint,
+                Err(DataStoreError::CheckpointNotFound(_)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            let log_root: AnyHash = state.log.root_at(log_length)?.into();
+            let map_root = state.map_store.root(version).cloned().map(AnyHash::from);
+            if log_root != checkpoint.log_root || map_root.as_ref() != Some(&checkpoint.map_root) {
+                continue;
+            }
+
+            tracing::debug!("Resuming from checkpoint at log length {log_length}");
+            if let RegistryLog::File(log) = &mut state.log {
+                if log_length < log.length() {
+                    log.truncate(log_length)?;
+                }
+            }
+
+            state.map = state.map_store.map(version)?;
+            state.operator_head =
+                latest_operator_record::<Digest>(self.store.as_ref(), log_length).await?;
+            return Ok(log_length);
+        }
+
+        Ok(0)
+    }
+
     async fn initialize_new(
         &mut self,
         namespaces: Option<Vec<(String, operator::NamespaceState)>>,
     ) -> Result<(), CoreServiceError> {
         let state = self.state.get_mut();
+        let operator_signer = self.operator_signer.get_mut().clone();
+
+        // Discard any log file and map versions left from a previous registry
+        if let RegistryLog::File(log) = &mut state.log {
+            log.truncate(0)?;
+        }
+        state.map_store.truncate(0)?;
 
         // Construct operator init record
         let init = operator::OperatorEntry::Init {
             hash_algorithm: Digest::ALGORITHM,
-            key: self.operator_key.public_key(),
+            key: operator_signer.public_key(),
         };
         let entries = if let Some(namespaces) = namespaces {
             let mut entries = Vec::with_capacity(1 + namespaces.len());
@@ -255,12 +765,14 @@ impl<Digest: SupportedDigest> Inner<Digest> {
 
         let init_record = operator::OperatorRecord {
             prev: None,
-            version: 0,
+            version: operator::OPERATOR_RECORD_VERSION,
             timestamp: SystemTime::now(),
             entries,
         };
         let signed_init_record =
-            ProtoEnvelope::signed_contents(&self.operator_key, init_record).unwrap();
+            ProtoEnvelope::signed_contents_with(operator_signer.as_ref(), init_record)
+                .await
+                .map_err(CoreServiceError::SigningFailure)?;
         let log_id = LogId::operator_log::<Digest>();
         let record_id = RecordId::operator_record::<Digest>(&signed_init_record);
 
@@ -273,7 +785,7 @@ impl<Digest: SupportedDigest> Inner<Digest> {
             .await?;
 
         // Update state with init record
-        state.push_entry(LogLeaf { log_id, record_id });
+        state.push_entry(LogLeaf { log_id, record_id })?;
 
         // "zero" checkpoint to be updated
         let mut checkpoint = Checkpoint {
@@ -287,11 +799,13 @@ impl<Digest: SupportedDigest> Inner<Digest> {
     }
 
     // Runs the service's state update loop.
+    //
+    // The loop stops with an error if an entry cannot be added to the log.
     async fn process_state_updates(
         self: Arc<Self>,
         mut submit_entry_rx: mpsc::Receiver<LogLeaf>,
         checkpoint_interval: Duration,
-    ) {
+    ) -> Result<(), CoreServiceError> {
         let mut checkpoint = self
             .store
             .get_latest_checkpoint()
@@ -306,27 +820,38 @@ impl<Digest: SupportedDigest> Inner<Digest> {
         loop {
             tokio::select! {
                 entry = submit_entry_rx.recv() => match entry {
-                    Some(entry) => self.process_package_entry(&entry).await,
+                    Some(entry) => self.process_entry(&entry).await?,
                     None => break, // Channel closed
                 },
                 _ = checkpoint_interval.tick() => self.update_checkpoint(&mut checkpoint).await,
             }
         }
+
+        Ok(())
     }
 
-    // Processes a submitted package entry
-    async fn process_package_entry(&self, entry: &LogLeaf) {
+    // Processes a submitted operator or package entry
+    async fn process_entry(&self, entry: &LogLeaf) -> Result<(), CoreServiceError> {
         tracing::debug!("Processing entry {entry:?}");
 
         let mut state = self.state.write().await;
+        if state.log_failed {
+            return Err(CoreServiceError::Stopped);
+        }
+
         let LogLeaf { log_id, record_id } = entry;
 
-        // Validate and commit the package entry to the store
+        // Validate and commit the entry to the store
         let registry_index = state.log.length() as RegistryIndex;
-        let commit_res = self
-            .store
-            .commit_package_record(log_id, record_id, registry_index)
-            .await;
+        let commit_res = if *log_id == LogId::operator_log::<Digest>() {
+            self.store
+                .commit_operator_record(log_id, record_id, registry_index)
+                .await
+        } else {
+            self.store
+                .commit_package_record(log_id, record_id, registry_index)
+                .await
+        };
 
         if let Err(err) = commit_res {
             match err {
@@ -339,80 +864,398 @@ impl<Digest: SupportedDigest> Inner<Digest> {
                 e => {
                     // TODO: this should be made more robust with a proper reliable message
                     // queue with retry logic
-                    tracing::error!("failed to validate package record `{record_id}`: {e}");
+                    tracing::error!("failed to validate record `{record_id}`: {e}");
                 }
             }
-            return;
+            return Ok(());
+        }
+
+        // The record is committed to the store, so if it cannot be added to
+        // the log, the service must stop; the log is rebuilt from the store
+        // on restart
+        state.push_entry(entry.clone())
+    }
+
+    // Rotates the operator signer by committing an operator record that moves
+    // the commit permission from the current key to the given signer's key
+    async fn rotate_operator_key(
+        &self,
+        operator_signer: Arc<dyn Signer>,
+    ) -> Result<RecordId, CoreServiceError> {
+        // Hold the state lock so that no checkpoint is signed mid-rotation
+        let mut state = self.state.write().await;
+        if state.log_failed {
+            return Err(CoreServiceError::Stopped);
+        }
+
+        let mut current_signer = self.operator_signer.write().await;
+
+        let current_key_id = current_signer.public_key().fingerprint();
+        let new_key = operator_signer.public_key();
+        if new_key.fingerprint() == current_key_id {
+            return Err(CoreServiceError::OperatorKeyUnchanged(current_key_id));
         }
 
-        state.push_entry(entry.clone());
+        let record = operator::OperatorRecord {
+            prev: state.operator_head.clone(),
+            version: operator::OPERATOR_RECORD_VERSION,
+            timestamp: SystemTime::now(),
+            entries: vec![
+                operator::OperatorEntry::GrantFlat {
+                    key: new_key,
+                    permissions: vec![operator::Permission::Commit],
+                },
+                operator::OperatorEntry::RevokeFlat {
+                    key_id: current_key_id,
+                    permissions: vec![operator::Permission::Commit],
+                },
+            ],
+        };
+        let signed_record = ProtoEnvelope::signed_contents_with(current_signer.as_ref(), record)
+            .await
+            .map_err(CoreServiceError::SigningFailure)?;
+        let log_id = LogId::operator_log::<Digest>();
+        let record_id = RecordId::operator_record::<Digest>(&signed_record);
+
+        // Store and commit the record directly so the rotation is part of the
+        // log before any checkpoint is signed with the new key
+        self.store
+            .store_operator_record(&log_id, &record_id, &signed_record)
+            .await?;
+        self.store
+            .commit_operator_record(&log_id, &record_id, state.log.length() as RegistryIndex)
+            .await?;
+
+        state.push_entry(LogLeaf {
+            log_id,
+            record_id: record_id.clone(),
+        })?;
+        *current_signer = operator_signer;
+
+        tracing::info!("rotated operator key in record `{record_id}`");
+        Ok(record_id)
     }
 
     // Store a checkpoint including the given new entries
+    //
+    // The given checkpoint is only advanced once the new checkpoint has been
+    // stored, so that a checkpoint that failed to be signed or stored is
+    // retried on the next update even if no new entries arrive.
     async fn update_checkpoint(&self, checkpoint: &mut Checkpoint) {
-        {
-            // Recalculate the checkpoint if necessary
+        let (next, signed) = {
+            // Only sign a new checkpoint if the log has grown; re-signing an
+            // unchanged checkpoint would invalidate its witness cosignatures
             let mut state = self.state.write().await;
-            if state.log.length() as RegistryLen != checkpoint.log_length {
-                *checkpoint = state.checkpoint();
-                tracing::debug!("Updating to checkpoint {checkpoint:?}");
+            if state.log.length() as RegistryLen == checkpoint.log_length {
+                return;
             }
+
+            // The entries must be durable before a checkpoint including them
+            // is stored
+            if let Err(err) = state.log.sync() {
+                tracing::error!("Error syncing log file: {err:?}");
+                return;
+            }
+
+            let next = match state.checkpoint() {
+                Ok(checkpoint) => checkpoint,
+                Err(err) => {
+                    tracing::error!("Error storing map version: {err:?}");
+                    return;
+                }
+            };
+            tracing::debug!("Updating to checkpoint {next:?}");
+
+            // Sign while holding the state lock so that the checkpoint is
+            // signed by the operator key authorized at its log length
+            let signed = self.sign_checkpoint(next.clone()).await;
+            (next, signed)
+        };
+
+        let res = match signed {
+            Ok(signed) => self.store_checkpoint(signed).await,
+            Err(e) => Err(e),
+        };
+
+        if let Err(err) = res {
+            tracing::error!("Error storing checkpoint {next:?}: {err:?}");
+            return;
         }
 
-        if let Err(err) = self.sign_and_store_checkpoint(checkpoint.clone()).await {
-            tracing::error!("Error storing checkpoint {checkpoint:?}: {err:?}");
+        let previous_log_length = checkpoint.log_length;
+        *checkpoint = next;
+
+        if let Err(err) = self.write_tiles(previous_log_length, checkpoint).await {
+            tracing::error!("Error writing log tiles for checkpoint {checkpoint:?}: {err:?}");
+        }
+    }
+
+    // Writes the tiles that changed since the given log length followed by
+    // a checkpoint note for the given checkpoint
+    async fn write_tiles(
+        &self,
+        from_log_length: RegistryLen,
+        checkpoint: &Checkpoint,
+    ) -> anyhow::Result<()> {
+        let Some(writer) = &self.tiles else {
+            return Ok(());
+        };
+
+        let (tiles, note) = {
+            let state = self.state.read().await;
+            let tiles = tiles_between(from_log_length, checkpoint.log_length)
+                .into_iter()
+                .map(|tile| {
+                    let data = tile.read(&state.log).ok_or_else(|| {
+                        anyhow::anyhow!("log is missing hashes of tile `{tile:?}`")
+                    })?;
+                    Ok((tile, data))
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?;
+
+            let operator_signer = self.operator_signer.read().await;
+            let note = writer
+                .sign_checkpoint(operator_signer.as_ref(), checkpoint)
+                .await?;
+            (tiles, note)
+        };
+
+        for (tile, data) in tiles {
+            if let Some(path) = tile.entries_path() {
+                let first = tile.index() * TILE_WIDTH;
+                let indexes = (first..first + tile.width()).collect::<Vec<_>>();
+                let leafs = self
+                    .store
+                    .get_log_leafs_with_registry_index(&indexes)
+                    .await?;
+                writer.write(&path, &encode_entry_bundle(&leafs))?;
+            }
+
+            writer.write(&tile.path(), &data)?;
         }
+
+        writer.write_checkpoint(&note)
+    }
+
+    // Writes any tiles missing for the latest checkpoint, such as when tiles
+    // are first enabled for an existing registry
+    async fn sync_tiles(&self) -> anyhow::Result<()> {
+        let Some(writer) = &self.tiles else {
+            return Ok(());
+        };
+
+        let checkpoint = self
+            .store
+            .get_latest_checkpoint()
+            .await?
+            .into_contents()
+            .checkpoint;
+
+        let from_log_length = match writer.checkpoint_length() {
+            Some(length) if length == checkpoint.log_length => return Ok(()),
+            Some(length) if length < checkpoint.log_length => length,
+            _ => 0,
+        };
+
+        self.write_tiles(from_log_length, &checkpoint).await
     }
 
-    async fn sign_and_store_checkpoint(&self, checkpoint: Checkpoint) -> anyhow::Result<()> {
-        let checkpoint_id = Hash::<Digest>::of(&checkpoint).into();
-        let timestamped = TimestampedCheckpoint::now(checkpoint.clone())?;
-        let signed = SerdeEnvelope::signed_contents(&self.operator_key, timestamped)?;
+    async fn sign_checkpoint(
+        &self,
+        checkpoint: Checkpoint,
+    ) -> anyhow::Result<SerdeEnvelope<TimestampedCheckpoint>> {
+        let timestamped = TimestampedCheckpoint::now(checkpoint)?;
+        let operator_signer = self.operator_signer.read().await;
+        Ok(SerdeEnvelope::signed_contents_with(operator_signer.as_ref(), timestamped).await?)
+    }
+
+    async fn store_checkpoint(
+        &self,
+        signed: SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> anyhow::Result<()> {
+        let checkpoint_id = Hash::<Digest>::of(&signed.as_ref().checkpoint).into();
         self.store.store_checkpoint(&checkpoint_id, signed).await?;
         Ok(())
     }
 }
 
+// Gets the latest operator record within the given registry log length
+async fn latest_operator_record<Digest: SupportedDigest>(
+    store: &dyn DataStore,
+    log_length: RegistryLen,
+) -> Result<Option<RecordId>, CoreServiceError> {
+    let log_id = LogId::operator_log::<Digest>();
+    let mut head = None;
+    loop {
+        let records = store
+            .get_operator_records(&log_id, log_length, head.as_ref(), REPLAY_BATCH_SIZE as u16)
+            .await?;
+        match records.last() {
+            Some(record) => head = Some(RecordId::operator_record::<Digest>(&record.envelope)),
+            None => return Ok(head),
+        }
+    }
+}
+
 type VerifiableMap<Digest> = Map<Digest, LogId, MapLeaf>;
 
+// The verifiable log of all package log entries
+enum RegistryLog<Digest: SupportedDigest> {
+    Memory(VecLog<Digest, LogLeaf>),
+    File(FileLog<Digest, LogLeaf>),
+}
+
+impl<Digest: SupportedDigest> RegistryLog<Digest> {
+    fn length(&self) -> usize {
+        match self {
+            Self::Memory(log) => log.length(),
+            Self::File(log) => log.length(),
+        }
+    }
+
+    fn root_at(&self, length: usize) -> Result<Hash<Digest>, CoreServiceError> {
+        match self {
+            Self::Memory(log) => log
+                .root_at(length)
+                .ok_or(CoreServiceError::CheckpointNotFound(length)),
+            Self::File(log) => Ok(log.checkpoint_at(length)?.root()),
+        }
+    }
+
+    fn sync(&self) -> Result<(), FileLogError> {
+        match self {
+            Self::Memory(_) => Ok(()),
+            Self::File(log) => log.sync(),
+        }
+    }
+
+    fn checkpoint(&self) -> log::Checkpoint<Digest> {
+        match self {
+            Self::Memory(log) => log.checkpoint(),
+            Self::File(log) => log.checkpoint(),
+        }
+    }
+
+    // Appends an entry to the log; on error, the log is unchanged
+    fn try_push(&mut self, entry: &LogLeaf) -> Result<Node, FileLogError> {
+        match self {
+            Self::Memory(log) => Ok(log.push(entry)),
+            Self::File(log) => log.try_push(entry),
+        }
+    }
+}
+
+impl<Digest: SupportedDigest> Default for RegistryLog<Digest> {
+    fn default() -> Self {
+        Self::Memory(VecLog::default())
+    }
+}
+
+impl<Digest: SupportedDigest> LogData<Digest, LogLeaf> for RegistryLog<Digest> {
+    fn has_hash(&self, node: Node) -> bool {
+        match self {
+            Self::Memory(log) => log.has_hash(node),
+            Self::File(log) => log.has_hash(node),
+        }
+    }
+
+    fn hash_for(&self, node: Node) -> Option<Hash<Digest>> {
+        match self {
+            Self::Memory(log) => log.hash_for(node),
+            Self::File(log) => log.hash_for(node),
+        }
+    }
+}
+
 #[derive(Default)]
 struct State<Digest: SupportedDigest> {
     // The verifiable log of all package log entries
-    log: VecLog<Digest, LogLeaf>,
-    // Index log tree nodes by registry log index of the record
-    leaf_index: Vec<Node>,
+    log: RegistryLog<Digest>,
 
     // The verifiable map of package logs' latest entries (log_id -> record_id)
     map: VerifiableMap<Digest>,
-    // Index verifiable map snapshots by log length (at checkpoints only)
-    map_index: HashMap<RegistryLen, (Hash<Digest>, VerifiableMap<Digest>)>,
+    // The map updates applied since the last checkpoint, not yet in the map
+    pending: Vec<(LogId, MapLeaf)>,
+    // The versions of the verifiable map by log length (at checkpoints only)
+    map_store: MapStore<Digest>,
+
+    // The latest record of the operator log
+    operator_head: Option<RecordId>,
+
+    // Set when an entry failed to be appended to the log; the entry may
+    // already be committed to the store, so no further entries are added
+    // until the log is rebuilt from the store on restart
+    log_failed: bool,
 }
 
 impl<Digest: SupportedDigest> State<Digest> {
-    fn push_entry(&mut self, log_leaf: LogLeaf) {
-        let node = self.log.push(&log_leaf);
-        self.leaf_index.push(node);
+    fn push_entry(&mut self, log_leaf: LogLeaf) -> Result<(), CoreServiceError> {
+        if let Err(err) = self.log.try_push(&log_leaf) {
+            self.log_failed = true;
+            return Err(err.into());
+        }
 
+        self.apply_entry(log_leaf);
+        Ok(())
+    }
+
+    // Applies an entry already in the log to the rest of the state
+    fn apply_entry(&mut self, log_leaf: LogLeaf) {
         let LogLeaf { log_id, record_id } = log_leaf;
-        self.map = self.map.insert(log_id, MapLeaf { record_id });
+        if log_id == LogId::operator_log::<Digest>() {
+            self.operator_head = Some(record_id.clone());
+        }
+
+        self.pending.push((log_id, MapLeaf { record_id }));
     }
 
-    fn checkpoint(&mut self) -> Checkpoint {
+    fn checkpoint(&mut self) -> Result<Checkpoint, CoreServiceError> {
         let log_checkpoint = self.log.checkpoint();
-        let map_root = self.map.root();
-        let log_length = log_checkpoint.length() as RegistryLen;
+        self.snapshot(log_checkpoint.length(), log_checkpoint.root())
+    }
+
+    // Gets the checkpoint at the given log length, which must be the length
+    // of the entries applied to the map
+    fn checkpoint_at(&mut self, log_length: RegistryLen) -> Result<Checkpoint, CoreServiceError> {
+        let log_root = self.log.root_at(log_length)?;
+        self.snapshot(log_length, log_root)
+    }
+
+    fn snapshot(
+        &mut self,
+        log_length: RegistryLen,
+        log_root: Hash<Digest>,
+    ) -> Result<Checkpoint, CoreServiceError> {
+        // Apply all updates pending since the last checkpoint in one batch
+        if !self.pending.is_empty() {
+            self.map = self.map.extend_sorted(std::mem::take(&mut self.pending));
+        }
+        let map_root = self.map.root().clone();
 
-        // Update map snapshot
+        // Store the map version of the checkpoint
         if log_length > 0 {
-            self.map_index
-                .insert(log_length, (map_root.clone(), self.map.clone()));
+            let version = log_length as u64;
+            let pruned = self
+                .map_store
+                .versions()
+                .next()
+                .map_or(false, |oldest| version < oldest);
+            match self.map_store.root(version) {
+                // Stored by a previous run of the service
+                Some(root) if root == &map_root => {}
+                None if pruned => {}
+                _ => {
+                    self.map_store.truncate(version - 1)?;
+                    self.map_store.commit(version, &self.map)?;
+                }
+            }
         }
 
-        Checkpoint {
+        Ok(Checkpoint {
             log_length,
-            log_root: log_checkpoint.root().into(),
+            log_root: log_root.into(),
             map_root: map_root.into(),
-        }
+        })
     }
 }
 
@@ -426,10 +1269,24 @@ pub enum CoreServiceError {
     BundleFailure(anyhow::Error),
     #[error("failed to prove inclusion of package `{0}`")]
     PackageNotIncluded(LogId),
+    #[error("failed to prove absence of package `{0}`")]
+    PackageIncluded(LogId),
     #[error("failed to prove inclusion: found root `{found}` but was given root `{root}`")]
     IncorrectProof { root: AnyHash, found: AnyHash },
     #[error("data store error: {0}")]
     DataStore(#[from] DataStoreError),
     #[error("initialization failed: {0}")]
     InitializationFailure(String),
+    #[error("key `{0}` is already the operator key")]
+    OperatorKeyUnchanged(KeyID),
+    #[error("failed to sign operator record: {0}")]
+    SigningFailure(SignatureError),
+    #[error("hash algorithm `{0}` is not supported")]
+    UnsupportedHashAlgorithm(HashAlgorithm),
+    #[error("log file error: {0}")]
+    LogFile(#[from] FileLogError),
+    #[error("map store error: {0}")]
+    MapStore(#[from] MapStoreError),
+    #[error("the core service has stopped")]
+    Stopped,
 }
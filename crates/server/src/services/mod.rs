@@ -1,3 +1,5 @@
 mod core;
+mod tiles;
 
-pub use self::core::{CoreService, CoreServiceError};
+pub use self::core::{CoreService, CoreServiceError, StateStorage};
+pub use self::tiles::{encode_entry_bundle, TileWriter};
@@ -0,0 +1,143 @@
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use warg_crypto::{
+    hash::{AnyHash, HashAlgorithm},
+    signing::{PublicKey, SignatureError, Signer},
+    Encode,
+};
+use warg_protocol::registry::{Checkpoint, LogLeaf, RegistryLen};
+
+/// The path of the checkpoint note within the tiles directory.
+const CHECKPOINT_PATH: &str = "checkpoint";
+
+// The signed note signature type of Ed25519 keys
+const ED25519_SIGNATURE_TYPE: u8 = 0x01;
+
+// The signed note signature type used for keys of other algorithms; there is
+// no standard signature type for them, so verifiers must know the key
+const OTHER_SIGNATURE_TYPE: u8 = 0xff;
+
+/// Writes the registry log as hash tiles and a signed checkpoint note in the
+/// [C2SP tlog-tiles](https://c2sp.org/tlog-tiles) layout.
+///
+/// The files are written to a directory so that they can be served by the
+/// registry or from a static file host.
+#[derive(Debug, Clone)]
+pub struct TileWriter {
+    dir: PathBuf,
+    origin: String,
+}
+
+impl TileWriter {
+    /// Creates a new tile writer for the given directory.
+    ///
+    /// The origin identifies the log in checkpoint notes and is also used
+    /// as the name of the operator key that signs them.
+    pub fn new(dir: PathBuf, origin: String) -> Self {
+        Self { dir, origin }
+    }
+
+    /// Gets the directory the tiles are written to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Gets the log length of the last checkpoint note written, if any.
+    pub fn checkpoint_length(&self) -> Option<RegistryLen> {
+        let note = fs::read_to_string(self.dir.join(CHECKPOINT_PATH)).ok()?;
+        let mut lines = note.lines();
+        if lines.next()? != self.origin {
+            return None;
+        }
+
+        lines.next()?.parse().ok()
+    }
+
+    /// Creates a checkpoint note for the given checkpoint signed by the given
+    /// operator signer.
+    pub async fn sign_checkpoint(
+        &self,
+        signer: &dyn Signer,
+        checkpoint: &Checkpoint,
+    ) -> Result<String, SignatureError> {
+        let body = format!(
+            "{origin}\n{length}\n{root}\n",
+            origin = self.origin,
+            length = checkpoint.log_length,
+            root = STANDARD.encode(checkpoint.log_root.bytes())
+        );
+
+        let mut signature = self.key_hash(&signer.public_key()).to_vec();
+        signature.extend(signer.sign(body.as_bytes()).await?.bytes());
+
+        Ok(format!(
+            "{body}\n\u{2014} {origin} {signature}\n",
+            origin = self.origin,
+            signature = STANDARD.encode(signature)
+        ))
+    }
+
+    /// Writes the checkpoint note.
+    ///
+    /// The note should be written after the tiles it covers.
+    pub fn write_checkpoint(&self, note: &str) -> Result<()> {
+        self.write(CHECKPOINT_PATH, note.as_bytes())
+    }
+
+    /// Writes the contents of a hash tile or entry bundle at the given path.
+    pub fn write(&self, path: &str, contents: &[u8]) -> Result<()> {
+        let path = self.dir.join(path);
+        let parent = path.parent().unwrap();
+        fs::create_dir_all(parent).with_context(|| {
+            format!(
+                "failed to create tile directory `{path}`",
+                path = parent.display()
+            )
+        })?;
+
+        // Write to a temporary file first so that a tile is never served
+        // partially written
+        let mut temp = tempfile::NamedTempFile::new_in(parent)?;
+        std::io::Write::write_all(&mut temp, contents)?;
+        temp.persist(&path)
+            .with_context(|| format!("failed to write tile `{path}`", path = path.display()))?;
+
+        Ok(())
+    }
+
+    // Computes the key hash that identifies the signing key in a note signature
+    fn key_hash(&self, key: &PublicKey) -> [u8; 4] {
+        let signature_type = match key {
+            PublicKey::Ed25519(_) => ED25519_SIGNATURE_TYPE,
+            _ => OTHER_SIGNATURE_TYPE,
+        };
+
+        let data = [
+            self.origin.as_bytes(),
+            b"\n",
+            &[signature_type],
+            &key.bytes(),
+        ]
+        .concat();
+        let hash = AnyHash::of(HashAlgorithm::Sha256, data.as_slice());
+        hash.bytes()[..4].try_into().unwrap()
+    }
+}
+
+/// Encodes log leafs as an entry bundle.
+///
+/// Each entry is the encoding of a log leaf prefixed by its length as a
+/// big-endian 16-bit integer.
+pub fn encode_entry_bundle(leafs: &[LogLeaf]) -> Vec<u8> {
+    let mut bundle = Vec::new();
+    for leaf in leafs {
+        let entry = leaf.encode();
+        bundle.extend((entry.len() as u16).to_be_bytes());
+        bundle.extend(entry);
+    }
+    bundle
+}
@@ -24,5 +24,38 @@ fn extend(c: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, extend);
+fn extend_sorted(c: &mut Criterion) {
+    let mut rng = rand::thread_rng();
+    let mut grp = c.benchmark_group("map");
+
+    grp.sample_size(50);
+    grp.warm_up_time(Duration::from_secs(1));
+
+    for size in [16, 128, 1024, 8192] {
+        grp.throughput(criterion::Throughput::Elements(size as u64));
+        grp.bench_with_input(BenchmarkId::new("extend_sorted", size), &size, |b, i| {
+            b.iter(|| {
+                Map::<Sha256, _, _>::default().extend_sorted(
+                    repeat_with(|| (rng.gen::<[u8; 32]>(), rng.gen::<[u8; 32]>())).take(*i),
+                )
+            })
+        });
+    }
+
+    // Batches of updates applied to a map that already has many entries
+    let map = create(repeat_with(|| (rng.gen(), rng.gen())).take(8192));
+    for size in [16, 128, 1024] {
+        grp.throughput(criterion::Throughput::Elements(size as u64));
+        grp.bench_with_input(BenchmarkId::new("extend_existing", size), &size, |b, i| {
+            b.iter(|| map.extend(repeat_with(|| (rng.gen(), rng.gen())).take(*i)))
+        });
+        grp.bench_with_input(
+            BenchmarkId::new("extend_sorted_existing", size),
+            &size,
+            |b, i| b.iter(|| map.extend_sorted(repeat_with(|| (rng.gen(), rng.gen())).take(*i))),
+        );
+    }
+}
+
+criterion_group!(benches, extend, extend_sorted);
 criterion_main!(benches);
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use rand::Rng;
+use warg_crypto::signing::{
+    generate_pair, BatchVerifier, PrivateKey, PublicKey, Signature, SignatureAlgorithm,
+};
+
+fn signatures(
+    keys: &[(PublicKey, PrivateKey)],
+    count: usize,
+) -> Vec<(PublicKey, [u8; 32], Signature)> {
+    let mut rng = rand::thread_rng();
+    (0..count)
+        .map(|i| {
+            let (public_key, private_key) = &keys[i % keys.len()];
+            let msg: [u8; 32] = rng.gen();
+            let signature = private_key.sign(&msg).unwrap();
+            (public_key.clone(), msg, signature)
+        })
+        .collect()
+}
+
+fn signatures_bench(c: &mut Criterion) {
+    let mut grp = c.benchmark_group("signatures");
+
+    grp.sample_size(20);
+    grp.warm_up_time(Duration::from_secs(1));
+
+    for algorithm in [SignatureAlgorithm::Ed25519, SignatureAlgorithm::EcdsaP256] {
+        let keys: Vec<_> = (0..4).map(|_| generate_pair(&algorithm)).collect();
+        for size in [16, 128, 1024] {
+            let signatures = signatures(&keys, size);
+
+            grp.throughput(criterion::Throughput::Elements(size as u64));
+            grp.bench_with_input(
+                BenchmarkId::new(format!("{algorithm}/verify"), size),
+                &signatures,
+                |b, signatures| {
+                    b.iter(|| {
+                        for (key, msg, signature) in signatures {
+                            key.verify(msg, signature).unwrap();
+                        }
+                    })
+                },
+            );
+            grp.bench_with_input(
+                BenchmarkId::new(format!("{algorithm}/verify-batch"), size),
+                &signatures,
+                |b, signatures| {
+                    b.iter(|| {
+                        let mut batch = BatchVerifier::new();
+                        for (key, msg, signature) in signatures {
+                            batch.push(key.clone(), *msg, signature.clone());
+                        }
+                        black_box(batch.verify()).unwrap();
+                    })
+                },
+            );
+        }
+    }
+}
+
+criterion_group!(benches, signatures_bench);
+criterion_main!(benches);
@@ -0,0 +1,514 @@
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    marker::PhantomData,
+    path::Path,
+};
+
+use alloc::{string::String, vec::Vec};
+use thiserror::Error;
+use warg_crypto::{
+    hash::{Hash, SupportedDigest},
+    VisitBytes,
+};
+
+use super::{
+    hash_branch, hash_empty, hash_leaf, node::Node, Checkpoint, LogBuilder, LogData, Side,
+};
+
+/// The magic bytes at the start of a log file.
+const MAGIC: &[u8; 8] = b"WARG-LOG";
+
+/// The length of the log file header: the magic bytes followed by the hash
+/// algorithm name, padded with zeros.
+const HEADER_LEN: u64 = 16;
+
+/// Represents an error using a [`FileLog`].
+#[derive(Error, Debug)]
+pub enum FileLogError {
+    /// An I/O error occurred reading or writing the log file.
+    #[error("failed to access log file: {0}")]
+    Io(#[from] io::Error),
+    /// The file is not a log file.
+    #[error("file is not a log file")]
+    InvalidHeader,
+    /// The log file uses a different hash algorithm.
+    #[error("log file uses hash algorithm `{0}`")]
+    AlgorithmMismatch(String),
+    /// The requested length exceeds the length of the log.
+    #[error("length {requested} exceeds the log length {length}")]
+    BeyondLength {
+        /// The length of the log.
+        length: usize,
+        /// The requested length.
+        requested: usize,
+    },
+}
+
+/// A log stored in an append-only file.
+///
+/// The file holds the same in-order node hashes as a [`VecLog`](super::VecLog)
+/// after a small header, so opening a log only reads its balanced roots and
+/// the hashes of a proof are read from the file as needed.
+///
+/// Writes are not durable until [`FileLog::sync`] is called. A log file left
+/// partially written by a crash is repaired when opened; callers that
+/// checkpoint the log should [`FileLog::truncate`] it back to the last
+/// checkpointed length afterwards.
+///
+/// [`LogBuilder::push`] panics if the log file cannot be written; use
+/// [`FileLog::try_push`] to handle the error instead.
+pub struct FileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    file: File,
+    length: usize,
+    /// The balanced roots of the log
+    stack: Vec<(Node, Hash<D>)>,
+    /// Marker for value type
+    _value: PhantomData<V>,
+}
+
+impl<D, V> FileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    /// Opens the log file at the given path, creating an empty log if the
+    /// file does not exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FileLogError> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let header = Self::header();
+        let size = file.metadata()?.len();
+        if size < HEADER_LEN {
+            // A new file, or one whose header was never completely written
+            file.set_len(0)?;
+            write_all_at(&file, &header, 0)?;
+            file.sync_all()?;
+        } else {
+            let mut found = [0; HEADER_LEN as usize];
+            read_exact_at(&file, &mut found, 0)?;
+            if &found[..MAGIC.len()] != MAGIC {
+                return Err(FileLogError::InvalidHeader);
+            }
+            if found != header {
+                let algorithm = found[MAGIC.len()..]
+                    .iter()
+                    .take_while(|b| **b != 0)
+                    .map(|b| *b as char)
+                    .collect();
+                return Err(FileLogError::AlgorithmMismatch(algorithm));
+            }
+        }
+
+        // Discard a partially written hash, and a spacer appended without
+        // its leaf, so that the file ends with a leaf
+        let hash_len = Self::hash_len();
+        let mut nodes = (file.metadata()?.len() - HEADER_LEN) / hash_len;
+        if nodes % 2 == 0 {
+            nodes = nodes.saturating_sub(1);
+        }
+        file.set_len(HEADER_LEN + nodes * hash_len)?;
+
+        let mut log = Self {
+            file,
+            length: ((nodes + 1) / 2) as usize,
+            stack: Vec::new(),
+            _value: PhantomData,
+        };
+        log.repair()?;
+        log.load_stack()?;
+        Ok(log)
+    }
+
+    /// Get the number of entries in the log.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Check if the log is empty.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Push a new entry into the log.
+    ///
+    /// If an error is returned, the log is unchanged in memory; the log file
+    /// may be partially written until it is reopened or truncated.
+    pub fn try_push(&mut self, entry: &V) -> Result<Node, FileLogError> {
+        let leaf = Node(self.length * 2);
+        let leaf_digest = hash_leaf::<D>(entry);
+
+        // Append the spacer (if necessary) and the entry digest
+        let mut appended = Vec::new();
+        if self.length != 0 {
+            appended.extend_from_slice(hash_empty::<D>().bytes());
+        }
+        appended.extend_from_slice(leaf_digest.bytes());
+        let first = Node(leaf.index() - usize::from(self.length != 0));
+        write_all_at(&self.file, &appended, self.offset(first))?;
+
+        // Fill in newly complete ancestors
+        let mut stack = self.stack.clone();
+        let mut current = (leaf, leaf_digest);
+        while current.0.side() == Side::Right {
+            // The left sibling of a right node is the last balanced root
+            let (_, sibling_digest) = stack.pop().unwrap();
+            let parent = current.0.parent();
+            let parent_digest = hash_branch::<D>(sibling_digest, current.1);
+            write_all_at(&self.file, parent_digest.bytes(), self.offset(parent))?;
+            current = (parent, parent_digest);
+        }
+        stack.push(current);
+
+        self.stack = stack;
+        self.length += 1;
+        Ok(leaf)
+    }
+
+    /// Get the checkpoint of the log at the given length.
+    pub fn checkpoint_at(&self, length: usize) -> Result<Checkpoint<D>, FileLogError> {
+        if length > self.length {
+            return Err(FileLogError::BeyondLength {
+                length: self.length,
+                requested: length,
+            });
+        }
+
+        let root = Node::broots_for_len(length)
+            .into_iter()
+            .rev()
+            .map(|node| self.read(node))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .reduce(|old, new| {
+                // Ordering due to reversal of iterator
+                hash_branch::<D>(new, old)
+            })
+            .unwrap_or_else(hash_empty::<D>);
+
+        Ok(Checkpoint { root, length })
+    }
+
+    /// Truncate the log to the given length, discarding later entries.
+    ///
+    /// The truncated log file is synced before returning.
+    pub fn truncate(&mut self, length: usize) -> Result<(), FileLogError> {
+        if length > self.length {
+            return Err(FileLogError::BeyondLength {
+                length: self.length,
+                requested: length,
+            });
+        }
+
+        // Hashes of ancestors that do not exist at the new length are left
+        // in the file; they are overwritten as entries are pushed
+        let nodes = (length * 2).saturating_sub(1) as u64;
+        self.file.set_len(HEADER_LEN + nodes * Self::hash_len())?;
+        self.file.sync_all()?;
+
+        self.length = length;
+        self.load_stack()
+    }
+
+    /// Flush all writes to the log file to disk.
+    pub fn sync(&self) -> Result<(), FileLogError> {
+        Ok(self.file.sync_data()?)
+    }
+
+    fn header() -> [u8; HEADER_LEN as usize] {
+        let mut header = [0; HEADER_LEN as usize];
+        header[..MAGIC.len()].copy_from_slice(MAGIC);
+        let algorithm = D::ALGORITHM.to_string();
+        let algorithm = &algorithm.as_bytes()[..algorithm.len().min(header.len() - MAGIC.len())];
+        header[MAGIC.len()..MAGIC.len() + algorithm.len()].copy_from_slice(algorithm);
+        header
+    }
+
+    fn hash_len() -> u64 {
+        hash_empty::<D>().len() as u64
+    }
+
+    fn offset(&self, node: Node) -> u64 {
+        HEADER_LEN + node.index() as u64 * Self::hash_len()
+    }
+
+    fn read(&self, node: Node) -> Result<Hash<D>, FileLogError> {
+        let mut bytes = vec![0; Self::hash_len() as usize];
+        read_exact_at(&self.file, &mut bytes, self.offset(node))?;
+        Ok(Hash::try_from(bytes).unwrap())
+    }
+
+    // Recomputes the ancestors completed by the last entry, which may not
+    // have been written if the entry was pushed just before a crash
+    fn repair(&self) -> Result<(), FileLogError> {
+        if self.length == 0 {
+            return Ok(());
+        }
+
+        let mut current = Node((self.length - 1) * 2);
+        let mut digest = self.read(current)?;
+        while current.side() == Side::Right {
+            let parent = current.parent();
+            digest = hash_branch::<D>(self.read(current.left_sibling())?, digest);
+            if self.read(parent)? != digest {
+                write_all_at(&self.file, digest.bytes(), self.offset(parent))?;
+            }
+            current = parent;
+        }
+
+        Ok(())
+    }
+
+    fn load_stack(&mut self) -> Result<(), FileLogError> {
+        self.stack = Node::broots_for_len(self.length)
+            .into_iter()
+            .map(|node| Ok((node, self.read(node)?)))
+            .collect::<Result<_, FileLogError>>()?;
+        Ok(())
+    }
+}
+
+impl<D, V> LogBuilder<D, V> for FileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    fn checkpoint(&self) -> Checkpoint<D> {
+        let root = self
+            .stack
+            .iter()
+            .rev()
+            .map(|(_n, hash)| hash.clone())
+            .reduce(|new, old| hash_branch::<D>(old, new))
+            .unwrap_or_else(hash_empty::<D>);
+
+        Checkpoint {
+            root,
+            length: self.length,
+        }
+    }
+
+    fn push(&mut self, entry: &V) -> Node {
+        self.try_push(entry).unwrap()
+    }
+}
+
+impl<D, V> LogData<D, V> for FileLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    fn hash_for(&self, node: Node) -> Option<Hash<D>> {
+        if !node.exists_at_length(self.length) {
+            return None;
+        }
+
+        self.read(node).ok()
+    }
+
+    fn has_hash(&self, node: Node) -> bool {
+        node.exists_at_length(self.length)
+    }
+}
+
+#[cfg(unix)]
+pub(crate) fn read_exact_at(file: &File, buf: &mut [u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::read_exact_at(file, buf, offset)
+}
+
+#[cfg(unix)]
+pub(crate) fn write_all_at(file: &File, buf: &[u8], offset: u64) -> io::Result<()> {
+    std::os::unix::fs::FileExt::write_all_at(file, buf, offset)
+}
+
+#[cfg(windows)]
+pub(crate) fn read_exact_at(file: &File, mut buf: &mut [u8], mut offset: u64) -> io::Result<()> {
+    while !buf.is_empty() {
+        match std::os::windows::fs::FileExt::seek_read(file, buf, offset)? {
+            0 => return Err(io::ErrorKind::UnexpectedEof.into()),
+            n => {
+                buf = &mut std::mem::take(&mut buf)[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub(crate) fn write_all_at(file: &File, mut buf: &[u8], mut offset: u64) -> io::Result<()> {
+    while !buf.is_empty() {
+        match std::os::windows::fs::FileExt::seek_write(file, buf, offset)? {
+            0 => return Err(io::ErrorKind::WriteZero.into()),
+            n => {
+                buf = &buf[n..];
+                offset += n as u64;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use warg_crypto::hash::{Sha256, Sha512};
+
+    use super::super::VecLog;
+    use super::*;
+
+    #[test]
+    fn test_file_log_matches_vec_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log");
+
+        let mut vec_log: VecLog<Sha256, u8> = VecLog::default();
+        let mut file_log: FileLog<Sha256, u8> = FileLog::open(&path).unwrap();
+        assert_eq!(file_log.checkpoint(), vec_log.checkpoint());
+
+        for i in 0..100 {
+            assert_eq!(file_log.push(&i), vec_log.push(&i));
+            assert_eq!(file_log.checkpoint(), vec_log.checkpoint());
+        }
+
+        for i in 0..100 {
+            let node = Node(i * 2);
+            let proof = vec_log.prove_inclusion(node, 100);
+            assert_eq!(
+                proof.evaluate_value(&file_log, &(i as u8)).unwrap(),
+                vec_log.checkpoint().root()
+            );
+        }
+
+        // Reopening the log reads only its balanced roots
+        file_log.sync().unwrap();
+        drop(file_log);
+        let mut file_log: FileLog<Sha256, u8> = FileLog::open(&path).unwrap();
+        assert_eq!(file_log.length(), 100);
+        assert_eq!(file_log.checkpoint(), vec_log.checkpoint());
+
+        for i in 100..150 {
+            file_log.push(&i);
+            vec_log.push(&i);
+        }
+        assert_eq!(file_log.checkpoint(), vec_log.checkpoint());
+        assert_eq!(
+            fs::read(&path).unwrap()[HEADER_LEN as usize..],
+            vec_log
+                .as_ref()
+                .iter()
+                .flat_map(|h| h.bytes().to_vec())
+                .collect::<Vec<_>>()[..]
+        );
+    }
+
+    #[test]
+    fn test_file_log_truncate() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log");
+
+        let mut file_log: FileLog<Sha256, u8> = FileLog::open(&path).unwrap();
+        let mut checkpoints = vec![file_log.checkpoint()];
+        for i in 0..40 {
+            file_log.push(&i);
+            checkpoints.push(file_log.checkpoint());
+        }
+
+        for (length, checkpoint) in checkpoints.iter().enumerate() {
+            assert_eq!(&file_log.checkpoint_at(length).unwrap(), checkpoint);
+        }
+        assert!(matches!(
+            file_log.checkpoint_at(41),
+            Err(FileLogError::BeyondLength {
+                length: 40,
+                requested: 41
+            })
+        ));
+
+        // Entries pushed after truncation replace the discarded entries
+        file_log.truncate(13).unwrap();
+        assert_eq!(file_log.checkpoint(), checkpoints[13]);
+        let mut vec_log: VecLog<Sha256, u8> = VecLog::default();
+        for i in 0..13 {
+            vec_log.push(&i);
+        }
+        for i in 100..120 {
+            file_log.push(&i);
+            vec_log.push(&i);
+            assert_eq!(file_log.checkpoint(), vec_log.checkpoint());
+        }
+
+        let proof = vec_log.prove_consistency(13, 33);
+        let (old_root, new_root) = proof.evaluate(&file_log).unwrap();
+        assert_eq!(old_root, checkpoints[13].root());
+        assert_eq!(new_root, vec_log.checkpoint().root());
+
+        file_log.truncate(0).unwrap();
+        assert!(file_log.is_empty());
+        assert_eq!(file_log.checkpoint(), checkpoints[0]);
+    }
+
+    #[test]
+    fn test_file_log_recovers_partial_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log");
+
+        let mut vec_log: VecLog<Sha256, u8> = VecLog::default();
+        let mut file_log: FileLog<Sha256, u8> = FileLog::open(&path).unwrap();
+        for i in 0..8 {
+            file_log.push(&i);
+            vec_log.push(&i);
+        }
+        let checkpoint = vec_log.checkpoint();
+        drop(file_log);
+
+        // A spacer and part of the next leaf hash were appended
+        let mut contents = fs::read(&path).unwrap();
+        contents.extend_from_slice(&[0; 48]);
+        fs::write(&path, &contents).unwrap();
+        let file_log: FileLog<Sha256, u8> = FileLog::open(&path).unwrap();
+        assert_eq!(file_log.checkpoint(), checkpoint);
+        drop(file_log);
+
+        // The last leaf was appended but its ancestors were not written
+        let root = Node(7);
+        let offset = HEADER_LEN as usize + root.index() * 32;
+        contents.truncate(HEADER_LEN as usize + 15 * 32);
+        contents[offset..offset + 32].copy_from_slice(&[0; 32]);
+        fs::write(&path, &contents).unwrap();
+        let file_log: FileLog<Sha256, u8> = FileLog::open(&path).unwrap();
+        assert_eq!(file_log.checkpoint(), checkpoint);
+        assert_eq!(file_log.hash_for(root), vec_log.hash_for(root));
+    }
+
+    #[test]
+    fn test_file_log_rejects_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("log");
+
+        let mut file_log: FileLog<Sha256, u8> = FileLog::open(&path).unwrap();
+        file_log.push(&0);
+        drop(file_log);
+
+        assert!(matches!(
+            FileLog::<Sha512, u8>::open(&path),
+            Err(FileLogError::AlgorithmMismatch(algorithm)) if algorithm == "sha256"
+        ));
+
+        fs::write(&path, b"not a log file with a header").unwrap();
+        assert!(matches!(
+            FileLog::<Sha256, u8>::open(&path),
+            Err(FileLogError::InvalidHeader)
+        ));
+    }
+}
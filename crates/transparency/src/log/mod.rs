@@ -11,12 +11,14 @@
 //! using binary in-order interval numbering as described in
 //! [Dat - Distributed Dataset Synchronization and Versioning][2].
 
+mod file_log;
 mod node;
 /// Logic for constructing and validating proofs
 mod proof;
 mod proof_bundle;
 mod sparse_data;
 mod stack_log;
+mod tiles;
 mod vec_log;
 
 use warg_crypto::{
@@ -24,6 +26,8 @@ use warg_crypto::{
     VisitBytes,
 };
 
+pub(crate) use file_log::{read_exact_at, write_all_at};
+pub use file_log::{FileLog, FileLogError};
 pub use node::{Node, Side};
 pub use proof::{
     ConsistencyProof, ConsistencyProofError, InclusionProof, InclusionProofError,
@@ -32,6 +36,7 @@ pub use proof::{
 pub use proof_bundle::ProofBundle;
 pub use proof_bundle::ProofBundle as LogProofBundle;
 pub use stack_log::StackLog;
+pub use tiles::{tiles_between, Tile, TileError, TiledLog, TILE_HEIGHT, TILE_WIDTH};
 pub use vec_log::VecLog;
 
 /// A [merkle tree][0] log data type based on [DAT][1].
@@ -0,0 +1,412 @@
+use alloc::{string::String, vec::Vec};
+use std::{collections::HashMap, fmt::Write, marker::PhantomData};
+
+use thiserror::Error;
+use warg_crypto::{
+    hash::{Hash, SupportedDigest},
+    VisitBytes,
+};
+
+use super::{hash_branch, hash_empty, LogData, Node};
+
+/// The height of the subtree covered by a full tile.
+pub const TILE_HEIGHT: u32 = 8;
+
+/// The number of hashes in a full tile.
+pub const TILE_WIDTH: usize = 1 << TILE_HEIGHT;
+
+/// A tile of log hashes in the [C2SP tlog-tiles][0] layout.
+///
+/// A tile at level `L` holds the hashes of up to 256 consecutive complete
+/// subtrees of height `8 * L`; level 0 tiles hold leaf hashes. A tile with
+/// fewer than 256 hashes is a partial tile at the right edge of the log.
+///
+/// [0]: https://c2sp.org/tlog-tiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Tile {
+    level: u8,
+    index: usize,
+    width: usize,
+}
+
+impl Tile {
+    /// Creates a new tile.
+    ///
+    /// Panics if `width` is not between 1 and [`TILE_WIDTH`].
+    pub fn new(level: u8, index: usize, width: usize) -> Self {
+        assert!(
+            (1..=TILE_WIDTH).contains(&width),
+            "tile width must be between 1 and {TILE_WIDTH}"
+        );
+        Self {
+            level,
+            index,
+            width,
+        }
+    }
+
+    /// The level of the tile.
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// The index of the tile within its level.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The number of hashes in the tile.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Determines if the tile has fewer than [`TILE_WIDTH`] hashes.
+    pub fn is_partial(&self) -> bool {
+        self.width < TILE_WIDTH
+    }
+
+    /// The path of the tile, e.g. `tile/0/x001/234` or `tile/1/005.p/7`.
+    pub fn path(&self) -> String {
+        self.path_with(&self.level.to_string())
+    }
+
+    /// The path of the entry bundle matching a level 0 tile, e.g.
+    /// `tile/entries/x001/234`.
+    ///
+    /// Returns `None` if the tile is not a level 0 tile.
+    pub fn entries_path(&self) -> Option<String> {
+        if self.level != 0 {
+            return None;
+        }
+
+        Some(self.path_with("entries"))
+    }
+
+    fn path_with(&self, level: &str) -> String {
+        let mut path = format!("tile/{level}/");
+
+        // The index is split into groups of three digits, all but the last
+        // of which are prefixed with `x`
+        let digits = self.index.to_string();
+        let padding = (3 - digits.len() % 3) % 3;
+        let digits = format!("{zeros}{digits}", zeros = "0".repeat(padding));
+        let groups = digits.len() / 3;
+        for (i, group) in digits.as_bytes().chunks(3).enumerate() {
+            if i + 1 < groups {
+                path.push('x');
+            }
+            path.push_str(std::str::from_utf8(group).unwrap());
+            if i + 1 < groups {
+                path.push('/');
+            }
+        }
+
+        if self.is_partial() {
+            write!(path, ".p/{width}", width = self.width).unwrap();
+        }
+
+        path
+    }
+
+    /// The log nodes whose hashes make up the tile.
+    pub fn nodes(&self) -> impl Iterator<Item = Node> {
+        let height = TILE_HEIGHT * u32::from(self.level);
+        let first = self.index * TILE_WIDTH;
+        (first..first + self.width).map(move |k| Node((k << (height + 1)) + (1 << height) - 1))
+    }
+
+    /// Reads the contents of the tile from the given log data.
+    ///
+    /// Returns `None` if a hash of the tile is not known.
+    pub fn read<D, V>(&self, log: &impl LogData<D, V>) -> Option<Vec<u8>>
+    where
+        D: SupportedDigest,
+        V: VisitBytes,
+    {
+        let mut data = Vec::new();
+        for node in self.nodes() {
+            data.extend_from_slice(log.hash_for(node)?.bytes());
+        }
+        Some(data)
+    }
+}
+
+/// Gets the tiles that are added or changed when a log grows from
+/// `old_length` to `new_length`.
+///
+/// Partial tiles are replaced by wider tiles as the log grows, so every tile
+/// returned is either new or wider than the tile it replaces.
+pub fn tiles_between(old_length: usize, new_length: usize) -> Vec<Tile> {
+    let mut tiles = Vec::new();
+    let mut level = 0u8;
+    loop {
+        let shift = TILE_HEIGHT * u32::from(level);
+        let old_count = old_length.checked_shr(shift).unwrap_or(0);
+        let new_count = new_length.checked_shr(shift).unwrap_or(0);
+        if new_count == 0 {
+            break;
+        }
+
+        if new_count > old_count {
+            for index in old_count / TILE_WIDTH..=(new_count - 1) / TILE_WIDTH {
+                let width = (new_count - index * TILE_WIDTH).min(TILE_WIDTH);
+                tiles.push(Tile::new(level, index, width));
+            }
+        }
+
+        level += 1;
+    }
+
+    tiles
+}
+
+/// Represents an error reading tiles into a [`TiledLog`].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TileError {
+    /// The tile contents are not the expected size.
+    #[error("expected tile contents of {expected} bytes but found {found} bytes")]
+    IncorrectSize {
+        /// The expected size in bytes.
+        expected: usize,
+        /// The size in bytes of the given contents.
+        found: usize,
+    },
+    /// The tile covers hashes beyond the length of the log.
+    #[error("tile `{0}` is beyond the length of the log")]
+    BeyondLength(String),
+}
+
+/// Log data read from tiles.
+///
+/// Hashes of nodes that are not stored in a tile are computed from the
+/// hashes of their descendants, so a [`TiledLog`] can be used to evaluate
+/// inclusion and consistency proofs of a log served as tiles.
+pub struct TiledLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    length: usize,
+    tiles: HashMap<(u8, usize), Vec<Hash<D>>>,
+    _value: PhantomData<V>,
+}
+
+impl<D, V> TiledLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    /// Creates an empty tiled log of the given length.
+    pub fn new(length: usize) -> Self {
+        Self {
+            length,
+            tiles: HashMap::new(),
+            _value: PhantomData,
+        }
+    }
+
+    /// The length of the log.
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    /// Inserts the contents of a tile.
+    ///
+    /// A tile narrower than one already inserted at the same position is
+    /// ignored.
+    pub fn insert(&mut self, tile: &Tile, data: &[u8]) -> Result<(), TileError> {
+        let hash_len = hash_empty::<D>().len();
+        let expected = tile.width * hash_len;
+        if data.len() != expected {
+            return Err(TileError::IncorrectSize {
+                expected,
+                found: data.len(),
+            });
+        }
+
+        let shift = TILE_HEIGHT * u32::from(tile.level);
+        let count = self.length.checked_shr(shift).unwrap_or(0);
+        if tile.index * TILE_WIDTH + tile.width > count {
+            return Err(TileError::BeyondLength(tile.path()));
+        }
+
+        let hashes = data
+            .chunks(hash_len)
+            .map(|bytes| Hash::try_from(bytes.to_vec()).unwrap())
+            .collect::<Vec<_>>();
+
+        let entry = self.tiles.entry((tile.level, tile.index)).or_default();
+        if hashes.len() > entry.len() {
+            *entry = hashes;
+        }
+
+        Ok(())
+    }
+
+    /// The root hash of the log.
+    ///
+    /// Returns `None` if a tile needed to compute the root is missing.
+    pub fn root(&self) -> Option<Hash<D>> {
+        let mut root = None;
+        for node in Node::broots_for_len(self.length).into_iter().rev() {
+            let hash = self.hash_for(node)?;
+            root = Some(match root {
+                Some(right) => hash_branch::<D>(hash, right),
+                None => hash,
+            });
+        }
+
+        Some(root.unwrap_or_else(hash_empty::<D>))
+    }
+}
+
+impl<D, V> LogData<D, V> for TiledLog<D, V>
+where
+    D: SupportedDigest,
+    V: VisitBytes,
+{
+    fn hash_for(&self, node: Node) -> Option<Hash<D>> {
+        if !node.exists_at_length(self.length) {
+            return None;
+        }
+
+        let height = node.height();
+        if height % TILE_HEIGHT == 0 {
+            let index = node.index() >> (height + 1);
+            let level = (height / TILE_HEIGHT) as u8;
+            return self
+                .tiles
+                .get(&(level, index / TILE_WIDTH))?
+                .get(index % TILE_WIDTH)
+                .cloned();
+        }
+
+        let (left, right) = node.children();
+        Some(hash_branch::<D>(
+            self.hash_for(left)?,
+            self.hash_for(right)?,
+        ))
+    }
+
+    fn has_hash(&self, node: Node) -> bool {
+        self.hash_for(node).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use warg_crypto::hash::Sha256;
+
+    use super::*;
+    use crate::log::{LogBuilder, VecLog};
+
+    #[test]
+    fn test_tile_paths() {
+        assert_eq!(Tile::new(0, 0, 256).path(), "tile/0/000");
+        assert_eq!(Tile::new(0, 5, 7).path(), "tile/0/005.p/7");
+        assert_eq!(Tile::new(1, 1234, 256).path(), "tile/1/x001/234");
+        assert_eq!(Tile::new(2, 1234067, 256).path(), "tile/2/x001/x234/067");
+        assert_eq!(
+            Tile::new(0, 1234067, 12).entries_path().unwrap(),
+            "tile/entries/x001/x234/067.p/12"
+        );
+        assert_eq!(Tile::new(1, 0, 1).entries_path(), None);
+    }
+
+    #[test]
+    fn test_tiles_between() {
+        assert_eq!(tiles_between(0, 0), vec![]);
+        assert_eq!(tiles_between(0, 10), vec![Tile::new(0, 0, 10)]);
+        assert_eq!(tiles_between(10, 10), vec![]);
+        assert_eq!(
+            tiles_between(10, 300),
+            vec![
+                Tile::new(0, 0, 256),
+                Tile::new(0, 1, 44),
+                Tile::new(1, 0, 1)
+            ]
+        );
+        assert_eq!(tiles_between(300, 400), vec![Tile::new(0, 1, 144)]);
+        assert_eq!(
+            tiles_between(400, 512),
+            vec![Tile::new(0, 1, 256), Tile::new(1, 0, 2)]
+        );
+        assert_eq!(
+            tiles_between(65535, 65536),
+            vec![
+                Tile::new(0, 255, 256),
+                Tile::new(1, 0, 256),
+                Tile::new(2, 0, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tiled_log() {
+        let mut log: VecLog<Sha256, u8> = VecLog::default();
+        let mut tiled: TiledLog<Sha256, u8> = TiledLog::new(0);
+        assert_eq!(tiled.root().unwrap(), log.checkpoint().root());
+
+        let mut length = 0;
+        for new_length in [1, 100, 256, 257, 600, 1000] {
+            for i in length..new_length {
+                log.push(&((i % 256) as u8));
+            }
+
+            let mut grown = TiledLog::new(new_length);
+            for (&(level, index), hashes) in tiled.tiles.iter() {
+                let data = hashes
+                    .iter()
+                    .flat_map(|h| h.bytes().to_vec())
+                    .collect::<Vec<_>>();
+                grown
+                    .insert(&Tile::new(level, index, hashes.len()), &data)
+                    .unwrap();
+            }
+            for tile in tiles_between(length, new_length) {
+                grown.insert(&tile, &tile.read(&log).unwrap()).unwrap();
+            }
+
+            assert_eq!(grown.root().unwrap(), log.checkpoint().root());
+
+            // Proofs can be evaluated against the tiles
+            let root = log.checkpoint().root();
+            for leaf in [0, new_length / 2, new_length - 1] {
+                let proof = log.prove_inclusion(Node(leaf * 2), new_length);
+                let found = proof.evaluate_value(&grown, &((leaf % 256) as u8)).unwrap();
+                assert_eq!(found, root);
+            }
+            if length > 0 {
+                let (old_root, new_root) = log
+                    .prove_consistency(length, new_length)
+                    .evaluate(&grown)
+                    .unwrap();
+                assert_eq!(old_root, tiled.root().unwrap());
+                assert_eq!(new_root, root);
+            }
+
+            tiled = grown;
+            length = new_length;
+        }
+    }
+
+    #[test]
+    fn test_tiled_log_rejects_invalid_tiles() {
+        let mut tiled: TiledLog<Sha256, u8> = TiledLog::new(10);
+        assert_eq!(
+            tiled.insert(&Tile::new(0, 0, 10), &[0; 31]),
+            Err(TileError::IncorrectSize {
+                expected: 320,
+                found: 31
+            })
+        );
+        assert_eq!(
+            tiled.insert(&Tile::new(0, 0, 11), &[0; 352]),
+            Err(TileError::BeyondLength("tile/0/000.p/11".into()))
+        );
+        assert_eq!(
+            tiled.insert(&Tile::new(1, 0, 1), &[0; 32]),
+            Err(TileError::BeyondLength("tile/1/000.p/1".into()))
+        );
+    }
+}
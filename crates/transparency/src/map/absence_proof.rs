@@ -0,0 +1,113 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use thiserror::Error;
+use warg_crypto::{
+    hash::{Hash, SupportedDigest},
+    VisitBytes,
+};
+
+use super::{
+    map::hash_branch,
+    path::{tree_height, Path, Side},
+    singleton::Singleton,
+};
+
+/// An error evaluating an [`AbsenceProof`].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AbsenceProofError {
+    /// The proof has more peers than the height of the map.
+    #[error("absence proof has more peers than the height of the map")]
+    TooManyPeers,
+    /// The leaf in the proof is the leaf of the key being proven absent.
+    #[error("absence proof contains the leaf of the key")]
+    KeyPresent,
+    /// The leaf in the proof is not on the path of the key.
+    #[error("absence proof leaf is not on the path of the key")]
+    LeafNotOnPath,
+}
+
+/// A proof that a key is not present in a map
+///
+/// The path of an absent key ends either at an empty subtree or at a
+/// subtree containing only the leaf of another key. The proof contains the
+/// peers of the nodes on the path down to that subtree and, if the subtree
+/// is not empty, the key hash and value hash of its leaf.
+///
+/// Like an inclusion [`Proof`](super::Proof), sparse peers may be
+/// represented by `None`.
+pub struct AbsenceProof<D, K>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+{
+    key: PhantomData<K>,
+    /// Sibling node hashes on the path of the key, from the bottom up
+    pub peers: Vec<Option<Hash<D>>>,
+    /// The key hash and value hash of the only leaf in the subtree where
+    /// the path of the key ends, if any
+    pub leaf: Option<(Hash<D>, Hash<D>)>,
+}
+
+impl<D, K> AbsenceProof<D, K>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+{
+    pub(crate) fn new(peers: Vec<Option<Hash<D>>>, leaf: Option<(Hash<D>, Hash<D>)>) -> Self {
+        Self {
+            key: PhantomData,
+            peers,
+            leaf,
+        }
+    }
+
+    pub(crate) fn push(&mut self, peer: Option<Hash<D>>) {
+        self.peers.push(peer);
+    }
+
+    /// Computes the root obtained by evaluating this absence proof for the
+    /// given key
+    pub fn evaluate(&self, key: &K) -> Result<Hash<D>, AbsenceProofError> {
+        let height = tree_height::<D>();
+        let depth = self.peers.len();
+        if depth > height {
+            return Err(AbsenceProofError::TooManyPeers);
+        }
+
+        let key_hash = Hash::<D>::of(key);
+        let path = Path::new(&key_hash);
+
+        // Calculate the hash of the subtree where the path ends
+        let mut hash = match &self.leaf {
+            None => D::empty_tree_hash(height - depth).clone(),
+            Some((leaf_key, value)) => {
+                if leaf_key == &key_hash {
+                    return Err(AbsenceProofError::KeyPresent);
+                }
+
+                let leaf_path = Path::new(leaf_key);
+                if (0..depth).any(|i| leaf_path.get(i) != path.get(i)) {
+                    return Err(AbsenceProofError::LeafNotOnPath);
+                }
+
+                Singleton::new(leaf_key.clone(), value.clone(), height - depth).hash()
+            }
+        };
+
+        // Loop over each peer from the bottom of the path up.
+        for (i, peer) in self.peers.iter().enumerate() {
+            let at = depth - i - 1;
+            let peer = match peer {
+                Some(peer) => peer.clone(),
+                None => D::empty_tree_hash(height - at - 1).clone(),
+            };
+            hash = match path.get(at) {
+                Side::Left => hash_branch(&hash, &peer),
+                Side::Right => hash_branch(&peer, &hash),
+            };
+        }
+
+        Ok(hash)
+    }
+}
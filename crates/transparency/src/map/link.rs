@@ -18,6 +18,13 @@ impl<D: SupportedDigest> Link<D> {
         }
     }
 
+    pub fn with_hash(hash: Hash<D>, node: Node<D>) -> Self {
+        Self {
+            hash,
+            node: Arc::new(node),
+        }
+    }
+
     pub fn hash(&self) -> &Hash<D> {
         &self.hash
     }
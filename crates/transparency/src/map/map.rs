@@ -1,13 +1,16 @@
+use core::convert::Infallible;
 use core::fmt::{Debug, Formatter};
 use std::marker::PhantomData;
 
 use warg_crypto::hash::{Hash, SupportedDigest};
 use warg_crypto::VisitBytes;
 
+use super::absence_proof::AbsenceProof;
 use super::link::Link;
 use super::node::Node;
-use super::path::Path;
+use super::path::{tree_height, Path};
 use super::proof::Proof;
+use super::transition_proof::{prove_batch, sorted_keys, TransitionProof};
 
 /// Immutable Map w/ Inclusion Proofs
 ///
@@ -86,6 +89,10 @@ where
             _value: PhantomData,
         }
     }
+
+    pub(crate) fn link(&self) -> &Link<D> {
+        &self.link
+    }
 }
 
 impl<D, K, V> Clone for Map<D, K, V>
@@ -112,7 +119,7 @@ where
 {
     fn default() -> Self {
         Self {
-            link: Link::new(Node::Empty(256)),
+            link: Link::new(Node::Empty(tree_height::<D>())),
             len: 0,
             _key: PhantomData,
             _value: PhantomData,
@@ -190,6 +197,28 @@ where {
         self.link.node().prove(Path::new(&Hash::of(key)))
     }
 
+    /// Gets a proof that a given key is absent from this map.
+    ///
+    /// Returns `None` if the key is present.
+    pub fn prove_absence(&self, key: K) -> Option<AbsenceProof<D, K>> {
+        self.link.node().prove_absence(Path::new(&Hash::of(key)))
+    }
+
+    /// Gets a proof of the transition of the given keys from this map to
+    /// a new map.
+    pub fn prove_transition(
+        &self,
+        new: &Self,
+        keys: impl IntoIterator<Item = K>,
+    ) -> TransitionProof<D, K, V> {
+        let keys = sorted_keys(keys);
+        let prove = |map: &Self| {
+            prove_batch(map.link.node(), &keys, &|node: &&Node<D>| Ok(node.shape()))
+                .unwrap_or_else(|e: Infallible| match e {})
+        };
+        TransitionProof::new(prove(self), prove(new))
+    }
+
     /// Insert a value into the map, creating a new map.
     ///
     /// This replaces any existing items with the same key.
@@ -213,6 +242,31 @@ where {
 
         here
     }
+
+    /// Inserts all key/value pairs into the map at once, creating a new map.
+    ///
+    /// Unlike [`Map::extend`], the pairs are sorted by the paths of their keys
+    /// so that pairs sharing a path prefix are grouped and every node on
+    /// their paths is hashed only once. Subtrees with many pairs are hashed
+    /// in parallel.
+    ///
+    /// This replaces any existing items with the same key; if a key is given
+    /// more than once, the last value is used.
+    pub fn extend_sorted(&self, iter: impl IntoIterator<Item = (K, V)>) -> Self {
+        let mut updates = iter
+            .into_iter()
+            .map(|(key, val)| (Hash::<D>::of(&key), hash_leaf(val)))
+            .collect::<Vec<_>>();
+
+        // Sort stably so that the last value of a key is kept
+        updates.sort_by(|a, b| a.0.bytes().cmp(b.0.bytes()));
+        updates.reverse();
+        updates.dedup_by(|a, b| a.0 == b.0);
+        updates.reverse();
+
+        let (node, new) = self.link.node().extend(0, &updates);
+        Self::new(Link::new(node), self.len + new)
+    }
 }
 
 // If updating this function, also update `hash_empty` in crypto crate
@@ -245,10 +299,16 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
-    use warg_crypto::hash::Sha256;
+    use warg_crypto::hash::{Blake3, Sha256, Sha512};
     #[test]
     fn empty_map() {
         let map: Map<Sha256, &str, &str> = Map::default();
         assert_eq!(Sha256::empty_tree_hash(256), map.link.hash());
+
+        let map: Map<Sha512, &str, &str> = Map::default();
+        assert_eq!(Sha512::empty_tree_hash(512), map.link.hash());
+
+        let map: Map<Blake3, &str, &str> = Map::default();
+        assert_eq!(Blake3::empty_tree_hash(256), map.link.hash());
     }
 }
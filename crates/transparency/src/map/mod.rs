@@ -10,6 +10,7 @@
 
 #![allow(clippy::module_inception)]
 
+mod absence_proof;
 mod fork;
 mod link;
 mod map;
@@ -18,19 +19,26 @@ mod path;
 mod proof;
 mod proof_bundle;
 mod singleton;
+mod store;
+mod transition_proof;
 
+pub use absence_proof::{AbsenceProof, AbsenceProofError};
 pub use map::Map;
 pub use proof::Proof;
 pub use proof_bundle::ProofBundle as MapProofBundle;
+pub use store::{MapStore, MapStoreError};
+pub use transition_proof::{BatchPeer, TransitionProof, TransitionProofError};
 
 #[cfg(test)]
 mod test {
     use warg_crypto::{
-        hash::{Sha256, SupportedDigest},
+        hash::{Blake3, Hash, Sha256, Sha512, SupportedDigest},
         VisitBytes,
     };
 
-    use super::Map;
+    use super::{
+        path::Path, AbsenceProof, AbsenceProofError, Map, MapProofBundle, TransitionProofError,
+    };
 
     #[test]
     fn insert() {
@@ -63,6 +71,27 @@ mod test {
         assert_eq!(fourth.len(), 2);
     }
 
+    #[test]
+    fn len_after_singletons_diverge() {
+        len_after_singletons_diverge_with::<Sha256>();
+        len_after_singletons_diverge_with::<Blake3>();
+    }
+
+    fn len_after_singletons_diverge_with<D: SupportedDigest>() {
+        // Inserting keys one at a time splits existing singletons into forks
+        // wherever the paths of two keys diverge below the root
+        let mut map = Map::<D, (u8, u8), (u8, u8)>::default();
+        for n in 0..64u8 {
+            map = map.insert((0, n), (n, 0));
+            assert_eq!(map.len(), usize::from(n) + 1);
+        }
+
+        // Replacing the value of a key does not change the length
+        let map = map.insert((0, 7), (1, 1));
+        assert_eq!(map.len(), 64);
+        assert_eq!(map, map.extend([((0, 7), (1, 1))]));
+    }
+
     #[test]
     fn is_empty() {
         let first = Map::<Sha256, &'static str, &'static str>::default();
@@ -92,6 +121,55 @@ mod test {
         assert_eq!(extended, third);
     }
 
+    #[test]
+    fn extend_sorted() {
+        extend_sorted_with::<Sha256>();
+        extend_sorted_with::<Blake3>();
+    }
+
+    fn extend_sorted_with<D: SupportedDigest>() {
+        fn pair(n: u16) -> (u8, u8) {
+            ((n >> 8) as u8, n as u8)
+        }
+
+        let first = Map::<D, (u8, u8), (u8, u8)>::default();
+        let batches: [Vec<(u16, u16)>; 6] = [
+            vec![(1, 1)],
+            vec![(2, 2), (1, 3)],
+            (0..200).map(|k| (k, k)).collect(),
+            (100..300).map(|k| (k, k + 1)).collect(),
+            vec![(7, 1), (7, 2), (1200, 0), (7, 3)],
+            vec![],
+        ];
+
+        let mut expected = first.clone();
+        let mut map = first;
+        for batch in batches {
+            let batch = batch.into_iter().map(|(k, v)| (pair(k), pair(v)));
+            expected = expected.extend(batch.clone());
+            map = map.extend_sorted(batch);
+            assert_eq!(map, expected);
+            assert_eq!(map.len(), expected.len());
+
+            // The maps have the same nodes on the paths of present and absent keys
+            for key in (0..1300).step_by(29).map(pair) {
+                assert_eq!(
+                    map.prove(key).map(|p| p.peers),
+                    expected.prove(key).map(|p| p.peers)
+                );
+                assert_eq!(
+                    map.prove_absence(key).map(|p| (p.peers, p.leaf)),
+                    expected.prove_absence(key).map(|p| (p.peers, p.leaf))
+                );
+            }
+        }
+
+        assert_eq!(map.len(), 301);
+        assert_eq!(expected.len(), 301);
+        let proof = map.prove(pair(7)).unwrap();
+        assert_eq!(map.root(), &proof.evaluate(&pair(7), &pair(3)));
+    }
+
     #[test]
     fn replace() {
         let first = Map::<Sha256, &'static str, &'static str>::default();
@@ -109,6 +187,12 @@ mod test {
 
     #[test]
     fn prove() {
+        prove_with::<Sha256>();
+        prove_with::<Sha512>();
+        prove_with::<Blake3>();
+    }
+
+    fn prove_with<D: SupportedDigest>() {
         fn check<D: SupportedDigest, K: VisitBytes + PartialEq + Clone, V: VisitBytes + Clone>(
             tree: &Map<D, K, V>,
             key: K,
@@ -118,7 +202,7 @@ mod test {
             assert_eq!(tree.root().clone(), proof.evaluate(&key, &value));
         }
 
-        let first = Map::<Sha256, &'static str, &'static str>::default();
+        let first = Map::<D, &'static str, &'static str>::default();
         assert!(first.prove("foo").is_none());
         assert!(first.prove("baz").is_none());
         assert!(first.prove("qux").is_none());
@@ -135,4 +219,156 @@ mod test {
         let fourth = third.insert("foo", "qux");
         check(&fourth, "foo", "qux");
     }
+
+    #[test]
+    fn prove_absence() {
+        prove_absence_with::<Sha256>();
+        prove_absence_with::<Sha512>();
+        prove_absence_with::<Blake3>();
+    }
+
+    fn prove_absence_with<D: SupportedDigest>() {
+        fn check<D: SupportedDigest, K: VisitBytes + Clone, V: VisitBytes + Clone>(
+            tree: &Map<D, K, V>,
+            key: K,
+        ) {
+            let proof = tree.prove_absence(key.clone()).unwrap();
+            assert_eq!(tree.root(), &proof.evaluate(&key).unwrap());
+        }
+
+        let first = Map::<D, &'static str, &'static str>::default();
+        check(&first, "foo");
+        check(&first, "bar");
+
+        let second = first.insert("foo", "bar");
+        assert!(second.prove_absence("foo").is_none());
+        check(&second, "bar");
+        check(&second, "baz");
+
+        let third = second.extend([("bar", "bat"), ("baz", "qux"), ("a", "b"), ("c", "d")]);
+        assert!(third.prove_absence("foo").is_none());
+        assert!(third.prove_absence("baz").is_none());
+        for key in ["qux", "x", "y", "z", "foobar"] {
+            check(&third, key);
+        }
+
+        // Absence proofs survive a round trip through a proof bundle
+        let proofs = vec![third.prove_absence("qux").unwrap()];
+        let bytes = MapProofBundle::<D, &str, &str>::bundle_absences(proofs).encode();
+        let proofs = MapProofBundle::<D, &str, &str>::decode(&bytes)
+            .unwrap()
+            .unbundle_absences();
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(third.root(), &proofs[0].evaluate(&"qux").unwrap());
+    }
+
+    #[test]
+    fn prove_transition() {
+        prove_transition_with::<Sha256>();
+        prove_transition_with::<Sha512>();
+        prove_transition_with::<Blake3>();
+    }
+
+    fn prove_transition_with<D: SupportedDigest>() {
+        let value = |key: u8, map: &Map<D, u8, u8>| map.prove(key).map(|_| key / 3 + 1);
+
+        let old = Map::<D, u8, u8>::default().extend((0..30).map(|k| (k, k / 3 + 1)));
+        let new = old.extend((20..50).map(|k| (k, k / 3 + 1)));
+        let new = new.insert(5, 99);
+
+        for keys in [
+            vec![0],
+            vec![5],
+            vec![25],
+            vec![60],
+            vec![5, 25, 60],
+            (0..64).collect(),
+            (10..40).step_by(7).collect(),
+        ] {
+            let proof = old.prove_transition(&new, keys.iter().copied());
+            let transitions = keys
+                .iter()
+                .map(|&k| {
+                    let new = if k == 5 { Some(99) } else { value(k, &new) };
+                    (k, value(k, &old), new)
+                })
+                .collect::<Vec<_>>();
+            let (old_root, new_root) = proof.evaluate(&transitions).unwrap();
+            assert_eq!(&old_root, old.root(), "old root for keys {keys:?}");
+            assert_eq!(&new_root, new.root(), "new root for keys {keys:?}");
+
+            // Claiming a different value yields a different root
+            let mut transitions = transitions;
+            transitions[0].2 = Some(200);
+            let (_, new_root) = proof.evaluate(&transitions).unwrap();
+            assert_ne!(&new_root, new.root());
+        }
+
+        // Transition proofs survive a round trip through a proof bundle
+        let proofs = vec![old.prove_transition(&new, [5, 25, 60])];
+        let bytes = MapProofBundle::<D, u8, u8>::bundle_transitions(proofs).encode();
+        let proofs = MapProofBundle::<D, u8, u8>::decode(&bytes)
+            .unwrap()
+            .unbundle_transitions();
+        assert_eq!(proofs.len(), 1);
+        let transitions = [
+            (5, Some(2), Some(99)),
+            (25, Some(9), Some(9)),
+            (60, None, None),
+        ];
+        let roots = proofs[0].evaluate(&transitions).unwrap();
+        assert_eq!((&roots.0, &roots.1), (old.root(), new.root()));
+
+        // An empty map transitions to an empty map
+        let empty = Map::<D, u8, u8>::default();
+        let proof = empty.prove_transition(&empty, [1, 2]);
+        let (old_root, new_root) = proof.evaluate(&[(1, None, None), (2, None, None)]).unwrap();
+        assert_eq!(&old_root, empty.root());
+        assert_eq!(&new_root, empty.root());
+    }
+
+    #[test]
+    fn transition_proof_rejects_malformed_input() {
+        let old = Map::<Sha256, u8, u8>::default().extend((0..10).map(|k| (k, k)));
+        let new = old.insert(3, 30);
+        let mut proof = old.prove_transition(&new, [3, 4]);
+
+        assert_eq!(proof.evaluate(&[]), Err(TransitionProofError::NoKeys));
+        assert_eq!(
+            proof.evaluate(&[(3, Some(3), Some(30)), (3, Some(3), Some(30))]),
+            Err(TransitionProofError::DuplicateKey)
+        );
+        assert_eq!(
+            proof.evaluate(&[(3, Some(3), Some(30))]),
+            Err(TransitionProofError::TooManyPeers)
+        );
+
+        proof.new.pop();
+        assert_eq!(
+            proof.evaluate(&[(3, Some(3), Some(30)), (4, Some(4), Some(4))]),
+            Err(TransitionProofError::TooFewPeers)
+        );
+    }
+
+    #[test]
+    fn absence_proof_rejects_present_key() {
+        let foo = Hash::<Sha256>::of("foo");
+        let value = Hash::<Sha256>::of("bar");
+
+        // A proof whose leaf is the key itself proves nothing
+        let proof =
+            AbsenceProof::<Sha256, &str>::new(Vec::new(), Some((foo.clone(), value.clone())));
+        assert_eq!(proof.evaluate(&"foo"), Err(AbsenceProofError::KeyPresent));
+
+        // A proof whose leaf is in a subtree the key isn't in proves nothing
+        let other = ["bar", "baz", "qux", "x", "y", "z"]
+            .into_iter()
+            .find(|k| Path::new(&Hash::<Sha256>::of(k)).get(0) != Path::new(&foo).get(0))
+            .unwrap();
+        let proof = AbsenceProof::<Sha256, &str>::new(vec![None], Some((foo, value)));
+        assert_eq!(
+            proof.evaluate(&other),
+            Err(AbsenceProofError::LeafNotOnPath)
+        );
+    }
 }
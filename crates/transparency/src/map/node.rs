@@ -5,11 +5,17 @@ use std::fmt::Debug;
 use warg_crypto::hash::{Hash, SupportedDigest};
 use warg_crypto::VisitBytes;
 
+use super::absence_proof::AbsenceProof;
 use super::fork::Fork;
 use super::link::Link;
-use super::path::{Path, Side};
+use super::path::{tree_height, Path, Side};
 use super::proof::Proof;
 use super::singleton::Singleton;
+use super::transition_proof::Shape;
+
+/// The number of updates on each side of a fork above which the sides are
+/// updated in parallel.
+const PARALLEL_UPDATES: usize = 64;
 
 #[derive(Debug)]
 pub enum Node<D: SupportedDigest> {
@@ -66,6 +72,121 @@ impl<D: SupportedDigest> Node<D> {
         }
     }
 
+    pub fn prove_absence<K: VisitBytes>(
+        &self,
+        mut path: Path<'_, D>,
+    ) -> Option<AbsenceProof<D, K>> {
+        match (path.next(), self) {
+            (_, Self::Empty(_)) => Some(AbsenceProof::new(Vec::new(), None)),
+            (_, Self::Singleton(singleton)) => {
+                if singleton.key() == path.hash() {
+                    None
+                } else {
+                    let leaf = (singleton.key.clone(), singleton.value.clone());
+                    Some(AbsenceProof::new(Vec::new(), Some(leaf)))
+                }
+            }
+            (Some(idx), Self::Fork(fork)) => {
+                let mut proof = fork[idx].as_ref().node().prove_absence(path)?;
+                let peer = fork[idx.opposite()].as_ref().hash();
+                proof.push(Some(peer.clone()));
+                Some(proof)
+            }
+
+            _ => None,
+        }
+    }
+
+    pub fn shape(&self) -> Shape<D, &Self> {
+        match self {
+            Self::Empty(_) => Shape::Empty,
+            Self::Leaf(_) => Shape::Leaf,
+            Self::Singleton(singleton) => {
+                Shape::Singleton(singleton.key.clone(), singleton.value.clone())
+            }
+            Self::Fork(fork) => Shape::Fork([Side::Left, Side::Right].map(|side| {
+                let link = fork[side].as_ref();
+                (link.hash().clone(), link.node())
+            })),
+        }
+    }
+
+    /// A recursive function for setting many values in the tree at once.
+    ///
+    /// The updates must be sorted by the paths of their keys and have no
+    /// duplicate keys. Subtrees with enough updates on both sides are
+    /// updated in parallel.
+    ///
+    /// Returns:
+    ///   * the new node that must replace the current node.
+    ///   * the number of new entries in the map.
+    pub fn extend(&self, depth: usize, updates: &[(Hash<D>, Hash<D>)]) -> (Self, usize) {
+        let height = tree_height::<D>();
+        match self {
+            _ if updates.is_empty() => (self.clone(), 0),
+            Node::Leaf(_) => (Node::Leaf(updates[0].1.clone()), 0),
+            Node::Empty(_) if depth == height => (Node::Leaf(updates[0].1.clone()), 1),
+            Node::Empty(_) if depth > 0 && updates.len() == 1 => {
+                let (key, value) = updates[0].clone();
+                (
+                    Node::Singleton(Singleton::new(key, value, height - depth)),
+                    1,
+                )
+            }
+            Node::Empty(_) => {
+                let empty = Node::Empty(height - depth - 1);
+                Self::extend_fork(depth, updates, [&empty, &empty], None)
+            }
+            Node::Singleton(singleton) => {
+                // Rebuild the subtree with the singleton's entry as an update
+                // unless it is replaced
+                let at = updates.partition_point(|(k, _)| k.bytes() < singleton.key.bytes());
+                let replaced = updates.get(at).map_or(false, |(k, _)| k == &singleton.key);
+                let mut merged = updates.to_vec();
+                if !replaced {
+                    merged.insert(at, (singleton.key.clone(), singleton.value.clone()));
+                }
+
+                // The singleton's entry is not new either way
+                let (node, new) = Node::Empty(height - depth).extend(depth, &merged);
+                (node, new - 1)
+            }
+            Node::Fork(fork) => Self::extend_fork(
+                depth,
+                updates,
+                [fork[Side::Left].node(), fork[Side::Right].node()],
+                Some(fork),
+            ),
+        }
+    }
+
+    fn extend_fork(
+        depth: usize,
+        updates: &[(Hash<D>, Hash<D>)],
+        [left, right]: [&Self; 2],
+        fork: Option<&Fork<D>>,
+    ) -> (Self, usize) {
+        let at = updates.partition_point(|(k, _)| Path::new(k).get(depth) == Side::Left);
+        let (lhs, rhs) = updates.split_at(at);
+        let ((left, left_new), (right, right_new)) =
+            if lhs.len() >= PARALLEL_UPDATES && rhs.len() >= PARALLEL_UPDATES {
+                rayon::join(
+                    || left.extend(depth + 1, lhs),
+                    || right.extend(depth + 1, rhs),
+                )
+            } else {
+                (left.extend(depth + 1, lhs), right.extend(depth + 1, rhs))
+            };
+
+        // Keep the existing links of sides without updates
+        let link = |side: Side, node: Self, updates: &[_]| match fork {
+            Some(fork) if updates.is_empty() => fork[side].clone(),
+            _ => Arc::new(Link::new(node)),
+        };
+        let fork = Fork::new(link(Side::Left, left, lhs), link(Side::Right, right, rhs));
+        (Node::Fork(fork), left_new + right_new)
+    }
+
     /// A recursive function for setting the value in the tree.
     ///
     /// Returns:
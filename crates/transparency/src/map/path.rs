@@ -1,4 +1,9 @@
-use warg_crypto::hash::{Hash, SupportedDigest};
+use warg_crypto::hash::{Digest, Hash, SupportedDigest};
+
+/// Gets the height of a sparse Merkle tree keyed by digests of `D`.
+pub(crate) fn tree_height<D: SupportedDigest>() -> usize {
+    <D as Digest>::output_size() * 8
+}
 
 pub struct Path<'a, D: SupportedDigest> {
     hash: &'a Hash<D>,
@@ -49,7 +54,7 @@ impl<'a, D: SupportedDigest> Path<'a, D> {
     }
 
     pub fn height(&self) -> usize {
-        256 - self.index
+        self.hash.bit_len() - self.index
     }
 }
 
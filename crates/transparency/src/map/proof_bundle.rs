@@ -7,9 +7,13 @@ use warg_crypto::{
 };
 use warg_protobuf::transparency as protobuf;
 
-use crate::map::proof::Proof;
+use crate::map::{
+    absence_proof::AbsenceProof,
+    proof::Proof,
+    transition_proof::{BatchPeer, TransitionProof},
+};
 
-/// A collection of inclusion proof info
+/// A collection of inclusion, absence, and transition proof info
 pub struct ProofBundle<D, K, V>
 where
     D: SupportedDigest,
@@ -17,6 +21,8 @@ where
     V: VisitBytes,
 {
     proofs: Vec<Proof<D, K, V>>,
+    absence_proofs: Vec<AbsenceProof<D, K>>,
+    transition_proofs: Vec<TransitionProof<D, K, V>>,
 }
 
 impl<D, K, V> ProofBundle<D, K, V>
@@ -27,7 +33,29 @@ where
 {
     /// Bundles inclusion proofs together
     pub fn bundle(proofs: Vec<Proof<D, K, V>>) -> Self {
-        ProofBundle { proofs }
+        ProofBundle {
+            proofs,
+            absence_proofs: Vec::new(),
+            transition_proofs: Vec::new(),
+        }
+    }
+
+    /// Bundles absence proofs together
+    pub fn bundle_absences(absence_proofs: Vec<AbsenceProof<D, K>>) -> Self {
+        ProofBundle {
+            proofs: Vec::new(),
+            absence_proofs,
+            transition_proofs: Vec::new(),
+        }
+    }
+
+    /// Bundles transition proofs together
+    pub fn bundle_transitions(transition_proofs: Vec<TransitionProof<D, K, V>>) -> Self {
+        ProofBundle {
+            proofs: Vec::new(),
+            absence_proofs: Vec::new(),
+            transition_proofs,
+        }
     }
 
     /// Splits a bundle into its constituent inclusion proofs
@@ -35,6 +63,16 @@ where
         self.proofs
     }
 
+    /// Splits a bundle into its constituent absence proofs
+    pub fn unbundle_absences(self) -> Vec<AbsenceProof<D, K>> {
+        self.absence_proofs
+    }
+
+    /// Splits a bundle into its constituent transition proofs
+    pub fn unbundle_transitions(self) -> Vec<TransitionProof<D, K, V>> {
+        self.transition_proofs
+    }
+
     /// Turn a bundle into bytes using protobuf
     pub fn encode(self) -> Vec<u8> {
         let proto: protobuf::MapProofBundle = self.into();
@@ -57,7 +95,21 @@ where
 {
     fn from(value: ProofBundle<D, K, V>) -> Self {
         let proofs = value.proofs.into_iter().map(|proof| proof.into()).collect();
-        protobuf::MapProofBundle { proofs }
+        let absence_proofs = value
+            .absence_proofs
+            .into_iter()
+            .map(|proof| proof.into())
+            .collect();
+        let transition_proofs = value
+            .transition_proofs
+            .into_iter()
+            .map(|proof| proof.into())
+            .collect();
+        protobuf::MapProofBundle {
+            proofs,
+            absence_proofs,
+            transition_proofs,
+        }
     }
 }
 
@@ -88,7 +140,19 @@ where
         for entry in value.proofs {
             proofs.push(entry.try_into()?);
         }
-        let bundle = ProofBundle { proofs };
+        let mut absence_proofs = Vec::new();
+        for entry in value.absence_proofs {
+            absence_proofs.push(entry.try_into()?);
+        }
+        let mut transition_proofs = Vec::new();
+        for entry in value.transition_proofs {
+            transition_proofs.push(entry.try_into()?);
+        }
+        let bundle = ProofBundle {
+            proofs,
+            absence_proofs,
+            transition_proofs,
+        };
         Ok(bundle)
     }
 }
@@ -108,3 +172,100 @@ where
         Ok(proof)
     }
 }
+
+impl<D, K> From<AbsenceProof<D, K>> for protobuf::MapAbsenceProof
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+{
+    fn from(value: AbsenceProof<D, K>) -> Self {
+        protobuf::MapAbsenceProof {
+            hashes: value.peers.into_iter().map(|h| h.into()).collect(),
+            leaf: value.leaf.map(|(key, value)| protobuf::MapSubtreeLeaf {
+                key: key.bytes().to_vec(),
+                value: value.bytes().to_vec(),
+            }),
+        }
+    }
+}
+
+impl<D, K> TryFrom<protobuf::MapAbsenceProof> for AbsenceProof<D, K>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+{
+    type Error = Error;
+
+    fn try_from(value: protobuf::MapAbsenceProof) -> Result<Self, Self::Error> {
+        let peers: Result<Vec<Option<Hash<D>>>, Error> =
+            value.hashes.into_iter().map(|h| h.try_into()).collect();
+        let leaf = match value.leaf {
+            Some(leaf) => Some((leaf.key.try_into()?, leaf.value.try_into()?)),
+            None => None,
+        };
+        let proof = AbsenceProof::new(peers?, leaf);
+        Ok(proof)
+    }
+}
+
+impl<D, K, V> From<TransitionProof<D, K, V>> for protobuf::MapTransitionProof
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+    V: VisitBytes,
+{
+    fn from(value: TransitionProof<D, K, V>) -> Self {
+        protobuf::MapTransitionProof {
+            old: value.old.into_iter().map(|peer| peer.into()).collect(),
+            new: value.new.into_iter().map(|peer| peer.into()).collect(),
+        }
+    }
+}
+
+impl<D: SupportedDigest> From<BatchPeer<D>> for protobuf::MapBatchPeer {
+    fn from(value: BatchPeer<D>) -> Self {
+        match value {
+            BatchPeer::Peer(peer) => protobuf::MapBatchPeer {
+                hash: peer.map(|h| h.bytes().to_vec()),
+                subtree: false,
+            },
+            BatchPeer::Subtree => protobuf::MapBatchPeer {
+                hash: None,
+                subtree: true,
+            },
+        }
+    }
+}
+
+impl<D, K, V> TryFrom<protobuf::MapTransitionProof> for TransitionProof<D, K, V>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+    V: VisitBytes,
+{
+    type Error = Error;
+
+    fn try_from(value: protobuf::MapTransitionProof) -> Result<Self, Self::Error> {
+        let old: Result<Vec<BatchPeer<D>>, Error> =
+            value.old.into_iter().map(|p| p.try_into()).collect();
+        let new: Result<Vec<BatchPeer<D>>, Error> =
+            value.new.into_iter().map(|p| p.try_into()).collect();
+        let proof = TransitionProof::new(old?, new?);
+        Ok(proof)
+    }
+}
+
+impl<D: SupportedDigest> TryFrom<protobuf::MapBatchPeer> for BatchPeer<D> {
+    type Error = Error;
+
+    fn try_from(value: protobuf::MapBatchPeer) -> Result<Self, Self::Error> {
+        match (value.subtree, value.hash) {
+            (true, None) => Ok(BatchPeer::Subtree),
+            (true, Some(_)) => Err(Error::msg("subtree batch peer has a hash")),
+            (false, hash) => Ok(BatchPeer::Peer(match hash {
+                Some(h) => Some(h.try_into()?),
+                None => None,
+            })),
+        }
+    }
+}
@@ -5,7 +5,7 @@ use super::{
     link::Link,
     map::hash_branch,
     node::Node,
-    path::{Path, ReversePath, Side},
+    path::{tree_height, Path, ReversePath, Side},
 };
 use std::{fmt::Debug, sync::Arc};
 
@@ -51,7 +51,7 @@ impl<D: SupportedDigest> Singleton<D> {
         if self.key() == &key {
             let new_singleton = Singleton::new(key, value, path.height() + 1);
             (Node::Singleton(new_singleton), false)
-        } else if cur_path.get(256 - self.height) != cur_side {
+        } else if cur_path.get(tree_height::<D>() - self.height) != cur_side {
             let node = Node::Singleton(Singleton::new(key, value, path.height()));
             let original = Node::Singleton(Singleton::new(
                 self.key.clone(),
@@ -62,7 +62,7 @@ impl<D: SupportedDigest> Singleton<D> {
                 Side::Left => Fork::new(Arc::new(Link::new(node)), Arc::new(Link::new(original))),
                 Side::Right => Fork::new(Arc::new(Link::new(original)), Arc::new(Link::new(node))),
             };
-            (Node::Fork(fork), false)
+            (Node::Fork(fork), true)
         } else {
             let cur_index = path.index();
             let pre_insert = Node::Singleton(Singleton::new(
@@ -74,10 +74,10 @@ impl<D: SupportedDigest> Singleton<D> {
             let fork = match cur_side {
                 Side::Left => Fork::new(
                     Arc::new(Link::new(down_one)),
-                    Arc::new(Link::new(Node::Empty(256 - cur_index))),
+                    Arc::new(Link::new(Node::Empty(tree_height::<D>() - cur_index))),
                 ),
                 Side::Right => Fork::new(
-                    Arc::new(Link::new(Node::Empty(256 - cur_index))),
+                    Arc::new(Link::new(Node::Empty(tree_height::<D>() - cur_index))),
                     Arc::new(Link::new(down_one)),
                 ),
             };
@@ -0,0 +1,1247 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    mem,
+    ops::Bound,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use alloc::{string::String, vec::Vec};
+use thiserror::Error;
+use warg_crypto::{
+    hash::{Hash, SupportedDigest},
+    VisitBytes,
+};
+
+use super::{
+    absence_proof::AbsenceProof,
+    fork::Fork,
+    link::Link,
+    map::Map,
+    node::Node,
+    path::{tree_height, Path, Side},
+    proof::Proof,
+    singleton::Singleton,
+    transition_proof::{prove_batch, sorted_keys, Shape, TransitionProof},
+};
+use crate::log::{read_exact_at, write_all_at};
+
+/// The magic bytes at the start of a map store file.
+const MAGIC: &[u8; 8] = b"WARG-MAP";
+
+/// The length of the map store header: the magic bytes followed by the hash
+/// algorithm name, padded with zeros.
+const HEADER_LEN: u64 = 16;
+
+/// The offset of a link to an empty subtree, which is not stored.
+const EMPTY: u64 = u64::MAX;
+
+// Record tags
+const FORK: u8 = 1;
+const SINGLETON: u8 = 2;
+const LEAF: u8 = 3;
+const VERSION: u8 = 4;
+const PRUNE: u8 = 5;
+
+/// The number of buffered bytes written at a time when compacting a store.
+const FLUSH_LEN: usize = 1 << 20;
+
+/// Represents an error using a [`MapStore`].
+#[derive(Error, Debug)]
+pub enum MapStoreError {
+    /// An I/O error occurred reading or writing the store.
+    #[error("failed to access map store: {0}")]
+    Io(#[from] io::Error),
+    /// The file is not a map store file.
+    #[error("file is not a map store file")]
+    InvalidHeader,
+    /// The map store file uses a different hash algorithm.
+    #[error("map store file uses hash algorithm `{0}`")]
+    AlgorithmMismatch(String),
+    /// The version is not in the store or has been pruned.
+    #[error("map version {0} was not found")]
+    VersionNotFound(u64),
+    /// The version does not follow the latest version in the store.
+    #[error("map version {version} does not follow the latest version {latest}")]
+    VersionOutOfOrder {
+        /// The latest version in the store.
+        latest: u64,
+        /// The version being committed.
+        version: u64,
+    },
+}
+
+/// A store of the versions of a [`Map`].
+///
+/// Each committed version only stores the nodes that changed since the
+/// previous version; unchanged subtrees are shared with it. This allows
+/// inclusion proofs to be produced for any retained version without keeping
+/// a copy of every version of the map in memory.
+///
+/// Versions must be committed in increasing order, such as the log length
+/// of a checkpoint. If a retention is set, only the latest versions are kept
+/// and the nodes only referenced by pruned versions are eventually
+/// discarded.
+///
+/// A store is kept in memory by default; [`MapStore::open`] opens a store
+/// file, which holds the nodes of each version in the order they were
+/// committed. A version partially written by a crash is discarded when the
+/// file is opened.
+pub struct MapStore<D: SupportedDigest> {
+    storage: Storage,
+    retention: Option<usize>,
+    versions: BTreeMap<u64, Version<D>>,
+    /// The number of node records in the storage
+    nodes: usize,
+    /// The number of node records no longer referenced by a retained version
+    garbage: usize,
+}
+
+impl<D: SupportedDigest> MapStore<D> {
+    /// Opens the map store file at the given path, creating an empty store
+    /// if the file does not exist.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, MapStoreError> {
+        let path = path.into();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&path)?;
+
+        let header = Self::header();
+        let size = file.metadata()?.len();
+        if size < HEADER_LEN {
+            // A new file, or one whose header was never completely written
+            file.set_len(0)?;
+            write_all_at(&file, &header, 0)?;
+            file.sync_all()?;
+        } else {
+            let mut found = [0; HEADER_LEN as usize];
+            read_exact_at(&file, &mut found, 0)?;
+            if &found[..MAGIC.len()] != MAGIC {
+                return Err(MapStoreError::InvalidHeader);
+            }
+            if found != header {
+                let algorithm = found[MAGIC.len()..]
+                    .iter()
+                    .take_while(|b| **b != 0)
+                    .map(|b| *b as char)
+                    .collect();
+                return Err(MapStoreError::AlgorithmMismatch(algorithm));
+            }
+        }
+
+        let len = file.metadata()?.len();
+        let mut store = Self {
+            storage: Storage::File { file, path, len },
+            retention: None,
+            versions: BTreeMap::new(),
+            nodes: 0,
+            garbage: 0,
+        };
+        store.load()?;
+        Ok(store)
+    }
+
+    /// Retains only the given number of latest versions.
+    ///
+    /// Older versions are pruned as new versions are committed; the latest
+    /// version is always retained.
+    pub fn with_retention(mut self, versions: usize) -> Self {
+        self.retention = Some(versions.max(1));
+        self
+    }
+
+    /// Gets the retained versions in increasing order.
+    pub fn versions(&self) -> impl Iterator<Item = u64> + '_ {
+        self.versions.keys().copied()
+    }
+
+    /// Gets the latest version, if any.
+    pub fn latest_version(&self) -> Option<u64> {
+        self.versions.keys().next_back().copied()
+    }
+
+    /// Gets the root of the map at the given version.
+    pub fn root(&self, version: u64) -> Option<&Hash<D>> {
+        self.versions.get(&version).map(|v| &v.root.hash)
+    }
+
+    /// Gets the map at the given version.
+    ///
+    /// The map is read from the store, so that it can be extended without
+    /// replaying the updates that produced it.
+    pub fn map<K, V>(&self, version: u64) -> Result<Map<D, K, V>, MapStoreError>
+    where
+        K: VisitBytes + Clone,
+        V: VisitBytes + Clone,
+    {
+        let stored = self
+            .versions
+            .get(&version)
+            .ok_or(MapStoreError::VersionNotFound(version))?;
+
+        let mut len = 0;
+        let link = self.read_link(&stored.root, tree_height::<D>(), &mut len)?;
+        Ok(Map::new(link, len))
+    }
+
+    /// Gets a proof of the presence of a key in the map at the given version.
+    ///
+    /// Returns `None` if the key is not present in that version.
+    pub fn prove<K, V>(&self, version: u64, key: K) -> Result<Option<Proof<D, K, V>>, MapStoreError>
+    where
+        K: VisitBytes,
+        V: VisitBytes + Clone,
+    {
+        let stored = self
+            .versions
+            .get(&version)
+            .ok_or(MapStoreError::VersionNotFound(version))?;
+
+        let key_hash = Hash::<D>::of(&key);
+        let mut path = Path::new(&key_hash);
+        let mut link = stored.root.clone();
+        let mut peers = Vec::new();
+        loop {
+            let side = path.next();
+            if link.offset == EMPTY {
+                return Ok(None);
+            }
+
+            match (side, self.read_node(link.offset)?) {
+                (Some(side), StoredNode::Fork(left, right)) => {
+                    let (next, peer) = match side {
+                        Side::Left => (left, right),
+                        Side::Right => (right, left),
+                    };
+                    peers.push(Some(peer.hash));
+                    link = next;
+                }
+                (Some(_), StoredNode::Singleton { key, .. }) => {
+                    if key != key_hash {
+                        return Ok(None);
+                    }
+                    break;
+                }
+                (None, StoredNode::Leaf(_)) => break,
+                _ => return Ok(None),
+            }
+        }
+
+        // Peers are ordered from the leaf to the root
+        peers.reverse();
+        Ok(Some(Proof::new(peers)))
+    }
+
+    /// Gets a proof of the absence of a key in the map at the given version.
+    ///
+    /// Returns `None` if the key is present in that version.
+    pub fn prove_absence<K>(
+        &self,
+        version: u64,
+        key: K,
+    ) -> Result<Option<AbsenceProof<D, K>>, MapStoreError>
+    where
+        K: VisitBytes,
+    {
+        let stored = self
+            .versions
+            .get(&version)
+            .ok_or(MapStoreError::VersionNotFound(version))?;
+
+        let key_hash = Hash::<D>::of(&key);
+        let mut path = Path::new(&key_hash);
+        let mut link = stored.root.clone();
+        let mut peers = Vec::new();
+        let leaf = loop {
+            let side = path.next();
+            if link.offset == EMPTY {
+                break None;
+            }
+
+            match (side, self.read_node(link.offset)?) {
+                (Some(side), StoredNode::Fork(left, right)) => {
+                    let (next, peer) = match side {
+                        Side::Left => (left, right),
+                        Side::Right => (right, left),
+                    };
+                    peers.push(Some(peer.hash));
+                    link = next;
+                }
+                (_, StoredNode::Singleton { key, value, .. }) => {
+                    if key == key_hash {
+                        return Ok(None);
+                    }
+                    break Some((key, value));
+                }
+                _ => return Ok(None),
+            }
+        };
+
+        // Peers are ordered from the bottom of the path to the root
+        peers.reverse();
+        Ok(Some(AbsenceProof::new(peers, leaf)))
+    }
+
+    /// Gets a proof of the transition of the given keys from the map at the
+    /// old version to the map at the new version.
+    pub fn prove_transition<K, V>(
+        &self,
+        old_version: u64,
+        new_version: u64,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Result<TransitionProof<D, K, V>, MapStoreError>
+    where
+        K: VisitBytes,
+        V: VisitBytes,
+    {
+        let keys = sorted_keys(keys);
+        let prove = |version| {
+            let stored = self
+                .versions
+                .get(&version)
+                .ok_or(MapStoreError::VersionNotFound(version))?;
+            prove_batch(stored.root.clone(), &keys, &|link| self.shape(link))
+        };
+        Ok(TransitionProof::new(
+            prove(old_version)?,
+            prove(new_version)?,
+        ))
+    }
+
+    /// Commits the given map as the given version.
+    ///
+    /// The version must be greater than the latest version in the store;
+    /// committing the latest version again with the same root does nothing.
+    /// The store is synced before returning.
+    pub fn commit<K, V>(&mut self, version: u64, map: &Map<D, K, V>) -> Result<(), MapStoreError>
+    where
+        K: VisitBytes + Clone,
+        V: VisitBytes + Clone,
+    {
+        let previous = match self.versions.iter().next_back() {
+            Some((&latest, stored)) if latest == version && &stored.root.hash == map.root() => {
+                return Ok(())
+            }
+            Some((&latest, _)) if latest >= version => {
+                return Err(MapStoreError::VersionOutOfOrder { latest, version })
+            }
+            Some((_, stored)) => Some(stored.root.clone()),
+            None => None,
+        };
+
+        let mut buffer = Buffer::new(self.storage.len());
+        let mut stale = Vec::new();
+        let link = map.link();
+        let root = self.write_node(
+            &mut buffer,
+            link.node(),
+            link.hash(),
+            previous.as_ref(),
+            &mut stale,
+        )?;
+        buffer.push(&encode_version(version, &root, &stale));
+        self.storage.append(&buffer.bytes)?;
+        self.storage.sync()?;
+
+        self.nodes += buffer.nodes;
+        self.versions.insert(
+            version,
+            Version {
+                root,
+                stale,
+                end: self.storage.len(),
+            },
+        );
+        self.prune()
+    }
+
+    /// Discards all versions after the given version.
+    ///
+    /// The store is synced before returning.
+    pub fn truncate(&mut self, version: u64) -> Result<(), MapStoreError> {
+        if self
+            .versions
+            .range((Bound::Excluded(version), Bound::Unbounded))
+            .next()
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        let end = self
+            .versions
+            .range(..=version)
+            .next_back()
+            .map_or(HEADER_LEN, |(_, stored)| stored.end);
+        self.storage.truncate(end)?;
+        self.storage.sync()?;
+        self.load()
+    }
+
+    fn header() -> [u8; HEADER_LEN as usize] {
+        let mut header = [0; HEADER_LEN as usize];
+        header[..MAGIC.len()].copy_from_slice(MAGIC);
+        let algorithm = D::ALGORITHM.to_string();
+        let algorithm = &algorithm.as_bytes()[..algorithm.len().min(header.len() - MAGIC.len())];
+        header[MAGIC.len()..MAGIC.len() + algorithm.len()].copy_from_slice(algorithm);
+        header
+    }
+
+    fn hash_len() -> usize {
+        D::empty_tree_hash(0).len()
+    }
+
+    // Writes the nodes of the given subtree that differ from the subtree
+    // of the previous version at the same position
+    fn write_node(
+        &self,
+        buffer: &mut Buffer,
+        node: &Node<D>,
+        hash: &Hash<D>,
+        previous: Option<&StoredLink<D>>,
+        stale: &mut Vec<u64>,
+    ) -> Result<StoredLink<D>, MapStoreError> {
+        if let Some(previous) = previous {
+            if &previous.hash == hash {
+                return Ok(previous.clone());
+            }
+        }
+
+        // The previous node is replaced by this version
+        let previous = match previous {
+            Some(previous) if previous.offset != EMPTY => {
+                stale.push(previous.offset);
+                Some(self.read_node(previous.offset)?)
+            }
+            _ => None,
+        };
+
+        let stored = match node {
+            Node::Empty(_) => {
+                return Ok(StoredLink {
+                    hash: hash.clone(),
+                    offset: EMPTY,
+                })
+            }
+            Node::Leaf(value) => StoredNode::Leaf(value.clone()),
+            Node::Singleton(singleton) => StoredNode::Singleton {
+                key: singleton.key.clone(),
+                value: singleton.value.clone(),
+                height: singleton.height,
+            },
+            Node::Fork(fork) => {
+                let (previous_left, previous_right) = match previous {
+                    Some(StoredNode::Fork(left, right)) => (Some(left), Some(right)),
+                    _ => (None, None),
+                };
+                let left = &fork[Side::Left];
+                let right = &fork[Side::Right];
+                StoredNode::Fork(
+                    self.write_node(
+                        buffer,
+                        left.node(),
+                        left.hash(),
+                        previous_left.as_ref(),
+                        stale,
+                    )?,
+                    self.write_node(
+                        buffer,
+                        right.node(),
+                        right.hash(),
+                        previous_right.as_ref(),
+                        stale,
+                    )?,
+                )
+            }
+        };
+
+        Ok(StoredLink {
+            hash: hash.clone(),
+            offset: buffer.push_node(&stored.encode()),
+        })
+    }
+
+    fn shape(&self, link: &StoredLink<D>) -> Result<Shape<D, StoredLink<D>>, MapStoreError> {
+        if link.offset == EMPTY {
+            return Ok(Shape::Empty);
+        }
+
+        Ok(match self.read_node(link.offset)? {
+            StoredNode::Fork(left, right) => {
+                Shape::Fork([(left.hash.clone(), left), (right.hash.clone(), right)])
+            }
+            StoredNode::Singleton { key, value, .. } => Shape::Singleton(key, value),
+            StoredNode::Leaf(_) => Shape::Leaf,
+        })
+    }
+
+    // Reads the subtree of the given height at the given link, counting its
+    // entries
+    fn read_link(
+        &self,
+        link: &StoredLink<D>,
+        height: usize,
+        len: &mut usize,
+    ) -> Result<Link<D>, MapStoreError> {
+        let node = if link.offset == EMPTY {
+            Node::Empty(height)
+        } else {
+            match self.read_node(link.offset)? {
+                StoredNode::Fork(left, right) => Node::Fork(Fork::new(
+                    Arc::new(self.read_link(&left, height - 1, len)?),
+                    Arc::new(self.read_link(&right, height - 1, len)?),
+                )),
+                StoredNode::Singleton { key, value, height } => {
+                    *len += 1;
+                    Node::Singleton(Singleton::new(key, value, height))
+                }
+                StoredNode::Leaf(value) => {
+                    *len += 1;
+                    Node::Leaf(value)
+                }
+            }
+        };
+
+        Ok(Link::with_hash(link.hash.clone(), node))
+    }
+
+    fn read_node(&self, offset: u64) -> Result<StoredNode<D>, MapStoreError> {
+        let mut tag = [0];
+        self.storage.read_at(&mut tag, offset)?;
+        let len = StoredNode::<D>::body_len(tag[0]).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid map node record at offset {offset}"),
+            )
+        })?;
+
+        let mut body = vec![0; len];
+        self.storage.read_at(&mut body, offset + 1)?;
+        Ok(StoredNode::decode(tag[0], &body))
+    }
+
+    // Prunes versions beyond the retention, compacting the storage once
+    // most of its nodes are no longer referenced
+    fn prune(&mut self) -> Result<(), MapStoreError> {
+        let Some(retention) = self.retention else {
+            return Ok(());
+        };
+
+        if self.versions.len() <= retention {
+            return Ok(());
+        }
+
+        let oldest = self
+            .versions
+            .keys()
+            .copied()
+            .nth(self.versions.len() - retention)
+            .unwrap();
+        let mut record = vec![PRUNE];
+        record.extend(oldest.to_be_bytes());
+        self.storage.append(&record)?;
+        self.storage.sync()?;
+        self.garbage += forget_before(&mut self.versions, oldest);
+
+        if self.garbage * 2 > self.nodes {
+            self.compact()?;
+        }
+
+        Ok(())
+    }
+
+    // Rewrites the storage with only the nodes of the retained versions
+    fn compact(&mut self) -> Result<(), MapStoreError> {
+        let mut storage = self.storage.create_replacement(&Self::header())?;
+        let mut buffer = Buffer::new(storage.len());
+        let mut copied = HashMap::new();
+        let mut versions = BTreeMap::new();
+        for (&version, stored) in &self.versions {
+            let root = self.copy_node(&mut storage, &mut buffer, &stored.root, &mut copied)?;
+
+            // The stale nodes of all but the oldest version are referenced
+            // by an older version, so they have been copied
+            let stale = stored
+                .stale
+                .iter()
+                .filter_map(|offset| copied.get(offset).copied())
+                .collect::<Vec<_>>();
+            buffer.push(&encode_version(version, &root, &stale));
+            buffer.flush(&mut storage)?;
+            versions.insert(
+                version,
+                Version {
+                    root,
+                    stale,
+                    end: storage.len(),
+                },
+            );
+        }
+
+        storage.sync()?;
+        self.storage.replace(storage)?;
+        self.versions = versions;
+        self.nodes = copied.len();
+        self.garbage = 0;
+        Ok(())
+    }
+
+    fn copy_node(
+        &self,
+        storage: &mut Storage,
+        buffer: &mut Buffer,
+        link: &StoredLink<D>,
+        copied: &mut HashMap<u64, u64>,
+    ) -> Result<StoredLink<D>, MapStoreError> {
+        if link.offset == EMPTY {
+            return Ok(link.clone());
+        }
+
+        if let Some(&offset) = copied.get(&link.offset) {
+            return Ok(StoredLink {
+                hash: link.hash.clone(),
+                offset,
+            });
+        }
+
+        let node = match self.read_node(link.offset)? {
+            StoredNode::Fork(left, right) => StoredNode::Fork(
+                self.copy_node(storage, buffer, &left, copied)?,
+                self.copy_node(storage, buffer, &right, copied)?,
+            ),
+            node => node,
+        };
+
+        let offset = buffer.push_node(&node.encode());
+        if buffer.bytes.len() >= FLUSH_LEN {
+            buffer.flush(storage)?;
+        }
+
+        copied.insert(link.offset, offset);
+        Ok(StoredLink {
+            hash: link.hash.clone(),
+            offset,
+        })
+    }
+
+    // Loads the versions from the storage, discarding any records after the
+    // last complete version or prune record
+    fn load(&mut self) -> Result<(), MapStoreError> {
+        let mut versions = BTreeMap::new();
+        let mut nodes = 0;
+        let mut garbage = 0;
+        let mut end = HEADER_LEN;
+        {
+            let mut reader = self.storage.reader()?;
+            let mut offset = HEADER_LEN;
+            let mut pending = 0;
+            while let Some((record, len)) = read_record::<D>(&mut reader)? {
+                offset += len;
+                match record {
+                    Record::Node => pending += 1,
+                    Record::Version {
+                        version,
+                        root,
+                        stale,
+                    } => {
+                        versions.insert(
+                            version,
+                            Version {
+                                root,
+                                stale,
+                                end: offset,
+                            },
+                        );
+                        nodes += mem::take(&mut pending);
+                        end = offset;
+                    }
+                    Record::Prune(oldest) => {
+                        garbage += forget_before(&mut versions, oldest);
+                        end = offset;
+                    }
+                }
+            }
+        }
+
+        if end < self.storage.len() {
+            self.storage.truncate(end)?;
+        }
+
+        if let Some(&oldest) = versions.keys().next() {
+            garbage += forget_before(&mut versions, oldest);
+        }
+
+        self.versions = versions;
+        self.nodes = nodes;
+        self.garbage = garbage;
+        Ok(())
+    }
+}
+
+impl<D: SupportedDigest> Default for MapStore<D> {
+    fn default() -> Self {
+        Self {
+            storage: Storage::Memory(Self::header().to_vec()),
+            retention: None,
+            versions: BTreeMap::new(),
+            nodes: 0,
+            garbage: 0,
+        }
+    }
+}
+
+// Removes the versions before the given version, returning the number of
+// nodes that are no longer referenced by the remaining versions
+fn forget_before<D: SupportedDigest>(
+    versions: &mut BTreeMap<u64, Version<D>>,
+    oldest: u64,
+) -> usize {
+    let retained = versions.split_off(&oldest);
+    let mut garbage = mem::replace(versions, retained)
+        .into_values()
+        .map(|stored| stored.stale.len())
+        .sum();
+
+    // The nodes replaced by the oldest version are only referenced by the
+    // versions before it
+    if let Some(stored) = versions.values_mut().next() {
+        garbage += mem::take(&mut stored.stale).len();
+    }
+
+    garbage
+}
+
+struct Version<D: SupportedDigest> {
+    root: StoredLink<D>,
+    /// The nodes of the previous version replaced by this version
+    stale: Vec<u64>,
+    /// The storage offset after the version record
+    end: u64,
+}
+
+/// A link to a stored node.
+struct StoredLink<D: SupportedDigest> {
+    hash: Hash<D>,
+    offset: u64,
+}
+
+impl<D: SupportedDigest> Clone for StoredLink<D> {
+    fn clone(&self) -> Self {
+        Self {
+            hash: self.hash.clone(),
+            offset: self.offset,
+        }
+    }
+}
+
+impl<D: SupportedDigest> StoredLink<D> {
+    fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(self.hash.bytes());
+        bytes.extend(self.offset.to_be_bytes());
+    }
+
+    fn decode(bytes: &[u8]) -> Self {
+        let (hash, offset) = bytes.split_at(MapStore::<D>::hash_len());
+        Self {
+            hash: Hash::try_from(hash.to_vec()).unwrap(),
+            offset: u64::from_be_bytes(offset.try_into().unwrap()),
+        }
+    }
+
+    fn encoded_len() -> usize {
+        MapStore::<D>::hash_len() + 8
+    }
+}
+
+enum StoredNode<D: SupportedDigest> {
+    Fork(StoredLink<D>, StoredLink<D>),
+    Singleton {
+        key: Hash<D>,
+        value: Hash<D>,
+        height: usize,
+    },
+    Leaf(Hash<D>),
+}
+
+impl<D: SupportedDigest> StoredNode<D> {
+    fn body_len(tag: u8) -> Option<usize> {
+        let hash_len = MapStore::<D>::hash_len();
+        match tag {
+            FORK => Some(StoredLink::<D>::encoded_len() * 2),
+            SINGLETON => Some(hash_len * 2 + 4),
+            LEAF => Some(hash_len),
+            _ => None,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            Self::Fork(left, right) => {
+                bytes.push(FORK);
+                left.encode(&mut bytes);
+                right.encode(&mut bytes);
+            }
+            Self::Singleton { key, value, height } => {
+                bytes.push(SINGLETON);
+                bytes.extend_from_slice(key.bytes());
+                bytes.extend_from_slice(value.bytes());
+                bytes.extend((*height as u32).to_be_bytes());
+            }
+            Self::Leaf(value) => {
+                bytes.push(LEAF);
+                bytes.extend_from_slice(value.bytes());
+            }
+        }
+        bytes
+    }
+
+    fn decode(tag: u8, body: &[u8]) -> Self {
+        let hash_len = MapStore::<D>::hash_len();
+        match tag {
+            FORK => {
+                let (left, right) = body.split_at(StoredLink::<D>::encoded_len());
+                Self::Fork(StoredLink::decode(left), StoredLink::decode(right))
+            }
+            SINGLETON => Self::Singleton {
+                key: Hash::try_from(body[..hash_len].to_vec()).unwrap(),
+                value: Hash::try_from(body[hash_len..hash_len * 2].to_vec()).unwrap(),
+                height: u32::from_be_bytes(body[hash_len * 2..].try_into().unwrap()) as usize,
+            },
+            _ => Self::Leaf(Hash::try_from(body.to_vec()).unwrap()),
+        }
+    }
+}
+
+fn encode_version<D: SupportedDigest>(
+    version: u64,
+    root: &StoredLink<D>,
+    stale: &[u64],
+) -> Vec<u8> {
+    let mut bytes = vec![VERSION];
+    bytes.extend(version.to_be_bytes());
+    root.encode(&mut bytes);
+    bytes.extend((stale.len() as u32).to_be_bytes());
+    for offset in stale {
+        bytes.extend(offset.to_be_bytes());
+    }
+    bytes
+}
+
+enum Record<D: SupportedDigest> {
+    Node,
+    Version {
+        version: u64,
+        root: StoredLink<D>,
+        stale: Vec<u64>,
+    },
+    Prune(u64),
+}
+
+// Reads the next record and its length, returning `None` at the end of the
+// storage or at a partially written record
+fn read_record<D: SupportedDigest>(
+    reader: &mut impl Read,
+) -> Result<Option<(Record<D>, u64)>, MapStoreError> {
+    fn read<const N: usize>(reader: &mut impl Read) -> io::Result<Option<[u8; N]>> {
+        let mut bytes = [0; N];
+        match reader.read_exact(&mut bytes) {
+            Ok(()) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn read_vec(reader: &mut impl Read, len: usize) -> io::Result<Option<Vec<u8>>> {
+        let mut bytes = vec![0; len];
+        match reader.read_exact(&mut bytes) {
+            Ok(()) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    let Some([tag]) = read::<1>(reader)? else {
+        return Ok(None);
+    };
+
+    if let Some(len) = StoredNode::<D>::body_len(tag) {
+        return Ok(read_vec(reader, len)?.map(|_| (Record::Node, 1 + len as u64)));
+    }
+
+    match tag {
+        VERSION => {
+            let Some(version) = read::<8>(reader)? else {
+                return Ok(None);
+            };
+            let Some(root) = read_vec(reader, StoredLink::<D>::encoded_len())? else {
+                return Ok(None);
+            };
+            let Some(count) = read::<4>(reader)? else {
+                return Ok(None);
+            };
+
+            // Read the stale offsets one at a time as a partially written
+            // count may be arbitrarily large
+            let count = u32::from_be_bytes(count) as usize;
+            let mut stale = Vec::new();
+            for _ in 0..count {
+                let Some(offset) = read::<8>(reader)? else {
+                    return Ok(None);
+                };
+                stale.push(u64::from_be_bytes(offset));
+            }
+
+            let len = 1 + 8 + StoredLink::<D>::encoded_len() + 4 + count * 8;
+            Ok(Some((
+                Record::Version {
+                    version: u64::from_be_bytes(version),
+                    root: StoredLink::decode(&root),
+                    stale,
+                },
+                len as u64,
+            )))
+        }
+        PRUNE => {
+            Ok(read::<8>(reader)?.map(|oldest| (Record::Prune(u64::from_be_bytes(oldest)), 9)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Records buffered to be appended to a storage.
+struct Buffer {
+    /// The storage offset of the buffered bytes
+    offset: u64,
+    bytes: Vec<u8>,
+    /// The number of node records pushed
+    nodes: usize,
+}
+
+impl Buffer {
+    fn new(offset: u64) -> Self {
+        Self {
+            offset,
+            bytes: Vec::new(),
+            nodes: 0,
+        }
+    }
+
+    fn push(&mut self, record: &[u8]) -> u64 {
+        let offset = self.offset + self.bytes.len() as u64;
+        self.bytes.extend_from_slice(record);
+        offset
+    }
+
+    fn push_node(&mut self, record: &[u8]) -> u64 {
+        self.nodes += 1;
+        self.push(record)
+    }
+
+    fn flush(&mut self, storage: &mut Storage) -> io::Result<()> {
+        storage.append(&self.bytes)?;
+        self.offset += self.bytes.len() as u64;
+        self.bytes.clear();
+        Ok(())
+    }
+}
+
+enum Storage {
+    Memory(Vec<u8>),
+    File { file: File, path: PathBuf, len: u64 },
+}
+
+impl Storage {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Memory(bytes) => bytes.len() as u64,
+            Self::File { len, .. } => *len,
+        }
+    }
+
+    fn append(&mut self, bytes: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Memory(storage) => storage.extend_from_slice(bytes),
+            Self::File { file, len, .. } => {
+                // A partially written append is overwritten by the next one
+                write_all_at(file, bytes, *len)?;
+                *len += bytes.len() as u64;
+            }
+        }
+        Ok(())
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        match self {
+            Self::Memory(bytes) => {
+                let start = offset as usize;
+                let end = start + buf.len();
+                if end > bytes.len() {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                buf.copy_from_slice(&bytes[start..end]);
+                Ok(())
+            }
+            Self::File { file, .. } => read_exact_at(file, buf, offset),
+        }
+    }
+
+    fn truncate(&mut self, new_len: u64) -> io::Result<()> {
+        match self {
+            Self::Memory(bytes) => bytes.truncate(new_len as usize),
+            Self::File { file, len, .. } => {
+                file.set_len(new_len)?;
+                *len = new_len;
+            }
+        }
+        Ok(())
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        match self {
+            Self::Memory(_) => Ok(()),
+            Self::File { file, .. } => file.sync_data(),
+        }
+    }
+
+    // Reads the records after the header
+    fn reader(&self) -> io::Result<Box<dyn Read + '_>> {
+        match self {
+            Self::Memory(bytes) => Ok(Box::new(&bytes[HEADER_LEN as usize..])),
+            Self::File { file, len, .. } => {
+                let mut file = file;
+                file.seek(SeekFrom::Start(HEADER_LEN))?;
+                Ok(Box::new(BufReader::new(file).take(len - HEADER_LEN)))
+            }
+        }
+    }
+
+    // Creates an empty storage of the same kind to replace this storage
+    fn create_replacement(&self, header: &[u8]) -> io::Result<Self> {
+        match self {
+            Self::Memory(_) => Ok(Self::Memory(header.to_vec())),
+            Self::File { path, .. } => {
+                let mut temp = OsString::from(path.as_os_str());
+                temp.push(".compact");
+                let path = PathBuf::from(temp);
+                let file = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(&path)?;
+                write_all_at(&file, header, 0)?;
+                Ok(Self::File {
+                    file,
+                    path,
+                    len: header.len() as u64,
+                })
+            }
+        }
+    }
+
+    fn replace(&mut self, mut other: Self) -> io::Result<()> {
+        if let (Self::File { path: target, .. }, Self::File { path, .. }) = (&*self, &mut other) {
+            fs::rename(&*path, target)?;
+            *path = target.clone();
+        }
+
+        *self = other;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use warg_crypto::hash::{Sha256, Sha512};
+
+    use super::*;
+
+    fn maps(count: usize) -> Vec<Map<Sha256, u8, (u8, u8)>> {
+        let mut map = Map::default();
+        (0..count)
+            .map(|i| {
+                map = map.extend([(i as u8, (i as u8, 0)), ((i / 2) as u8, (i as u8, 1))]);
+                map.clone()
+            })
+            .collect()
+    }
+
+    fn check(store: &MapStore<Sha256>, version: u64, map: &Map<Sha256, u8, (u8, u8)>) {
+        assert_eq!(store.root(version), Some(map.root()));
+        for i in 0..=version + 1 {
+            let key = i as u8;
+            let expected = map.prove(key);
+            let proof = store.prove::<_, (u8, u8)>(version, key).unwrap();
+            assert_eq!(
+                proof.map(|p| p.peers),
+                expected.map(|p| p.peers),
+                "proof of key {i} at version {version}"
+            );
+
+            let expected = map.prove_absence(key);
+            let proof = store.prove_absence(version, key).unwrap();
+            assert_eq!(
+                proof.as_ref().map(|p| (&p.peers, &p.leaf)),
+                expected.as_ref().map(|p| (&p.peers, &p.leaf)),
+                "absence proof of key {i} at version {version}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_map_store_proves_every_version() {
+        let maps = maps(40);
+        let mut store = MapStore::default();
+        for (version, map) in maps.iter().enumerate() {
+            store.commit(version as u64, map).unwrap();
+        }
+
+        assert_eq!(store.versions().count(), 40);
+        for (version, map) in maps.iter().enumerate() {
+            check(&store, version as u64, map);
+        }
+
+        let proof = store.prove::<_, (u8, u8)>(7, 3).unwrap().unwrap();
+        assert_eq!(&proof.evaluate(&3, &(7, 1)), maps[7].root());
+
+        for (old, new) in [(0, 1), (7, 8), (3, 39)] {
+            let keys = (0..45).step_by(4);
+            let proof = store
+                .prove_transition::<_, (u8, u8)>(old, new, keys.clone())
+                .unwrap();
+            let expected = maps[old as usize].prove_transition(&maps[new as usize], keys);
+            assert_eq!(proof.old, expected.old, "transition from {old} to {new}");
+            assert_eq!(proof.new, expected.new, "transition from {old} to {new}");
+        }
+
+        // Committing the latest version again is allowed only for its root
+        store.commit(39, &maps[39]).unwrap();
+        assert!(matches!(
+            store.commit(39, &maps[38]),
+            Err(MapStoreError::VersionOutOfOrder {
+                latest: 39,
+                version: 39
+            })
+        ));
+        assert!(matches!(
+            store.commit(12, &maps[12]),
+            Err(MapStoreError::VersionOutOfOrder {
+                latest: 39,
+                version: 12
+            })
+        ));
+    }
+
+    #[test]
+    fn test_map_store_retention() {
+        let maps = maps(100);
+        let mut store = MapStore::default().with_retention(5);
+        let mut largest = 0;
+        for (version, map) in maps.iter().enumerate() {
+            store.commit(version as u64, map).unwrap();
+            assert!(store.garbage * 2 <= store.nodes);
+            largest = largest.max(store.storage.len());
+        }
+
+        assert_eq!(store.versions().collect::<Vec<_>>(), [95, 96, 97, 98, 99]);
+        for version in 95..100 {
+            check(&store, version, &maps[version as usize]);
+        }
+        assert!(matches!(
+            store.prove::<_, (u8, u8)>(94, 0),
+            Err(MapStoreError::VersionNotFound(94))
+        ));
+
+        // Pruned nodes are eventually discarded
+        let mut all = MapStore::default();
+        for (version, map) in maps.iter().enumerate() {
+            all.commit(version as u64, map).unwrap();
+        }
+        assert!(largest < all.storage.len());
+    }
+
+    #[test]
+    fn test_map_store_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map");
+        let maps = maps(30);
+
+        let mut store: MapStore<Sha256> = MapStore::open(&path).unwrap().with_retention(10);
+        for (version, map) in maps.iter().enumerate().take(20) {
+            store.commit(version as u64, map).unwrap();
+        }
+        drop(store);
+
+        // Reopening the store keeps the retained versions
+        let mut store: MapStore<Sha256> = MapStore::open(&path).unwrap().with_retention(10);
+        assert_eq!(
+            store.versions().collect::<Vec<_>>(),
+            (10..20).collect::<Vec<_>>()
+        );
+        for (version, map) in maps.iter().enumerate().skip(20) {
+            store.commit(version as u64, map).unwrap();
+        }
+        for version in 20..30 {
+            check(&store, version, &maps[version as usize]);
+        }
+        drop(store);
+
+        // A partially written version is discarded
+        let mut contents = fs::read(&path).unwrap();
+        let len = contents.len();
+        contents.extend_from_slice(&[FORK; 50]);
+        fs::write(&path, &contents).unwrap();
+        let mut store: MapStore<Sha256> = MapStore::open(&path).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().len(), len as u64);
+        assert_eq!(store.latest_version(), Some(29));
+
+        // A version can be reopened as a map
+        let map = store.map::<u8, (u8, u8)>(29).unwrap();
+        assert_eq!(map, maps[29]);
+        assert_eq!(map.len(), maps[29].len());
+        let extended = map.insert(200, (1, 2));
+        assert_eq!(extended, maps[29].insert(200, (1, 2)));
+        assert_eq!(extended.len(), maps[29].len() + 1);
+
+        // Truncated versions are replaced by newly committed versions
+        store.truncate(24).unwrap();
+        assert_eq!(store.latest_version(), Some(24));
+        store.commit(25, &maps[27]).unwrap();
+        drop(store);
+
+        let store: MapStore<Sha256> = MapStore::open(&path).unwrap();
+        assert_eq!(store.latest_version(), Some(25));
+        check(&store, 24, &maps[24]);
+        check(&store, 25, &maps[27]);
+    }
+
+    #[test]
+    fn test_map_store_rejects_other_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map");
+
+        let mut store: MapStore<Sha256> = MapStore::open(&path).unwrap();
+        store.commit(1, &maps(1)[0]).unwrap();
+        drop(store);
+
+        assert!(matches!(
+            MapStore::<Sha512>::open(&path),
+            Err(MapStoreError::AlgorithmMismatch(algorithm)) if algorithm == "sha256"
+        ));
+
+        fs::write(&path, b"not a map store with a header").unwrap();
+        assert!(matches!(
+            MapStore::<Sha256>::open(&path),
+            Err(MapStoreError::InvalidHeader)
+        ));
+    }
+}
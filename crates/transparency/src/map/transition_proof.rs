@@ -0,0 +1,334 @@
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+
+use thiserror::Error;
+use warg_crypto::{
+    hash::{Hash, SupportedDigest},
+    VisitBytes,
+};
+
+use super::{
+    map::{hash_branch, hash_leaf},
+    path::{tree_height, Path, Side},
+    singleton::Singleton,
+};
+
+/// An error evaluating a [`TransitionProof`].
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TransitionProofError {
+    /// The proof was evaluated without any keys.
+    #[error("transition proof must be evaluated for at least one key")]
+    NoKeys,
+    /// The same key was given more than once.
+    #[error("transition proof was evaluated with a duplicate key")]
+    DuplicateKey,
+    /// The proof ended before every key was evaluated.
+    #[error("transition proof has too few peers")]
+    TooFewPeers,
+    /// The proof has peers left over after every key was evaluated.
+    #[error("transition proof has too many peers")]
+    TooManyPeers,
+}
+
+/// A peer in one of the batched proofs of a [`TransitionProof`]
+#[derive(Debug)]
+pub enum BatchPeer<D: SupportedDigest> {
+    /// The sibling of a node on the paths of the keys, `None` if it is empty
+    Peer(Option<Hash<D>>),
+    /// The subtree below a node contains no keys other than the proven ones
+    Subtree,
+}
+
+impl<D: SupportedDigest> Clone for BatchPeer<D> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Peer(peer) => Self::Peer(peer.clone()),
+            Self::Subtree => Self::Subtree,
+        }
+    }
+}
+
+impl<D: SupportedDigest> PartialEq for BatchPeer<D> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Peer(a), Self::Peer(b)) => a == b,
+            (Self::Subtree, Self::Subtree) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<D: SupportedDigest> Eq for BatchPeer<D> {}
+
+/// A proof of the transition of a set of keys from one map root to another
+///
+/// The proof consists of a batched proof for each root. A batched proof
+/// contains each peer needed to compute the root from the values of the keys
+/// only once, so the peers near the root that the paths of the keys share
+/// are not repeated for every key.
+///
+/// The peers of a batched proof are ordered by walking the paths of the keys
+/// from the root, left before right. Nodes where the paths of the keys split
+/// have no peer. A walk stops at [`BatchPeer::Subtree`], after which the
+/// subtree is computed from the values of the keys alone.
+pub struct TransitionProof<D, K, V>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+    V: VisitBytes,
+{
+    key: PhantomData<K>,
+    value: PhantomData<V>,
+    /// The batched proof of the keys in the old map
+    pub old: Vec<BatchPeer<D>>,
+    /// The batched proof of the keys in the new map
+    pub new: Vec<BatchPeer<D>>,
+}
+
+impl<D, K, V> TransitionProof<D, K, V>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+    V: VisitBytes,
+{
+    pub(crate) fn new(old: Vec<BatchPeer<D>>, new: Vec<BatchPeer<D>>) -> Self {
+        Self {
+            key: PhantomData,
+            value: PhantomData,
+            old,
+            new,
+        }
+    }
+
+    /// Computes the old and new roots obtained by evaluating this proof with
+    /// the given keys and their old and new values.
+    ///
+    /// A value of `None` means the key is absent from that map.
+    pub fn evaluate(
+        &self,
+        transitions: &[(K, Option<V>, Option<V>)],
+    ) -> Result<(Hash<D>, Hash<D>), TransitionProofError> {
+        if transitions.is_empty() {
+            return Err(TransitionProofError::NoKeys);
+        }
+
+        let mut leafs = transitions
+            .iter()
+            .map(|(key, old, new)| {
+                (
+                    Hash::<D>::of(key),
+                    old.as_ref().map(hash_leaf),
+                    new.as_ref().map(hash_leaf),
+                )
+            })
+            .collect::<Vec<_>>();
+        leafs.sort_by(|a, b| a.0.bytes().cmp(b.0.bytes()));
+        if leafs.windows(2).any(|w| w[0].0 == w[1].0) {
+            return Err(TransitionProofError::DuplicateKey);
+        }
+
+        let keys = leafs.iter().map(|l| l.0.clone()).collect::<Vec<_>>();
+        let old = leafs.iter().map(|l| l.1.clone()).collect::<Vec<_>>();
+        let new = leafs.iter().map(|l| l.2.clone()).collect::<Vec<_>>();
+        Ok((
+            evaluate_batch(&self.old, &keys, &old)?,
+            evaluate_batch(&self.new, &keys, &new)?,
+        ))
+    }
+}
+
+// Computes the root of a batched proof for the given sorted keys and the
+// hashes of their leafs.
+fn evaluate_batch<D: SupportedDigest>(
+    peers: &[BatchPeer<D>],
+    keys: &[Hash<D>],
+    leafs: &[Option<Hash<D>>],
+) -> Result<Hash<D>, TransitionProofError> {
+    let mut peers = peers.iter();
+    let root = evaluate_node(&mut peers, 0, keys, leafs)?;
+    if peers.next().is_some() {
+        return Err(TransitionProofError::TooManyPeers);
+    }
+    Ok(root)
+}
+
+fn evaluate_node<'a, D: SupportedDigest>(
+    peers: &mut impl Iterator<Item = &'a BatchPeer<D>>,
+    depth: usize,
+    keys: &[Hash<D>],
+    leafs: &[Option<Hash<D>>],
+) -> Result<Hash<D>, TransitionProofError> {
+    let height = tree_height::<D>();
+    if depth == height {
+        return Ok(subtree_hash(depth, keys, leafs));
+    }
+
+    if let Some(at) = split(depth, keys) {
+        let left = evaluate_node(peers, depth + 1, &keys[..at], &leafs[..at])?;
+        let right = evaluate_node(peers, depth + 1, &keys[at..], &leafs[at..])?;
+        return Ok(hash_branch(&left, &right));
+    }
+
+    match peers.next().ok_or(TransitionProofError::TooFewPeers)? {
+        BatchPeer::Subtree => Ok(subtree_hash(depth, keys, leafs)),
+        BatchPeer::Peer(peer) => {
+            let hash = evaluate_node(peers, depth + 1, keys, leafs)?;
+            let peer = match peer {
+                Some(peer) => peer.clone(),
+                None => D::empty_tree_hash(height - depth - 1).clone(),
+            };
+            Ok(match Path::new(&keys[0]).get(depth) {
+                Side::Left => hash_branch(&hash, &peer),
+                Side::Right => hash_branch(&peer, &hash),
+            })
+        }
+    }
+}
+
+// Computes the hash of the subtree at the given depth containing only the
+// given sorted keys, some of which may be absent.
+fn subtree_hash<D: SupportedDigest>(
+    depth: usize,
+    keys: &[Hash<D>],
+    leafs: &[Option<Hash<D>>],
+) -> Hash<D> {
+    let height = tree_height::<D>() - depth;
+    let mut present = keys
+        .iter()
+        .zip(leafs)
+        .filter_map(|(k, l)| Some((k, l.as_ref()?)));
+    match (present.next(), present.next()) {
+        (None, _) => D::empty_tree_hash(height).clone(),
+        (Some((key, leaf)), None) => Singleton::new(key.clone(), leaf.clone(), height).hash(),
+        _ => {
+            let at = keys.partition_point(|k| Path::new(k).get(depth) == Side::Left);
+            hash_branch(
+                &subtree_hash(depth + 1, &keys[..at], &leafs[..at]),
+                &subtree_hash(depth + 1, &keys[at..], &leafs[at..]),
+            )
+        }
+    }
+}
+
+/// Hashes the given keys, sorted by their paths and without duplicates.
+pub(crate) fn sorted_keys<D, K>(keys: impl IntoIterator<Item = K>) -> Vec<Hash<D>>
+where
+    D: SupportedDigest,
+    K: VisitBytes,
+{
+    let mut keys = keys.into_iter().map(|k| Hash::of(k)).collect::<Vec<_>>();
+    keys.sort_by(|a, b| a.bytes().cmp(b.bytes()));
+    keys.dedup();
+    keys
+}
+
+// Gets the index of the first sorted key whose path goes right at the given
+// depth if the paths of the keys split there.
+pub(crate) fn split<D: SupportedDigest>(depth: usize, keys: &[Hash<D>]) -> Option<usize> {
+    let at = keys.partition_point(|k| Path::new(k).get(depth) == Side::Left);
+    (at > 0 && at < keys.len()).then_some(at)
+}
+
+/// The shape of a node in a map being walked to build a batched proof.
+pub(crate) enum Shape<D: SupportedDigest, N> {
+    Empty,
+    Leaf,
+    Singleton(Hash<D>, Hash<D>),
+    Fork([(Hash<D>, N); 2]),
+}
+
+/// Builds the batched proof of the given sorted keys in the map with the
+/// given root node.
+pub(crate) fn prove_batch<D, N, E>(
+    root: N,
+    keys: &[Hash<D>],
+    expand: &impl Fn(&N) -> Result<Shape<D, N>, E>,
+) -> Result<Vec<BatchPeer<D>>, E>
+where
+    D: SupportedDigest,
+{
+    let mut peers = Vec::new();
+    if !keys.is_empty() {
+        prove_node(&mut peers, 0, keys, expand(&root)?, expand)?;
+    }
+    Ok(peers)
+}
+
+fn prove_node<D, N, E>(
+    peers: &mut Vec<BatchPeer<D>>,
+    depth: usize,
+    keys: &[Hash<D>],
+    shape: Shape<D, N>,
+    expand: &impl Fn(&N) -> Result<Shape<D, N>, E>,
+) -> Result<(), E>
+where
+    D: SupportedDigest,
+{
+    let [(left_hash, left), (right_hash, right)] = match shape {
+        Shape::Empty => {
+            prove_sparse(peers, depth, keys, None);
+            return Ok(());
+        }
+        Shape::Singleton(key, value) => {
+            prove_sparse(peers, depth, keys, Some((key, value)));
+            return Ok(());
+        }
+        Shape::Leaf => return Ok(()),
+        Shape::Fork(children) => children,
+    };
+
+    if let Some(at) = split(depth, keys) {
+        prove_node(peers, depth + 1, &keys[..at], expand(&left)?, expand)?;
+        return prove_node(peers, depth + 1, &keys[at..], expand(&right)?, expand);
+    }
+
+    let (next, peer) = match Path::new(&keys[0]).get(depth) {
+        Side::Left => (left, right_hash),
+        Side::Right => (right, left_hash),
+    };
+    let empty = D::empty_tree_hash(tree_height::<D>() - depth - 1);
+    peers.push(BatchPeer::Peer((&peer != empty).then_some(peer)));
+    prove_node(peers, depth + 1, keys, expand(&next)?, expand)
+}
+
+// Proves the keys in a subtree that is either empty or contains a single leaf.
+fn prove_sparse<D: SupportedDigest>(
+    peers: &mut Vec<BatchPeer<D>>,
+    depth: usize,
+    keys: &[Hash<D>],
+    leaf: Option<(Hash<D>, Hash<D>)>,
+) {
+    let height = tree_height::<D>();
+    if depth == height {
+        return;
+    }
+
+    if let Some(at) = split(depth, keys) {
+        let side = |key: &Hash<D>| Path::new(key).get(depth);
+        let (left, right) = match leaf {
+            Some(leaf) if side(&leaf.0) == Side::Right => (None, Some(leaf)),
+            leaf => (leaf, None),
+        };
+        prove_sparse(peers, depth + 1, &keys[..at], left);
+        prove_sparse(peers, depth + 1, &keys[at..], right);
+        return;
+    }
+
+    match leaf {
+        Some((key, value))
+            if keys
+                .binary_search_by(|k| k.bytes().cmp(key.bytes()))
+                .is_err() =>
+        {
+            if Path::new(&key).get(depth) == Path::new(&keys[0]).get(depth) {
+                peers.push(BatchPeer::Peer(None));
+                prove_sparse(peers, depth + 1, keys, Some((key, value)));
+            } else {
+                let peer = Singleton::new(key, value, height - depth - 1).hash();
+                peers.push(BatchPeer::Peer(Some(peer)));
+                prove_sparse(peers, depth + 1, keys, None);
+            }
+        }
+        _ => peers.push(BatchPeer::Subtree),
+    }
+}
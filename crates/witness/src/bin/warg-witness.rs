@@ -0,0 +1,108 @@
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+use secrecy::SecretString;
+use std::{fs, path::PathBuf, time::Duration};
+use tracing_subscriber::filter::LevelFilter;
+use warg_crypto::signing::{PrivateKey, PublicKey};
+use warg_witness::Witness;
+
+#[derive(Parser, Debug)]
+struct Args {
+    /// Use verbose output
+    #[arg(short, long, env = "WARG_VERBOSE", action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// The URL of the registry to witness.
+    #[arg(long, env = "WARG_REGISTRY")]
+    registry: String,
+
+    /// The witness private key.
+    #[arg(long, env = "WARG_WITNESS_KEY")]
+    key: Option<SecretString>,
+
+    /// The path to the witness private key.
+    #[arg(long, env = "WARG_WITNESS_KEY_FILE", conflicts_with = "key")]
+    key_file: Option<PathBuf>,
+
+    /// The public key of a registry operator trusted to sign checkpoints.
+    ///
+    /// May be specified multiple times. If not specified, the operator
+    /// signature of checkpoints is not verified.
+    #[arg(long = "operator-key", value_name = "KEY")]
+    operator_keys: Vec<PublicKey>,
+
+    /// The path to the file storing the last cosigned checkpoint.
+    #[arg(long, env = "WARG_WITNESS_STATE")]
+    state: PathBuf,
+
+    /// The number of seconds to wait between checkpoints.
+    #[arg(long, env = "WARG_WITNESS_INTERVAL", default_value = "10")]
+    interval: u64,
+
+    /// Witness the latest checkpoint once and exit.
+    #[arg(long)]
+    once: bool,
+}
+
+impl Args {
+    fn init_tracing(&self) {
+        let level_filter = match self.verbose {
+            0 => LevelFilter::INFO,
+            1 => LevelFilter::DEBUG,
+            _ => LevelFilter::TRACE,
+        };
+        tracing_subscriber::fmt()
+            .with_max_level(level_filter)
+            .init();
+    }
+
+    fn witness_key(&self) -> Result<PrivateKey> {
+        let key = match (&self.key_file, &self.key) {
+            (Some(path), _) => fs::read_to_string(path)
+                .with_context(|| format!("failed to read file `{path}`", path = path.display()))?
+                .trim()
+                .to_string()
+                .into(),
+            (None, Some(key)) => key.clone(),
+            (None, None) => bail!("either option `key-file` or `key` needs to be specified"),
+        };
+
+        PrivateKey::decode(key).context("failed to parse witness key")
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    args.init_tracing();
+
+    let witness = Witness::new(&args.registry, args.witness_key()?, &args.state)?
+        .with_operator_keys(args.operator_keys.iter().cloned());
+
+    tracing::info!(
+        "witnessing registry `{registry}` with key `{key_id}`",
+        registry = args.registry,
+        key_id = witness.public_key().fingerprint()
+    );
+
+    if args.once {
+        witness.witness().await?;
+        return Ok(());
+    }
+
+    let mut interval = tokio::time::interval(Duration::from_secs(args.interval));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {
+                // Transient failures are retried at the next interval; an
+                // inconsistent registry log is reported on every attempt
+                if let Err(e) = witness.witness().await {
+                    tracing::error!("failed to witness checkpoint: {e:#}");
+                }
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+//! A reference witness for Warg registry checkpoints.
+//!
+//! A witness periodically fetches the latest checkpoint of a registry,
+//! verifies that the registry log is an append-only extension of the last
+//! checkpoint the witness cosigned, and submits a cosignature of the new
+//! checkpoint to the registry.
+//!
+//! Only the consistency of the registry log is verified; the map root of a
+//! checkpoint is cosigned as given by the registry.
+
+#![deny(missing_docs)]
+
+use anyhow::{bail, Context, Result};
+use std::{borrow::Cow, fs, path::PathBuf};
+use warg_api::v1::{proof::ConsistencyRequest, witness::CosignCheckpointRequest};
+use warg_client::api::{self, ClientError};
+use warg_crypto::{
+    signing::{PrivateKey, PublicKey},
+    Encode, Signable,
+};
+use warg_protocol::{
+    registry::{CosignedCheckpoint, TimestampedCheckpoint, WitnessCosignature},
+    SerdeEnvelope,
+};
+
+/// A witness of a registry's checkpoints.
+pub struct Witness {
+    api: api::Client,
+    key: PrivateKey,
+    operator_keys: Vec<PublicKey>,
+    state_path: PathBuf,
+}
+
+impl Witness {
+    /// Creates a new witness for the registry at the given URL.
+    ///
+    /// The last checkpoint cosigned by the witness is kept in the file at
+    /// `state_path`; if the file does not exist, the witness trusts the first
+    /// checkpoint it sees.
+    pub fn new(url: &str, key: PrivateKey, state_path: impl Into<PathBuf>) -> Result<Self> {
+        Ok(Self {
+            api: api::Client::new(url)?,
+            key,
+            operator_keys: Vec::new(),
+            state_path: state_path.into(),
+        })
+    }
+
+    /// Requires checkpoints to be signed by one of the given operator keys.
+    ///
+    /// If no operator keys are given, the operator signature of a checkpoint
+    /// is not verified.
+    pub fn with_operator_keys(mut self, keys: impl IntoIterator<Item = PublicKey>) -> Self {
+        self.operator_keys = keys.into_iter().collect();
+        self
+    }
+
+    /// Gets the public key of the witness.
+    pub fn public_key(&self) -> PublicKey {
+        self.key.public_key()
+    }
+
+    /// Loads the last checkpoint cosigned by the witness.
+    pub fn last_checkpoint(&self) -> Result<Option<SerdeEnvelope<TimestampedCheckpoint>>> {
+        if !self.state_path.is_file() {
+            return Ok(None);
+        }
+
+        let contents = fs::read_to_string(&self.state_path).with_context(|| {
+            format!(
+                "failed to read witness state `{path}`",
+                path = self.state_path.display()
+            )
+        })?;
+
+        serde_json::from_str(&contents).map(Some).with_context(|| {
+            format!(
+                "failed to deserialize witness state `{path}`",
+                path = self.state_path.display()
+            )
+        })
+    }
+
+    /// Witnesses the latest checkpoint of the registry.
+    ///
+    /// The checkpoint is cosigned only if the registry log is consistent with
+    /// the last checkpoint cosigned by the witness.
+    ///
+    /// Returns the checkpoint with the cosignatures received by the registry.
+    pub async fn witness(&self) -> Result<CosignedCheckpoint> {
+        let checkpoint = self.api.witness_checkpoint().await?;
+        self.verify_operator_signature(&checkpoint)?;
+
+        let last = self.last_checkpoint()?;
+        if let Some(last) = &last {
+            self.verify_consistency(last.as_ref(), checkpoint.as_ref())
+                .await?;
+        }
+
+        let log_length = checkpoint.as_ref().checkpoint.log_length;
+        tracing::info!("cosigning checkpoint log length `{log_length}`");
+
+        let cosigned = self
+            .api
+            .cosign_checkpoint(&CosignCheckpointRequest {
+                log_length,
+                cosignature: WitnessCosignature::sign(&self.key, checkpoint.as_ref())?,
+            })
+            .await?;
+
+        self.store_checkpoint(&checkpoint)?;
+        Ok(cosigned)
+    }
+
+    fn verify_operator_signature(
+        &self,
+        checkpoint: &SerdeEnvelope<TimestampedCheckpoint>,
+    ) -> Result<()> {
+        if self.operator_keys.is_empty() {
+            return Ok(());
+        }
+
+        let Some(key) = self
+            .operator_keys
+            .iter()
+            .find(|k| &k.fingerprint() == checkpoint.key_id())
+        else {
+            bail!(
+                "checkpoint was signed by untrusted key `{key_id}`",
+                key_id = checkpoint.key_id()
+            );
+        };
+
+        TimestampedCheckpoint::verify(key, &checkpoint.as_ref().encode(), checkpoint.signature())
+            .context("invalid checkpoint signature")
+    }
+
+    async fn verify_consistency(
+        &self,
+        last: &TimestampedCheckpoint,
+        checkpoint: &TimestampedCheckpoint,
+    ) -> Result<()> {
+        let from = &last.checkpoint;
+        let to = &checkpoint.checkpoint;
+
+        if to.log_length < from.log_length {
+            bail!(
+                "registry log length `{to}` is less than the last witnessed log length `{from}`",
+                to = to.log_length,
+                from = from.log_length
+            );
+        }
+
+        if to.log_length == from.log_length {
+            if to.log_root != from.log_root || to.map_root != from.map_root {
+                bail!(
+                    "registry checkpoint for log length `{length}` differs from the last witnessed checkpoint",
+                    length = to.log_length
+                );
+            }
+
+            return Ok(());
+        }
+
+        // An empty log is consistent with every log
+        if from.log_length == 0 {
+            return Ok(());
+        }
+
+        match self
+            .api
+            .prove_log_consistency(
+                ConsistencyRequest {
+                    from: from.log_length,
+                    to: to.log_length,
+                },
+                Cow::Borrowed(&from.log_root),
+                Cow::Borrowed(&to.log_root),
+            )
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(e @ ClientError::IncorrectConsistencyProof { .. })
+            | Err(e @ ClientError::ConsistencyProof(_)) => Err(e).with_context(|| {
+                format!(
+                    "registry log is not consistent with the last witnessed log length `{length}`",
+                    length = from.log_length
+                )
+            }),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn store_checkpoint(&self, checkpoint: &SerdeEnvelope<TimestampedCheckpoint>) -> Result<()> {
+        // Write to a temporary file first so that the state is never left
+        // partially written
+        let temp_path = self.state_path.with_extension("tmp");
+        fs::write(&temp_path, serde_json::to_vec_pretty(checkpoint)?).with_context(|| {
+            format!(
+                "failed to write witness state `{path}`",
+                path = temp_path.display()
+            )
+        })?;
+
+        fs::rename(&temp_path, &self.state_path).with_context(|| {
+            format!(
+                "failed to write witness state `{path}`",
+                path = self.state_path.display()
+            )
+        })
+    }
+}
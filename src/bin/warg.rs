@@ -3,8 +3,8 @@ use clap::Parser;
 use std::process::exit;
 use tracing_subscriber::EnvFilter;
 use warg_cli::commands::{
-    ClearCommand, ConfigCommand, DownloadCommand, InfoCommand, KeyCommand, PublishCommand,
-    ResetCommand, UpdateCommand,
+    ClearCommand, ConfigCommand, DownloadCommand, InfoCommand, KeyCommand, OperatorCommand,
+    PublishCommand, ResetCommand, UpdateCommand,
 };
 use warg_client::ClientError;
 
@@ -29,6 +29,8 @@ enum WargCli {
     Update(UpdateCommand),
     #[clap(subcommand)]
     Publish(PublishCommand),
+    #[clap(subcommand)]
+    Operator(OperatorCommand),
     Reset(ResetCommand),
     Clear(ClearCommand),
 }
@@ -46,6 +48,7 @@ async fn main() -> Result<()> {
         WargCli::Download(cmd) => cmd.exec().await,
         WargCli::Update(cmd) => cmd.exec().await,
         WargCli::Publish(cmd) => cmd.exec().await,
+        WargCli::Operator(cmd) => cmd.exec().await,
         WargCli::Reset(cmd) => cmd.exec().await,
         WargCli::Clear(cmd) => cmd.exec().await,
     } {
@@ -3,27 +3,34 @@
 use anyhow::Context;
 use anyhow::Result;
 use clap::Args;
-use std::path::PathBuf;
+use secrecy::zeroize::Zeroizing;
+use std::{path::PathBuf, time::Duration};
 use warg_client::RegistryUrl;
 use warg_client::{ClientError, Config, FileSystemClient, StorageLockResult};
-use warg_crypto::signing::PrivateKey;
+use warg_crypto::signing::{
+    external::{self, SignerEndpoint},
+    ExternalSigner, PrivateKey, Signer,
+};
+use warg_protocol::{package::Deprecation, registry::PackageName};
 
 mod clear;
 mod config;
 mod download;
 mod info;
 mod key;
+mod operator;
 mod publish;
 mod reset;
 mod update;
 
-use crate::keyring::get_signing_key;
+use crate::keyring::{decode_private_key, get_signing_key};
 
 pub use self::clear::*;
 pub use self::config::*;
 pub use self::download::*;
 pub use self::info::*;
 pub use self::key::*;
+pub use self::operator::*;
 pub use self::publish::*;
 pub use self::reset::*;
 pub use self::update::*;
@@ -40,6 +47,31 @@ pub struct CommonOptions {
     /// The path to the signing key file.
     #[clap(long, value_name = "KEY_FILE", env = "WARG_SIGNING_KEY_FILE")]
     pub key_file: Option<PathBuf>,
+    /// The external signer to sign with instead of a signing key.
+    ///
+    /// Either `exec:<command>` to run a signing command for each request or `unix:<path>` to connect to a signing daemon's Unix domain socket.
+    #[clap(
+        long,
+        value_name = "SIGNER",
+        env = "WARG_SIGNER",
+        conflicts_with = "key_file"
+    )]
+    pub signer: Option<SignerEndpoint>,
+    /// The number of seconds the external signer has to answer a request.
+    ///
+    /// Defaults to 30 seconds.
+    #[clap(
+        long,
+        value_name = "SECONDS",
+        env = "WARG_SIGNER_TIMEOUT",
+        requires = "signer"
+    )]
+    pub signer_timeout: Option<u64>,
+    /// The path to a file containing the passphrase of an encrypted key file.
+    ///
+    /// If not specified, the passphrase is read from the `WARG_KEY_PASSPHRASE` environment variable or prompted for.
+    #[clap(long, value_name = "PASSPHRASE_FILE", env = "WARG_KEY_PASSPHRASE_FILE")]
+    pub passphrase_file: Option<PathBuf>,
     /// The path to the client configuration file to use.
     ///
     /// If not specified, the following locations are searched in order: `./warg-config.json`, `<system-config-dir>/warg/config.json`.
@@ -78,17 +110,49 @@ impl CommonOptions {
         }
     }
 
+    /// Gets the signer for the given registry URL.
+    ///
+    /// An external signer is used if one was specified; otherwise, the signing
+    /// key is used.
+    pub async fn signer(&self, registry_url: &RegistryUrl) -> Result<Box<dyn Signer>> {
+        if let Some(endpoint) = &self.signer {
+            return Ok(Box::new(
+                ExternalSigner::connect_with_timeout(
+                    endpoint.clone(),
+                    self.signer_timeout
+                        .map_or(external::DEFAULT_TIMEOUT, Duration::from_secs),
+                )
+                .await
+                .context("failed to connect to the signer")?,
+            ));
+        }
+
+        Ok(Box::new(self.signing_key(registry_url)?))
+    }
+
     /// Gets the signing key for the given registry URL.
     pub fn signing_key(&self, registry_url: &RegistryUrl) -> Result<PrivateKey> {
         if let Some(file) = &self.key_file {
-            let key_str = std::fs::read_to_string(file)
-                .with_context(|| format!("failed to read key from {file:?}"))?
-                .trim_end()
-                .to_string();
-            PrivateKey::decode(key_str)
+            let key_str = Zeroizing::new(
+                std::fs::read_to_string(file)
+                    .with_context(|| format!("failed to read key from {file:?}"))?,
+            );
+            decode_private_key(key_str.trim(), self.passphrase_file.as_deref())
                 .with_context(|| format!("failed to parse key from {file:?}"))
         } else {
             get_signing_key(registry_url, &self.key_name)
         }
     }
 }
+
+/// Prints a warning to stderr that the given package is deprecated.
+fn print_deprecation_warning(name: &PackageName, deprecation: &Deprecation) {
+    let mut warning = format!("warning: package `{name}` is deprecated");
+    if let Some(successor) = &deprecation.successor {
+        warning.push_str(&format!("; use `{successor}` instead"));
+    }
+    if let Some(message) = &deprecation.message {
+        warning.push_str(&format!(": {message}"));
+    }
+    eprintln!("{warning}");
+}
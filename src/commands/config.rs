@@ -2,6 +2,7 @@ use anyhow::{bail, Context, Result};
 use clap::Args;
 use std::path::PathBuf;
 use warg_client::{Config, RegistryUrl};
+use warg_crypto::signing::PublicKey;
 
 /// Creates a new warg configuration file.
 #[derive(Args)]
@@ -18,6 +19,22 @@ pub struct ConfigCommand {
     #[clap(long, value_name = "CONTENT")]
     pub content_dir: Option<PathBuf>,
 
+    /// The public key of a witness trusted to cosign registry checkpoints.
+    ///
+    /// May be specified multiple times.
+    #[clap(long = "witness-key", value_name = "KEY")]
+    pub witness_keys: Vec<PublicKey>,
+
+    /// The number of trusted witnesses that must cosign a checkpoint.
+    #[clap(long, value_name = "COUNT", default_value = "0")]
+    pub witness_threshold: usize,
+
+    /// Accept package log updates without proving their map transition when
+    /// the registry no longer has the map of a package's last known
+    /// checkpoint.
+    #[clap(long)]
+    pub allow_unproven_map_transitions: bool,
+
     /// Overwrite the existing configuration file.
     #[clap(long)]
     pub overwrite: bool,
@@ -37,6 +54,13 @@ impl ConfigCommand {
             .map(Ok)
             .unwrap_or_else(Config::default_config_path)?;
 
+        if self.witness_threshold > self.witness_keys.len() {
+            bail!(
+                "witness threshold of {threshold} exceeds the number of witness keys",
+                threshold = self.witness_threshold
+            );
+        }
+
         if !self.overwrite && path.is_file() {
             bail!(
                 "configuration file `{path}` already exists; use `--overwrite` to overwrite it",
@@ -60,6 +84,9 @@ impl ConfigCommand {
             default_url,
             registries_dir: self.registries_dir.map(|p| cwd.join(p)),
             content_dir: self.content_dir.map(|p| cwd.join(p)),
+            witness_keys: self.witness_keys,
+            witness_threshold: self.witness_threshold,
+            allow_unproven_map_transitions: self.allow_unproven_map_transitions,
         };
 
         config.write_to_file(&path)?;
@@ -1,4 +1,4 @@
-use super::CommonOptions;
+use super::{print_deprecation_warning, CommonOptions};
 use anyhow::{anyhow, Result};
 use clap::Args;
 use warg_protocol::{registry::PackageName, VersionReq};
@@ -47,6 +47,10 @@ impl DownloadCommand {
             digest = res.digest
         );
 
+        if let Some(deprecation) = &res.deprecation {
+            print_deprecation_warning(&self.name, deprecation);
+        }
+
         Ok(())
     }
 }
@@ -1,9 +1,13 @@
-use super::CommonOptions;
+use super::{print_deprecation_warning, CommonOptions};
 use anyhow::Result;
 use clap::Args;
 use warg_client::storage::{PackageInfo, RegistryStorage};
 use warg_crypto::hash::AnyHash;
-use warg_protocol::{registry::PackageName, Version};
+use warg_protocol::{
+    package::{PackageMetadata, YankReason},
+    registry::PackageName,
+    Version,
+};
 
 /// Display client storage information.
 #[derive(Args)]
@@ -46,15 +50,46 @@ impl InfoCommand {
 
     fn print_package_info(info: &PackageInfo) {
         println!("  name: {name}", name = info.name);
+        if let Some(deprecation) = info.state.deprecation() {
+            print_deprecation_warning(&info.name, deprecation);
+        }
+        Self::print_metadata(info.state.metadata(), "  ");
         println!("  versions:");
         info.state.releases().for_each(|r| {
-            if let Some(content) = r.content() {
-                Self::print_release(&r.version, content);
+            match r.content() {
+                Some(content) => Self::print_release(&r.version, content),
+                None => Self::print_yanked_release(&r.version, r.yank_reason()),
+            }
+            Self::print_metadata(&r.metadata, "      ");
+            for dependency in &r.dependencies {
+                println!("      depends on: {dependency}");
             }
         });
     }
 
+    fn print_metadata(metadata: &PackageMetadata, indent: &str) {
+        let fields = [
+            ("description", &metadata.description),
+            ("license", &metadata.license),
+            ("repository", &metadata.repository),
+            ("homepage", &metadata.homepage),
+        ];
+
+        for (name, value) in fields {
+            if let Some(value) = value {
+                println!("{indent}{name}: {value}");
+            }
+        }
+    }
+
     fn print_release(version: &Version, content: &AnyHash) {
         println!("    {version} ({content})");
     }
+
+    fn print_yanked_release(version: &Version, reason: Option<&YankReason>) {
+        match reason {
+            Some(reason) => println!("    {version} (yanked: {reason})"),
+            None => println!("    {version} (yanked)"),
+        }
+    }
 }
@@ -1,12 +1,19 @@
-use crate::keyring::{delete_signing_key, get_signing_key, get_signing_key_entry, set_signing_key};
+use crate::keyring::{
+    decode_private_key, delete_signing_key, get_signing_key, get_signing_key_entry,
+    read_passphrase, set_signing_key,
+};
 use anyhow::{bail, Context, Result};
-use clap::{Args, Subcommand};
+use clap::{Args, Subcommand, ValueEnum};
 use dialoguer::{theme::ColorfulTheme, Confirm};
 use keyring::{Entry, Error as KeyringError};
-use p256::ecdsa::SigningKey;
-use rand_core::OsRng;
+use secrecy::zeroize::Zeroizing;
+use std::{
+    fs::OpenOptions,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+};
 use warg_client::RegistryUrl;
-use warg_crypto::signing::PrivateKey;
+use warg_crypto::signing::{generate_pair, EncryptedPrivateKey, PrivateKey, SignatureAlgorithm};
 
 /// Manage signing keys for interacting with a registry.
 #[derive(Args)]
@@ -23,6 +30,8 @@ impl KeyCommand {
             KeySubcommand::New(cmd) => cmd.exec().await,
             KeySubcommand::Info(cmd) => cmd.exec().await,
             KeySubcommand::Set(cmd) => cmd.exec().await,
+            KeySubcommand::Export(cmd) => cmd.exec().await,
+            KeySubcommand::Import(cmd) => cmd.exec().await,
             KeySubcommand::Delete(cmd) => cmd.exec().await,
         }
     }
@@ -37,6 +46,10 @@ pub enum KeySubcommand {
     Info(KeyInfoCommand),
     /// Sets the signing key for a registry in the local keyring.
     Set(KeySetCommand),
+    /// Exports the signing key for a registry from the local keyring.
+    Export(KeyExportCommand),
+    /// Imports a signing key for a registry into the local keyring.
+    Import(KeyImportCommand),
     /// Deletes the signing key for a registry from the local keyring.
     Delete(KeyDeleteCommand),
 }
@@ -85,11 +98,51 @@ impl std::fmt::Display for KeyringEntryArgs {
 pub struct KeyNewCommand {
     #[clap(flatten)]
     keyring_entry: KeyringEntryArgs,
+    /// The signature algorithm of the new key (`ecdsa-p256` or `ed25519`).
+    #[clap(long, value_name = "ALGORITHM", default_value = "ecdsa-p256")]
+    algorithm: SignatureAlgorithm,
+    /// The path of a key file to write the new key to instead of the local keyring.
+    #[clap(long, short, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Encrypt the key file with a passphrase.
+    #[clap(long, requires = "output")]
+    encrypt: bool,
+    /// The path to a file containing the passphrase to encrypt the key file with.
+    ///
+    /// If not specified, the passphrase is read from the `WARG_KEY_PASSPHRASE` environment variable or prompted for.
+    #[clap(long, value_name = "PASSPHRASE_FILE", requires = "encrypt")]
+    passphrase_file: Option<PathBuf>,
 }
 
 impl KeyNewCommand {
     /// Executes the command.
     pub async fn exec(self) -> Result<()> {
+        if let Some(path) = &self.output {
+            if path.exists() {
+                bail!("key file `{}` already exists", path.display());
+            }
+
+            let (public_key, key) = generate_pair(&self.algorithm);
+            let contents = if self.encrypt {
+                let passphrase = read_passphrase(self.passphrase_file.as_deref(), true)?;
+                Zeroizing::new(
+                    EncryptedPrivateKey::encrypt(&key, &passphrase)
+                        .context("failed to encrypt signing key")?
+                        .to_string(),
+                )
+            } else {
+                key.encode()
+            };
+
+            write_key_file(path, &contents, true)?;
+            println!(
+                "signing key `{fingerprint}` was written to `{path}`",
+                fingerprint = public_key.fingerprint(),
+                path = path.display()
+            );
+            return Ok(());
+        }
+
         let entry = self.keyring_entry.get_entry()?;
 
         match entry.get_password() {
@@ -111,7 +164,7 @@ impl KeyNewCommand {
             }
         }
 
-        let key = SigningKey::random(&mut OsRng).into();
+        let (_, key) = generate_pair(&self.algorithm);
         self.keyring_entry.set_entry(&key)?;
 
         Ok(())
@@ -163,6 +216,122 @@ impl KeySetCommand {
     }
 }
 
+/// The format of an exported key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum KeyFormat {
+    /// A PEM-encoded PKCS#8 private key or SubjectPublicKeyInfo public key.
+    Pem,
+    /// A JSON Web Key.
+    Jwk,
+}
+
+/// Exports the signing key for a registry from the local keyring.
+#[derive(Args)]
+pub struct KeyExportCommand {
+    #[clap(flatten)]
+    keyring_entry: KeyringEntryArgs,
+    /// The format to export the key in.
+    #[clap(long, value_enum, default_value = "pem")]
+    format: KeyFormat,
+    /// Export only the public key.
+    #[clap(long)]
+    public: bool,
+    /// The path of the file to write the key to; defaults to stdout.
+    #[clap(long, short, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+impl KeyExportCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let private_key = self.keyring_entry.get_key()?;
+        let mut exported = match (self.format, self.public) {
+            (KeyFormat::Pem, false) => private_key.to_pkcs8_pem(),
+            (KeyFormat::Jwk, false) => private_key.to_jwk(),
+            (KeyFormat::Pem, true) => Zeroizing::new(private_key.public_key().to_spki_pem()),
+            (KeyFormat::Jwk, true) => Zeroizing::new(private_key.public_key().to_jwk()),
+        };
+
+        if !exported.ends_with('\n') {
+            exported.push('\n');
+        }
+
+        match &self.output {
+            Some(path) => write_key_file(path, &exported, false)?,
+            None => print!("{}", *exported),
+        }
+
+        Ok(())
+    }
+}
+
+/// Imports a signing key for a registry into the local keyring.
+#[derive(Args)]
+pub struct KeyImportCommand {
+    #[clap(flatten)]
+    keyring_entry: KeyringEntryArgs,
+    /// The path of the key file to import; defaults to stdin.
+    ///
+    /// PEM-encoded PKCS#8, JSON Web Key, and `<alg>:<base64>` keys are supported.
+    #[clap(value_name = "PATH")]
+    path: Option<PathBuf>,
+    /// The path to a file containing the passphrase of an encrypted key.
+    ///
+    /// If not specified, the passphrase is read from the `WARG_KEY_PASSPHRASE` environment variable or prompted for.
+    #[clap(long, value_name = "PASSPHRASE_FILE")]
+    passphrase_file: Option<PathBuf>,
+}
+
+impl KeyImportCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let mut contents = Zeroizing::new(String::new());
+        match &self.path {
+            Some(path) => std::fs::File::open(path)
+                .and_then(|mut file| file.read_to_string(&mut contents))
+                .with_context(|| format!("failed to read key from `{}`", path.display()))?,
+            None => std::io::stdin()
+                .read_to_string(&mut contents)
+                .context("failed to read key from stdin")?,
+        };
+
+        let key = decode_private_key(contents.trim(), self.passphrase_file.as_deref())?;
+        self.keyring_entry.set_entry(&key)?;
+
+        println!(
+            "signing key {keyring} was imported successfully",
+            keyring = self.keyring_entry
+        );
+
+        Ok(())
+    }
+}
+
+/// Writes a key file that is readable only by the current user.
+///
+/// A new key is never written over an existing file.
+fn write_key_file(path: &Path, contents: &str, new: bool) -> Result<()> {
+    let mut options = OpenOptions::new();
+    if new {
+        options.write(true).create_new(true);
+    } else {
+        options.write(true).create(true).truncate(true);
+    }
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    options
+        .open(path)
+        .and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            if !contents.ends_with('\n') {
+                file.write_all(b"\n")?;
+            }
+            Ok(())
+        })
+        .with_context(|| format!("failed to write key to `{}`", path.display()))
+}
+
 /// Deletes the signing key for a registry from the local keyring.
 #[derive(Args)]
 pub struct KeyDeleteCommand {
@@ -0,0 +1,302 @@
+use super::CommonOptions;
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use itertools::Itertools;
+use std::time::Duration;
+use warg_crypto::{
+    hash::AnyHash,
+    signing::{KeyID, PublicKey},
+};
+use warg_protocol::{
+    operator::{OperatorEntry, Permission},
+    registry::PackageName,
+};
+
+const DEFAULT_WAIT_INTERVAL: Duration = Duration::from_secs(1);
+
+fn parse_permission(s: &str) -> Result<Permission, String> {
+    s.parse()
+        .map_err(|_| format!("invalid operator permission `{s}`"))
+}
+
+/// Publish records to the operator log of a warg registry.
+#[derive(Subcommand)]
+pub enum OperatorCommand {
+    /// Define a namespace in the registry.
+    DefineNamespace(OperatorDefineNamespaceCommand),
+    /// Import a namespace from another registry.
+    ImportNamespace(OperatorImportNamespaceCommand),
+    /// Grant operator permissions to a key.
+    Grant(OperatorGrantCommand),
+    /// Revoke operator permissions from a key.
+    Revoke(OperatorRevokeCommand),
+    /// Block a package from being published or downloaded.
+    BlockPackage(OperatorBlockPackageCommand),
+    /// Block release content from being served or downloaded.
+    BlockContent(OperatorBlockContentCommand),
+}
+
+impl OperatorCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        match self {
+            Self::DefineNamespace(cmd) => cmd.exec().await,
+            Self::ImportNamespace(cmd) => cmd.exec().await,
+            Self::Grant(cmd) => cmd.exec().await,
+            Self::Revoke(cmd) => cmd.exec().await,
+            Self::BlockPackage(cmd) => cmd.exec().await,
+            Self::BlockContent(cmd) => cmd.exec().await,
+        }
+    }
+}
+
+/// Publishes a single entry to the operator log, optionally waiting for it
+/// to be published.
+async fn publish(common: &CommonOptions, entry: OperatorEntry, no_wait: bool) -> Result<bool> {
+    let config = common.read_config()?;
+    let client = common.create_client(&config)?;
+    let signer = common.signer(client.url()).await?;
+
+    let record_id = client
+        .publish_operator_record(signer.as_ref(), vec![entry])
+        .await?;
+
+    if no_wait {
+        println!("submitted operator record `{record_id}` for publishing");
+        return Ok(false);
+    }
+
+    client
+        .wait_for_operator_publish(&record_id, DEFAULT_WAIT_INTERVAL)
+        .await?;
+
+    Ok(true)
+}
+
+/// Define a namespace in the registry.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct OperatorDefineNamespaceCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The namespace being defined.
+    #[clap(value_name = "NAMESPACE")]
+    pub namespace: String,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl OperatorDefineNamespaceCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let entry = OperatorEntry::DefineNamespace {
+            namespace: self.namespace.clone(),
+        };
+
+        if publish(&self.common, entry, self.no_wait).await? {
+            println!(
+                "defined namespace `{namespace}`",
+                namespace = self.namespace
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Import a namespace from another registry.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct OperatorImportNamespaceCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The namespace being imported.
+    #[clap(value_name = "NAMESPACE")]
+    pub namespace: String,
+    /// The registry the namespace is imported from.
+    #[clap(long, value_name = "REGISTRY")]
+    pub from: String,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl OperatorImportNamespaceCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let entry = OperatorEntry::ImportNamespace {
+            namespace: self.namespace.clone(),
+            registry: self.from.clone(),
+        };
+
+        if publish(&self.common, entry, self.no_wait).await? {
+            println!(
+                "imported namespace `{namespace}` from registry `{registry}`",
+                namespace = self.namespace,
+                registry = self.from
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Grant operator permissions to a key.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct OperatorGrantCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The public key to grant permissions to.
+    #[clap(value_name = "PUBLIC_KEY")]
+    pub public_key: PublicKey,
+    /// The permission(s) to grant.
+    #[clap(
+        long = "permission",
+        value_delimiter = ',',
+        value_parser = parse_permission,
+        required = true
+    )]
+    pub permissions: Vec<Permission>,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl OperatorGrantCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let entry = OperatorEntry::GrantFlat {
+            key: self.public_key.clone(),
+            permissions: self.permissions.clone(),
+        };
+
+        if publish(&self.common, entry, self.no_wait).await? {
+            println!(
+                "granted operator permission(s) ({permissions_str}) to key ID `{key_id}`",
+                permissions_str = self.permissions.iter().join(","),
+                key_id = self.public_key.fingerprint(),
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Revoke operator permissions from a key.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct OperatorRevokeCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The key ID to revoke permissions from.
+    #[clap(value_name = "KEY_ID")]
+    pub key: KeyID,
+    /// The permission(s) to revoke.
+    #[clap(
+        long = "permission",
+        value_delimiter = ',',
+        value_parser = parse_permission,
+        required = true
+    )]
+    pub permissions: Vec<Permission>,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl OperatorRevokeCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let entry = OperatorEntry::RevokeFlat {
+            key_id: self.key.clone(),
+            permissions: self.permissions.clone(),
+        };
+
+        if publish(&self.common, entry, self.no_wait).await? {
+            println!(
+                "revoked operator permission(s) ({permissions_str}) from key ID `{key_id}`",
+                permissions_str = self.permissions.iter().join(","),
+                key_id = self.key,
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Block a package from being published or downloaded.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct OperatorBlockPackageCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The name of the package being blocked.
+    #[clap(value_name = "PACKAGE")]
+    pub name: PackageName,
+    /// The reason the package is being blocked.
+    #[clap(long, value_name = "REASON")]
+    pub reason: String,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl OperatorBlockPackageCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let entry = OperatorEntry::BlockPackage {
+            name: self.name.clone(),
+            reason: self.reason.clone(),
+        };
+
+        if publish(&self.common, entry, self.no_wait).await? {
+            println!("blocked package `{name}`", name = self.name);
+        }
+
+        Ok(())
+    }
+}
+
+/// Block release content from being served or downloaded.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct OperatorBlockContentCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The digest of the content being blocked.
+    #[clap(value_name = "DIGEST")]
+    pub digest: AnyHash,
+    /// The reason the content is being blocked.
+    #[clap(long, value_name = "REASON")]
+    pub reason: String,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl OperatorBlockContentCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let entry = OperatorEntry::BlockContent {
+            content: self.digest.clone(),
+            reason: self.reason.clone(),
+        };
+
+        if publish(&self.common, entry, self.no_wait).await? {
+            println!(
+                "blocked content with digest `{digest}`",
+                digest = self.digest
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -3,9 +3,14 @@ use anyhow::{anyhow, bail, Context, Result};
 use clap::{Args, Subcommand};
 use futures::TryStreamExt;
 use itertools::Itertools;
-use std::{future::Future, path::PathBuf, time::Duration};
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 use tokio::io::BufReader;
 use tokio_util::io::ReaderStream;
+use walkdir::WalkDir;
 use warg_client::{
     storage::{ContentStorage as _, PublishEntry, PublishInfo, RegistryStorage as _},
     FileSystemClient,
@@ -15,13 +20,79 @@ use warg_crypto::{
     signing::{KeyID, PublicKey},
 };
 use warg_protocol::{
-    package::Permission,
+    content::{ContentIndex, ContentPath},
+    package::{PackageMetadata, PackageRecord, Permission, ReleaseDependency, YankReason},
     registry::{PackageName, RecordId},
-    Version,
+    ProtoEnvelope, Version, VersionReq,
 };
 
 const DEFAULT_WAIT_INTERVAL: Duration = Duration::from_secs(1);
 
+/// Stores the file at the given path in client content storage.
+async fn store_file(client: &FileSystemClient, path: &Path) -> Result<AnyHash> {
+    client
+        .content()
+        .store_content(
+            Box::pin(
+                ReaderStream::new(BufReader::new(
+                    tokio::fs::File::open(path).await.with_context(|| {
+                        format!("failed to open `{path}`", path = path.display())
+                    })?,
+                ))
+                .map_err(|e| anyhow!(e)),
+            ),
+            None,
+        )
+        .await
+}
+
+/// Stores every file in the given directory in client content storage.
+///
+/// Returns the content index of the directory.
+async fn store_directory(client: &FileSystemClient, dir: &Path) -> Result<ContentIndex> {
+    let mut index = ContentIndex::new();
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry
+            .with_context(|| format!("failed to read directory `{dir}`", dir = dir.display()))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative = entry
+            .path()
+            .strip_prefix(dir)
+            .expect("walked path should be within the directory");
+        let path = relative
+            .iter()
+            .map(|segment| {
+                segment.to_str().with_context(|| {
+                    format!(
+                        "path `{path}` is not valid UTF-8",
+                        path = relative.display()
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?
+            .join("/");
+        let path = ContentPath::new(path)?;
+
+        let digest = store_file(client, entry.path()).await?;
+        index.insert(path, digest)?;
+    }
+
+    if index.is_empty() {
+        bail!("directory `{dir}` contains no files", dir = dir.display());
+    }
+
+    Ok(index)
+}
+
+fn parse_timestamp(s: &str) -> Result<SystemTime, String> {
+    chrono::DateTime::parse_from_rfc3339(s)
+        .map(Into::into)
+        .map_err(|e| format!("invalid RFC 3339 timestamp `{s}`: {e}"))
+}
+
 /// Used to enqueue a publish entry if there is a pending publish.
 /// Returns `Ok(None)` if the entry was enqueued or `Ok(Some(entry))` if there
 /// was no pending publish.
@@ -42,6 +113,13 @@ where
                 );
             }
 
+            if info.signed() {
+                bail!(
+                    "the pending publish for package `{name}` has been signed and can no longer be changed; use `publish abort` to abort the current publish",
+                    name = info.name
+                );
+            }
+
             let entry = entry(client).await?;
 
             if matches!(entry, PublishEntry::Init) && info.initializing() {
@@ -56,6 +134,77 @@ where
     }
 }
 
+/// Unyank a previously yanked package release from a warg registry.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct PublishUnyankCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The package name being unyanked.
+    #[clap(long, short, value_name = "PACKAGE")]
+    pub name: PackageName,
+    /// The version of the package being unyanked.
+    #[clap(long, short, value_name = "VERSION")]
+    pub version: Version,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl PublishUnyankCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config)?;
+
+        let version = self.version.clone();
+        match enqueue(&client, &self.name, move |_| async move {
+            Ok(PublishEntry::Unyank { version })
+        })
+        .await?
+        {
+            Some(entry) => {
+                let signer = self.common.signer(client.url()).await?;
+                let record_id = client
+                    .publish_with_info(
+                        signer.as_ref(),
+                        PublishInfo {
+                            name: self.name.clone(),
+                            head: None,
+                            entries: vec![entry],
+                            record: None,
+                        },
+                    )
+                    .await?;
+
+                if self.no_wait {
+                    println!("submitted record `{record_id}` for publishing");
+                } else {
+                    client
+                        .wait_for_publish(&self.name, &record_id, DEFAULT_WAIT_INTERVAL)
+                        .await?;
+
+                    println!(
+                        "unyanked version {version} of package `{name}`",
+                        version = self.version,
+                        name = self.name
+                    );
+                }
+            }
+            None => {
+                println!(
+                    "added unyank of version {version} for package `{name}` to pending publish",
+                    version = self.version,
+                    name = self.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Publish a package to a warg registry.
 #[derive(Subcommand)]
 pub enum PublishCommand {
@@ -65,16 +214,30 @@ pub enum PublishCommand {
     Release(PublishReleaseCommand),
     /// Yank a package version.
     Yank(PublishYankCommand),
+    /// Unyank a previously yanked package version.
+    Unyank(PublishUnyankCommand),
     /// Grant permissions for the package.
     Grant(PublishGrantCommand),
     /// Revoke permissions for the package.
     Revoke(PublishRevokeCommand),
+    /// Rotate a key of the package to a new key.
+    RotateKey(PublishRotateKeyCommand),
+    /// Deprecate the package.
+    Deprecate(PublishDeprecateCommand),
+    /// Remove the deprecation of the package.
+    Undeprecate(PublishUndeprecateCommand),
+    /// Set the metadata of the package or a package version.
+    Metadata(PublishMetadataCommand),
+    /// Set the number of keys that must sign releases of the package.
+    ReleaseThreshold(PublishReleaseThresholdCommand),
     /// Start a new pending publish.
     Start(PublishStartCommand),
     /// List the records in a pending publish.
     List(PublishListCommand),
     /// Abort a pending publish.
     Abort(PublishAbortCommand),
+    /// Sign or cosign a pending publish.
+    Cosign(PublishCosignCommand),
     /// Submit a pending publish.
     Submit(PublishSubmitCommand),
     /// Wait for a pending publish to complete.
@@ -88,11 +251,18 @@ impl PublishCommand {
             Self::Init(cmd) => cmd.exec().await,
             Self::Release(cmd) => cmd.exec().await,
             Self::Yank(cmd) => cmd.exec().await,
+            Self::Unyank(cmd) => cmd.exec().await,
             Self::Grant(cmd) => cmd.exec().await,
             Self::Revoke(cmd) => cmd.exec().await,
+            Self::RotateKey(cmd) => cmd.exec().await,
+            Self::Deprecate(cmd) => cmd.exec().await,
+            Self::Undeprecate(cmd) => cmd.exec().await,
+            Self::Metadata(cmd) => cmd.exec().await,
+            Self::ReleaseThreshold(cmd) => cmd.exec().await,
             Self::Start(cmd) => cmd.exec().await,
             Self::List(cmd) => cmd.exec().await,
             Self::Abort(cmd) => cmd.exec().await,
+            Self::Cosign(cmd) => cmd.exec().await,
             Self::Submit(cmd) => cmd.exec().await,
             Self::Wait(cmd) => cmd.exec().await,
         }
@@ -126,14 +296,15 @@ impl PublishInitCommand {
         .await?
         {
             Some(entry) => {
-                let signing_key = self.common.signing_key(client.url())?;
+                let signer = self.common.signer(client.url()).await?;
                 let record_id = client
                     .publish_with_info(
-                        &signing_key,
+                        signer.as_ref(),
                         PublishInfo {
                             name: self.name.clone(),
                             head: None,
                             entries: vec![entry],
+                            record: None,
                         },
                     )
                     .await?;
@@ -177,8 +348,15 @@ pub struct PublishReleaseCommand {
     #[clap(long, short, value_name = "VERSION")]
     pub version: Version,
     /// The path to the package being published.
+    ///
+    /// If the path is a directory, a multi-file release of every file in
+    /// the directory is published.
     #[clap(value_name = "PATH")]
     pub path: PathBuf,
+    /// A registry package the release was built against, of the form
+    /// `<package>@<requirement>` (e.g. `example:dep@^1.0`).
+    #[clap(long = "dependency", value_name = "DEPENDENCY")]
+    pub dependencies: Vec<ReleaseDependency>,
     /// Whether to wait for the publish to complete.
     #[clap(long)]
     pub no_wait: bool,
@@ -192,35 +370,38 @@ impl PublishReleaseCommand {
 
         let path = self.path.clone();
         let version = self.version.clone();
+        let dependencies = self.dependencies.clone();
         match enqueue(&client, &self.name, move |c| async move {
-            let content = c
-                .content()
-                .store_content(
-                    Box::pin(
-                        ReaderStream::new(BufReader::new(
-                            tokio::fs::File::open(&path).await.with_context(|| {
-                                format!("failed to open `{path}`", path = path.display())
-                            })?,
-                        ))
-                        .map_err(|e| anyhow!(e)),
-                    ),
-                    None,
-                )
-                .await?;
+            if path.is_dir() {
+                let index = store_directory(c, &path).await?;
+                return Ok(PublishEntry::Release {
+                    version,
+                    content: index.digest(),
+                    index: Some(index),
+                    dependencies,
+                });
+            }
 
-            Ok(PublishEntry::Release { version, content })
+            let content = store_file(c, &path).await?;
+            Ok(PublishEntry::Release {
+                version,
+                content,
+                index: None,
+                dependencies,
+            })
         })
         .await?
         {
             Some(entry) => {
-                let signing_key = self.common.signing_key(client.url())?;
+                let signer = self.common.signer(client.url()).await?;
                 let record_id = client
                     .publish_with_info(
-                        &signing_key,
+                        signer.as_ref(),
                         PublishInfo {
                             name: self.name.clone(),
                             head: None,
                             entries: vec![entry],
+                            record: None,
                         },
                     )
                     .await?;
@@ -265,6 +446,11 @@ pub struct PublishYankCommand {
     /// The version of the package being yanked.
     #[clap(long, short, value_name = "VERSION")]
     pub version: Version,
+    /// The reason for the yank.
+    ///
+    /// Either `security`, `broken`, `superseded`, or free text.
+    #[clap(long, value_name = "REASON")]
+    pub reason: Option<YankReason>,
     /// Whether to wait for the publish to complete.
     #[clap(long)]
     pub no_wait: bool,
@@ -277,20 +463,22 @@ impl PublishYankCommand {
         let client = self.common.create_client(&config)?;
 
         let version = self.version.clone();
+        let reason = self.reason.clone();
         match enqueue(&client, &self.name, move |_| async move {
-            Ok(PublishEntry::Yank { version })
+            Ok(PublishEntry::Yank { version, reason })
         })
         .await?
         {
             Some(entry) => {
-                let signing_key = self.common.signing_key(client.url())?;
+                let signer = self.common.signer(client.url()).await?;
                 let record_id = client
                     .publish_with_info(
-                        &signing_key,
+                        signer.as_ref(),
                         PublishInfo {
                             name: self.name.clone(),
                             head: None,
                             entries: vec![entry],
+                            record: None,
                         },
                     )
                     .await?;
@@ -342,6 +530,12 @@ pub struct PublishGrantCommand {
         default_value = "release,yank"
     )]
     pub permissions: Vec<Permission>,
+    /// The RFC 3339 timestamp after which the granted permission(s) expire.
+    #[clap(long, value_name = "TIMESTAMP", value_parser = parse_timestamp)]
+    pub expires: Option<SystemTime>,
+    /// The semver requirement of the versions the granted permission(s) are limited to.
+    #[clap(long, value_name = "VERSIONS")]
+    pub versions: Option<VersionReq>,
     /// Whether to wait for the publish to complete.
     #[clap(long)]
     pub no_wait: bool,
@@ -357,19 +551,22 @@ impl PublishGrantCommand {
             Ok(PublishEntry::Grant {
                 key: self.public_key.clone(),
                 permissions: self.permissions.clone(),
+                expires: self.expires,
+                versions: self.versions.clone(),
             })
         })
         .await?
         {
             Some(entry) => {
-                let signing_key = self.common.signing_key(client.url())?;
+                let signer = self.common.signer(client.url()).await?;
                 let record_id = client
                     .publish_with_info(
-                        &signing_key,
+                        signer.as_ref(),
                         PublishInfo {
                             name: self.name.clone(),
                             head: None,
                             entries: vec![entry],
+                            record: None,
                         },
                     )
                     .await?;
@@ -443,14 +640,15 @@ impl PublishRevokeCommand {
         .await?
         {
             Some(entry) => {
-                let signing_key = self.common.signing_key(client.url())?;
+                let signer = self.common.signer(client.url()).await?;
                 let record_id = client
                     .publish_with_info(
-                        &signing_key,
+                        signer.as_ref(),
                         PublishInfo {
                             name: self.name.clone(),
                             head: None,
                             entries: vec![entry],
+                            record: None,
                         },
                     )
                     .await?;
@@ -484,6 +682,392 @@ impl PublishRevokeCommand {
     }
 }
 
+/// Rotate a key of a package, moving all of its permissions to a new key.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct PublishRotateKeyCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The package name.
+    #[clap(long, short, value_name = "PACKAGE")]
+    pub name: PackageName,
+    /// The public key to move the permissions to.
+    #[clap(value_name = "NEW_PUBLIC_KEY")]
+    pub new_key: PublicKey,
+    /// The key ID being rotated; defaults to the ID of the signing key.
+    #[clap(long, value_name = "KEY_ID")]
+    pub old_key_id: Option<KeyID>,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl PublishRotateKeyCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config)?;
+        let signer = self.common.signer(client.url()).await?;
+
+        let old_key_id = self
+            .old_key_id
+            .clone()
+            .unwrap_or_else(|| signer.public_key().fingerprint());
+
+        match enqueue(&client, &self.name, |_| async {
+            Ok(PublishEntry::RotateKey {
+                old_key_id: old_key_id.clone(),
+                new_key: self.new_key.clone(),
+            })
+        })
+        .await?
+        {
+            Some(entry) => {
+                let record_id = client
+                    .publish_with_info(
+                        signer.as_ref(),
+                        PublishInfo {
+                            name: self.name.clone(),
+                            head: None,
+                            entries: vec![entry],
+                            record: None,
+                        },
+                    )
+                    .await?;
+
+                if self.no_wait {
+                    println!("submitted record `{record_id}` for publishing");
+                } else {
+                    client
+                        .wait_for_publish(&self.name, &record_id, DEFAULT_WAIT_INTERVAL)
+                        .await?;
+
+                    println!(
+                        "rotated key ID `{old_key_id}` to key ID `{new_key_id}` for package `{name}`",
+                        new_key_id = self.new_key.fingerprint(),
+                        name = self.name
+                    );
+                }
+            }
+            None => {
+                println!(
+                    "added rotation of key ID `{old_key_id}` to key ID `{new_key_id}` for package `{name}` to pending publish",
+                    new_key_id = self.new_key.fingerprint(),
+                    name = self.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Deprecate a package in a warg registry.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct PublishDeprecateCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The package name being deprecated.
+    #[clap(long, short, value_name = "PACKAGE")]
+    pub name: PackageName,
+    /// The package that supersedes the deprecated package.
+    #[clap(long, value_name = "PACKAGE")]
+    pub successor: Option<PackageName>,
+    /// The deprecation message.
+    #[clap(long, value_name = "MESSAGE")]
+    pub message: Option<String>,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl PublishDeprecateCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config)?;
+
+        let successor = self.successor.clone();
+        let message = self.message.clone();
+        match enqueue(&client, &self.name, move |_| async move {
+            Ok(PublishEntry::Deprecate { successor, message })
+        })
+        .await?
+        {
+            Some(entry) => {
+                let signer = self.common.signer(client.url()).await?;
+                let record_id = client
+                    .publish_with_info(
+                        signer.as_ref(),
+                        PublishInfo {
+                            name: self.name.clone(),
+                            head: None,
+                            entries: vec![entry],
+                            record: None,
+                        },
+                    )
+                    .await?;
+
+                if self.no_wait {
+                    println!("submitted record `{record_id}` for publishing");
+                } else {
+                    client
+                        .wait_for_publish(&self.name, &record_id, DEFAULT_WAIT_INTERVAL)
+                        .await?;
+
+                    println!("deprecated package `{name}`", name = self.name);
+                }
+            }
+            None => {
+                println!(
+                    "added deprecation of package `{name}` to pending publish",
+                    name = self.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Remove the deprecation of a package in a warg registry.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct PublishUndeprecateCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The package name being undeprecated.
+    #[clap(long, short, value_name = "PACKAGE")]
+    pub name: PackageName,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl PublishUndeprecateCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config)?;
+
+        match enqueue(&client, &self.name, |_| async {
+            Ok(PublishEntry::Undeprecate)
+        })
+        .await?
+        {
+            Some(entry) => {
+                let signer = self.common.signer(client.url()).await?;
+                let record_id = client
+                    .publish_with_info(
+                        signer.as_ref(),
+                        PublishInfo {
+                            name: self.name.clone(),
+                            head: None,
+                            entries: vec![entry],
+                            record: None,
+                        },
+                    )
+                    .await?;
+
+                if self.no_wait {
+                    println!("submitted record `{record_id}` for publishing");
+                } else {
+                    client
+                        .wait_for_publish(&self.name, &record_id, DEFAULT_WAIT_INTERVAL)
+                        .await?;
+
+                    println!("undeprecated package `{name}`", name = self.name);
+                }
+            }
+            None => {
+                println!(
+                    "added undeprecation of package `{name}` to pending publish",
+                    name = self.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Set the metadata of a package or a package version.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct PublishMetadataCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The package name.
+    #[clap(long, short, value_name = "PACKAGE")]
+    pub name: PackageName,
+    /// The version of the package the metadata applies to.
+    ///
+    /// If not specified, the metadata applies to the package itself.
+    #[clap(long, short, value_name = "VERSION")]
+    pub version: Option<Version>,
+    /// A short description of the package.
+    #[clap(long, value_name = "DESCRIPTION")]
+    pub description: Option<String>,
+    /// The license of the package (e.g. an SPDX license expression).
+    #[clap(long, value_name = "LICENSE")]
+    pub license: Option<String>,
+    /// The URL of the package's source repository.
+    #[clap(long, value_name = "URL")]
+    pub repository: Option<String>,
+    /// The URL of the package's homepage.
+    #[clap(long, value_name = "URL")]
+    pub homepage: Option<String>,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl PublishMetadataCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config)?;
+
+        let metadata = PackageMetadata {
+            description: self.description.clone(),
+            license: self.license.clone(),
+            repository: self.repository.clone(),
+            homepage: self.homepage.clone(),
+        };
+
+        if metadata.is_empty() {
+            bail!("at least one metadata field must be specified");
+        }
+
+        let version = self.version.clone();
+        match enqueue(&client, &self.name, move |_| async move {
+            Ok(PublishEntry::Metadata { version, metadata })
+        })
+        .await?
+        {
+            Some(entry) => {
+                let signer = self.common.signer(client.url()).await?;
+                let record_id = client
+                    .publish_with_info(
+                        signer.as_ref(),
+                        PublishInfo {
+                            name: self.name.clone(),
+                            head: None,
+                            entries: vec![entry],
+                            record: None,
+                        },
+                    )
+                    .await?;
+
+                if self.no_wait {
+                    println!("submitted record `{record_id}` for publishing");
+                } else {
+                    client
+                        .wait_for_publish(&self.name, &record_id, DEFAULT_WAIT_INTERVAL)
+                        .await?;
+
+                    match &self.version {
+                        Some(version) => println!(
+                            "set metadata of version {version} of package `{name}`",
+                            name = self.name
+                        ),
+                        None => println!("set metadata of package `{name}`", name = self.name),
+                    }
+                }
+            }
+            None => match &self.version {
+                Some(version) => println!(
+                    "added metadata of version {version} for package `{name}` to pending publish",
+                    name = self.name
+                ),
+                None => println!(
+                    "added metadata for package `{name}` to pending publish",
+                    name = self.name
+                ),
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Set the number of keys that must sign releases of a package.
+#[derive(Args)]
+#[clap(disable_version_flag = true)]
+pub struct PublishReleaseThresholdCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+    /// The package name.
+    #[clap(long, short, value_name = "PACKAGE")]
+    pub name: PackageName,
+    /// The number of keys with the release permission that must sign releases.
+    #[clap(value_name = "THRESHOLD")]
+    pub threshold: u32,
+    /// Whether to wait for the publish to complete.
+    #[clap(long)]
+    pub no_wait: bool,
+}
+
+impl PublishReleaseThresholdCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config)?;
+
+        match enqueue(&client, &self.name, |_| async {
+            Ok(PublishEntry::ReleaseThreshold {
+                threshold: self.threshold,
+            })
+        })
+        .await?
+        {
+            Some(entry) => {
+                let signer = self.common.signer(client.url()).await?;
+                let record_id = client
+                    .publish_with_info(
+                        signer.as_ref(),
+                        PublishInfo {
+                            name: self.name.clone(),
+                            head: None,
+                            entries: vec![entry],
+                            record: None,
+                        },
+                    )
+                    .await?;
+
+                if self.no_wait {
+                    println!("submitted record `{record_id}` for publishing");
+                } else {
+                    client
+                        .wait_for_publish(&self.name, &record_id, DEFAULT_WAIT_INTERVAL)
+                        .await?;
+
+                    println!(
+                        "set release threshold of package `{name}` to {threshold}",
+                        name = self.name,
+                        threshold = self.threshold
+                    );
+                }
+            }
+            None => {
+                println!(
+                    "added release threshold of {threshold} for package `{name}` to pending publish",
+                    threshold = self.threshold,
+                    name = self.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
 /// Start a new pending publish.
 #[derive(Args)]
 #[clap(disable_version_flag = true)]
@@ -509,6 +1093,7 @@ impl PublishStartCommand {
                     name: self.name.clone(),
                     head: None,
                     entries: Default::default(),
+                    record: None,
                 }))
                 .await?;
 
@@ -550,13 +1135,35 @@ impl PublishListCommand {
                         PublishEntry::Init => {
                             println!("initialize package");
                         }
-                        PublishEntry::Release { version, content } => {
-                            println!("release {version} with content digest `{content}`")
+                        PublishEntry::Release {
+                            version,
+                            content,
+                            index,
+                            dependencies,
+                        } => {
+                            match index {
+                                Some(index) => println!(
+                                    "release {version} of {count} file(s) with content digest `{content}`",
+                                    count = index.len()
+                                ),
+                                None => {
+                                    println!("release {version} with content digest `{content}`")
+                                }
+                            }
+                            for dependency in dependencies {
+                                println!("  depends on `{dependency}`");
+                            }
                         }
-                        PublishEntry::Yank { version } => {
-                            println!("yank {version}")
+                        PublishEntry::Yank { version, reason } => match reason {
+                            Some(reason) => println!("yank {version} ({reason})"),
+                            None => println!("yank {version}"),
+                        },
+                        PublishEntry::Unyank { version } => {
+                            println!("unyank {version}")
                         }
-                        PublishEntry::Grant { key, permissions } => println!(
+                        PublishEntry::Grant {
+                            key, permissions, ..
+                        } => println!(
                             "grant ({permissions_str}) to `{key_id}`",
                             permissions_str = permissions.iter().join(","),
                             key_id = key.fingerprint(),
@@ -568,6 +1175,33 @@ impl PublishListCommand {
                             "revoke ({permissions_str}) from `{key_id}`",
                             permissions_str = permissions.iter().join(","),
                         ),
+                        PublishEntry::RotateKey {
+                            old_key_id,
+                            new_key,
+                        } => println!(
+                            "rotate `{old_key_id}` to `{new_key_id}`",
+                            new_key_id = new_key.fingerprint(),
+                        ),
+                        PublishEntry::Deprecate { successor, .. } => match successor {
+                            Some(successor) => println!("deprecate (successor `{successor}`)"),
+                            None => println!("deprecate"),
+                        },
+                        PublishEntry::Undeprecate => println!("undeprecate"),
+                        PublishEntry::Metadata { version, .. } => match version {
+                            Some(version) => println!("set metadata of {version}"),
+                            None => println!("set package metadata"),
+                        },
+                        PublishEntry::ReleaseThreshold { threshold } => {
+                            println!("set release threshold to {threshold}")
+                        }
+                    }
+                }
+
+                if let Some(record) = info.record {
+                    let envelope: ProtoEnvelope<PackageRecord> = record.try_into()?;
+                    println!("\nsigned by key ID `{key_id}`", key_id = envelope.key_id());
+                    for cosignature in envelope.cosignatures() {
+                        println!("cosigned by key ID `{key_id}`", key_id = cosignature.key_id);
                     }
                 }
             }
@@ -607,6 +1241,44 @@ impl PublishAbortCommand {
     }
 }
 
+/// Sign or cosign a pending publish.
+///
+/// The first signature finalizes the pending publish; each additional
+/// signature is a cosignature from another key (e.g. `--key-name`).
+#[derive(Args)]
+pub struct PublishCosignCommand {
+    /// The common command options.
+    #[clap(flatten)]
+    pub common: CommonOptions,
+}
+
+impl PublishCosignCommand {
+    /// Executes the command.
+    pub async fn exec(self) -> Result<()> {
+        let config = self.common.read_config()?;
+        let client = self.common.create_client(&config)?;
+
+        match client.registry().load_publish().await? {
+            Some(mut info) => {
+                let signer = self.common.signer(client.url()).await?;
+                let cosigning = info.signed();
+                client.sign_with_info(signer.as_ref(), &mut info).await?;
+                client.registry().store_publish(Some(&info)).await?;
+
+                println!(
+                    "{action} the pending publish for package `{name}` with key ID `{key_id}`",
+                    action = if cosigning { "cosigned" } else { "signed" },
+                    name = info.name,
+                    key_id = signer.public_key().fingerprint()
+                );
+            }
+            None => bail!("no pending publish to cosign"),
+        }
+
+        Ok(())
+    }
+}
+
 /// Submit a pending publish.
 #[derive(Args)]
 pub struct PublishSubmitCommand {
@@ -631,8 +1303,10 @@ impl PublishSubmitCommand {
                     name = info.name
                 );
 
-                let signing_key = self.common.signing_key(client.url())?;
-                let record_id = client.publish_with_info(&signing_key, info.clone()).await?;
+                let signer = self.common.signer(client.url()).await?;
+                let record_id = client
+                    .publish_with_info(signer.as_ref(), info.clone())
+                    .await?;
 
                 client.registry().store_publish(None).await?;
 
@@ -652,10 +1326,15 @@ impl PublishSubmitCommand {
                             PublishEntry::Release { version, .. } => {
                                 println!("published version {version} of package `{name}`");
                             }
-                            PublishEntry::Yank { version } => {
+                            PublishEntry::Yank { version, .. } => {
                                 println!("yanked version {version} of package `{name}`")
                             }
-                            PublishEntry::Grant { key, permissions } => {
+                            PublishEntry::Unyank { version } => {
+                                println!("unyanked version {version} of package `{name}`")
+                            }
+                            PublishEntry::Grant {
+                                key, permissions, ..
+                            } => {
                                 println!(
                                     "granted ({permissions_str}) to `{key_id}`",
                                     permissions_str = permissions.iter().join(","),
@@ -669,6 +1348,28 @@ impl PublishSubmitCommand {
                                 "revoked ({permissions_str}) from `{key_id}`",
                                 permissions_str = permissions.iter().join(","),
                             ),
+                            PublishEntry::RotateKey {
+                                old_key_id,
+                                new_key,
+                            } => println!(
+                                "rotated `{old_key_id}` to `{new_key_id}`",
+                                new_key_id = new_key.fingerprint(),
+                            ),
+                            PublishEntry::Deprecate { .. } => {
+                                println!("deprecated package `{name}`")
+                            }
+                            PublishEntry::Undeprecate => {
+                                println!("undeprecated package `{name}`")
+                            }
+                            PublishEntry::Metadata { version, .. } => match version {
+                                Some(version) => println!(
+                                    "set metadata of version {version} of package `{name}`"
+                                ),
+                                None => println!("set metadata of package `{name}`"),
+                            },
+                            PublishEntry::ReleaseThreshold { threshold } => {
+                                println!("set release threshold of package `{name}` to {threshold}")
+                            }
                         }
                     }
                 }
@@ -2,8 +2,10 @@
 
 use anyhow::{bail, Context, Result};
 use keyring::Entry;
+use secrecy::{zeroize::Zeroizing, ExposeSecret, SecretString};
+use std::path::Path;
 use warg_client::RegistryUrl;
-use warg_crypto::signing::PrivateKey;
+use warg_crypto::signing::{EncryptedPrivateKey, PrivateKey};
 
 /// Gets the signing key entry for the given registry and key name.
 pub fn get_signing_key_entry(registry_url: &RegistryUrl, key_name: &str) -> Result<Entry> {
@@ -62,3 +64,66 @@ pub fn delete_signing_key(registry_url: &RegistryUrl, key_name: &str) -> Result<
         }
     }
 }
+
+/// Decodes a private key that is PEM-encoded PKCS#8, a JSON Web Key, in
+/// `<alg>:<base64>` form, or encrypted with a passphrase.
+///
+/// The passphrase of an encrypted key is read as described by
+/// [`read_passphrase`].
+pub fn decode_private_key(key: &str, passphrase_file: Option<&Path>) -> Result<PrivateKey> {
+    if EncryptedPrivateKey::is_encrypted(key) {
+        let encrypted: EncryptedPrivateKey = key
+            .parse()
+            .context("failed to parse encrypted private key")?;
+        let passphrase = read_passphrase(passphrase_file, false)?;
+        encrypted
+            .decrypt(&passphrase)
+            .context("failed to decrypt private key")
+    } else if key.starts_with("-----BEGIN") {
+        PrivateKey::from_pkcs8_pem(key).context("failed to parse PEM private key")
+    } else if key.starts_with('{') {
+        PrivateKey::from_jwk(key).context("failed to parse JSON Web Key")
+    } else {
+        PrivateKey::decode(key.to_string())
+            .context("signing key is not in a supported format (PEM, JWK, or `<alg>:<base64>`)")
+    }
+}
+
+/// Reads the passphrase of an encrypted private key.
+///
+/// The passphrase is read from the given file if there is one, then from the
+/// `WARG_KEY_PASSPHRASE` environment variable; otherwise, the user is
+/// prompted for it. When `confirm` is set, a prompted passphrase must be
+/// entered twice.
+pub fn read_passphrase(passphrase_file: Option<&Path>, confirm: bool) -> Result<SecretString> {
+    if let Some(path) = passphrase_file {
+        let contents = Zeroizing::new(
+            std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read passphrase from `{}`", path.display()))?,
+        );
+        return Ok(SecretString::new(
+            contents.trim_end_matches(['\r', '\n']).to_string(),
+        ));
+    }
+
+    if let Ok(passphrase) = std::env::var("WARG_KEY_PASSPHRASE") {
+        return Ok(SecretString::new(passphrase));
+    }
+
+    let passphrase = SecretString::new(
+        rpassword::prompt_password("enter key passphrase: ")
+            .context("failed to read key passphrase")?,
+    );
+
+    if confirm {
+        let confirmation = Zeroizing::new(
+            rpassword::prompt_password("confirm key passphrase: ")
+                .context("failed to read key passphrase")?,
+        );
+        if passphrase.expose_secret() != confirmation.as_str() {
+            bail!("passphrases do not match");
+        }
+    }
+
+    Ok(passphrase)
+}
@@ -47,6 +47,7 @@ async fn client_incrementally_fetches() -> Result<()> {
                 name: name.clone(),
                 head: None,
                 entries: vec![PublishEntry::Init],
+                record: None,
             },
         )
         .await?;
@@ -61,7 +62,10 @@ async fn client_incrementally_fetches() -> Result<()> {
                     entries: vec![PublishEntry::Release {
                         version: format!("0.{i}.0").parse().unwrap(),
                         content: digest.clone(),
+                        index: None,
+                        dependencies: Vec::new(),
                     }],
+                    record: None,
                 },
             )
             .await?;
@@ -3,6 +3,8 @@
 use super::{support::*, *};
 use anyhow::Result;
 use warg_client::api;
+#[cfg(unix)]
+use warg_crypto::signing::ExternalSigner;
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn it_starts_with_initial_checkpoint() -> Result<()> {
@@ -29,6 +31,38 @@ async fn it_publishes_a_component() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_publishes_with_a_log_file() -> Result<()> {
+    let root = root().await?;
+    let log_file = root.join("registry.log");
+    let (_server, config) =
+        spawn_server_with(&root, None, None, None, |c| c.with_log_file(log_file)).await?;
+    test_component_publishing(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_retains_the_latest_map() -> Result<()> {
+    let root = root().await?;
+    let map_file = root.join("registry.map");
+    let (_server, config) = spawn_server_with(&root, None, None, None, |c| {
+        c.with_map_file(map_file).with_map_retention(1)
+    })
+    .await?;
+    test_map_retention(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_proves_package_absence() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_package_absence(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_proves_map_transitions() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_map_transition(&config).await
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn it_yanks_a_package() -> Result<()> {
     let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
@@ -46,6 +80,35 @@ async fn it_yanks_a_package() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_unyanks_a_package() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_package_unyanking(&config).await?;
+
+    // There should be four entries in the registry
+    let client = api::Client::new(config.default_url.as_ref().unwrap())?;
+    let ts_checkpoint = client.latest_checkpoint().await?;
+    assert_eq!(
+        ts_checkpoint.as_ref().checkpoint.log_length,
+        4,
+        "expected four log entries (initial + release + yank + unyank)"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_deprecates_a_package() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_package_deprecation(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_sets_package_metadata() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_package_metadata(&config).await
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn it_publishes_a_wit_package() -> Result<()> {
     let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
@@ -85,6 +148,70 @@ async fn it_rejects_unauthorized_signing_key() -> Result<()> {
     test_unauthorized_signing_key(&config).await
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_rotates_a_package_key() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_key_rotation(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_publishes_with_an_ed25519_key() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_ed25519_signing_key(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_runs_a_sha512_registry() -> Result<()> {
+    let (_server, config) = spawn_server_with(&root().await?, None, None, None, |c| {
+        c.with_hash_algorithm(HashAlgorithm::Sha512)
+    })
+    .await?;
+    test_hash_algorithm(&config, HashAlgorithm::Sha512).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_runs_a_blake3_registry() -> Result<()> {
+    let (_server, config) = spawn_server_with(&root().await?, None, None, None, |c| {
+        c.with_hash_algorithm(HashAlgorithm::Blake3)
+    })
+    .await?;
+    test_hash_algorithm(&config, HashAlgorithm::Blake3).await
+}
+
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_publishes_with_an_external_signer() -> Result<()> {
+    let root = root().await?;
+    let (_server, config) = spawn_server(&root, None, None, None).await?;
+    let endpoint = spawn_signing_daemon(&root.join("signer.sock"), test_signing_key())?;
+    let signer = ExternalSigner::connect(endpoint).await?;
+    test_external_signer(&config, &signer).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_enforces_version_scoped_grants() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_version_scoped_grant(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_requires_admin_to_grant() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_grant_requires_admin(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_requires_release_threshold_signatures() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_release_threshold(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_rotates_the_operator_key() -> Result<()> {
+    let (server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_operator_key_rotation(&server, &config).await
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn it_rejects_unknown_signing_key() -> Result<()> {
     let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
@@ -103,6 +230,41 @@ async fn it_rejects_invalid_signature() -> Result<()> {
     test_invalid_signature(&config).await
 }
 
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_publishes_operator_records() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_operator_publishing(&config).await?;
+
+    // There should be three entries in the registry
+    let client = api::Client::new(config.default_url.as_ref().unwrap())?;
+    let ts_checkpoint = client.latest_checkpoint().await?;
+    assert_eq!(
+        ts_checkpoint.as_ref().checkpoint.log_length,
+        3,
+        "expected three log entries (initial + operator + component)"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_blocks_packages_and_content() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_operator_blocking(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_publishes_multi_file_releases() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_multi_file_release(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_records_release_dependencies() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_release_dependencies(&config).await
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn it_formats_custom_content_urls() -> Result<()> {
     let (_server, config) = spawn_server(
@@ -120,3 +282,20 @@ async fn it_get_ledger() -> Result<()> {
     let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
     test_get_ledger(&config).await
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_serves_log_tiles() -> Result<()> {
+    let (_server, config) = spawn_server(&root().await?, None, None, None).await?;
+    test_log_tiles(&config).await
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn it_serves_witness_cosigned_checkpoints() -> Result<()> {
+    let witnesses = vec![generate_ed25519_pair().1, generate_ed25519_pair().1];
+    let witness_keys = witnesses.iter().map(|k| k.public_key()).collect::<Vec<_>>();
+    let (_server, config) = spawn_server_with(&root().await?, None, None, None, |c| {
+        c.with_witnesses(witness_keys, 2)
+    })
+    .await?;
+    test_witness_cosigning(&config, witnesses).await
+}
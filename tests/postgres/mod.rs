@@ -31,7 +31,9 @@ fn data_store() -> Result<Box<dyn DataStore>> {
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn it_works_with_postgres() -> TestResult {
     let root = root().await?;
-    let (server, config) = spawn_server(
+    let log_file = root.join("registry.log");
+    let map_file = root.join("registry.map");
+    let (server, config) = spawn_server_with(
         &root,
         None,
         Some(data_store()?),
@@ -39,6 +41,10 @@ async fn it_works_with_postgres() -> TestResult {
             "test".to_string(),
             test_signing_key().public_key().fingerprint(),
         )]),
+        |c| {
+            c.with_log_file(log_file.clone())
+                .with_map_file(map_file.clone())
+        },
     )
     .await?;
 
@@ -77,8 +83,16 @@ async fn it_works_with_postgres() -> TestResult {
 
     drop(server);
 
-    // Restart the server and ensure the data is still there
-    let (server, config) = spawn_server(&root, None, Some(data_store()?), None).await?;
+    // Restart the server and ensure the data is still there; the registry
+    // log and map versions are reopened from their files
+    let witnesses = vec![generate_ed25519_pair().1, generate_ed25519_pair().1];
+    let witness_keys = witnesses.iter().map(|k| k.public_key()).collect::<Vec<_>>();
+    let (server, config) = spawn_server_with(&root, None, Some(data_store()?), None, |c| {
+        c.with_log_file(log_file.clone())
+            .with_map_file(map_file.clone())
+            .with_witnesses(witness_keys, 2)
+    })
+    .await?;
 
     test_unknown_signing_key(&config).await?;
 
@@ -93,6 +107,12 @@ async fn it_works_with_postgres() -> TestResult {
         len = packages.len()
     );
 
+    // These tests publish with keys that are not authorized by the first server
+    test_release_threshold(&config).await?;
+    test_operator_publishing(&config).await?;
+    test_operator_blocking(&config).await?;
+    test_witness_cosigning(&config, witnesses).await?;
+
     // Delete the client cache to force a complete download of all packages below
     fs::remove_dir_all(root.join("content"))?;
     fs::remove_dir_all(root.join("registries"))?;
@@ -1,5 +1,6 @@
 use self::support::*;
 use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use rand_core::OsRng;
 use reqwest::StatusCode;
 use std::{
@@ -12,24 +13,35 @@ use warg_api::v1::{
     content::{ContentSource, ContentSourcesResponse},
     fetch::{FetchPackageNamesRequest, FetchPackageNamesResponse},
     ledger::{LedgerSource, LedgerSourceContentType, LedgerSourcesResponse},
+    monitor::VerificationState,
     package::PublishRecordRequest,
     paths,
+    proof::{AbsenceRequest, InclusionRequest, ProofError, TransitionRequest},
+    witness::{CosignCheckpointRequest, WitnessError},
 };
 use warg_client::{
     api,
-    storage::{PublishEntry, PublishInfo, RegistryStorage},
+    storage::{ContentStorage, PackageInfo, PublishEntry, PublishInfo, RegistryStorage},
     ClientError, Config,
 };
 use warg_crypto::{
-    hash::{HashAlgorithm, Sha256},
-    signing::PrivateKey,
+    hash::{AnyHash, Hash, HashAlgorithm, Sha256},
+    signing::{generate_ed25519_pair, PrivateKey, Signer},
     Encode, Signable,
 };
 use warg_protocol::{
-    package::{PackageEntry, PackageRecord, PACKAGE_RECORD_VERSION},
-    registry::{LogId, PackageName},
-    ProtoEnvelope, ProtoEnvelopeBody, Version,
+    content::{ContentIndex, COMPONENT_ENTRY_PATH},
+    operator::{self, NamespaceState, OperatorEntry},
+    package::{
+        PackageEntry, PackageMetadata, PackageRecord, Permission, ReleaseDependency, YankReason,
+        PACKAGE_RECORD_VERSION,
+    },
+    registry::{
+        Checkpoint, LogId, LogLeaf, MapLeaf, PackageName, TimestampedCheckpoint, WitnessCosignature,
+    },
+    ProtoEnvelope, ProtoEnvelopeBody, SerdeEnvelope, Version,
 };
+use warg_transparency::log::{tiles_between, LogData, Node, TiledLog};
 use wit_component::DecodedWasm;
 
 mod support;
@@ -141,7 +153,9 @@ async fn test_package_yanking(config: &Config) -> Result<()> {
                 head: None,
                 entries: vec![PublishEntry::Yank {
                     version: PACKAGE_VERSION.parse()?,
+                    reason: Some(YankReason::Broken),
                 }],
+                record: None,
             },
         )
         .await?;
@@ -151,8 +165,244 @@ async fn test_package_yanking(config: &Config) -> Result<()> {
 
     // Assert that the package is yanked
     client.upsert([&name]).await?;
-    let opt = client.download(&name, &PACKAGE_VERSION.parse()?).await?;
-    assert!(opt.is_none(), "expected no download, got {opt:?}");
+    match client.download(&name, &PACKAGE_VERSION.parse()?).await {
+        Err(ClientError::PackageVersionYanked {
+            version, reason, ..
+        }) => {
+            assert_eq!(version.to_string(), PACKAGE_VERSION);
+            assert_eq!(reason, Some(YankReason::Broken));
+        }
+        res => panic!("expected the version to be yanked, got {res:?}"),
+    }
+    Ok(())
+}
+
+async fn test_package_unyanking(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:unyankee";
+    const PACKAGE_VERSION: &str = "0.1.0";
+
+    // Publish release
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    let digest = publish(
+        &client,
+        &name,
+        PACKAGE_VERSION,
+        wat::parse_str("(component)")?,
+        true,
+        &signing_key,
+    )
+    .await?;
+
+    // Yank and then unyank the release
+    for entry in [
+        PublishEntry::Yank {
+            version: PACKAGE_VERSION.parse()?,
+            reason: None,
+        },
+        PublishEntry::Unyank {
+            version: PACKAGE_VERSION.parse()?,
+        },
+    ] {
+        if let PublishEntry::Unyank { .. } = entry {
+            // Store the yank as a client predating the retention of the
+            // yanked content would have; the package must be fetched again
+            client.upsert([&name]).await?;
+            forget_yanked_content(config, &name)?;
+        }
+
+        let record_id = client
+            .publish_with_info(
+                &signing_key,
+                PublishInfo {
+                    name: name.clone(),
+                    head: None,
+                    entries: vec![entry],
+                    record: None,
+                },
+            )
+            .await?;
+        client
+            .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+            .await?;
+    }
+
+    // Assert that the release is available with its original content
+    client.upsert([&name]).await?;
+    let download = client
+        .download(&name, &PACKAGE_VERSION.parse()?)
+        .await?
+        .context("expected the release to be available")?;
+    assert_eq!(download.digest, digest);
+    Ok(())
+}
+
+fn forget_yanked_content(config: &Config, name: &PackageName) -> Result<()> {
+    fn find(dir: &std::path::Path, file_name: &str) -> Result<Option<std::path::PathBuf>> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                if let Some(path) = find(&path, file_name)? {
+                    return Ok(Some(path));
+                }
+            } else if path.file_name().and_then(|n| n.to_str()) == Some(file_name) {
+                return Ok(Some(path));
+            }
+        }
+        Ok(None)
+    }
+
+    let log_id = LogId::package_log::<Sha256>(name).to_string();
+    let path = find(
+        config.registries_dir.as_ref().unwrap(),
+        log_id.split_once(':').unwrap().1,
+    )?
+    .context("expected the package to be stored")?;
+
+    let mut info: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path)?)?;
+    for release in info["state"]["releases"]
+        .as_object_mut()
+        .context("expected releases")?
+        .values_mut()
+    {
+        release["state"]
+            .as_object_mut()
+            .context("expected a release state")?
+            .remove("content");
+    }
+    fs::write(&path, serde_json::to_string(&info)?)?;
+    Ok(())
+}
+
+async fn test_package_deprecation(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:deprecated";
+    const PACKAGE_VERSION: &str = "0.1.0";
+
+    // Publish release
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    publish(
+        &client,
+        &name,
+        PACKAGE_VERSION,
+        wat::parse_str("(component)")?,
+        true,
+        &signing_key,
+    )
+    .await?;
+
+    let successor = PackageName::new("test:successor")?;
+    for (entry, expected) in [
+        (
+            PublishEntry::Deprecate {
+                successor: Some(successor.clone()),
+                message: Some("use test:successor instead".to_string()),
+            },
+            Some(successor),
+        ),
+        (PublishEntry::Undeprecate, None),
+    ] {
+        let deprecate = matches!(entry, PublishEntry::Deprecate { .. });
+        let record_id = client
+            .publish_with_info(
+                &signing_key,
+                PublishInfo {
+                    name: name.clone(),
+                    head: None,
+                    entries: vec![entry],
+                    record: None,
+                },
+            )
+            .await?;
+        client
+            .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+            .await?;
+
+        // Assert that the deprecation is returned with the download
+        client.upsert([&name]).await?;
+        let download = client
+            .download(&name, &PACKAGE_VERSION.parse()?)
+            .await?
+            .context("expected the release to be available")?;
+        assert_eq!(download.deprecation.is_some(), deprecate);
+        assert_eq!(
+            download.deprecation.and_then(|d| d.successor),
+            expected,
+            "unexpected deprecation successor"
+        );
+    }
+
+    Ok(())
+}
+
+async fn test_package_metadata(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:metadata";
+    const PACKAGE_VERSION: &str = "0.1.0";
+
+    // Publish release
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    publish(
+        &client,
+        &name,
+        PACKAGE_VERSION,
+        wat::parse_str("(component)")?,
+        true,
+        &signing_key,
+    )
+    .await?;
+
+    // Set package and release metadata
+    let package_metadata = PackageMetadata {
+        description: Some("a test package".to_string()),
+        license: Some("Apache-2.0".to_string()),
+        ..Default::default()
+    };
+    let release_metadata = PackageMetadata {
+        repository: Some("https://example.com/test/metadata".to_string()),
+        ..Default::default()
+    };
+    let record_id = client
+        .publish_with_info(
+            &signing_key,
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![
+                    PublishEntry::Metadata {
+                        version: None,
+                        metadata: package_metadata.clone(),
+                    },
+                    PublishEntry::Metadata {
+                        version: Some(PACKAGE_VERSION.parse()?),
+                        metadata: release_metadata.clone(),
+                    },
+                ],
+                record: None,
+            },
+        )
+        .await?;
+    client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await?;
+
+    // Assert that the metadata is reflected in the package state
+    client.upsert([&name]).await?;
+    let package = client
+        .registry()
+        .load_package(&name)
+        .await?
+        .context("package does not exist in client storage")?;
+    assert_eq!(package.state.metadata(), &package_metadata);
+    let release = package
+        .state
+        .release(&PACKAGE_VERSION.parse()?)
+        .context("release does not exist")?;
+    assert_eq!(release.metadata, release_metadata);
+
     Ok(())
 }
 
@@ -299,83 +549,138 @@ async fn test_unauthorized_signing_key(config: &Config) -> Result<()> {
     Ok(())
 }
 
-async fn test_unknown_signing_key(config: &Config) -> Result<()> {
-    const PACKAGE_NAME: &str = "test:unknown-key";
-    const PACKAGE_VERSION: &str = "0.1.0";
+async fn test_key_rotation(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:rotated-key";
 
     // Start by publishing a new component package
     let name = PackageName::new(PACKAGE_NAME)?;
     let client = create_client(config)?;
-    let signing_key = test_signing_key();
-    publish_component(
-        &client,
-        &name,
-        PACKAGE_VERSION,
-        "(component)",
-        true,
-        &signing_key,
-    )
-    .await?;
+    let old_key = test_signing_key();
+    publish_component(&client, &name, "0.1.0", "(component)", true, &old_key).await?;
 
-    // Next, we're going to publish a new record signed by a different key
-    // The new key is not currently known to the package log.
-    let signing_key = PrivateKey::from(p256::ecdsa::SigningKey::random(&mut OsRng));
+    // Rotate the maintainer key to a new key
+    let new_key = PrivateKey::from(p256::ecdsa::SigningKey::random(&mut OsRng));
+    let record_id = client
+        .publish_with_info(
+            &old_key,
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![PublishEntry::RotateKey {
+                    old_key_id: old_key.public_key().fingerprint(),
+                    new_key: new_key.public_key(),
+                }],
+                record: None,
+            },
+        )
+        .await?;
+    client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await?;
 
+    // The old key should no longer be able to publish
     let message = format!(
         "{:#}",
-        publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key,)
+        publish_component(&client, &name, "0.2.0", "(component)", false, &old_key)
             .await
             .expect_err("expected publish to fail")
     );
-
     assert!(
-        message.contains("unknown key id"),
+        message.contains("did not have required permission release"),
         "unexpected error message: {message}"
     );
 
+    // The new key should be able to publish
+    publish_component(&client, &name, "0.2.0", "(component)", false, &new_key).await?;
+
     Ok(())
 }
 
-async fn test_publishing_name_conflict(config: &Config) -> Result<()> {
+async fn test_ed25519_signing_key(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:ed25519-key";
+
+    // Publish a new component package with an Ed25519 maintainer key
+    let name = PackageName::new(PACKAGE_NAME)?;
     let client = create_client(config)?;
-    let signing_key = test_signing_key();
+    let (_, signing_key) = generate_ed25519_pair();
+    let digest =
+        publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
 
-    publish_component(
-        &client,
-        &PackageName::new("test:name")?,
-        "0.1.0",
-        "(component)",
-        true,
-        &signing_key,
-    )
-    .await?;
+    client.upsert([&name]).await?;
+    let info = client
+        .registry()
+        .load_package(&name)
+        .await?
+        .context("package does not exist in client storage")?;
+    let release = info
+        .state
+        .release(&"0.1.0".parse()?)
+        .context("release does not exist")?;
+    assert_eq!(release.by, signing_key.public_key().fingerprint());
+    assert_eq!(release.content(), Some(&digest));
 
-    // should be rejected
-    publish_component(
-        &client,
-        &PackageName::new("test:NAME")?,
-        "0.1.1",
-        "(component)",
-        true,
-        &signing_key,
-    )
-    .await
-    .expect_err("expected publish to fail");
+    // Another Ed25519 key should not be able to publish
+    let (_, other_key) = generate_ed25519_pair();
+    let message = format!(
+        "{:#}",
+        publish_component(&client, &name, "0.2.0", "(component)", false, &other_key)
+            .await
+            .expect_err("expected publish to fail")
+    );
+    assert!(
+        message.contains(&format!(
+            "unknown key id `{key_id}`",
+            key_id = other_key.public_key().fingerprint()
+        )),
+        "unexpected error message: {message}"
+    );
 
     Ok(())
 }
 
-async fn test_invalid_signature(config: &Config) -> Result<()> {
-    const PACKAGE_NAME: &str = "test:invalid-signature";
+async fn test_hash_algorithm(config: &Config, algorithm: HashAlgorithm) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:hash-algorithm";
 
-    // Use a reqwest client directly here as we're going to be sending an invalid signature
+    // Publish and fetch a component package; fetching verifies the
+    // registry's inclusion proofs using its hash algorithm
     let name = PackageName::new(PACKAGE_NAME)?;
-    let log_id = LogId::package_log::<Sha256>(&name);
-    let url = Url::parse(config.default_url.as_ref().unwrap())?
-        .join(&paths::publish_package_record(&log_id))
-        .unwrap();
-
+    let client = create_client(config)?;
     let signing_key = test_signing_key();
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    client.upsert([&name]).await?;
+
+    // The checkpoint roots use the registry's hash algorithm
+    let api = api::Client::new(config.default_url.as_ref().unwrap())?;
+    let checkpoint = api.latest_checkpoint().await?;
+    assert_eq!(
+        checkpoint.as_ref().checkpoint.log_root.algorithm(),
+        algorithm
+    );
+    assert_eq!(
+        checkpoint.as_ref().checkpoint.map_root.algorithm(),
+        algorithm
+    );
+
+    // The operator log is initialized with the registry's hash algorithm
+    let operator = client
+        .registry()
+        .load_operator()
+        .await?
+        .context("operator log does not exist in client storage")?;
+    assert_eq!(operator.state.hash_algorithm(), Some(algorithm));
+
+    // The package log is initialized with, and identifies records by, the
+    // registry's hash algorithm
+    let info = client
+        .registry()
+        .load_package(&name)
+        .await?
+        .context("package does not exist in client storage")?;
+    let head = info.state.head().as_ref().context("package log is empty")?;
+    assert_eq!(head.digest.algorithm(), algorithm);
+
+    // Publishing with a package log of another hash algorithm is rejected
+    let other_name = PackageName::new("test:other-hash-algorithm")?;
     let record = ProtoEnvelope::signed_contents(
         &signing_key,
         PackageRecord {
@@ -383,80 +688,774 @@ async fn test_invalid_signature(config: &Config) -> Result<()> {
             version: PACKAGE_RECORD_VERSION,
             timestamp: SystemTime::now(),
             entries: vec![PackageEntry::Init {
-                hash_algorithm: warg_crypto::hash::HashAlgorithm::Sha256,
+                hash_algorithm: HashAlgorithm::Sha256,
                 key: signing_key.public_key(),
             }],
         },
     )?;
-
-    let body = PublishRecordRequest {
-        package_name: Cow::Borrowed(&name),
-        record: Cow::Owned(ProtoEnvelopeBody::from(record)),
-        content_sources: Default::default(),
+    let message = match api
+        .publish_package_record(
+            &LogId::package_log_for(algorithm, &other_name),
+            PublishRecordRequest {
+                package_name: Cow::Borrowed(&other_name),
+                record: Cow::Owned(record.into()),
+                content_sources: Default::default(),
+            },
+        )
+        .await
+    {
+        Ok(_) => panic!("expected publish to fail"),
+        Err(e) => e.to_string(),
     };
-
-    // Update the signature to one that does not match the contents
-    let mut body = serde_json::to_value(&body).unwrap();
-    body["record"]["signature"] = serde_json::Value::String("ecdsa-p256:MEUCIQCzWZBW6ux9LecP66Y+hjmLZTP/hZVz7puzlPTXcRT2wwIgQZO7nxP0nugtw18MwHZ26ROFWcJmgCtKOguK031Y1D0=".to_string());
-
-    let client = reqwest::Client::new();
-    let response = client
-        .post(url)
-        .json(&serde_json::to_value(&body).unwrap())
-        .send()
-        .await?;
-
-    let status = response.status();
-    let body = response.text().await?;
-    assert_eq!(
-        status,
-        StatusCode::FORBIDDEN,
-        "unexpected response from server: {status}\n{body}",
-    );
     assert!(
-        body.contains("verification failed"),
-        "unexpected response body: {body}"
+        message.contains(&format!(
+            "package log uses hash algorithm `sha256` but the registry uses `{algorithm}`"
+        )),
+        "unexpected error message: {message}"
     );
 
     Ok(())
 }
 
-async fn test_custom_content_url(config: &Config) -> Result<()> {
-    const PACKAGE_NAME: &str = "test:custom-content-url";
-    const PACKAGE_VERSION: &str = "0.1.0";
+#[cfg(unix)]
+async fn test_external_signer(config: &Config, signer: &dyn Signer) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:external-signer";
 
     let name = PackageName::new(PACKAGE_NAME)?;
     let client = create_client(config)?;
-    let signing_key = test_signing_key();
-    let digest = publish_component(
-        &client,
-        &name,
-        PACKAGE_VERSION,
-        "(component)",
-        true,
-        &signing_key,
-    )
-    .await?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, signer).await?;
 
+    // The package log is initialized with the key held by the signer
     client.upsert([&name]).await?;
-    let package = client
+    let info = client
         .registry()
         .load_package(&name)
         .await?
-        .expect("expected the package to exist");
-    package
-        .state
-        .release(&Version::parse(PACKAGE_VERSION)?)
-        .expect("expected the package version to exist");
-
-    // Look up the content URL for the record
-    let client = api::Client::new(config.default_url.as_ref().unwrap())?;
-    let ContentSourcesResponse { content_sources } = client.content_sources(&digest).await?;
-    assert_eq!(content_sources.len(), 1);
-    let sources = content_sources
-        .get(&digest)
-        .expect("expected content source to be provided for the requested digest");
-    assert_eq!(sources.len(), 1);
+        .context("package does not exist in client storage")?;
+    let key_id = signer.public_key().fingerprint();
+    assert_eq!(info.state.public_key(&key_id), Some(&signer.public_key()));
+
+    Ok(())
+}
+
+async fn test_version_scoped_grant(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:scoped-grant";
+
+    // Start by publishing a new component package
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    // Grant a bot key permission to release only 1.x versions
+    let bot_key = PrivateKey::from(p256::ecdsa::SigningKey::random(&mut OsRng));
+    let record_id = client
+        .publish_with_info(
+            &signing_key,
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![PublishEntry::Grant {
+                    key: bot_key.public_key(),
+                    permissions: vec![Permission::Release],
+                    expires: None,
+                    versions: Some("^1.0".parse()?),
+                }],
+                record: None,
+            },
+        )
+        .await?;
+    client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await?;
+
+    // The bot key should be able to publish a version in scope
+    publish_component(&client, &name, "1.0.0", "(component)", false, &bot_key).await?;
+
+    // The bot key should not be able to publish a version out of scope
+    let message = format!(
+        "{:#}",
+        publish_component(&client, &name, "2.0.0", "(component)", false, &bot_key)
+            .await
+            .expect_err("expected publish to fail")
+    );
+    assert!(
+        message.contains("is not granted permission release for version 2.0.0"),
+        "unexpected error message: {message}"
+    );
+
+    Ok(())
+}
+
+async fn test_grant_requires_admin(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:grant-admin";
+
+    // Start by publishing a new component package
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    // Grant a release key permission to release and yank
+    let release_key = PrivateKey::from(p256::ecdsa::SigningKey::random(&mut OsRng));
+    let record_id = client
+        .publish_with_info(
+            &signing_key,
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![PublishEntry::Grant {
+                    key: release_key.public_key(),
+                    permissions: vec![Permission::Release, Permission::Yank],
+                    expires: None,
+                    versions: None,
+                }],
+                record: None,
+            },
+        )
+        .await?;
+    client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await?;
+
+    // The release key should not be able to delegate its permissions
+    let other_key = PrivateKey::from(p256::ecdsa::SigningKey::random(&mut OsRng));
+    let record_id = client
+        .publish_with_info(
+            &release_key,
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![PublishEntry::Grant {
+                    key: other_key.public_key(),
+                    permissions: vec![Permission::Release],
+                    expires: None,
+                    versions: None,
+                }],
+                record: None,
+            },
+        )
+        .await?;
+    let message = format!(
+        "{:#}",
+        client
+            .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+            .await
+            .expect_err("expected publish to fail")
+    );
+    assert!(
+        message.contains("did not have required permission admin"),
+        "unexpected error message: {message}"
+    );
+
+    Ok(())
+}
+
+async fn test_release_threshold(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:release-threshold";
+
+    // Start by publishing a new component package
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    let digest =
+        publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    // Grant a second key permission to release and require two release signatures
+    let cosigning_key = PrivateKey::from(p256::ecdsa::SigningKey::random(&mut OsRng));
+    let record_id = client
+        .publish_with_info(
+            &signing_key,
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![
+                    PublishEntry::Grant {
+                        key: cosigning_key.public_key(),
+                        permissions: vec![Permission::Release],
+                        expires: None,
+                        versions: None,
+                    },
+                    PublishEntry::ReleaseThreshold { threshold: 2 },
+                ],
+                record: None,
+            },
+        )
+        .await?;
+    client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await?;
+
+    // A release signed by a single key should be rejected
+    let release = PublishInfo {
+        name: name.clone(),
+        head: None,
+        entries: vec![PublishEntry::Release {
+            version: "0.2.0".parse().unwrap(),
+            content: digest,
+            index: None,
+            dependencies: Vec::new(),
+        }],
+        record: None,
+    };
+    let message = format!(
+        "{:#}",
+        client
+            .publish_with_info(&signing_key, release.clone())
+            .await
+            .expect_err("expected publish to fail")
+    );
+    assert!(
+        message.contains("requires signatures from 2 keys"),
+        "unexpected error message: {message}"
+    );
+
+    // A release signed by both keys should be accepted
+    let mut release = release;
+    client.sign_with_info(&signing_key, &mut release).await?;
+    client.sign_with_info(&cosigning_key, &mut release).await?;
+    let record_id = client.publish_with_info(&signing_key, release).await?;
+    client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await?;
+
+    // Assert that the release is reflected in the package state
+    client.upsert([&name]).await?;
+    let package = client
+        .registry()
+        .load_package(&name)
+        .await?
+        .context("package does not exist in client storage")?;
+    assert!(package.state.release(&"0.2.0".parse()?).is_some());
+
+    Ok(())
+}
+
+async fn test_unknown_signing_key(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:unknown-key";
+    const PACKAGE_VERSION: &str = "0.1.0";
+
+    // Start by publishing a new component package
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    publish_component(
+        &client,
+        &name,
+        PACKAGE_VERSION,
+        "(component)",
+        true,
+        &signing_key,
+    )
+    .await?;
+
+    // Next, we're going to publish a new record signed by a different key
+    // The new key is not currently known to the package log.
+    let signing_key = PrivateKey::from(p256::ecdsa::SigningKey::random(&mut OsRng));
+
+    let message = format!(
+        "{:#}",
+        publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key,)
+            .await
+            .expect_err("expected publish to fail")
+    );
+
+    assert!(
+        message.contains("unknown key id"),
+        "unexpected error message: {message}"
+    );
+
+    Ok(())
+}
+
+async fn test_publishing_name_conflict(config: &Config) -> Result<()> {
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+
+    publish_component(
+        &client,
+        &PackageName::new("test:name")?,
+        "0.1.0",
+        "(component)",
+        true,
+        &signing_key,
+    )
+    .await?;
+
+    // should be rejected
+    publish_component(
+        &client,
+        &PackageName::new("test:NAME")?,
+        "0.1.1",
+        "(component)",
+        true,
+        &signing_key,
+    )
+    .await
+    .expect_err("expected publish to fail");
+
+    Ok(())
+}
+
+async fn test_invalid_signature(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:invalid-signature";
+
+    // Use a reqwest client directly here as we're going to be sending an invalid signature
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let log_id = LogId::package_log::<Sha256>(&name);
+    let url = Url::parse(config.default_url.as_ref().unwrap())?
+        .join(&paths::publish_package_record(&log_id))
+        .unwrap();
+
+    let signing_key = test_signing_key();
+    let record = ProtoEnvelope::signed_contents(
+        &signing_key,
+        PackageRecord {
+            prev: None,
+            version: PACKAGE_RECORD_VERSION,
+            timestamp: SystemTime::now(),
+            entries: vec![PackageEntry::Init {
+                hash_algorithm: warg_crypto::hash::HashAlgorithm::Sha256,
+                key: signing_key.public_key(),
+            }],
+        },
+    )?;
+
+    let body = PublishRecordRequest {
+        package_name: Cow::Borrowed(&name),
+        record: Cow::Owned(ProtoEnvelopeBody::from(record)),
+        content_sources: Default::default(),
+    };
+
+    // Update the signature to one that does not match the contents
+    let mut body = serde_json::to_value(&body).unwrap();
+    body["record"]["signature"] = serde_json::Value::String("ecdsa-p256:MEUCIQCzWZBW6ux9LecP66Y+hjmLZTP/hZVz7puzlPTXcRT2wwIgQZO7nxP0nugtw18MwHZ26ROFWcJmgCtKOguK031Y1D0=".to_string());
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(url)
+        .json(&serde_json::to_value(&body).unwrap())
+        .send()
+        .await?;
+
+    let status = response.status();
+    let body = response.text().await?;
+    assert_eq!(
+        status,
+        StatusCode::FORBIDDEN,
+        "unexpected response from server: {status}\n{body}",
+    );
+    assert!(
+        body.contains("verification failed"),
+        "unexpected response body: {body}"
+    );
+
+    Ok(())
+}
+
+async fn test_operator_publishing(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "other:component";
+
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+
+    // The namespace is not yet defined, so publishing should fail
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key)
+        .await
+        .expect_err("expected publish to fail");
+
+    // Only keys known to the operator log may publish operator records
+    let message = format!(
+        "{:#}",
+        client
+            .publish_operator_record(
+                &signing_key,
+                vec![OperatorEntry::DefineNamespace {
+                    namespace: "other".to_string(),
+                }],
+            )
+            .await
+            .expect_err("expected publish to fail")
+    );
+
+    assert!(
+        message.contains("unknown key id"),
+        "unexpected error message: {message}"
+    );
+
+    // Define the namespace with the operator key
+    let record_id = client
+        .publish_operator_record(
+            &test_operator_key(),
+            vec![OperatorEntry::DefineNamespace {
+                namespace: "other".to_string(),
+            }],
+        )
+        .await?;
+
+    client
+        .wait_for_operator_publish(&record_id, Duration::from_millis(100))
+        .await?;
+
+    // Ensure the client sees the new namespace
+    client.update().await?;
+    let operator = client
+        .registry()
+        .load_operator()
+        .await?
+        .context("operator log does not exist in client storage")?;
+    assert_eq!(
+        operator.state.namespace_state("other"),
+        Ok(Some(&NamespaceState::Defined))
+    );
+
+    // Publishing to the new namespace should now succeed
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    Ok(())
+}
+
+async fn test_operator_blocking(config: &Config) -> Result<()> {
+    let blocked_package = PackageName::new("test:blocked-package")?;
+    let blocked_content = PackageName::new("test:blocked-content")?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+
+    // Publish two packages with content not used by other tests, as blocking
+    // the content also blocks any other release of it
+    let package_digest = publish_component(
+        &client,
+        &blocked_package,
+        "0.1.0",
+        "(component (core module (memory 1)))",
+        true,
+        &signing_key,
+    )
+    .await?;
+    let digest = publish_component(
+        &client,
+        &blocked_content,
+        "0.1.0",
+        "(component (core module (memory 2)))",
+        true,
+        &signing_key,
+    )
+    .await?;
+
+    // Block the first package and the content of the second; the operator
+    // key must be granted the block permission first
+    let record_id = client
+        .publish_operator_record(
+            &test_operator_key(),
+            vec![
+                OperatorEntry::GrantFlat {
+                    key: test_operator_key().public_key(),
+                    permissions: vec![operator::Permission::Block],
+                },
+                OperatorEntry::BlockPackage {
+                    name: blocked_package.clone(),
+                    reason: "violates registry practices".to_string(),
+                },
+                OperatorEntry::BlockContent {
+                    content: digest.clone(),
+                    reason: "violates registry practices".to_string(),
+                },
+            ],
+        )
+        .await?;
+    client
+        .wait_for_operator_publish(&record_id, Duration::from_millis(100))
+        .await?;
+
+    // The client should refuse to download either release
+    client.update().await?;
+    match client.download(&blocked_package, &"0.1.0".parse()?).await {
+        Err(ClientError::PackageBlocked { name, reason }) => {
+            assert_eq!(name, blocked_package);
+            assert_eq!(reason, "violates registry practices");
+        }
+        res => panic!("expected the package to be blocked, got {res:?}"),
+    }
+    match client
+        .download_exact(&blocked_content, &"0.1.0".parse()?)
+        .await
+    {
+        Err(ClientError::ContentBlocked {
+            digest: blocked, ..
+        }) => assert_eq!(blocked, digest),
+        res => panic!("expected the content to be blocked, got {res:?}"),
+    }
+
+    // The registry should no longer serve the blocked content or the
+    // content released by the blocked package
+    for digest in [&digest, &package_digest] {
+        let url = config
+            .default_url
+            .as_ref()
+            .unwrap()
+            .parse::<Url>()?
+            .join(&format!("content/{}", digest.to_string().replace(':', "-")))?;
+        let response = reqwest::get(url).await?;
+        assert_eq!(response.status(), StatusCode::GONE);
+    }
+
+    // Publishing to the blocked package should be rejected
+    let message = format!(
+        "{:#}",
+        publish_component(
+            &client,
+            &blocked_package,
+            "0.2.0",
+            "(component)",
+            false,
+            &signing_key
+        )
+        .await
+        .expect_err("expected publish to fail")
+    );
+    assert!(
+        message.contains("has been blocked by the registry operator"),
+        "unexpected error message: {message}"
+    );
+
+    Ok(())
+}
+
+async fn test_operator_key_rotation(server: &ServerInstance, config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:operator-key-rotation";
+
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    client.upsert([&name]).await?;
+
+    let api = api::Client::new(config.default_url.as_ref().unwrap())?;
+    let old_key = test_operator_key();
+    let old_checkpoint = api.latest_checkpoint().await?;
+    assert_eq!(old_checkpoint.key_id(), &old_key.public_key().fingerprint());
+
+    // Rotate to a new operator key and wait for a checkpoint signed by it
+    let new_key = PrivateKey::from(p256::ecdsa::SigningKey::random(&mut OsRng));
+    let new_key_id = new_key.public_key().fingerprint();
+    let rotated = SystemTime::now();
+    server.rotate_operator_key(new_key)?;
+
+    let mut attempts = 0;
+    let new_checkpoint = loop {
+        let checkpoint = api.latest_checkpoint().await?;
+        if checkpoint.key_id() == &new_key_id {
+            break checkpoint;
+        }
+
+        attempts += 1;
+        if attempts > 50 {
+            anyhow::bail!("timed out waiting for a checkpoint signed by the new operator key");
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+
+    // The client should accept checkpoints signed by the new key
+    publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key).await?;
+    client.update().await?;
+
+    // Checkpoints signed by the retired key that do not include the
+    // rotation remain valid
+    let historical = SerdeEnvelope::signed_contents(
+        &old_key,
+        TimestampedCheckpoint::new(
+            old_checkpoint.as_ref().checkpoint.clone(),
+            rotated - Duration::from_secs(1),
+        )?,
+    )?;
+    let response = api.verify_checkpoint(historical).await?;
+    assert_eq!(response.signature, VerificationState::Verified);
+
+    // Checkpoints signed by the retired key that include the rotation are
+    // invalid, even when backdated to before the rotation
+    let forged = SerdeEnvelope::signed_contents(
+        &old_key,
+        TimestampedCheckpoint::new(
+            new_checkpoint.as_ref().checkpoint.clone(),
+            rotated - Duration::from_secs(1),
+        )?,
+    )?;
+    let response = api.verify_checkpoint(forged).await?;
+    assert_eq!(response.signature, VerificationState::Invalid);
+
+    Ok(())
+}
+
+async fn test_multi_file_release(config: &Config) -> Result<()> {
+    let name = PackageName::new("test:multi-file")?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+
+    // Store the content of each entry and build the content index
+    let component = wat::parse_str("(component)")?;
+    let asset = wat::parse_str("(module)")?;
+    let mut index = ContentIndex::new();
+    for (path, content) in [
+        (COMPONENT_ENTRY_PATH, component.clone()),
+        ("assets/extra.wasm", asset.clone()),
+    ] {
+        let digest = client
+            .content()
+            .store_content(
+                Box::pin(futures::stream::once(async move { Ok(content.into()) })),
+                None,
+            )
+            .await?;
+        index.insert(path.parse()?, digest)?;
+    }
+
+    let digest = index.digest();
+    let record_id = client
+        .publish_with_info(
+            &signing_key,
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![
+                    PublishEntry::Init,
+                    PublishEntry::Release {
+                        version: "0.1.0".parse()?,
+                        content: digest.clone(),
+                        index: Some(index),
+                        dependencies: Vec::new(),
+                    },
+                ],
+                record: None,
+            },
+        )
+        .await?;
+    client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await?;
+
+    // Download the release from the registry and check the file tree
+    client.content().clear().await?;
+    let download = client.download_exact(&name, &"0.1.0".parse()?).await?;
+    assert_eq!(download.digest, digest);
+    assert!(download.path.is_dir());
+    assert_eq!(
+        fs::read(download.path.join(COMPONENT_ENTRY_PATH))?,
+        component
+    );
+    assert_eq!(
+        fs::read(download.path.join("assets").join("extra.wasm"))?,
+        asset
+    );
+
+    Ok(())
+}
+
+async fn test_release_dependencies(config: &Config) -> Result<()> {
+    let dependency = PackageName::new("test:dependency")?;
+    let name = PackageName::new("test:dependent")?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+
+    publish_component(
+        &client,
+        &dependency,
+        "1.2.0",
+        "(component)",
+        true,
+        &signing_key,
+    )
+    .await?;
+
+    let content = wat::parse_str("(component (core module))")?;
+    let digest = client
+        .content()
+        .store_content(
+            Box::pin(futures::stream::once(async move { Ok(content.into()) })),
+            None,
+        )
+        .await?;
+    let dependencies = vec![ReleaseDependency {
+        name: dependency.clone(),
+        requirement: "^1.2".parse()?,
+    }];
+    let record_id = client
+        .publish_with_info(
+            &signing_key,
+            PublishInfo {
+                name: name.clone(),
+                head: None,
+                entries: vec![
+                    PublishEntry::Init,
+                    PublishEntry::Release {
+                        version: "0.1.0".parse()?,
+                        content: digest,
+                        index: None,
+                        dependencies: dependencies.clone(),
+                    },
+                ],
+                record: None,
+            },
+        )
+        .await?;
+    client
+        .wait_for_publish(&name, &record_id, Duration::from_millis(100))
+        .await?;
+
+    // The dependencies should be available from the package log alone
+    client.reset_registry(false).await?;
+    assert_eq!(
+        client.dependencies(&name, &"0.1.0".parse()?).await?,
+        dependencies
+    );
+    assert!(client
+        .dependencies(&dependency, &"1.2.0".parse()?)
+        .await?
+        .is_empty());
+
+    let download = client.download_exact(&name, &"0.1.0".parse()?).await?;
+    assert_eq!(download.dependencies, dependencies);
+
+    Ok(())
+}
+
+async fn test_custom_content_url(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:custom-content-url";
+    const PACKAGE_VERSION: &str = "0.1.0";
+
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    let digest = publish_component(
+        &client,
+        &name,
+        PACKAGE_VERSION,
+        "(component)",
+        true,
+        &signing_key,
+    )
+    .await?;
+
+    client.upsert([&name]).await?;
+    let package = client
+        .registry()
+        .load_package(&name)
+        .await?
+        .expect("expected the package to exist");
+    package
+        .state
+        .release(&Version::parse(PACKAGE_VERSION)?)
+        .expect("expected the package version to exist");
+
+    // Look up the content URL for the record
+    let client = api::Client::new(config.default_url.as_ref().unwrap())?;
+    let ContentSourcesResponse { content_sources } = client.content_sources(&digest).await?;
+    assert_eq!(content_sources.len(), 1);
+    let sources = content_sources
+        .get(&digest)
+        .expect("expected content source to be provided for the requested digest");
+    assert_eq!(sources.len(), 1);
 
     let expected_url = format!(
         "https://example.com/content/{digest}",
@@ -589,3 +1588,455 @@ async fn test_get_ledger(config: &Config) -> Result<()> {
 
     Ok(())
 }
+
+async fn test_witness_cosigning(config: &Config, witnesses: Vec<PrivateKey>) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:witnessed";
+
+    let root = config
+        .registries_dir
+        .as_ref()
+        .and_then(|p| p.parent())
+        .context("registries directory has no parent")?
+        .to_path_buf();
+    let url = config.default_url.as_ref().unwrap();
+    let api = api::Client::new(url)?;
+
+    // Publish with a client that does not require witnesses
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    // A client requiring two witnesses rejects the uncosigned checkpoint
+    let witnessed_config = Config {
+        registries_dir: Some(root.join("witnessed-registries")),
+        content_dir: Some(root.join("witnessed-content")),
+        witness_keys: witnesses.iter().map(|k| k.public_key()).collect(),
+        witness_threshold: 2,
+        ..config.clone()
+    };
+    let witnessed_client = create_client(&witnessed_config)?;
+    match witnessed_client.upsert([&name]).await {
+        Err(ClientError::InsufficientWitnesses {
+            needed: 2,
+            found: 0,
+            ..
+        }) => {}
+        res => panic!("expected insufficient witnesses, got {res:?}"),
+    }
+
+    let witnesses = witnesses
+        .into_iter()
+        .enumerate()
+        .map(|(i, key)| {
+            warg_witness::Witness::new(url, key, root.join(format!("witness-{i}.json")))
+                .map(|w| w.with_operator_keys([test_operator_key().public_key()]))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let cosigned = witnesses[0].witness().await?;
+    assert_eq!(cosigned.witnesses.len(), 1);
+    assert!(witnessed_client.upsert([&name]).await.is_err());
+
+    let cosigned = witnesses[1].witness().await?;
+    assert_eq!(cosigned.witnesses.len(), 2);
+    witnessed_client.upsert([&name]).await?;
+    assert!(witnessed_client
+        .registry()
+        .load_package(&name)
+        .await?
+        .is_some());
+
+    // Cosignatures by unknown witnesses are rejected
+    let (_, unknown_key) = generate_ed25519_pair();
+    let checkpoint = api.witness_checkpoint().await?;
+    match api
+        .cosign_checkpoint(&CosignCheckpointRequest {
+            log_length: checkpoint.as_ref().checkpoint.log_length,
+            cosignature: WitnessCosignature::sign(&unknown_key, checkpoint.as_ref())?,
+        })
+        .await
+    {
+        Err(api::ClientError::Witness(WitnessError::UnknownWitness(key_id))) => {
+            assert_eq!(key_id, unknown_key.public_key().fingerprint())
+        }
+        res => panic!("expected unknown witness, got {res:?}"),
+    }
+
+    // A new checkpoint is not served until it is cosigned by the quorum
+    publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key).await?;
+    let latest = api.witness_checkpoint().await?;
+    assert!(latest.as_ref().checkpoint.log_length > checkpoint.as_ref().checkpoint.log_length);
+    assert_eq!(
+        api.latest_cosigned_checkpoint().await?.checkpoint,
+        checkpoint
+    );
+
+    // Witnesses verify the new checkpoint is consistent with the last one
+    for witness in &witnesses {
+        witness.witness().await?;
+    }
+    let cosigned = api.latest_cosigned_checkpoint().await?;
+    assert_eq!(cosigned.checkpoint, latest);
+    assert_eq!(cosigned.witnesses.len(), 2);
+    witnessed_client.upsert([&name]).await?;
+
+    // A witness refuses to cosign a checkpoint that rolls back the log
+    let rolled_back = SerdeEnvelope::from_parts_unchecked(
+        TimestampedCheckpoint {
+            checkpoint: Checkpoint {
+                log_length: latest.as_ref().checkpoint.log_length + 1,
+                ..latest.as_ref().checkpoint.clone()
+            },
+            timestamp: latest.as_ref().timestamp,
+        },
+        latest.key_id().clone(),
+        latest.signature().clone(),
+    );
+    fs::write(
+        root.join("witness-0.json"),
+        serde_json::to_vec(&rolled_back)?,
+    )?;
+    let message = format!("{:#}", witnesses[0].witness().await.unwrap_err());
+    assert!(
+        message.contains("is less than the last witnessed log length"),
+        "unexpected error message: {message}"
+    );
+
+    Ok(())
+}
+
+async fn test_log_tiles(config: &Config) -> Result<()> {
+    const PACKAGE_NAME: &str = "test:tiled";
+
+    let name = PackageName::new(PACKAGE_NAME)?;
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+
+    let url = config.default_url.as_ref().unwrap();
+    let checkpoint = api::Client::new(url)?
+        .latest_checkpoint()
+        .await?
+        .into_contents()
+        .checkpoint;
+
+    // The checkpoint note is written after the checkpoint is stored
+    let tiles_url = Url::parse(url)?.join("tiles/")?;
+    let http = reqwest::Client::new();
+    let mut attempts = 0;
+    let note = loop {
+        let response = http.get(tiles_url.join("checkpoint")?).send().await?;
+        if response.status() == StatusCode::OK {
+            let note = response.text().await?;
+            if note.lines().nth(1) == Some(checkpoint.log_length.to_string().as_str()) {
+                break note;
+            }
+        }
+
+        attempts += 1;
+        if attempts > 50 {
+            anyhow::bail!("timed out waiting for the checkpoint note");
+        }
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    };
+
+    // The note is signed by the operator key
+    let (body, signature_line) = note
+        .split_once("\n\n")
+        .context("checkpoint note has no signature")?;
+    let body = format!("{body}\n");
+    let mut lines = body.lines();
+    let origin = lines.next().context("checkpoint note has no origin")?;
+    lines.next();
+    let root = BASE64.decode(lines.next().context("checkpoint note has no root")?)?;
+    assert_eq!(root, checkpoint.log_root.bytes());
+
+    let (key_name, signature) = signature_line
+        .trim_end()
+        .strip_prefix("\u{2014} ")
+        .and_then(|line| line.split_once(' '))
+        .context("malformed checkpoint note signature")?;
+    assert_eq!(key_name, origin);
+    let operator_key = test_operator_key().public_key();
+    let signature = format!(
+        "{algorithm}:{signature}",
+        algorithm = operator_key.signature_algorithm(),
+        signature = BASE64.encode(&BASE64.decode(signature)?[4..])
+    )
+    .parse()?;
+    operator_key.verify(body.as_bytes(), &signature)?;
+
+    // The tiles reproduce the checkpoint's log root and prove inclusion of
+    // the entries in the entry bundles
+    let length = checkpoint.log_length;
+    let mut log = TiledLog::<Sha256, LogLeaf>::new(length);
+    let mut entries = Vec::new();
+    for tile in tiles_between(0, length) {
+        let data = http
+            .get(tiles_url.join(&tile.path())?)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        log.insert(&tile, &data)?;
+
+        if let Some(path) = tile.entries_path() {
+            let mut bundle = &http
+                .get(tiles_url.join(&path)?)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?[..];
+            while !bundle.is_empty() {
+                let len = u16::from_be_bytes([bundle[0], bundle[1]]) as usize;
+                entries.push(bundle[2..2 + len].to_vec());
+                bundle = &bundle[2 + len..];
+            }
+        }
+    }
+
+    let root: AnyHash = log.root().context("tiles are incomplete")?.into();
+    assert_eq!(root, checkpoint.log_root);
+    assert_eq!(entries.len(), length);
+    for (index, entry) in entries.iter().enumerate() {
+        let leaf_hash = Hash::<Sha256>::of((0u8, entry.as_slice()));
+        let found: AnyHash = log
+            .prove_inclusion(Node(index * 2), length)
+            .evaluate_hash(&log, leaf_hash)?
+            .into();
+        assert_eq!(found, checkpoint.log_root);
+    }
+
+    Ok(())
+}
+
+async fn test_map_retention(config: &Config) -> Result<()> {
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    let url = config.default_url.as_ref().unwrap();
+    let api = api::Client::new(url)?;
+
+    let name = PackageName::new("test:retained")?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    let old = api.latest_checkpoint().await?.into_contents().checkpoint;
+
+    let name = PackageName::new("test:retained-latest")?;
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    let latest = api.latest_checkpoint().await?.into_contents().checkpoint;
+    assert!(latest.log_length > old.log_length);
+
+    // Only the map of the latest checkpoint is retained
+    let http = reqwest::Client::new();
+    let url = Url::parse(url)?.join(paths::prove_inclusion())?;
+    for (checkpoint, expected) in [(&latest, StatusCode::OK), (&old, StatusCode::NOT_FOUND)] {
+        let response = http
+            .post(url.clone())
+            .json(&InclusionRequest {
+                log_length: checkpoint.log_length,
+                leafs: vec![old.log_length - 1],
+            })
+            .send()
+            .await?;
+        assert_eq!(
+            response.status(),
+            expected,
+            "unexpected response for log length {length}",
+            length = checkpoint.log_length
+        );
+    }
+
+    // A client whose last known checkpoint of a package is no longer
+    // retained cannot prove the package's map transition
+    let dir = config.registries_dir.as_ref().unwrap().parent().unwrap();
+    let reader_config = Config {
+        registries_dir: Some(dir.join("reader-registries")),
+        content_dir: Some(dir.join("reader-content")),
+        ..config.clone()
+    };
+    let retained = PackageName::new("test:retained")?;
+    let reader = create_client(&reader_config)?;
+    reader.upsert([&retained]).await?;
+    let known = api.latest_checkpoint().await?.into_contents().checkpoint;
+
+    publish_component(
+        &client,
+        &retained,
+        "0.2.0",
+        "(component)",
+        false,
+        &signing_key,
+    )
+    .await?;
+    match reader.update().await {
+        Err(ClientError::MapTransitionUnavailable { log_length }) => {
+            assert_eq!(log_length, known.log_length)
+        }
+        other => panic!("expected the map transition to be unavailable, got {other:?}"),
+    }
+    drop(reader);
+
+    // Unless the client opts in to skipping the proof
+    let reader = create_client(&Config {
+        allow_unproven_map_transitions: true,
+        ..reader_config
+    })?;
+    reader.update().await?;
+
+    Ok(())
+}
+
+async fn test_package_absence(config: &Config) -> Result<()> {
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    let api = api::Client::new(config.default_url.as_ref().unwrap())?;
+
+    let present = PackageName::new("test:present")?;
+    publish_component(
+        &client,
+        &present,
+        "0.1.0",
+        "(component)",
+        true,
+        &signing_key,
+    )
+    .await?;
+    let checkpoint = api.latest_checkpoint().await?.into_contents().checkpoint;
+
+    // The client verifies the absence of a package the registry reports as missing
+    let absent = PackageName::new("test:absent")?;
+    match client.upsert([&absent]).await {
+        Err(ClientError::PackageDoesNotExist { name }) => assert_eq!(name, absent),
+        other => panic!("expected the package to not exist, got {other:?}"),
+    }
+
+    let absent_id = LogId::package_log::<Sha256>(&absent);
+    api.prove_absence(
+        AbsenceRequest {
+            log_length: checkpoint.log_length,
+            log_ids: vec![absent_id],
+        },
+        &checkpoint,
+    )
+    .await?;
+
+    // The absence of a published package cannot be proven
+    let present_id = LogId::package_log::<Sha256>(&present);
+    match api
+        .prove_absence(
+            AbsenceRequest {
+                log_length: checkpoint.log_length,
+                log_ids: vec![present_id.clone()],
+            },
+            &checkpoint,
+        )
+        .await
+    {
+        Err(api::ClientError::Proof(ProofError::PackageLogIncluded(id))) => {
+            assert_eq!(id, present_id)
+        }
+        other => panic!("expected the absence proof to fail, got {other:?}"),
+    }
+
+    // The absence is not trusted for a checkpoint that fails authentication
+    let (proxy, url) = spawn_forging_proxy(config.default_url.as_ref().unwrap()).await?;
+    let mut proxied_config = config.clone();
+    proxied_config.default_url = Some(url);
+    proxied_config.registries_dir = Some(config.registries_dir.as_ref().unwrap().join("proxied"));
+    proxied_config.content_dir = Some(config.content_dir.as_ref().unwrap().join("proxied"));
+    let proxied_client = create_client(&proxied_config)?;
+    match proxied_client.upsert([&absent]).await {
+        Err(ClientError::InvalidCheckpointSignature) => {}
+        other => panic!("expected an invalid checkpoint signature, got {other:?}"),
+    }
+    proxy.abort();
+
+    // A package the client has seen in a previous checkpoint cannot be absent
+    let vanished = PackageName::new("test:vanished")?;
+    let mut info = PackageInfo::new(vanished.clone());
+    info.head_registry_index = Some(0);
+    client.registry().store_package(&info).await?;
+    match client.update().await {
+        Err(ClientError::PackageLogRemoved { name }) => assert_eq!(name, vanished),
+        other => panic!("expected the package log to be reported removed, got {other:?}"),
+    }
+
+    Ok(())
+}
+
+async fn test_map_transition(config: &Config) -> Result<()> {
+    let client = create_client(config)?;
+    let signing_key = test_signing_key();
+    let api = api::Client::new(config.default_url.as_ref().unwrap())?;
+
+    let name = PackageName::new("test:transition")?;
+    let head = || async {
+        let info = client
+            .registry()
+            .load_package(&name)
+            .await?
+            .context("package does not exist in client storage")?;
+        let head = info.state.head().as_ref().context("package log is empty")?;
+        Ok::<_, anyhow::Error>(MapLeaf {
+            record_id: head.digest.clone(),
+        })
+    };
+
+    publish_component(&client, &name, "0.1.0", "(component)", true, &signing_key).await?;
+    client.upsert([&name]).await?;
+    let from = api.latest_checkpoint().await?.into_contents().checkpoint;
+    let from_head = head().await?;
+
+    // Updating the package proves its transition from the previous checkpoint
+    publish_component(&client, &name, "0.2.0", "(component)", false, &signing_key).await?;
+    client.upsert([&name]).await?;
+    let to = api.latest_checkpoint().await?.into_contents().checkpoint;
+    let to_head = head().await?;
+    assert_ne!(from_head, to_head);
+
+    let log_id = LogId::package_log::<Sha256>(&name);
+    let absent_id = LogId::package_log::<Sha256>(&PackageName::new("test:absent")?);
+    let request = || TransitionRequest {
+        from: from.log_length,
+        to: to.log_length,
+        log_ids: vec![log_id.clone(), absent_id.clone()],
+    };
+    api.prove_transition(
+        request(),
+        &from,
+        &to,
+        &[
+            (
+                log_id.clone(),
+                Some(from_head.clone()),
+                Some(to_head.clone()),
+            ),
+            (absent_id.clone(), None, None),
+        ],
+    )
+    .await?;
+
+    // A transition that did not happen cannot be proven
+    match api
+        .prove_transition(
+            request(),
+            &from,
+            &to,
+            &[
+                (log_id.clone(), Some(from_head.clone()), Some(from_head)),
+                (absent_id, None, None),
+            ],
+        )
+        .await
+    {
+        Err(api::ClientError::Proof(ProofError::IncorrectProof { root, .. })) => {
+            assert_eq!(root, to.map_root)
+        }
+        other => panic!("expected the transition proof to fail, got {other:?}"),
+    }
+
+    Ok(())
+}
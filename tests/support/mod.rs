@@ -1,4 +1,5 @@
 use anyhow::{bail, Context, Result};
+use futures::channel::mpsc::{unbounded, UnboundedSender};
 use std::{
     env,
     path::{Path, PathBuf},
@@ -13,9 +14,11 @@ use warg_client::{
     storage::{ContentStorage, PublishEntry, PublishInfo},
     FileSystemClient, StorageLockResult,
 };
+#[cfg(unix)]
+use warg_crypto::signing::external::SignerEndpoint;
 use warg_crypto::{
     hash::AnyHash,
-    signing::{KeyID, PrivateKey},
+    signing::{KeyID, PrivateKey, Signer},
 };
 use warg_protocol::{operator, registry::PackageName};
 use warg_server::{
@@ -52,9 +55,18 @@ pub fn create_client(config: &warg_client::Config) -> Result<FileSystemClient> {
 pub struct ServerInstance {
     task: Option<JoinHandle<()>>,
     shutdown: CancellationToken,
+    operator_keys: UnboundedSender<PrivateKey>,
     _subscriber_guard: DefaultGuard,
 }
 
+impl ServerInstance {
+    /// Requests the server rotate to the given operator key.
+    pub fn rotate_operator_key(&self, operator_key: PrivateKey) -> Result<()> {
+        self.operator_keys.unbounded_send(operator_key)?;
+        Ok(())
+    }
+}
+
 impl Drop for ServerInstance {
     fn drop(&mut self) {
         futures::executor::block_on(async move {
@@ -116,13 +128,27 @@ pub async fn spawn_server(
     content_base_url: Option<Url>,
     data_store: Option<Box<dyn DataStore>>,
     authorized_keys: Option<Vec<(String, KeyID)>>,
+) -> Result<(ServerInstance, warg_client::Config)> {
+    spawn_server_with(root, content_base_url, data_store, authorized_keys, |c| c).await
+}
+
+/// Spawns a server as a background task, applying the given function to
+/// its configuration.
+pub async fn spawn_server_with(
+    root: &Path,
+    content_base_url: Option<Url>,
+    data_store: Option<Box<dyn DataStore>>,
+    authorized_keys: Option<Vec<(String, KeyID)>>,
+    configure: impl FnOnce(Config) -> Config,
 ) -> Result<(ServerInstance, warg_client::Config)> {
     let _subscriber_guard = thread_test_logging();
 
     let shutdown = CancellationToken::new();
+    let (operator_keys, operator_key_rotation) = unbounded();
     let mut config = Config::new(test_operator_key(), test_namespaces(), root.join("server"))
         .with_addr(([127, 0, 0, 1], 0))
         .with_shutdown(shutdown.clone().cancelled_owned())
+        .with_operator_key_rotation(operator_key_rotation)
         .with_checkpoint_interval(Duration::from_millis(100))
         .with_content_policy(WasmContentPolicy::default()); // For the tests, we assume only wasm content is allowed.
 
@@ -143,7 +169,7 @@ pub async fn spawn_server(
         config = config.with_boxed_data_store(store);
     }
 
-    let server = Server::new(config).initialize().await?;
+    let server = Server::new(configure(config)).initialize().await?;
 
     let addr = server.local_addr()?;
     tracing::debug!("Test server running at {addr}");
@@ -156,6 +182,7 @@ pub async fn spawn_server(
     let instance = ServerInstance {
         task: Some(task),
         shutdown,
+        operator_keys,
         _subscriber_guard,
     };
 
@@ -163,6 +190,9 @@ pub async fn spawn_server(
         default_url: Some(format!("http://{addr}")),
         registries_dir: Some(root.join("registries")),
         content_dir: Some(root.join("content")),
+        witness_keys: Vec::new(),
+        witness_threshold: 0,
+        allow_unproven_map_transitions: false,
     };
 
     Ok((instance, config))
@@ -174,7 +204,7 @@ pub async fn publish(
     version: &str,
     content: Vec<u8>,
     init: bool,
-    signing_key: &PrivateKey,
+    signer: &dyn Signer,
 ) -> Result<AnyHash> {
     let digest = client
         .content()
@@ -191,15 +221,18 @@ pub async fn publish(
     entries.push(PublishEntry::Release {
         version: version.parse().unwrap(),
         content: digest.clone(),
+        index: None,
+        dependencies: Vec::new(),
     });
 
     let record_id = client
         .publish_with_info(
-            signing_key,
+            signer,
             PublishInfo {
                 name: name.clone(),
                 head: None,
                 entries,
+                record: None,
             },
         )
         .await?;
@@ -217,17 +250,9 @@ pub async fn publish_component(
     version: &str,
     wat: &str,
     init: bool,
-    signing_key: &PrivateKey,
+    signer: &dyn Signer,
 ) -> Result<AnyHash> {
-    publish(
-        client,
-        name,
-        version,
-        wat::parse_str(wat)?,
-        init,
-        signing_key,
-    )
-    .await
+    publish(client, name, version, wat::parse_str(wat)?, init, signer).await
 }
 
 pub async fn publish_wit(
@@ -236,7 +261,7 @@ pub async fn publish_wit(
     version: &str,
     wit: &str,
     init: bool,
-    signing_key: &PrivateKey,
+    signer: &dyn Signer,
 ) -> Result<AnyHash> {
     let mut resolve = Resolve::new();
     let pkg = resolve.push(UnresolvedPackage::parse(Path::new("foo.wit"), wit)?)?;
@@ -247,7 +272,120 @@ pub async fn publish_wit(
         version,
         wit_component::encode(Some(true), &resolve, pkg)?,
         init,
-        signing_key,
+        signer,
     )
     .await
 }
+
+/// Spawns a proxy in front of the registry at the given URL that serves the
+/// registry's latest checkpoint with a forged operator signature.
+///
+/// All other requests are forwarded to the registry unchanged.
+pub async fn spawn_forging_proxy(registry: &str) -> Result<(JoinHandle<()>, String)> {
+    use axum::{
+        body::{to_bytes, Body},
+        extract::{Request, State},
+        response::Response,
+        routing::get,
+        Json, Router,
+    };
+    use warg_api::v1::paths;
+    use warg_protocol::{registry::CosignedCheckpoint, SerdeEnvelope};
+
+    async fn forge_checkpoint(State(registry): State<Url>) -> Json<CosignedCheckpoint> {
+        let url = registry.join(paths::fetch_checkpoint()).unwrap();
+        let mut cosigned: CosignedCheckpoint =
+            reqwest::get(url).await.unwrap().json().await.unwrap();
+
+        // Sign the checkpoint with a key other than the one it claims
+        let forged = SerdeEnvelope::signed_contents(
+            &test_signing_key(),
+            cosigned.checkpoint.as_ref().clone(),
+        )
+        .unwrap();
+        cosigned.checkpoint = SerdeEnvelope::from_parts_unchecked(
+            cosigned.checkpoint.as_ref().clone(),
+            cosigned.checkpoint.key_id().clone(),
+            forged.signature().clone(),
+        );
+        Json(cosigned)
+    }
+
+    async fn forward(State(registry): State<Url>, request: Request) -> Response {
+        let (parts, body) = request.into_parts();
+        let path = parts
+            .uri
+            .path_and_query()
+            .map(|p| p.as_str())
+            .unwrap_or("/");
+        let mut forwarded = reqwest::Client::new().request(
+            parts.method.as_str().parse().unwrap(),
+            registry.join(path).unwrap(),
+        );
+        for (name, value) in &parts.headers {
+            if name != "host" {
+                forwarded = forwarded.header(name.as_str(), value.as_bytes());
+            }
+        }
+
+        let body = to_bytes(body, usize::MAX).await.unwrap();
+        let response = forwarded.body(body).send().await.unwrap();
+        let mut builder = Response::builder().status(response.status().as_u16());
+        for (name, value) in response.headers() {
+            builder = builder.header(name.as_str(), value.as_bytes());
+        }
+        builder
+            .body(Body::from(response.bytes().await.unwrap()))
+            .unwrap()
+    }
+
+    let registry = Url::parse(registry)?;
+    let app = Router::new()
+        .route(
+            &format!("/{path}", path = paths::fetch_checkpoint()),
+            get(forge_checkpoint),
+        )
+        .fallback(forward)
+        .with_state(registry);
+
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await?;
+    let addr = listener.local_addr()?;
+    let task = tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    Ok((task, format!("http://{addr}")))
+}
+
+/// Spawns a signing daemon for the given key that listens on a Unix domain
+/// socket at the given path.
+#[cfg(unix)]
+pub fn spawn_signing_daemon(path: &Path, key: PrivateKey) -> Result<SignerEndpoint> {
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::UnixListener,
+    };
+    use warg_crypto::signing::external::{SignerRequest, SignerResponse};
+
+    let listener = UnixListener::bind(path)?;
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut stream = BufReader::new(stream);
+            let mut line = String::new();
+            if stream.read_line(&mut line).await.is_err() {
+                continue;
+            }
+
+            let response = match serde_json::from_str::<SignerRequest>(&line) {
+                Ok(request) => request.respond(&key).await,
+                Err(e) => SignerResponse::Error(e.to_string()),
+            };
+
+            let mut response = serde_json::to_vec(&response).unwrap();
+            response.push(b'\n');
+            let _ = stream.get_mut().write_all(&response).await;
+        }
+    });
+
+    Ok(SignerEndpoint::Socket(path.to_owned()))
+}